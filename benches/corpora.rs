@@ -0,0 +1,112 @@
+extern crate criterion;
+extern crate tiff;
+
+mod corpus;
+
+use criterion::{black_box, BenchmarkId, Criterion, Throughput};
+use std::io::Cursor;
+use std::sync::Arc;
+use std::thread;
+use tiff::decoder::{ChunkIndex, Decoder, DecodingResult, SampleConversion};
+use tiff::encoder::{colortype, Compression, TiffEncoder};
+
+const THREADS: u32 = 4;
+
+fn read_image(image: &[u8]) -> DecodingResult {
+    let mut decoder = Decoder::new(black_box(Cursor::new(image))).unwrap();
+    decoder.read_image().unwrap()
+}
+
+/// Decodes the chunks of the image on several threads, sharing the parsed directory.
+fn read_chunks_parallel(image: &Arc<[u8]>) {
+    let decoder = Decoder::new(black_box(Cursor::new(image.clone()))).unwrap();
+    let chunks = decoder.chunk_layout().unwrap().chunks_per_plane();
+
+    let workers: Vec<_> = (0..THREADS)
+        .map(|worker| {
+            let mut decoder = decoder.try_clone().unwrap();
+            thread::spawn(move || {
+                for chunk in (worker..chunks).step_by(THREADS as usize) {
+                    black_box(decoder.read_chunk_at(ChunkIndex(chunk)).unwrap());
+                }
+            })
+        })
+        .collect();
+    for worker in workers {
+        worker.join().unwrap();
+    }
+}
+
+fn main() {
+    let corpora = corpus::all();
+    let mut c = Criterion::default().configure_from_args();
+
+    let mut group = c.benchmark_group("tiff-corpora-decode");
+    for corpus in &corpora {
+        group
+            .sample_size(20)
+            .throughput(Throughput::Bytes(corpus.decoded_bytes))
+            .bench_with_input(
+                BenchmarkId::new("read_image", corpus.name),
+                &corpus.data,
+                |b, input| b.iter(|| read_image(input)),
+            );
+
+        let shared: Arc<[u8]> = corpus.data.clone().into();
+        group.bench_with_input(
+            BenchmarkId::new("read_chunks_parallel", corpus.name),
+            &shared,
+            |b, input| b.iter(|| read_chunks_parallel(input)),
+        );
+    }
+    group.finish();
+
+    // The encoder writes strips, so the decoded samples are encoded again rather than the
+    // layout of the corpora.
+    let mut group = c.benchmark_group("tiff-corpora-encode");
+    for corpus in &corpora {
+        let mut decoder = Decoder::new(Cursor::new(&corpus.data)).unwrap();
+        let (width, height) = decoder.dimensions().unwrap();
+        let samples = decoder
+            .read_image_unpacked(SampleConversion::Scale)
+            .unwrap();
+        let input_bytes = match &samples {
+            DecodingResult::F32(samples) => samples.len() as u64 * 4,
+            DecodingResult::U8(samples) => samples.len() as u64,
+            _ => 0,
+        };
+        group
+            .sample_size(20)
+            .throughput(Throughput::Bytes(input_bytes));
+        match samples {
+            DecodingResult::F32(samples) => {
+                group.bench_function(BenchmarkId::new("lzw", corpus.name), |b| {
+                    b.iter(|| {
+                        let mut file = Cursor::new(Vec::new());
+                        TiffEncoder::new(&mut file)
+                            .unwrap()
+                            .with_compression(Compression::Lzw)
+                            .write_image::<colortype::Gray32Float>(width, height, &samples)
+                            .unwrap();
+                        file
+                    })
+                });
+            }
+            DecodingResult::U8(samples) if samples.len() as u64 == u64::from(width * height) => {
+                group.bench_function(BenchmarkId::new("packbits", corpus.name), |b| {
+                    b.iter(|| {
+                        let mut file = Cursor::new(Vec::new());
+                        TiffEncoder::new(&mut file)
+                            .unwrap()
+                            .with_compression(Compression::Packbits)
+                            .write_image::<colortype::Gray8>(width, height, &samples)
+                            .unwrap();
+                        file
+                    })
+                });
+            }
+            _ => {}
+        }
+    }
+    group.finish();
+}
@@ -0,0 +1,245 @@
+//! Representative images for the benchmarks, generated deterministically.
+//!
+//! Every run benchmarks the same bytes, so results can be compared between changes with the
+//! baselines of criterion:
+//!
+//! ```text
+//! cargo bench --bench corpora -- --save-baseline before
+//! cargo bench --bench corpora -- --baseline before
+//! ```
+//!
+//! Set `TIFF_BENCH_CORPUS_DIR` to also write the generated files to that directory, e.g. to
+//! compare against other readers.
+use std::io::Cursor;
+
+use tiff::encoder::compression::{CompressionAlgorithm, Lzw, Packbits};
+use tiff::encoder::TiffEncoder;
+use tiff::tags::{CompressionMethod, PhotometricInterpretation, SampleFormat, Tag};
+
+/// An encoded image and the size of its decoded samples.
+pub struct Corpus {
+    pub name: &'static str,
+    pub data: Vec<u8>,
+    /// The number of bytes of the decoded image, used as the throughput of the benchmarks.
+    pub decoded_bytes: u64,
+}
+
+/// All corpora, written to `TIFF_BENCH_CORPUS_DIR` if it is set.
+pub fn all() -> Vec<Corpus> {
+    let corpora = vec![
+        tiled_lzw_geotiff(2048, 256),
+        packbits_scan(4096, 4096),
+        single_strip_rgb16(2048, 2048),
+        jpeg_ycbcr(),
+    ];
+
+    if let Some(dir) = std::env::var_os("TIFF_BENCH_CORPUS_DIR") {
+        let dir = std::path::PathBuf::from(dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        for corpus in &corpora {
+            std::fs::write(dir.join(corpus.name), &corpus.data).unwrap();
+        }
+    }
+
+    corpora
+}
+
+/// A xorshift generator, so that the noise does not depend on any external crate.
+struct Noise(u32);
+
+impl Noise {
+    fn next(&mut self) -> u32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        self.0
+    }
+}
+
+/// A square elevation model of `f32` samples in LZW compressed tiles, with GeoTIFF tags.
+pub fn tiled_lzw_geotiff(size: u32, tile: u32) -> Corpus {
+    let mut noise = Noise(0x2545_f491);
+    let elevation: Vec<f32> = (0..size * size)
+        .map(|i| {
+            let (x, y) = ((i % size) as f32, (i / size) as f32);
+            let terrain = (x * 0.01).sin() * (y * 0.013).cos() * 1000.0;
+            terrain + (noise.next() % 100) as f32 * 0.01
+        })
+        .collect();
+
+    let tiles = size / tile;
+    let mut file = Cursor::new(Vec::new());
+    {
+        let mut tiff = TiffEncoder::new(&mut file).unwrap();
+        let mut dir = tiff.new_directory().unwrap();
+        let mut offsets = Vec::new();
+        let mut byte_counts = Vec::new();
+        for index in 0..tiles * tiles {
+            let (left, top) = (index % tiles * tile, index / tiles * tile);
+            let mut samples = Vec::with_capacity((tile * tile * 4) as usize);
+            for y in top..top + tile {
+                let row = (y * size + left) as usize;
+                for value in &elevation[row..row + tile as usize] {
+                    samples.extend_from_slice(&value.to_ne_bytes());
+                }
+            }
+
+            let mut compressed = Vec::new();
+            Lzw.write_to(&mut compressed, &samples).unwrap();
+            offsets.push(dir.write_data(&compressed[..]).unwrap() as u32);
+            byte_counts.push(compressed.len() as u32);
+        }
+
+        dir.write_tag(Tag::ImageWidth, size).unwrap();
+        dir.write_tag(Tag::ImageLength, size).unwrap();
+        dir.write_tag(Tag::BitsPerSample, 32u16).unwrap();
+        dir.write_tag(Tag::Compression, CompressionMethod::LZW.to_u16())
+            .unwrap();
+        let photometric = PhotometricInterpretation::BlackIsZero.to_u16();
+        dir.write_tag(Tag::PhotometricInterpretation, photometric)
+            .unwrap();
+        dir.write_tag(Tag::SamplesPerPixel, 1u16).unwrap();
+        dir.write_tag(Tag::TileWidth, tile).unwrap();
+        dir.write_tag(Tag::TileLength, tile).unwrap();
+        dir.write_tag(Tag::TileOffsets, &offsets[..]).unwrap();
+        dir.write_tag(Tag::TileByteCounts, &byte_counts[..])
+            .unwrap();
+        dir.write_tag(Tag::SampleFormat, SampleFormat::IEEEFP.to_u16())
+            .unwrap();
+        dir.write_tag(Tag::ModelPixelScaleTag, &[30.0f64, 30.0, 0.0][..])
+            .unwrap();
+        let tiepoint = [0.0f64, 0.0, 0.0, 500_000.0, 4_600_000.0, 0.0];
+        dir.write_tag(Tag::ModelTiepointTag, &tiepoint[..]).unwrap();
+        // Projected UTM zone 33N, with the raster as areas.
+        let keys = [
+            1u16, 1, 0, 3, 1024, 0, 1, 1, 1025, 0, 1, 1, 3072, 0, 1, 32633,
+        ];
+        dir.write_tag(Tag::GeoKeyDirectoryTag, &keys[..]).unwrap();
+        dir.finish().unwrap();
+    }
+
+    Corpus {
+        name: "tiled-lzw-f32-geotiff.tif",
+        data: file.into_inner(),
+        decoded_bytes: u64::from(size * size) * 4,
+    }
+}
+
+/// A bilevel scan of text-like dark marks on white, with PackBits compressed strips.
+pub fn packbits_scan(width: u32, height: u32) -> Corpus {
+    const ROWS_PER_STRIP: u32 = 64;
+
+    let mut noise = Noise(0x9e37_79b9);
+    let row_bytes = ((width + 7) / 8) as usize;
+    let mut file = Cursor::new(Vec::new());
+    {
+        let mut tiff = TiffEncoder::new(&mut file).unwrap();
+        let mut dir = tiff.new_directory().unwrap();
+        let mut offsets = Vec::new();
+        let mut byte_counts = Vec::new();
+        for top in (0..height).step_by(ROWS_PER_STRIP as usize) {
+            let mut strip = Vec::new();
+            for y in top..(top + ROWS_PER_STRIP).min(height) {
+                // Lines of glyphs with white margins and white space between lines.
+                let mut row = vec![0u8; row_bytes];
+                if y % 48 < 32 {
+                    for byte in row.iter_mut().take(row_bytes - 16).skip(16) {
+                        if noise.next() % 4 == 0 {
+                            *byte = noise.next() as u8;
+                        }
+                    }
+                }
+                // PackBits runs must not cross rows.
+                Packbits.write_to(&mut strip, &row).unwrap();
+            }
+            offsets.push(dir.write_data(&strip[..]).unwrap() as u32);
+            byte_counts.push(strip.len() as u32);
+        }
+
+        dir.write_tag(Tag::ImageWidth, width).unwrap();
+        dir.write_tag(Tag::ImageLength, height).unwrap();
+        dir.write_tag(Tag::BitsPerSample, 1u16).unwrap();
+        dir.write_tag(Tag::Compression, CompressionMethod::PackBits.to_u16())
+            .unwrap();
+        let photometric = PhotometricInterpretation::WhiteIsZero.to_u16();
+        dir.write_tag(Tag::PhotometricInterpretation, photometric)
+            .unwrap();
+        dir.write_tag(Tag::SamplesPerPixel, 1u16).unwrap();
+        dir.write_tag(Tag::RowsPerStrip, ROWS_PER_STRIP).unwrap();
+        dir.write_tag(Tag::StripOffsets, &offsets[..]).unwrap();
+        dir.write_tag(Tag::StripByteCounts, &byte_counts[..])
+            .unwrap();
+        dir.finish().unwrap();
+    }
+
+    Corpus {
+        name: "packbits-bilevel-scan.tif",
+        data: file.into_inner(),
+        // Decoded images hold eight samples per byte.
+        decoded_bytes: row_bytes as u64 * u64::from(height),
+    }
+}
+
+/// A photograph-like RGB image of `u16` samples, uncompressed in a single strip.
+///
+/// This is decoded with a single read, rather than chunk by chunk.
+pub fn single_strip_rgb16(width: u32, height: u32) -> Corpus {
+    let mut noise = Noise(0x6a09_e667);
+    let mut strip = Vec::with_capacity((width * height * 6) as usize);
+    for y in 0..height {
+        for x in 0..width {
+            let base = [x * 16, y * 16, (x + y) * 8];
+            for channel in base {
+                let sample = (channel + noise.next() % 256) as u16;
+                strip.extend_from_slice(&sample.to_le_bytes());
+            }
+        }
+    }
+
+    let mut file = Cursor::new(Vec::new());
+    {
+        let mut tiff = TiffEncoder::new(&mut file).unwrap();
+        let mut dir = tiff.new_directory().unwrap();
+        let offset = dir.write_data(&strip[..]).unwrap() as u32;
+
+        dir.write_tag(Tag::ImageWidth, width).unwrap();
+        dir.write_tag(Tag::ImageLength, height).unwrap();
+        dir.write_tag(Tag::BitsPerSample, &[16u16, 16, 16][..])
+            .unwrap();
+        dir.write_tag(Tag::Compression, CompressionMethod::None.to_u16())
+            .unwrap();
+        let photometric = PhotometricInterpretation::RGB.to_u16();
+        dir.write_tag(Tag::PhotometricInterpretation, photometric)
+            .unwrap();
+        dir.write_tag(Tag::SamplesPerPixel, 3u16).unwrap();
+        dir.write_tag(Tag::RowsPerStrip, height).unwrap();
+        dir.write_tag(Tag::StripOffsets, offset).unwrap();
+        dir.write_tag(Tag::StripByteCounts, strip.len() as u32)
+            .unwrap();
+        dir.finish().unwrap();
+    }
+
+    Corpus {
+        name: "single-strip-rgb16.tif",
+        data: file.into_inner(),
+        decoded_bytes: strip.len() as u64,
+    }
+}
+
+/// A tiled JPEG image with YCbCr samples.
+///
+/// The crate has no JPEG encoder, so this is the test image rather than a generated one.
+pub fn jpeg_ycbcr() -> Corpus {
+    let data = include_bytes!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/images/tiled-jpeg-ycbcr.tif"
+    ));
+    let mut decoder = tiff::decoder::Decoder::new(Cursor::new(&data[..])).unwrap();
+    let (width, height) = decoder.dimensions().unwrap();
+
+    Corpus {
+        name: "tiled-jpeg-ycbcr.tif",
+        data: data.to_vec(),
+        decoded_bytes: u64::from(width * height) * 3,
+    }
+}
@@ -0,0 +1,48 @@
+extern crate criterion;
+extern crate tiff;
+
+use criterion::{black_box, Criterion, Throughput};
+use std::io::Cursor;
+use tiff::decoder::Decoder;
+use tiff::encoder::{colortype, Compression, TiffEncoder};
+
+fn read_image(image: &[u8]) {
+    let image = Cursor::new(image);
+    let mut decoder = Decoder::new(black_box(image)).unwrap();
+    decoder.read_image().unwrap();
+}
+
+/// A bilevel-like scan: long white runs interrupted by short, noisy dark features.
+fn scan_image(width: u32, height: u32) -> Vec<u8> {
+    let mut state = 0x2545_f491u32;
+    (0..width * height)
+        .map(|i| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            if (i % width) % 97 < 8 {
+                state as u8
+            } else {
+                0xff
+            }
+        })
+        .collect()
+}
+
+fn main() {
+    let (width, height) = (2048, 2048);
+    let mut encoded = Cursor::new(Vec::new());
+    TiffEncoder::new(&mut encoded)
+        .unwrap()
+        .with_compression(Compression::Packbits)
+        .write_image::<colortype::Gray8>(width, height, &scan_image(width, height))
+        .unwrap();
+    let encoded = encoded.into_inner();
+
+    let mut c = Criterion::default().configure_from_args();
+    let mut group = c.benchmark_group("tiff-packbits");
+    group
+        .sample_size(20)
+        .throughput(Throughput::Bytes(u64::from(width * height)))
+        .bench_function("scan-2048x2048", |b| b.iter(|| read_image(&encoded)));
+}
@@ -0,0 +1,136 @@
+//! Tag requirements of the baseline TIFF image classes.
+//!
+//! These tables follow sections 3 to 6 of the TIFF 6.0 specification, which define the tags a
+//! baseline bilevel, grayscale, palette-color and RGB image must contain. Some required tags have
+//! a default value, readers assume that value when the tag is missing.
+
+use crate::tags::Tag;
+
+/// A baseline image class.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ImageClass {
+    /// Bilevel images, section 3.
+    Bilevel,
+    /// Grayscale images, section 4.
+    Grayscale,
+    /// Palette-color images, section 5.
+    Palette,
+    /// Full-color RGB images, section 6.
+    Rgb,
+}
+
+/// A tag that must be present in an image of a baseline class.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TagRequirement {
+    /// The required tag.
+    pub tag: Tag,
+    /// The value assumed if the tag is missing, or `None` if it must always be written.
+    pub default: Option<u32>,
+}
+
+const fn required(tag: Tag) -> TagRequirement {
+    TagRequirement { tag, default: None }
+}
+
+const fn defaulted(tag: Tag, default: u32) -> TagRequirement {
+    TagRequirement {
+        tag,
+        default: Some(default),
+    }
+}
+
+/// Required tags of bilevel images.
+pub const BILEVEL: &[TagRequirement] = &[
+    required(Tag::ImageWidth),
+    required(Tag::ImageLength),
+    defaulted(Tag::Compression, 1),
+    required(Tag::PhotometricInterpretation),
+    required(Tag::StripOffsets),
+    defaulted(Tag::RowsPerStrip, u32::MAX),
+    required(Tag::StripByteCounts),
+    required(Tag::XResolution),
+    required(Tag::YResolution),
+    defaulted(Tag::ResolutionUnit, 2),
+];
+
+/// Required tags of grayscale images.
+pub const GRAYSCALE: &[TagRequirement] = &[
+    required(Tag::ImageWidth),
+    required(Tag::ImageLength),
+    defaulted(Tag::BitsPerSample, 1),
+    defaulted(Tag::Compression, 1),
+    required(Tag::PhotometricInterpretation),
+    required(Tag::StripOffsets),
+    defaulted(Tag::RowsPerStrip, u32::MAX),
+    required(Tag::StripByteCounts),
+    required(Tag::XResolution),
+    required(Tag::YResolution),
+    defaulted(Tag::ResolutionUnit, 2),
+];
+
+/// Required tags of palette-color images.
+pub const PALETTE: &[TagRequirement] = &[
+    required(Tag::ImageWidth),
+    required(Tag::ImageLength),
+    defaulted(Tag::BitsPerSample, 1),
+    defaulted(Tag::Compression, 1),
+    required(Tag::PhotometricInterpretation),
+    required(Tag::StripOffsets),
+    defaulted(Tag::RowsPerStrip, u32::MAX),
+    required(Tag::StripByteCounts),
+    required(Tag::XResolution),
+    required(Tag::YResolution),
+    defaulted(Tag::ResolutionUnit, 2),
+    required(Tag::ColorMap),
+];
+
+/// Required tags of RGB images.
+///
+/// Unlike in the other classes, `BitsPerSample` and `SamplesPerPixel` have no default here: an RGB
+/// image has at least 3 samples of 8 bits, which the field defaults of 1 do not describe.
+pub const RGB: &[TagRequirement] = &[
+    required(Tag::ImageWidth),
+    required(Tag::ImageLength),
+    required(Tag::BitsPerSample),
+    defaulted(Tag::Compression, 1),
+    required(Tag::PhotometricInterpretation),
+    required(Tag::StripOffsets),
+    required(Tag::SamplesPerPixel),
+    defaulted(Tag::RowsPerStrip, u32::MAX),
+    required(Tag::StripByteCounts),
+    required(Tag::XResolution),
+    required(Tag::YResolution),
+    defaulted(Tag::ResolutionUnit, 2),
+];
+
+/// Default values of baseline tags that no image class requires with a default.
+pub const OPTIONAL_DEFAULTS: &[TagRequirement] = &[
+    defaulted(Tag::NewSubfileType, 0),
+    defaulted(Tag::SamplesPerPixel, 1),
+    defaulted(Tag::Threshholding, 1),
+    defaulted(Tag::FillOrder, 1),
+    defaulted(Tag::Orientation, 1),
+    defaulted(Tag::PlanarConfiguration, 1),
+    defaulted(Tag::GrayResponseUnit, 2),
+];
+
+impl ImageClass {
+    /// The tags an image of this class must contain.
+    pub fn required_tags(self) -> &'static [TagRequirement] {
+        match self {
+            ImageClass::Bilevel => BILEVEL,
+            ImageClass::Grayscale => GRAYSCALE,
+            ImageClass::Palette => PALETTE,
+            ImageClass::Rgb => RGB,
+        }
+    }
+}
+
+/// The value readers assume for a baseline tag that is missing.
+pub fn default_value(tag: Tag) -> Option<u32> {
+    [BILEVEL, GRAYSCALE, PALETTE, RGB, OPTIONAL_DEFAULTS]
+        .iter()
+        .flat_map(|requirements| requirements.iter())
+        .filter(|requirement| requirement.tag == tag)
+        .find_map(|requirement| requirement.default)
+}
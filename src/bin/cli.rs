@@ -0,0 +1,463 @@
+//! A command line tool to inspect, validate and convert TIFF files.
+//!
+//! Built with the `cli` feature, e.g. `cargo install tiff --features cli`. Run `tiff-cli help`
+//! for the available commands.
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Seek, Write};
+use std::process;
+
+use tiff::decoder::{ChunkType, Decoder, DecoderOptions, DecodingResult, Limits};
+use tiff::encoder::compression::DeflateLevel;
+use tiff::encoder::{AutoTiffEncoder, Compression, Predictor, TiffEncoder};
+use tiff::tags::{CompressionMethod, PhotometricInterpretation, PlanarConfiguration, Tag};
+use tiff::ColorType;
+
+const USAGE: &str = "\
+Usage: tiff-cli <command> [options] <files>
+
+Commands:
+  inspect [--tags] <file>...
+      Print the layout of every image, and with --tags the values of all tags.
+  validate <file>...
+      Decode every chunk of every image in validation mode and report the problems found.
+  convert [options] <input> <output>
+      Decode every image and write it again.
+      --compression <none|lzw|deflate|packbits>  compression of the output, none by default
+      --predictor <none|horizontal|float>        predictor of the output, none by default
+      --tile <size>                              write tiles of size by size pixels
+      --bigtiff                                  write a BigTIFF file, even if not needed
+  help
+      Print this message.
+
+Exits with 1 if a file is invalid or could not be processed, and with 2 on wrong usage.";
+
+/// The result of a command, `false` if a file turned out to be invalid.
+type CommandResult = Result<bool, Box<dyn Error>>;
+
+/// Wrong usage of the tool, as opposed to a problem with a file.
+#[derive(Debug)]
+struct Usage(String);
+
+impl fmt::Display for Usage {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.write_str(&self.0)
+    }
+}
+
+impl Error for Usage {}
+
+fn usage<T>(message: impl Into<String>) -> Result<T, Box<dyn Error>> {
+    Err(Box::new(Usage(message.into())))
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (command, args) = match args.split_first() {
+        Some((command, args)) => (command.as_str(), args),
+        None => ("", &args[..]),
+    };
+    let result = match command {
+        "inspect" => inspect(args),
+        "validate" => validate(args),
+        "convert" => convert(args),
+        "help" | "-h" | "--help" => {
+            println!("{}", USAGE);
+            return;
+        }
+        "" => usage("missing command"),
+        command => usage(format!("unknown command `{}`", command)),
+    };
+
+    match result {
+        Ok(true) => {}
+        Ok(false) => process::exit(1),
+        Err(err) if err.is::<Usage>() => {
+            eprintln!("error: {}\n\n{}", err, USAGE);
+            process::exit(2);
+        }
+        Err(err) => {
+            eprintln!("error: {}", err);
+            process::exit(1);
+        }
+    }
+}
+
+/// Splits `args` into options and their values, as accepted by `accept`, and the other
+/// arguments.
+fn parse_args<'a>(
+    args: &'a [String],
+    mut accept: impl FnMut(&str, &mut dyn Iterator<Item = &'a String>) -> Result<(), Box<dyn Error>>,
+) -> Result<Vec<&'a str>, Box<dyn Error>> {
+    let mut paths = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg.starts_with("--") {
+            accept(arg, &mut args)?;
+        } else {
+            paths.push(arg.as_str());
+        }
+    }
+    if paths.is_empty() {
+        return usage("missing file");
+    }
+    Ok(paths)
+}
+
+/// Returns the value of `option`.
+fn value<'a>(
+    option: &str,
+    args: &mut dyn Iterator<Item = &'a String>,
+) -> Result<&'a str, Box<dyn Error>> {
+    match args.next() {
+        Some(value) => Ok(value),
+        None => usage(format!("missing value of `{}`", option)),
+    }
+}
+
+/// Runs `visit` on every image of `decoder`, along with its index.
+fn for_each_image<R: Read + Seek>(
+    decoder: &mut Decoder<R>,
+    mut visit: impl FnMut(&mut Decoder<R>, usize) -> CommandResult,
+) -> CommandResult {
+    let mut valid = true;
+    for index in 0.. {
+        valid &= visit(decoder, index)?;
+        if !decoder.more_images() {
+            break;
+        }
+        decoder.next_image()?;
+    }
+    Ok(valid)
+}
+
+fn open(path: &str, options: DecoderOptions) -> Result<Decoder<BufReader<File>>, Box<dyn Error>> {
+    let file = File::open(path).map_err(|err| format!("{}: {}", path, err))?;
+    Ok(Decoder::with_options(BufReader::new(file), options)?)
+}
+
+fn inspect(args: &[String]) -> CommandResult {
+    let mut tags = false;
+    let paths = parse_args(args, |option, _| match option {
+        "--tags" => {
+            tags = true;
+            Ok(())
+        }
+        _ => usage(format!("unknown option `{}`", option)),
+    })?;
+
+    for path in paths {
+        let mut decoder = open(path, DecoderOptions::new())?;
+        let kind = if decoder.is_bigtiff() {
+            "BigTIFF"
+        } else {
+            "TIFF"
+        };
+        println!("{}: {:?} {}", path, decoder.byte_order(), kind);
+        let cog = decoder.cog_report()?;
+        if cog.tiled {
+            println!("  cloud optimized: {}", cog.is_compliant());
+        }
+
+        for_each_image(&mut decoder, |decoder, index| {
+            inspect_image(decoder, index, tags)?;
+            Ok(true)
+        })?;
+    }
+
+    Ok(true)
+}
+
+fn inspect_image<R: Read + Seek>(
+    decoder: &mut Decoder<R>,
+    index: usize,
+    tags: bool,
+) -> Result<(), Box<dyn Error>> {
+    let (width, height) = decoder.dimensions()?;
+    let color_type = match decoder.colortype() {
+        Ok(color_type) => format!("{:?}", color_type),
+        Err(err) => format!("not decodable ({})", err),
+    };
+    let compression = decoder.find_tag_unsigned::<u16>(Tag::Compression)?.map_or(
+        CompressionMethod::None,
+        CompressionMethod::from_u16_exhaustive,
+    );
+    let predictor = decoder
+        .find_tag_unsigned::<u16>(Tag::Predictor)?
+        .and_then(Predictor::from_u16)
+        .unwrap_or(Predictor::None);
+    println!(
+        "image {}: {}x{} {}, {:?} compression, {:?} predictor",
+        index, width, height, color_type, compression, predictor
+    );
+
+    let layout = decoder.chunk_layout()?;
+    let chunk_type = match layout.chunk_type {
+        ChunkType::Strip => "strips",
+        ChunkType::Tile => "tiles",
+    };
+    let planar = if layout.planes > 1 { ", planar" } else { "" };
+    println!(
+        "  {} {} of {}x{}{}",
+        layout.chunks_per_plane() * u32::from(layout.planes),
+        chunk_type,
+        layout.chunk_width,
+        layout.chunk_height,
+        planar,
+    );
+
+    if let Some(geo) = decoder.geo_info()? {
+        println!(
+            "  georeferenced: {} keys, {} tiepoints, pixel scale {:?}",
+            geo.keys.len(),
+            geo.tiepoints.len(),
+            geo.pixel_scale,
+        );
+    }
+
+    if tags {
+        let mut entries = decoder.tag_iter().collect::<Result<Vec<_>, _>>()?;
+        entries.sort_by_key(|(tag, _)| tag.to_u16());
+        println!("  tags:");
+        for (tag, value) in entries {
+            let mut value = format!("{:?}", value);
+            if value.len() > 100 {
+                let end = (0..=100)
+                    .rev()
+                    .find(|&end| value.is_char_boundary(end))
+                    .unwrap();
+                value.truncate(end);
+                value.push_str("...");
+            }
+            println!("    {:?}: {}", tag, value);
+        }
+    }
+
+    Ok(())
+}
+
+fn validate(args: &[String]) -> CommandResult {
+    let paths = parse_args(args, |option, _| {
+        usage(format!("unknown option `{}`", option))
+    })?;
+
+    let mut valid = true;
+    for path in paths {
+        valid &= validate_file(path)?;
+    }
+    Ok(valid)
+}
+
+fn validate_file(path: &str) -> CommandResult {
+    let options = DecoderOptions::new()
+        .with_validation(true)
+        .with_warnings(true);
+    let mut decoder = match open(path, options) {
+        Ok(decoder) => decoder,
+        Err(err) => {
+            println!("{}: {}", path, err);
+            return Ok(false);
+        }
+    };
+
+    let result = for_each_image(&mut decoder, |decoder, index| {
+        let mut valid = true;
+        match decoder.chunks() {
+            Ok(chunks) => {
+                for (chunk, result) in chunks.enumerate() {
+                    if let Err(err) = result {
+                        println!("{}: image {}, chunk {}: {}", path, index, chunk, err);
+                        valid = false;
+                    }
+                }
+            }
+            Err(err) => {
+                println!("{}: image {}: {}", path, index, err);
+                valid = false;
+            }
+        }
+        for warning in decoder.take_warnings() {
+            println!("{}: image {}: warning: {}", path, index, warning);
+        }
+        Ok(valid)
+    });
+
+    match result {
+        Ok(true) => {
+            println!("{}: ok", path);
+            Ok(true)
+        }
+        Ok(false) => Ok(false),
+        Err(err) => {
+            println!("{}: {}", path, err);
+            Ok(false)
+        }
+    }
+}
+
+/// The output settings of `convert`.
+struct ConvertOptions {
+    compression: Compression,
+    predictor: Predictor,
+    tile_size: Option<u32>,
+}
+
+fn convert(args: &[String]) -> CommandResult {
+    let mut options = ConvertOptions {
+        compression: Compression::Uncompressed,
+        predictor: Predictor::None,
+        tile_size: None,
+    };
+    let mut bigtiff = false;
+    let paths = parse_args(args, |option, args| {
+        match option {
+            "--compression" => {
+                options.compression = match value(option, args)? {
+                    "none" => Compression::Uncompressed,
+                    "lzw" => Compression::Lzw,
+                    "deflate" => Compression::Deflate(DeflateLevel::default()),
+                    "packbits" => Compression::Packbits,
+                    other => return usage(format!("unknown compression `{}`", other)),
+                }
+            }
+            "--predictor" => {
+                options.predictor = match value(option, args)? {
+                    "none" => Predictor::None,
+                    "horizontal" => Predictor::Horizontal,
+                    "float" => Predictor::FloatingPoint,
+                    other => return usage(format!("unknown predictor `{}`", other)),
+                }
+            }
+            "--tile" => match value(option, args)?.parse() {
+                Ok(size) => options.tile_size = Some(size),
+                Err(_) => return usage("the tile size must be a number"),
+            },
+            "--bigtiff" => bigtiff = true,
+            _ => return usage(format!("unknown option `{}`", option)),
+        }
+        Ok(())
+    })?;
+    let (input, output) = match paths[..] {
+        [input, output] => (input, output),
+        _ => return usage("convert takes an input and an output file"),
+    };
+    if options.tile_size.is_some() && options.predictor != Predictor::None {
+        return usage("predictors can only be used with strips");
+    }
+
+    let mut decoder = open(
+        input,
+        DecoderOptions::new().with_limits(Limits::unlimited()),
+    )?;
+    // BigTIFF is chosen up front if the decoded images come close to 4 GiB. The size saturates,
+    // images too large to count are certainly too large for a standard file.
+    let mut expected_size = 0;
+    for_each_image(&mut decoder, |decoder, _| {
+        let (width, height) = decoder.dimensions()?;
+        let image_size = decoder.pixel_format()?.image_size(width, height);
+        expected_size = image_size.saturating_add(expected_size);
+        Ok(true)
+    })?;
+    decoder.seek_to_image(0)?;
+
+    let file = File::create(output).map_err(|err| format!("{}: {}", output, err))?;
+    let writer = BufWriter::new(file);
+    let encoder = if bigtiff || decoder.is_bigtiff() {
+        AutoTiffEncoder::Big(TiffEncoder::new_big(writer)?)
+    } else {
+        TiffEncoder::new_auto(writer, expected_size)?
+    };
+    convert_images(&mut decoder, encoder, &options)
+}
+
+fn convert_images<R: Read + Seek, W: Write + Seek>(
+    decoder: &mut Decoder<R>,
+    encoder: AutoTiffEncoder<W>,
+    options: &ConvertOptions,
+) -> CommandResult {
+    let mut encoder = encoder
+        .with_compression(options.compression)
+        .with_predictor(options.predictor);
+    for_each_image(decoder, |decoder, index| {
+        let (width, height) = decoder.dimensions()?;
+        let color_type = decoder.colortype()?;
+        let mut format = decoder.pixel_format()?;
+        // The samples are decoded as listed by the color type, interleaved pixel by pixel.
+        format.planar_config = PlanarConfiguration::Chunky;
+        format.interpretation = match color_type {
+            ColorType::RGB(_) | ColorType::RGBA(_) => PhotometricInterpretation::RGB,
+            // Whole byte gray samples are decoded inverted to BlackIsZero.
+            ColorType::Gray(n) | ColorType::GrayA(n)
+                if n >= 8 && format.interpretation == PhotometricInterpretation::WhiteIsZero =>
+            {
+                PhotometricInterpretation::BlackIsZero
+            }
+            ColorType::Palette(_) => {
+                return Err(format!("image {}: palette images can not be converted", index).into())
+            }
+            _ => format.interpretation,
+        };
+
+        let data = decoder.read_image()?;
+        match options.tile_size {
+            Some(size) => {
+                let data = native_bytes(&data);
+                encoder.write_tiled_image_with_format(
+                    width,
+                    height,
+                    (size, size),
+                    &format,
+                    &data,
+                )?
+            }
+            None if options.predictor != Predictor::None => {
+                encoder.write_decoding_result(width, height, color_type, &data)?
+            }
+            None => {
+                encoder.write_image_with_format(width, height, &format, &native_bytes(&data))?
+            }
+        }
+        Ok(true)
+    })
+}
+
+/// Returns the samples of `data` as bytes in native byte order.
+fn native_bytes(data: &DecodingResult) -> Vec<u8> {
+    match data {
+        DecodingResult::U8(data) => data.clone(),
+        DecodingResult::I8(data) => data.iter().map(|&sample| sample as u8).collect(),
+        DecodingResult::U16(data) => data
+            .iter()
+            .flat_map(|sample| sample.to_ne_bytes())
+            .collect(),
+        DecodingResult::I16(data) => data
+            .iter()
+            .flat_map(|sample| sample.to_ne_bytes())
+            .collect(),
+        DecodingResult::U32(data) => data
+            .iter()
+            .flat_map(|sample| sample.to_ne_bytes())
+            .collect(),
+        DecodingResult::I32(data) => data
+            .iter()
+            .flat_map(|sample| sample.to_ne_bytes())
+            .collect(),
+        DecodingResult::U64(data) => data
+            .iter()
+            .flat_map(|sample| sample.to_ne_bytes())
+            .collect(),
+        DecodingResult::I64(data) => data
+            .iter()
+            .flat_map(|sample| sample.to_ne_bytes())
+            .collect(),
+        DecodingResult::F32(data) => data
+            .iter()
+            .flat_map(|sample| sample.to_ne_bytes())
+            .collect(),
+        DecodingResult::F64(data) => data
+            .iter()
+            .flat_map(|sample| sample.to_ne_bytes())
+            .collect(),
+    }
+}
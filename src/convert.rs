@@ -0,0 +1,105 @@
+//! Conversion of decoded images to the buffers of the `image` crate.
+//!
+//! This is only available with the `image-interop` feature.
+use std::io::{Read, Seek};
+
+use image::{DynamicImage, ImageBuffer, Luma, LumaA, Pixel, Rgb, Rgba};
+
+use crate::decoder::{Decoder, DecodingResult, SampleConversion};
+use crate::{ColorType, TiffError, TiffFormatError, TiffResult, TiffUnsupportedError};
+
+/// Converts the samples of a decoded image to a [`DynamicImage`].
+///
+/// Gray, gray with alpha, RGB and RGBA images of `u8`, `u16`, `f32` and `f64` samples are
+/// supported. Gray floating point images become RGB, as `image` has no floating point gray
+/// buffers, and `f64` samples are narrowed to `f32`. The samples are taken as they are, so the
+/// photometric interpretation of e.g. `WhiteIsZero` images is not applied.
+///
+/// Images of less than 8 bits per sample must be unpacked with
+/// [`Decoder::read_image_unpacked`] first, packed samples fail with
+/// [`TiffFormatError::InconsistentSizesEncountered`].
+pub fn to_dynamic_image(
+    width: u32,
+    height: u32,
+    color_type: ColorType,
+    result: DecodingResult,
+) -> TiffResult<DynamicImage> {
+    let image = match (color_type, result) {
+        (ColorType::Gray(_), DecodingResult::U8(buf)) => {
+            DynamicImage::ImageLuma8(buffer::<Luma<u8>>(width, height, buf)?)
+        }
+        (ColorType::Gray(_), DecodingResult::U16(buf)) => {
+            DynamicImage::ImageLuma16(buffer::<Luma<u16>>(width, height, buf)?)
+        }
+        (ColorType::GrayA(_), DecodingResult::U8(buf)) => {
+            DynamicImage::ImageLumaA8(buffer::<LumaA<u8>>(width, height, buf)?)
+        }
+        (ColorType::GrayA(_), DecodingResult::U16(buf)) => {
+            DynamicImage::ImageLumaA16(buffer::<LumaA<u16>>(width, height, buf)?)
+        }
+        (ColorType::RGB(_), DecodingResult::U8(buf)) => {
+            DynamicImage::ImageRgb8(buffer::<Rgb<u8>>(width, height, buf)?)
+        }
+        (ColorType::RGB(_), DecodingResult::U16(buf)) => {
+            DynamicImage::ImageRgb16(buffer::<Rgb<u16>>(width, height, buf)?)
+        }
+        (ColorType::RGBA(_), DecodingResult::U8(buf)) => {
+            DynamicImage::ImageRgba8(buffer::<Rgba<u8>>(width, height, buf)?)
+        }
+        (ColorType::RGBA(_), DecodingResult::U16(buf)) => {
+            DynamicImage::ImageRgba16(buffer::<Rgba<u16>>(width, height, buf)?)
+        }
+        (ColorType::Gray(_), result @ (DecodingResult::F32(_) | DecodingResult::F64(_))) => {
+            let buf = float_samples(result)
+                .into_iter()
+                .flat_map(|gray| [gray; 3])
+                .collect();
+            DynamicImage::ImageRgb32F(buffer::<Rgb<f32>>(width, height, buf)?)
+        }
+        (ColorType::GrayA(_), result @ (DecodingResult::F32(_) | DecodingResult::F64(_))) => {
+            let buf = float_samples(result)
+                .chunks_exact(2)
+                .flat_map(|pixel| [pixel[0], pixel[0], pixel[0], pixel[1]])
+                .collect();
+            DynamicImage::ImageRgba32F(buffer::<Rgba<f32>>(width, height, buf)?)
+        }
+        (ColorType::RGB(_), result @ (DecodingResult::F32(_) | DecodingResult::F64(_))) => {
+            let buf = float_samples(result);
+            DynamicImage::ImageRgb32F(buffer::<Rgb<f32>>(width, height, buf)?)
+        }
+        (ColorType::RGBA(_), result @ (DecodingResult::F32(_) | DecodingResult::F64(_))) => {
+            let buf = float_samples(result);
+            DynamicImage::ImageRgba32F(buffer::<Rgba<f32>>(width, height, buf)?)
+        }
+        _ => return Err(TiffUnsupportedError::UnsupportedColorType(color_type).into()),
+    };
+
+    Ok(image)
+}
+
+/// Decodes the current image of `decoder` to a [`DynamicImage`], see [`to_dynamic_image`].
+///
+/// Images of less than 8 bits per sample are scaled to 8 bits.
+pub fn read_dynamic_image<R: Read + Seek>(decoder: &mut Decoder<R>) -> TiffResult<DynamicImage> {
+    let (width, height) = decoder.dimensions()?;
+    let color_type = decoder.colortype()?;
+    let result = decoder.read_image_unpacked(SampleConversion::Scale)?;
+    to_dynamic_image(width, height, color_type, result)
+}
+
+fn buffer<P: Pixel>(
+    width: u32,
+    height: u32,
+    samples: Vec<P::Subpixel>,
+) -> TiffResult<ImageBuffer<P, Vec<P::Subpixel>>> {
+    ImageBuffer::from_raw(width, height, samples).ok_or(TiffError::FormatError(
+        TiffFormatError::InconsistentSizesEncountered,
+    ))
+}
+
+fn float_samples(result: DecodingResult) -> Vec<f32> {
+    match result {
+        DecodingResult::F32(buf) => buf,
+        result => result.convert_to(SampleConversion::Saturate),
+    }
+}
@@ -0,0 +1,153 @@
+//! Names and units of the bands of multiband images, as stored by GIS and scientific writers.
+use std::io::{Read, Seek};
+
+use super::Decoder;
+use crate::tags::{ExtraSample, Tag};
+use crate::TiffResult;
+
+/// Descriptive metadata of a single band of an image, see [`Decoder::bands`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct BandInfo {
+    /// The meaning given by `ExtraSamples`, for the bands following the color samples.
+    pub extra_sample: Option<ExtraSample>,
+    /// The name or description of the band.
+    pub name: Option<String>,
+    /// The unit of the sample values.
+    pub unit: Option<String>,
+}
+
+/// The text tags of an image that writers store band metadata in.
+#[derive(Clone, Copy, Debug)]
+#[non_exhaustive]
+pub struct BandText<'a> {
+    /// The `ImageDescription`, which some writers fill with JSON or other structured text.
+    pub image_description: Option<&'a str>,
+    /// The XML of the `GdalMetadata` tag.
+    pub gdal_metadata: Option<&'a str>,
+}
+
+/// Parses band metadata from the text tags of an image.
+///
+/// Implement this for metadata of a writer not covered by [`GdalMetadataParser`] and pass it to
+/// [`Decoder::bands_with`].
+pub trait BandMetadataParser {
+    /// Fills in the metadata found in `text`, with one entry of `bands` per sample of the image.
+    ///
+    /// Entries that are already filled in may be overwritten.
+    fn parse(&self, text: &BandText<'_>, bands: &mut [BandInfo]) -> TiffResult<()>;
+}
+
+/// Parses the band descriptions and unit types GDAL writes to the `GdalMetadata` tag.
+///
+/// These are the `Item` elements with a `sample` attribute and the role `description` or
+/// `unittype`. Other items, such as statistics and the metadata of the dataset, are skipped.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct GdalMetadataParser;
+
+impl BandMetadataParser for GdalMetadataParser {
+    fn parse(&self, text: &BandText<'_>, bands: &mut [BandInfo]) -> TiffResult<()> {
+        let mut xml = match text.gdal_metadata {
+            Some(xml) => xml,
+            None => return Ok(()),
+        };
+
+        while let Some(start) = xml.find("<Item") {
+            xml = &xml[start + "<Item".len()..];
+            let (attributes, rest) = match xml.split_once('>') {
+                Some(split) => split,
+                None => break,
+            };
+            let (content, rest) = match rest.split_once("</Item>") {
+                Some(split) => split,
+                None => break,
+            };
+            xml = rest;
+
+            let band = attribute(attributes, "sample")
+                .and_then(|sample| sample.parse::<usize>().ok())
+                .and_then(|sample| bands.get_mut(sample));
+            let band = match band {
+                Some(band) => band,
+                None => continue,
+            };
+            match attribute(attributes, "role") {
+                Some("description") => band.name = Some(unescape(content)),
+                Some("unittype") => band.unit = Some(unescape(content)),
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the value of the attribute `name` in the attributes of an XML element.
+fn attribute<'a>(attributes: &'a str, name: &str) -> Option<&'a str> {
+    let mut rest = attributes;
+    while let Some(start) = rest.find(name) {
+        let preceded_by_space = rest[..start].ends_with(char::is_whitespace);
+        rest = &rest[start + name.len()..];
+        let value = match rest.trim_start().strip_prefix('=') {
+            Some(value) if preceded_by_space => value.trim_start(),
+            _ => continue,
+        };
+        let quote = value.chars().next()?;
+        if quote != '"' && quote != '\'' {
+            return None;
+        }
+        return value[1..].split(quote).next();
+    }
+
+    None
+}
+
+/// Replaces the predefined XML entities.
+fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+impl<R: Read + Seek> Decoder<R> {
+    /// Returns the metadata of every band of the current image, parsed by [`GdalMetadataParser`].
+    ///
+    /// There is one entry per sample. The bands following the color samples carry the meaning
+    /// given by the `ExtraSamples` tag.
+    pub fn bands(&mut self) -> TiffResult<Vec<BandInfo>> {
+        self.bands_with(&GdalMetadataParser)
+    }
+
+    /// Returns the metadata of every band of the current image, parsed by `parser`.
+    ///
+    /// This is like [`bands`][Decoder::bands] for metadata stored by other writers, e.g. as JSON
+    /// in the `ImageDescription`.
+    pub fn bands_with(&mut self, parser: &dyn BandMetadataParser) -> TiffResult<Vec<BandInfo>> {
+        let extra_samples = self.pixel_format()?.extra_samples;
+        let mut bands = vec![BandInfo::default(); usize::from(self.image().samples)];
+        let first_extra = bands.len().saturating_sub(extra_samples.len());
+        for (band, extra_sample) in bands[first_extra..].iter_mut().zip(extra_samples) {
+            band.extra_sample = Some(extra_sample);
+        }
+
+        let image_description = self.find_text_tag(Tag::ImageDescription)?;
+        let gdal_metadata = self.find_text_tag(Tag::GdalMetadata)?;
+        let text = BandText {
+            image_description: image_description.as_deref(),
+            gdal_metadata: gdal_metadata.as_deref(),
+        };
+        parser.parse(&text, &mut bands)?;
+
+        Ok(bands)
+    }
+
+    /// Reads an ASCII tag, skipping it if it holds another type.
+    fn find_text_tag(&mut self, tag: Tag) -> TiffResult<Option<String>> {
+        Ok(match self.find_tag(tag)? {
+            Some(super::ifd::Value::Ascii(text)) => Some(text),
+            _ => None,
+        })
+    }
+}
@@ -0,0 +1,80 @@
+//! Lazy iteration over the decoded chunks of an image.
+use std::io::{Read, Seek};
+use std::iter::FusedIterator;
+
+use super::{ChunkIndex, ChunkType, Decoder, DecodingResult};
+use crate::TiffResult;
+
+/// An iterator over the decoded strips or tiles of the current image, see [`Decoder::chunks`].
+///
+/// Each chunk is decoded only when it is reached, so a single chunk is held in memory at a time
+/// and [`Limits::decoding_buffer_size`][super::Limits::decoding_buffer_size] needs to fit a chunk
+/// rather than the whole image. A chunk that fails to decode yields an error, and the iteration
+/// continues with the next chunk.
+#[derive(Debug)]
+pub struct Chunks<'a, R: Read + Seek> {
+    decoder: &'a mut Decoder<R>,
+    next: u32,
+    end: u32,
+}
+
+impl<R: Read + Seek> Iterator for Chunks<'_, R> {
+    type Item = TiffResult<(ChunkIndex, DecodingResult)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next >= self.end {
+            return None;
+        }
+
+        let index = ChunkIndex(self.next);
+        self.next += 1;
+        Some(
+            self.decoder
+                .read_chunk_at(index)
+                .map(|chunk| (index, chunk)),
+        )
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = (self.end - self.next) as usize;
+        (remaining, Some(remaining))
+    }
+}
+
+impl<R: Read + Seek> ExactSizeIterator for Chunks<'_, R> {}
+
+impl<R: Read + Seek> FusedIterator for Chunks<'_, R> {}
+
+impl<R: Read + Seek> Decoder<R> {
+    /// Returns an iterator decoding the chunks of the current image one after another, in the
+    /// order of their indices.
+    ///
+    /// The chunks are laid out as reported by [`chunk_layout`][Decoder::chunk_layout].
+    pub fn chunks(&mut self) -> TiffResult<Chunks<'_, R>> {
+        let layout = self.chunk_layout()?;
+        let end = u64::from(layout.chunks_per_plane()) * u64::from(layout.planes);
+        Ok(Chunks {
+            decoder: self,
+            next: 0,
+            end: u32::try_from(end)?,
+        })
+    }
+
+    /// Returns an iterator decoding the strips of the current image, see [`Decoder::chunks`].
+    ///
+    /// Fails with [`UsageError::InvalidChunkType`][crate::UsageError::InvalidChunkType] if the
+    /// image is tiled.
+    pub fn strips(&mut self) -> TiffResult<Chunks<'_, R>> {
+        self.check_chunk_type(ChunkType::Strip)?;
+        self.chunks()
+    }
+
+    /// Returns an iterator decoding the tiles of the current image, see [`Decoder::chunks`].
+    ///
+    /// Fails with [`UsageError::InvalidChunkType`][crate::UsageError::InvalidChunkType] if the
+    /// image is divided into strips.
+    pub fn tiles(&mut self) -> TiffResult<Chunks<'_, R>> {
+        self.check_chunk_type(ChunkType::Tile)?;
+        self.chunks()
+    }
+}
@@ -0,0 +1,56 @@
+//! Reading the EXIF and GPS private directories of an image.
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::io::{Read, Seek};
+
+use super::ifd::Value;
+use super::Decoder;
+use crate::tags::{ExifTag, GpsTag, Tag};
+use crate::{TiffError, TiffFormatError, TiffResult};
+
+impl<R: Read + Seek> Decoder<R> {
+    /// Reads the EXIF directory of the current image, which holds the camera metadata.
+    ///
+    /// Returns `None` if the image has no `ExifDirectory` tag. The values are decoded within the
+    /// decoding limits, tags unknown to [`ExifTag`] are kept as [`ExifTag::Unknown`].
+    pub fn exif(&mut self) -> TiffResult<Option<HashMap<ExifTag, Value>>> {
+        self.read_private_directory(Tag::ExifDirectory, ExifTag::from_u16_exhaustive)
+    }
+
+    /// Reads the GPS directory of the current image, which holds the location it was taken at.
+    ///
+    /// Returns `None` if the image has no `GpsDirectory` tag, see [`Decoder::exif`].
+    pub fn gps(&mut self) -> TiffResult<Option<HashMap<GpsTag, Value>>> {
+        self.read_private_directory(Tag::GpsDirectory, GpsTag::from_u16_exhaustive)
+    }
+
+    /// Reads the values of the directory that `pointer` of the current image points to, with the
+    /// tags converted by `to_tag`.
+    fn read_private_directory<T: Eq + Hash>(
+        &mut self,
+        pointer: Tag,
+        to_tag: fn(u16) -> T,
+    ) -> TiffResult<Option<HashMap<T, Value>>> {
+        let offset = match self.find_tag(pointer)? {
+            Some(value) => match value.into_ifd_vec()?[..] {
+                [offset] => offset,
+                _ => {
+                    return Err(TiffError::FormatError(
+                        TiffFormatError::InvalidTagValueType(pointer),
+                    ))
+                }
+            },
+            None => return Ok(None),
+        };
+
+        let (directory, _) = self.read_ifd_at(offset)?;
+        directory
+            .into_iter()
+            .map(|(tag, entry)| {
+                let value = entry.tag_val(tag, &self.limits, self.bigtiff, &mut self.reader)?;
+                Ok((to_tag(tag.to_u16()), value))
+            })
+            .collect::<TiffResult<_>>()
+            .map(Some)
+    }
+}
@@ -1,13 +1,11 @@
 //! Function for reading TIFF tags
 
-use std::collections::HashMap;
 use std::io::{self, Read, Seek};
-use std::mem;
-use std::str;
+use std::{iter, mem, ops, slice, str, vec};
 
 use super::stream::{ByteOrder, EndianReader, SmartReader};
 use crate::tags::{Tag, Type};
-use crate::{TiffError, TiffFormatError, TiffResult};
+use crate::{OffsetSource, TiffError, TiffFormatError, TiffResult};
 
 use self::Value::{
     Ascii, Byte, Double, Float, Ifd, IfdBig, List, Rational, RationalBig, SRational, SRationalBig,
@@ -16,6 +14,7 @@ use self::Value::{
 
 #[allow(unused_qualifications)]
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[non_exhaustive]
 pub enum Value {
     Byte(u8),
@@ -163,6 +162,7 @@ impl Value {
                 }
                 Ok(new_vec)
             }
+            Short(val) => Ok(vec![val.into()]),
             Unsigned(val) => Ok(vec![val]),
             UnsignedBig(val) => Ok(vec![u32::try_from(val)?]),
             Rational(numerator, denominator) => Ok(vec![numerator, denominator]),
@@ -260,22 +260,47 @@ impl Value {
         }
     }
 
+    /// Converts `DOUBLE` values, or `RATIONAL` and `SRATIONAL` values by dividing their numerator
+    /// by their denominator.
+    ///
+    /// Fractions with a zero denominator are rejected.
     pub fn into_f64_vec(self) -> TiffResult<Vec<f64>> {
         match self {
             List(vec) => {
                 let mut new_vec = Vec::with_capacity(vec.len());
                 for v in vec {
-                    new_vec.push(v.into_f64()?)
+                    match v.fraction() {
+                        Some(val) => new_vec.push(val),
+                        None => new_vec.push(v.into_f64()?),
+                    }
                 }
                 Ok(new_vec)
             }
             Double(val) => Ok(vec![val]),
-            val => Err(TiffError::FormatError(
-                TiffFormatError::UnsignedIntegerExpected(val),
-            )),
+            val => match val.fraction() {
+                Some(val) => Ok(vec![val]),
+                None => Err(TiffError::FormatError(
+                    TiffFormatError::UnsignedIntegerExpected(val),
+                )),
+            },
         }
     }
 
+    /// The quotient of a rational value, if it is one with a denominator other than zero.
+    fn fraction(&self) -> Option<f64> {
+        let (numerator, denominator) = match *self {
+            Rational(n, d) => (f64::from(n), f64::from(d)),
+            RationalBig(n, d) => (n as f64, d as f64),
+            SRational(n, d) => (f64::from(n), f64::from(d)),
+            SRationalBig(n, d) => (n as f64, d as f64),
+            _ => return None,
+        };
+        if denominator == 0.0 {
+            return None;
+        }
+        Some(numerator / denominator)
+    }
+
     pub fn into_u64_vec(self) -> TiffResult<Vec<u64>> {
         match self {
             List(vec) => {
@@ -285,6 +310,7 @@ impl Value {
                 }
                 Ok(new_vec)
             }
+            Short(val) => Ok(vec![val.into()]),
             Unsigned(val) => Ok(vec![val.into()]),
             UnsignedBig(val) => Ok(vec![val]),
             Rational(numerator, denominator) => Ok(vec![numerator.into(), denominator.into()]),
@@ -298,6 +324,28 @@ impl Value {
         }
     }
 
+    /// Converts the value of a directory pointer such as `SubIFDs` to the offsets it points to.
+    ///
+    /// Besides the `IFD` and `IFD8` types, this accepts offsets written as `LONG` or `LONG8`.
+    pub fn into_ifd_vec(self) -> TiffResult<Vec<u64>> {
+        match self {
+            List(vec) => {
+                let mut new_vec = Vec::with_capacity(vec.len());
+                for v in vec {
+                    new_vec.push(v.into_u64()?)
+                }
+                Ok(new_vec)
+            }
+            Unsigned(val) => Ok(vec![val.into()]),
+            UnsignedBig(val) => Ok(vec![val]),
+            Ifd(val) => Ok(vec![val.into()]),
+            IfdBig(val) => Ok(vec![val]),
+            val => Err(TiffError::FormatError(
+                TiffFormatError::UnsignedIntegerExpected(val),
+            )),
+        }
+    }
+
     pub fn into_i64_vec(self) -> TiffResult<Vec<i64>> {
         match self {
             List(vec) => {
@@ -348,9 +396,9 @@ impl ::std::fmt::Debug for Entry {
 
 impl Entry {
     pub fn new(type_: Type, count: u32, offset: [u8; 4]) -> Entry {
-        let mut offset = offset.to_vec();
-        offset.append(&mut vec![0; 4]);
-        Entry::new_u64(type_, count.into(), offset[..].try_into().unwrap())
+        let mut offset_u64 = [0; 8];
+        offset_u64[..4].copy_from_slice(&offset);
+        Entry::new_u64(type_, count.into(), offset_u64)
     }
 
     pub fn new_u64(type_: Type, count: u64, offset: [u8; 8]) -> Entry {
@@ -361,9 +409,134 @@ impl Entry {
         }
     }
 
+    /// The field type of the entry's values.
+    pub fn field_type(&self) -> Type {
+        self.type_
+    }
+
+    /// The number of values in the entry.
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// The size in bytes of a single value of the entry.
+    fn tag_size(&self) -> u64 {
+        match self.type_ {
+            Type::BYTE | Type::SBYTE | Type::ASCII | Type::UNDEFINED => 1,
+            Type::SHORT | Type::SSHORT => 2,
+            Type::LONG | Type::SLONG | Type::FLOAT | Type::IFD => 4,
+            Type::LONG8
+            | Type::SLONG8
+            | Type::DOUBLE
+            | Type::RATIONAL
+            | Type::SRATIONAL
+            | Type::IFD8 => 8,
+        }
+    }
+
+    /// The size in bytes of all values of the entry, as stored in the file.
+    pub(crate) fn value_bytes(&self) -> u64 {
+        self.count.saturating_mul(self.tag_size())
+    }
+
     /// Returns a mem_reader for the offset/value field
-    fn r(&self, byte_order: ByteOrder) -> SmartReader<io::Cursor<Vec<u8>>> {
-        SmartReader::wrap(io::Cursor::new(self.offset.to_vec()), byte_order)
+    fn r(&self, byte_order: ByteOrder) -> SmartReader<io::Cursor<[u8; 8]>> {
+        SmartReader::wrap(io::Cursor::new(self.offset), byte_order)
+    }
+
+    /// Decodes a single value stored directly in the offset/value field.
+    ///
+    /// This neither allocates nor touches the file. Returns `None` if the entry does not contain
+    /// exactly one value, if the value is stored elsewhere in the file or if it is an ASCII value.
+    pub(crate) fn inline_val(&self, byte_order: ByteOrder, bigtiff: bool) -> Option<Value> {
+        if self.count != 1 {
+            return None;
+        }
+
+        let bo = byte_order;
+        Some(match self.type_ {
+            Type::BYTE => Unsigned(u32::from(self.offset[0])),
+            Type::SBYTE => Signed(i32::from(self.offset[0] as i8)),
+            Type::UNDEFINED => Byte(self.offset[0]),
+            Type::SHORT => Unsigned(u32::from(self.r(bo).read_u16().ok()?)),
+            Type::SSHORT => Signed(i32::from(self.r(bo).read_i16().ok()?)),
+            Type::LONG => Unsigned(self.r(bo).read_u32().ok()?),
+            Type::SLONG => Signed(self.r(bo).read_i32().ok()?),
+            Type::FLOAT => Float(self.r(bo).read_f32().ok()?),
+            Type::IFD => Ifd(self.r(bo).read_u32().ok()?),
+            // The value is 5-8 bytes and only fits into the field in BigTiff mode.
+            Type::LONG8 if bigtiff => UnsignedBig(self.r(bo).read_u64().ok()?),
+            Type::SLONG8 if bigtiff => SignedBig(self.r(bo).read_i64().ok()?),
+            Type::DOUBLE if bigtiff => Double(self.r(bo).read_f64().ok()?),
+            Type::RATIONAL if bigtiff => {
+                let mut r = self.r(bo);
+                Rational(r.read_u32().ok()?, r.read_u32().ok()?)
+            }
+            Type::SRATIONAL if bigtiff => {
+                let mut r = self.r(bo);
+                SRational(r.read_i32().ok()?, r.read_i32().ok()?)
+            }
+            Type::IFD8 if bigtiff => IfdBig(self.r(bo).read_u64().ok()?),
+            Type::ASCII
+            | Type::LONG8
+            | Type::SLONG8
+            | Type::DOUBLE
+            | Type::RATIONAL
+            | Type::SRATIONAL
+            | Type::IFD8 => return None,
+        })
+    }
+
+    /// The location of the values of the entry, or `None` if they are stored in the offset field.
+    pub(crate) fn value_offset(&self, byte_order: ByteOrder, bigtiff: bool) -> Option<u64> {
+        let value_bytes = self.value_bytes();
+        if bigtiff {
+            if value_bytes <= 8 {
+                return None;
+            }
+            self.r(byte_order).read_u64().ok()
+        } else {
+            if value_bytes <= 4 {
+                return None;
+            }
+            self.r(byte_order).read_u32().ok().map(u64::from)
+        }
+    }
+
+    /// Checks that the values of the entry, if not stored in the offset field, lie within the
+    /// stream.
+    pub(crate) fn check_value_range<R: Read + Seek>(
+        &self,
+        tag: Tag,
+        bigtiff: bool,
+        reader: &SmartReader<R>,
+    ) -> TiffResult<()> {
+        match self.value_offset(reader.byte_order(), bigtiff) {
+            Some(offset) => reader.check_range(offset, self.value_bytes(), OffsetSource::Tag(tag)),
+            None => Ok(()),
+        }
+    }
+
+    /// Decodes the values of the entry with `tag`, see [`Entry::val`].
+    ///
+    /// Unlike `val` this names the tag if its values lie beyond the end of the stream. The range
+    /// is only checked once reading failed, so that exceeding the limits is reported first.
+    pub(crate) fn tag_val<R: Read + Seek>(
+        &self,
+        tag: Tag,
+        limits: &super::Limits,
+        bigtiff: bool,
+        reader: &mut SmartReader<R>,
+    ) -> TiffResult<Value> {
+        self.val(limits, bigtiff, reader).map_err(|err| match err {
+            TiffError::IoError(ref io_err) if io_err.kind() == io::ErrorKind::UnexpectedEof => {
+                match self.check_value_range(tag, bigtiff, reader) {
+                    Err(range_err) => range_err,
+                    Ok(()) => err,
+                }
+            }
+            err => err,
+        })
     }
 
     pub fn val<R: Read + Seek>(
@@ -379,19 +552,7 @@ impl Entry {
 
         let bo = reader.byte_order();
 
-        let tag_size = match self.type_ {
-            Type::BYTE | Type::SBYTE | Type::ASCII | Type::UNDEFINED => 1,
-            Type::SHORT | Type::SSHORT => 2,
-            Type::LONG | Type::SLONG | Type::FLOAT | Type::IFD => 4,
-            Type::LONG8
-            | Type::SLONG8
-            | Type::DOUBLE
-            | Type::RATIONAL
-            | Type::SRATIONAL
-            | Type::IFD8 => 8,
-        };
-
-        let value_bytes = match self.count.checked_mul(tag_size) {
+        let value_bytes = match self.count.checked_mul(self.tag_size()) {
             Some(n) => n,
             None => {
                 return Err(TiffError::LimitsExceeded);
@@ -400,44 +561,13 @@ impl Entry {
 
         // Case 2: there is one value.
         if self.count == 1 {
-            // 2a: the value is 5-8 bytes and we're in BigTiff mode.
-            if bigtiff && value_bytes > 4 && value_bytes <= 8 {
-                return Ok(match self.type_ {
-                    Type::LONG8 => UnsignedBig(self.r(bo).read_u64()?),
-                    Type::SLONG8 => SignedBig(self.r(bo).read_i64()?),
-                    Type::DOUBLE => Double(self.r(bo).read_f64()?),
-                    Type::RATIONAL => {
-                        let mut r = self.r(bo);
-                        Rational(r.read_u32()?, r.read_u32()?)
-                    }
-                    Type::SRATIONAL => {
-                        let mut r = self.r(bo);
-                        SRational(r.read_i32()?, r.read_i32()?)
-                    }
-                    Type::IFD8 => IfdBig(self.r(bo).read_u64()?),
-                    Type::BYTE
-                    | Type::SBYTE
-                    | Type::ASCII
-                    | Type::UNDEFINED
-                    | Type::SHORT
-                    | Type::SSHORT
-                    | Type::LONG
-                    | Type::SLONG
-                    | Type::FLOAT
-                    | Type::IFD => unreachable!(),
-                });
+            // 2a: the value fits in the offset field.
+            if let Some(value) = self.inline_val(bo, bigtiff) {
+                return Ok(value);
             }
 
-            // 2b: the value is at most 4 bytes or doesn't fit in the offset field.
+            // 2b: the value doesn't fit in the offset field.
             return Ok(match self.type_ {
-                Type::BYTE => Unsigned(u32::from(self.offset[0])),
-                Type::SBYTE => Signed(i32::from(self.offset[0] as i8)),
-                Type::UNDEFINED => Byte(self.offset[0]),
-                Type::SHORT => Unsigned(u32::from(self.r(bo).read_u16()?)),
-                Type::SSHORT => Signed(i32::from(self.r(bo).read_i16()?)),
-                Type::LONG => Unsigned(self.r(bo).read_u32()?),
-                Type::SLONG => Signed(self.r(bo).read_i32()?),
-                Type::FLOAT => Float(self.r(bo).read_f32()?),
                 Type::ASCII => {
                     if self.offset[0] == 0 {
                         Ascii("".to_string())
@@ -465,11 +595,19 @@ impl Entry {
                     reader.goto_offset(self.r(bo).read_u32()?.into())?;
                     SRational(reader.read_i32()?, reader.read_i32()?)
                 }
-                Type::IFD => Ifd(self.r(bo).read_u32()?),
                 Type::IFD8 => {
                     reader.goto_offset(self.r(bo).read_u32()?.into())?;
                     IfdBig(reader.read_u64()?)
                 }
+                Type::BYTE
+                | Type::SBYTE
+                | Type::UNDEFINED
+                | Type::SHORT
+                | Type::SSHORT
+                | Type::LONG
+                | Type::SLONG
+                | Type::FLOAT
+                | Type::IFD => unreachable!(),
             });
         }
 
@@ -557,6 +695,10 @@ impl Entry {
         }
 
         // Case 4: there is more than one value, and it doesn't fit in the offset field.
+        if !bigtiff {
+            check_classic_range(self.r(bo).read_u32()?.into(), value_bytes)?;
+        }
+
         match self.type_ {
             // TODO check if this could give wrong results
             // at a different endianess of file/computer.
@@ -638,6 +780,42 @@ impl Entry {
         }
     }
 
+    /// Reads the values of the entry as raw bytes, in the byte order of the file.
+    ///
+    /// Unlike [`Entry::val`] this does not create a `Value` for every byte, which matters for
+    /// large binary blobs.
+    pub(crate) fn bytes_val<R: Read + Seek>(
+        &self,
+        tag: Tag,
+        limits: &super::Limits,
+        bigtiff: bool,
+        reader: &mut SmartReader<R>,
+    ) -> TiffResult<Vec<u8>> {
+        let bo = reader.byte_order();
+        let value_bytes = self.value_bytes();
+        let len = usize::try_from(value_bytes)?;
+        if len > limits.decoding_buffer_size {
+            return Err(TiffError::LimitsExceeded);
+        }
+
+        if len <= 4 || bigtiff && len <= 8 {
+            return Ok(self.offset[..len].to_vec());
+        }
+
+        let offset = if bigtiff {
+            self.r(bo).read_u64()?
+        } else {
+            let offset = self.r(bo).read_u32()?.into();
+            check_classic_range(offset, value_bytes)?;
+            offset
+        };
+        reader.goto_range(offset, value_bytes, OffsetSource::Tag(tag))?;
+
+        let mut bytes = vec![0; len];
+        reader.read_exact(&mut bytes)?;
+        Ok(bytes)
+    }
+
     #[inline]
     fn decode_offset<R, F>(
         &self,
@@ -673,6 +851,19 @@ impl Entry {
     }
 }
 
+/// Checks that data lies within the 4 GiB addressable by a classic (non-BigTIFF) file.
+///
+/// Data extending beyond that limit can only stem from an offset or size that wrapped around
+/// when the file was written, so reading at the stated offset would produce garbage.
+pub(crate) fn check_classic_range(offset: u64, length: u64) -> TiffResult<()> {
+    match offset.checked_add(length) {
+        Some(end) if end <= 1 << 32 => Ok(()),
+        _ => Err(TiffError::FormatError(
+            TiffFormatError::OffsetBeyondClassicTiff(offset, length),
+        )),
+    }
+}
+
 /// Extracts a list of BYTE tags stored in an offset
 #[inline]
 fn offset_to_bytes(n: usize, entry: &Entry) -> TiffResult<Value> {
@@ -696,4 +887,180 @@ fn offset_to_sbytes(n: usize, entry: &Entry) -> TiffResult<Value> {
 }
 
 /// Type representing an Image File Directory
-pub type Directory = HashMap<Tag, Entry>;
+///
+/// The entries are kept in ascending tag order, whatever order they are stored in by the file, so
+/// iterating over them neither sorts nor allocates. The presence of the baseline tags, which the
+/// decoder checks for every image, is also kept as a bitset.
+#[derive(Clone, Debug, Default)]
+pub struct Directory {
+    /// The entries, sorted by tag.
+    entries: Vec<(Tag, Entry)>,
+    /// Bit `n` is set if the directory holds the tag `PRESENCE_START + n`.
+    present: u128,
+}
+
+/// The first tag tracked by the presence bitset, `NewSubfileType`. The bitset covers all baseline
+/// tags besides `Copyright`, as well as the tile and `SampleFormat` extension tags.
+const PRESENCE_START: u16 = 254;
+
+impl Directory {
+    /// Create an empty directory.
+    pub fn new() -> Directory {
+        Directory::default()
+    }
+
+    /// The bit of `tag` in the presence bitset, if it is tracked.
+    fn presence_bit(tag: Tag) -> Option<u128> {
+        match tag.to_u16().checked_sub(PRESENCE_START) {
+            Some(n) if n < 128 => Some(1 << n),
+            _ => None,
+        }
+    }
+
+    fn position(&self, tag: Tag) -> Result<usize, usize> {
+        self.entries
+            .binary_search_by_key(&tag.to_u16(), |(tag, _)| tag.to_u16())
+    }
+
+    /// The number of entries.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether the directory has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Whether the directory holds an entry for `tag`.
+    pub fn contains_key(&self, tag: &Tag) -> bool {
+        match Self::presence_bit(*tag) {
+            Some(bit) => self.present & bit != 0,
+            None => self.position(*tag).is_ok(),
+        }
+    }
+
+    /// Returns the entry of `tag`, if the directory has one.
+    pub fn get(&self, tag: &Tag) -> Option<&Entry> {
+        if Self::presence_bit(*tag).map_or(false, |bit| self.present & bit == 0) {
+            return None;
+        }
+
+        self.position(*tag).ok().map(|index| &self.entries[index].1)
+    }
+
+    /// Inserts the entry of `tag`, returning the entry it replaces.
+    pub fn insert(&mut self, tag: Tag, entry: Entry) -> Option<Entry> {
+        if let Some(bit) = Self::presence_bit(tag) {
+            self.present |= bit;
+        }
+
+        // Entries are usually stored in ascending order and thus appended.
+        match self.entries.last() {
+            Some((last, _)) if last.to_u16() >= tag.to_u16() => {}
+            _ => {
+                self.entries.push((tag, entry));
+                return None;
+            }
+        }
+
+        match self.position(tag) {
+            Ok(index) => Some(mem::replace(&mut self.entries[index].1, entry)),
+            Err(index) => {
+                self.entries.insert(index, (tag, entry));
+                None
+            }
+        }
+    }
+
+    /// Returns a mutable reference to the entry of `tag`, if the directory has one.
+    pub fn get_mut(&mut self, tag: &Tag) -> Option<&mut Entry> {
+        match self.position(*tag) {
+            Ok(index) => Some(&mut self.entries[index].1),
+            Err(_) => None,
+        }
+    }
+
+    /// Removes the entry of `tag`, returning it if the directory had one.
+    pub fn remove(&mut self, tag: &Tag) -> Option<Entry> {
+        let index = self.position(*tag).ok()?;
+        if let Some(bit) = Self::presence_bit(*tag) {
+            self.present &= !bit;
+        }
+        Some(self.entries.remove(index).1)
+    }
+
+    /// Returns an iterator over the entries in ascending tag order.
+    pub fn iter(&self) -> DirectoryIter<'_> {
+        self.entries.iter().map(|(tag, entry)| (tag, entry))
+    }
+
+    /// Returns an iterator over the tags in ascending order.
+    pub fn keys(&self) -> impl DoubleEndedIterator<Item = &Tag> + ExactSizeIterator {
+        self.entries.iter().map(|(tag, _)| tag)
+    }
+
+    /// Returns an iterator over the entries in ascending tag order, without their tags.
+    pub fn values(&self) -> impl DoubleEndedIterator<Item = &Entry> + ExactSizeIterator {
+        self.entries.iter().map(|(_, entry)| entry)
+    }
+}
+
+/// An iterator over the entries of a [`Directory`] in ascending tag order.
+pub type DirectoryIter<'a> =
+    iter::Map<slice::Iter<'a, (Tag, Entry)>, fn(&'a (Tag, Entry)) -> (&'a Tag, &'a Entry)>;
+
+impl ops::Index<&Tag> for Directory {
+    type Output = Entry;
+
+    /// Returns the entry of `tag`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the directory has no entry for `tag`.
+    fn index(&self, tag: &Tag) -> &Entry {
+        self.get(tag).expect("tag not present in the directory")
+    }
+}
+
+impl FromIterator<(Tag, Entry)> for Directory {
+    /// Collects the entries, keeping the last entry of a tag that occurs more than once like
+    /// [`Directory::insert`] does.
+    fn from_iter<I: IntoIterator<Item = (Tag, Entry)>>(iter: I) -> Directory {
+        let mut entries: Vec<_> = iter.into_iter().collect();
+        // The sort is stable, so the last entry of a tag is also the last one of its run.
+        entries.sort_by_key(|(tag, _)| tag.to_u16());
+        entries.dedup_by(|later, earlier| {
+            if later.0 != earlier.0 {
+                return false;
+            }
+            mem::swap(later, earlier);
+            true
+        });
+
+        let present = entries
+            .iter()
+            .filter_map(|(tag, _)| Self::presence_bit(*tag))
+            .fold(0, |present, bit| present | bit);
+        Directory { entries, present }
+    }
+}
+
+impl IntoIterator for Directory {
+    type Item = (Tag, Entry);
+    type IntoIter = vec::IntoIter<(Tag, Entry)>;
+
+    /// Returns the entries in ascending tag order.
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Directory {
+    type Item = (&'a Tag, &'a Entry);
+    type IntoIter = DirectoryIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
@@ -1,21 +1,25 @@
 use super::ifd::{Directory, Value};
-use super::stream::{ByteOrder, DeflateReader, LZWReader, PackBitsReader};
+use super::stream::{
+    ByteOrder, CountingReader, DeflateReader, EndianReader, LZWReader, PackBitsReader,
+};
 use super::tag_reader::TagReader;
-use super::{predict_f32, predict_f64, Limits};
+use super::{predict_f16, predict_f32, predict_f64, Limits, LogLuvOutput};
 use super::{stream::SmartReader, ChunkType};
 use crate::tags::{
-    CompressionMethod, PhotometricInterpretation, PlanarConfiguration, Predictor, SampleFormat, Tag,
+    CompressionMethod, PhotometricInterpretation, PlanarConfiguration, Predictor, SampleFormat,
+    Tag, Type,
 };
 use crate::{ColorType, TiffError, TiffFormatError, TiffResult, TiffUnsupportedError, UsageError};
 use std::io::{self, Cursor, Read, Seek};
+use std::ops::Range;
 use std::sync::Arc;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(crate) struct StripDecodeState {
     pub rows_per_strip: u32,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 /// Computed values useful for tile decoding
 pub(crate) struct TileAttributes {
     pub image_width: usize,
@@ -39,6 +43,8 @@ impl TileAttributes {
         (self.tile_length - self.image_height % self.tile_length) % self.tile_length
     }
     pub fn get_padding(&self, tile: usize) -> (usize, usize) {
+        // The tiles of each plane of a planar image follow those of the previous plane.
+        let tile = tile % (self.tiles_across() * self.tiles_down());
         let row = tile / self.tiles_across();
         let column = tile % self.tiles_across();
 
@@ -58,9 +64,127 @@ impl TileAttributes {
     }
 }
 
-#[derive(Debug)]
+/// The offsets or byte counts of the chunks of an image.
+///
+/// Tables are read as a whole when the image is loaded, unless lazy chunk tables are enabled with
+/// [`DecoderOptions::with_lazy_chunk_tables`](super::DecoderOptions::with_lazy_chunk_tables).
+/// Then a table stored outside of its directory entry is left in the file, and the values of the
+/// chunks that are accessed are read from it on demand.
+#[derive(Debug, Clone)]
+pub(crate) enum ChunkTable {
+    Loaded(Arc<Vec<u64>>),
+    Lazy {
+        /// The location of the first value.
+        offset: u64,
+        field_type: Type,
+        len: usize,
+    },
+}
+
+impl ChunkTable {
+    /// The number of values read at once when walking over all chunks of an image.
+    pub(crate) const BATCH: usize = 4096;
+
+    /// Reads the table of `tag`, which must be in the directory.
+    fn read<R: Read + Seek>(
+        tag_reader: &mut TagReader<R>,
+        tag: Tag,
+        lazy: bool,
+    ) -> TiffResult<ChunkTable> {
+        if lazy {
+            let entry = &tag_reader.ifd[&tag];
+            let byte_order = tag_reader.reader.byte_order();
+            let offset = entry.value_offset(byte_order, tag_reader.bigtiff);
+            if let (Some(offset), Type::SHORT | Type::LONG | Type::LONG8) =
+                (offset, entry.field_type())
+            {
+                entry.check_value_range(tag, tag_reader.bigtiff, tag_reader.reader)?;
+                return Ok(ChunkTable::Lazy {
+                    offset,
+                    field_type: entry.field_type(),
+                    len: usize::try_from(entry.count())?,
+                });
+            }
+        }
+
+        let values = tag_reader.require_tag(tag)?.into_u64_vec()?;
+        Ok(ChunkTable::Loaded(Arc::new(values)))
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        match self {
+            ChunkTable::Loaded(values) => values.len(),
+            ChunkTable::Lazy { len, .. } => *len,
+        }
+    }
+
+    /// Returns the value of the chunk at `index`.
+    pub(crate) fn get<R: Read + Seek>(
+        &self,
+        index: usize,
+        reader: &mut SmartReader<R>,
+    ) -> TiffResult<u64> {
+        match self {
+            ChunkTable::Loaded(values) => values.get(index).copied().ok_or(TiffError::FormatError(
+                TiffFormatError::InconsistentSizesEncountered,
+            )),
+            ChunkTable::Lazy { .. } => Ok(self.values(index..index + 1, reader)?[0]),
+        }
+    }
+
+    /// Returns the values of the chunks in `range`, reading them with a single read if the table
+    /// is lazy.
+    pub(crate) fn values<R: Read + Seek>(
+        &self,
+        range: Range<usize>,
+        reader: &mut SmartReader<R>,
+    ) -> TiffResult<Vec<u64>> {
+        let inconsistent = TiffError::FormatError(TiffFormatError::InconsistentSizesEncountered);
+        let (offset, field_type, len) = match self {
+            ChunkTable::Loaded(values) => {
+                return values.get(range).map(<[u64]>::to_vec).ok_or(inconsistent)
+            }
+            ChunkTable::Lazy {
+                offset,
+                field_type,
+                len,
+            } => (*offset, *field_type, *len),
+        };
+        if range.start > range.end || range.end > len {
+            return Err(inconsistent);
+        }
+
+        let size = match field_type {
+            Type::SHORT => 2,
+            Type::LONG => 4,
+            _ => 8,
+        };
+        let mut bytes = vec![0; range.len() * size];
+        reader.goto_offset(offset + (range.start * size) as u64)?;
+        reader.read_exact(&mut bytes)?;
+
+        let byte_order = reader.byte_order();
+        Ok(bytes
+            .chunks_exact(size)
+            .map(|value| {
+                let mut reader = SmartReader::wrap(value, byte_order);
+                match field_type {
+                    Type::SHORT => reader.read_u16().map(u64::from),
+                    Type::LONG => reader.read_u32().map(u64::from),
+                    _ => reader.read_u64(),
+                }
+            })
+            .collect::<io::Result<_>>()?)
+    }
+}
+
+/// Decoding metadata of a single image.
+///
+/// The directory and chunk tables are reference counted so that cloning an `Image`, e.g. to hand
+/// it to worker threads decoding chunks in parallel, does not copy them.
+#[derive(Debug, Clone)]
 pub(crate) struct Image {
-    pub ifd: Option<Directory>,
+    pub ifd: Option<Arc<Directory>>,
     pub width: u32,
     pub height: u32,
     pub bits_per_sample: u8,
@@ -74,8 +198,9 @@ pub(crate) struct Image {
     pub planar_config: PlanarConfiguration,
     pub strip_decoder: Option<StripDecodeState>,
     pub tile_attributes: Option<TileAttributes>,
-    pub chunk_offsets: Vec<u64>,
-    pub chunk_bytes: Vec<u64>,
+    pub chunk_offsets: ChunkTable,
+    pub chunk_bytes: ChunkTable,
+    pub log_luv_output: LogLuvOutput,
 }
 
 impl Image {
@@ -84,35 +209,43 @@ impl Image {
         ifd: Directory,
         limits: &Limits,
         bigtiff: bool,
+        log_luv_output: LogLuvOutput,
+        lenient: bool,
+        lazy_chunk_tables: bool,
     ) -> TiffResult<Image> {
         let mut tag_reader = TagReader {
             reader,
             limits,
             ifd: &ifd,
             bigtiff,
+            lenient,
         };
 
         let width = tag_reader.require_tag(Tag::ImageWidth)?.into_u32()?;
         let height = tag_reader.require_tag(Tag::ImageLength)?.into_u32()?;
-        if width == 0 || height == 0 {
-            return Err(TiffError::FormatError(TiffFormatError::InvalidDimensions(
-                width, height,
-            )));
-        }
+        // An empty image holds no pixels, so its chunks are never read and need not be consistent
+        // with its dimensions.
+        let empty = width == 0 || height == 0;
 
-        let photometric_interpretation = tag_reader
+        let photometric_interpretation = match tag_reader
             .find_tag(Tag::PhotometricInterpretation)?
             .map(Value::into_u16)
             .transpose()?
-            .and_then(PhotometricInterpretation::from_u16)
-            .ok_or(TiffUnsupportedError::UnknownInterpretation)?;
+        {
+            Some(value) => PhotometricInterpretation::from_u16(value)
+                .ok_or(TiffUnsupportedError::UnknownInterpretation)?,
+            // Some minimal writers omit the tag, libtiff assumes black is zero for them.
+            None if lenient => PhotometricInterpretation::BlackIsZero,
+            None => return Err(TiffUnsupportedError::UnknownInterpretation.into()),
+        };
 
         // Try to parse both the compression method and the number, format, and bits of the included samples.
         // If they are not explicitly specified, those tags are reset to their default values and not carried from previous images.
-        let compression_method = match tag_reader.find_tag(Tag::Compression)? {
-            Some(val) => CompressionMethod::from_u16_exhaustive(val.into_u16()?),
-            None => CompressionMethod::None,
-        };
+        let compression_method = CompressionMethod::from_u16_exhaustive(
+            tag_reader
+                .require_tag_or_default(Tag::Compression)?
+                .into_u16()?,
+        );
 
         let jpeg_tables = if compression_method == CompressionMethod::ModernJPEG
             && ifd.contains_key(&Tag::JPEGTables)
@@ -133,10 +266,8 @@ impl Image {
         };
 
         let samples: u16 = tag_reader
-            .find_tag(Tag::SamplesPerPixel)?
-            .map(Value::into_u16)
-            .transpose()?
-            .unwrap_or(1);
+            .require_tag_or_default(Tag::SamplesPerPixel)?
+            .into_u16()?;
         if samples == 0 {
             return Err(TiffFormatError::SamplesPerPixelIsZero.into());
         }
@@ -176,6 +307,15 @@ impl Image {
             return Err(TiffUnsupportedError::InconsistentBitsPerSample(bits_per_sample).into());
         }
 
+        // SGI log encoded pixels always decode to floating point samples, whichever data format the
+        // writer declared.
+        let (bits_per_sample, sample_format) = match photometric_interpretation {
+            PhotometricInterpretation::LogL | PhotometricInterpretation::LogLuv => {
+                (32, SampleFormat::IEEEFP)
+            }
+            _ => (bits_per_sample[0], sample_format),
+        };
+
         let predictor = tag_reader
             .find_tag(Tag::Predictor)?
             .map(Value::into_u16)
@@ -188,16 +328,13 @@ impl Image {
             .unwrap_or(Predictor::None);
 
         let planar_config = tag_reader
-            .find_tag(Tag::PlanarConfiguration)?
-            .map(Value::into_u16)
-            .transpose()?
-            .map(|p| {
+            .require_tag_or_default(Tag::PlanarConfiguration)?
+            .into_u16()
+            .and_then(|p| {
                 PlanarConfiguration::from_u16(p).ok_or(TiffError::FormatError(
                     TiffFormatError::UnknownPlanarConfiguration(p),
                 ))
-            })
-            .transpose()?
-            .unwrap_or(PlanarConfiguration::Chunky);
+            })?;
 
         let planes = match planar_config {
             PlanarConfiguration::Chunky => 1,
@@ -218,26 +355,23 @@ impl Image {
             (true, true, false, false) => {
                 chunk_type = ChunkType::Strip;
 
-                chunk_offsets = tag_reader
-                    .find_tag(Tag::StripOffsets)?
-                    .unwrap()
-                    .into_u64_vec()?;
-                chunk_bytes = tag_reader
-                    .find_tag(Tag::StripByteCounts)?
-                    .unwrap()
-                    .into_u64_vec()?;
+                chunk_offsets =
+                    ChunkTable::read(&mut tag_reader, Tag::StripOffsets, lazy_chunk_tables)?;
+                chunk_bytes =
+                    ChunkTable::read(&mut tag_reader, Tag::StripByteCounts, lazy_chunk_tables)?;
                 let rows_per_strip = tag_reader
                     .find_tag(Tag::RowsPerStrip)?
                     .map(Value::into_u32)
                     .transpose()?
-                    .unwrap_or(height);
+                    .unwrap_or(height.max(1));
                 strip_decoder = Some(StripDecodeState { rows_per_strip });
                 tile_attributes = None;
 
                 if chunk_offsets.len() != chunk_bytes.len()
                     || rows_per_strip == 0
-                    || u32::try_from(chunk_offsets.len())?
-                        != (height.saturating_sub(1) / rows_per_strip + 1) * planes as u32
+                    || !empty
+                        && u32::try_from(chunk_offsets.len())?
+                            != (height.saturating_sub(1) / rows_per_strip + 1) * planes as u32
                 {
                     return Err(TiffError::FormatError(
                         TiffFormatError::InconsistentSizesEncountered,
@@ -265,19 +399,16 @@ impl Image {
                     tile_width,
                     tile_length,
                 });
-                chunk_offsets = tag_reader
-                    .find_tag(Tag::TileOffsets)?
-                    .unwrap()
-                    .into_u64_vec()?;
-                chunk_bytes = tag_reader
-                    .find_tag(Tag::TileByteCounts)?
-                    .unwrap()
-                    .into_u64_vec()?;
+                chunk_offsets =
+                    ChunkTable::read(&mut tag_reader, Tag::TileOffsets, lazy_chunk_tables)?;
+                chunk_bytes =
+                    ChunkTable::read(&mut tag_reader, Tag::TileByteCounts, lazy_chunk_tables)?;
 
                 let tile = tile_attributes.as_ref().unwrap();
                 if chunk_offsets.len() != chunk_bytes.len()
-                    || chunk_offsets.len()
-                        != tile.tiles_down() * tile.tiles_across() * planes as usize
+                    || !empty
+                        && chunk_offsets.len()
+                            != tile.tiles_down() * tile.tiles_across() * planes as usize
                 {
                     return Err(TiffError::FormatError(
                         TiffFormatError::InconsistentSizesEncountered,
@@ -292,10 +423,10 @@ impl Image {
         };
 
         Ok(Image {
-            ifd: Some(ifd),
+            ifd: Some(Arc::new(ifd)),
             width,
             height,
-            bits_per_sample: bits_per_sample[0],
+            bits_per_sample,
             samples,
             sample_format,
             photometric_interpretation,
@@ -308,19 +439,23 @@ impl Image {
             tile_attributes,
             chunk_offsets,
             chunk_bytes,
+            log_luv_output,
         })
     }
 
     pub(crate) fn colortype(&self) -> TiffResult<ColorType> {
         match self.photometric_interpretation {
+            // > Beware of extra components. Some TIFF files may have more components per pixel
+            // than you think. A Baseline TIFF reader must skip over them gracefully,using the
+            // values of the SamplesPerPixel and BitsPerSample fields.
+            // > -- TIFF 6.0 Specification, Section 7, Additional Baseline requirements.
+            //
+            // A single extra sample is taken as alpha, more of them, e.g. alpha and spot colors,
+            // make a multiband image that keeps all samples.
             PhotometricInterpretation::RGB => match self.samples {
                 3 => Ok(ColorType::RGB(self.bits_per_sample)),
                 4 => Ok(ColorType::RGBA(self.bits_per_sample)),
-                // FIXME: We should _ignore_ other components. In particular:
-                // > Beware of extra components. Some TIFF files may have more components per pixel
-                // than you think. A Baseline TIFF reader must skip over them gracefully,using the
-                // values of the SamplesPerPixel and BitsPerSample fields.
-                // > -- TIFF 6.0 Specification, Section 7, Additional Baseline requirements.
+                5.. => Ok(self.multiband()),
                 _ => Err(TiffError::UnsupportedError(
                     TiffUnsupportedError::InterpretationWithBits(
                         self.photometric_interpretation,
@@ -330,6 +465,8 @@ impl Image {
             },
             PhotometricInterpretation::CMYK => match self.samples {
                 4 => Ok(ColorType::CMYK(self.bits_per_sample)),
+                5 => Ok(ColorType::CMYKA(self.bits_per_sample)),
+                6.. => Ok(self.multiband()),
                 _ => Err(TiffError::UnsupportedError(
                     TiffUnsupportedError::InterpretationWithBits(
                         self.photometric_interpretation,
@@ -339,6 +476,8 @@ impl Image {
             },
             PhotometricInterpretation::YCbCr => match self.samples {
                 3 => Ok(ColorType::YCbCr(self.bits_per_sample)),
+                4 => Ok(ColorType::YCbCrA(self.bits_per_sample)),
+                5.. => Ok(self.multiband()),
                 _ => Err(TiffError::UnsupportedError(
                     TiffUnsupportedError::InterpretationWithBits(
                         self.photometric_interpretation,
@@ -349,14 +488,22 @@ impl Image {
             PhotometricInterpretation::BlackIsZero | PhotometricInterpretation::WhiteIsZero => {
                 match self.samples {
                     1 => Ok(ColorType::Gray(self.bits_per_sample)),
-                    _ => Ok(ColorType::Multiband {
-                        bit_depth: self.bits_per_sample,
-                        num_samples: self.samples,
-                    }),
+                    2 => Ok(ColorType::GrayA(self.bits_per_sample)),
+                    _ => Ok(self.multiband()),
                 }
             }
+            PhotometricInterpretation::RGBPalette if self.samples == 1 => {
+                Ok(ColorType::Palette(self.bits_per_sample))
+            }
+            PhotometricInterpretation::LogL if self.samples == 1 => Ok(ColorType::Gray(32)),
+            PhotometricInterpretation::LogLuv if self.samples == 3 => Ok(ColorType::Multiband {
+                bit_depth: 32,
+                num_samples: 3,
+            }),
             // TODO: this is bad we should not fail at this point
-            PhotometricInterpretation::RGBPalette
+            PhotometricInterpretation::LogL
+            | PhotometricInterpretation::LogLuv
+            | PhotometricInterpretation::RGBPalette
             | PhotometricInterpretation::TransparencyMask
             | PhotometricInterpretation::CIELab => Err(TiffError::UnsupportedError(
                 TiffUnsupportedError::InterpretationWithBits(
@@ -367,13 +514,50 @@ impl Image {
         }
     }
 
+    fn multiband(&self) -> ColorType {
+        ColorType::Multiband {
+            bit_depth: self.bits_per_sample,
+            num_samples: self.samples,
+        }
+    }
+
+    #[cfg_attr(
+        not(any(feature = "legacy-compressions", feature = "webp", feature = "lzma")),
+        allow(unused_variables)
+    )]
+    #[allow(clippy::too_many_arguments)]
     fn create_reader<'r, R: 'r + Read>(
         reader: R,
         photometric_interpretation: PhotometricInterpretation,
+        samples: usize,
         compression_method: CompressionMethod,
         compressed_length: u64,
         jpeg_tables: Option<&[u8]>,
+        bits_per_sample: u8,
+        chunk_width: u32,
+        chunk_rows: u32,
+        log_luv_output: LogLuvOutput,
+        limits: &Limits,
     ) -> TiffResult<Box<dyn Read + 'r>> {
+        // SGI log encoded pixels are only stored with the SGILog compressions, interleaved.
+        let log_encoded = matches!(
+            photometric_interpretation,
+            PhotometricInterpretation::LogL | PhotometricInterpretation::LogLuv
+        );
+        if log_encoded && compression_method != CompressionMethod::SGILog {
+            return Err(TiffError::UnsupportedError(
+                TiffUnsupportedError::UnsupportedCompressionMethod(compression_method),
+            ));
+        }
+        if photometric_interpretation == PhotometricInterpretation::LogLuv && samples != 3 {
+            return Err(TiffError::UnsupportedError(
+                TiffUnsupportedError::UnsupportedPlanarConfig(Some(PlanarConfiguration::Planar)),
+            ));
+        }
+
+        // No decompressor may read beyond the chunk, e.g. into the next one.
+        let mut reader = reader.take(compressed_length);
+
         Ok(match compression_method {
             CompressionMethod::None => Box::new(reader),
             CompressionMethod::LZW => {
@@ -383,10 +567,112 @@ impl Image {
             CompressionMethod::ZSTD => {
                 Box::new(zstd::Decoder::new(reader)?)
             },
+            #[cfg(feature = "webp")]
+            CompressionMethod::WebP => {
+                // Every chunk is a complete WebP image, which only holds 8-bit RGB or RGBA samples.
+                if bits_per_sample != 8 || !(samples == 3 || samples == 4) {
+                    return Err(TiffError::UnsupportedError(
+                        TiffUnsupportedError::UnsupportedCompressionMethod(compression_method),
+                    ));
+                }
+
+                let mut data = Vec::new();
+                reader.read_to_end(&mut data)?;
+                let mut decoder = image_webp::WebPDecoder::new(Cursor::new(data))?;
+                // The image covers the whole chunk, and padded rows beyond the data at most.
+                let (width, height) = decoder.dimensions();
+                if width != chunk_width || height < chunk_rows {
+                    return Err(TiffError::FormatError(
+                        TiffFormatError::InconsistentSizesEncountered,
+                    ));
+                }
+                let size = decoder.output_buffer_size().ok_or(TiffError::LimitsExceeded)?;
+                if size > limits.intermediate_buffer_size {
+                    return Err(TiffError::LimitsExceeded);
+                }
+                let mut decoded = vec![0; size];
+                decoder.read_image(&mut decoded)?;
+
+                // The alpha channel is dropped by some writers if it is fully opaque.
+                let decoded = match (decoder.has_alpha(), samples) {
+                    (true, 3) => decoded
+                        .chunks_exact(4)
+                        .flat_map(|pixel| [pixel[0], pixel[1], pixel[2]])
+                        .collect(),
+                    (false, 4) => decoded
+                        .chunks_exact(3)
+                        .flat_map(|pixel| [pixel[0], pixel[1], pixel[2], 0xff])
+                        .collect(),
+                    _ => decoded,
+                };
+
+                Box::new(Cursor::new(decoded))
+            }
+            #[cfg(feature = "lzma")]
+            CompressionMethod::LZMA => {
+                // Only the rows that are read are kept, the rest of the stream is decoded but
+                // dropped so that a corrupt stream can not take up arbitrary memory.
+                let row_bits = (u64::from(chunk_width) * u64::from(bits_per_sample))
+                    .checked_mul(samples as u64)
+                    .ok_or(TiffError::LimitsExceeded)?;
+                let limit = ((row_bits + 7) / 8)
+                    .checked_mul(u64::from(chunk_rows))
+                    .ok_or(TiffError::LimitsExceeded)?;
+
+                let mut input = io::BufReader::new(reader);
+                let mut output = super::stream::TruncatingWriter::new(usize::try_from(limit)?);
+                lzma_rs::xz_decompress(&mut input, &mut output)?;
+
+                Box::new(Cursor::new(output.into_inner()))
+            }
             CompressionMethod::PackBits => Box::new(PackBitsReader::new(reader, compressed_length)),
             CompressionMethod::Deflate | CompressionMethod::OldDeflate => {
                 Box::new(DeflateReader::new(reader))
             }
+            #[cfg(feature = "legacy-compressions")]
+            CompressionMethod::ThunderScan | CompressionMethod::NeXT => {
+                // Both encodings only exist for single-sample grayscale images of a fixed depth.
+                let expected_bits = match compression_method {
+                    CompressionMethod::ThunderScan => 4,
+                    _ => 2,
+                };
+                if samples != 1 || bits_per_sample != expected_bits {
+                    return Err(TiffError::UnsupportedError(
+                        TiffUnsupportedError::UnsupportedCompressionMethod(compression_method),
+                    ));
+                }
+
+                let mut data = Vec::new();
+                reader.read_to_end(&mut data)?;
+                let (width, rows) = (chunk_width as usize, chunk_rows as usize);
+                let decoded = match compression_method {
+                    CompressionMethod::ThunderScan => {
+                        super::legacy::decode_thunderscan(&data, width, rows)?
+                    }
+                    _ => super::legacy::decode_next(&data, width, rows)?,
+                };
+
+                Box::new(Cursor::new(decoded))
+            }
+            CompressionMethod::SGILog => {
+                if !log_encoded {
+                    return Err(TiffError::UnsupportedError(
+                        TiffUnsupportedError::UnsupportedInterpretation(photometric_interpretation),
+                    ));
+                }
+
+                let mut data = Vec::new();
+                reader.read_to_end(&mut data)?;
+                let decoded = super::logluv::decode_sgilog(
+                    &data,
+                    chunk_width as usize,
+                    chunk_rows as usize,
+                    samples,
+                    log_luv_output,
+                )?;
+
+                Box::new(Cursor::new(decoded))
+            }
             CompressionMethod::ModernJPEG => {
                 if jpeg_tables.is_some() && compressed_length < 2 {
                     return Err(TiffError::FormatError(
@@ -406,15 +692,13 @@ impl Image {
                 // this has to be removed as well (last two bytes of `jpeg_tables`).
                 let jpeg_reader = match jpeg_tables {
                     Some(jpeg_tables) => {
-                        let mut reader = reader.take(compressed_length);
                         reader.read_exact(&mut [0; 2])?;
 
                         Box::new(
-                            Cursor::new(&jpeg_tables[..jpeg_tables.len() - 2])
-                                .chain(reader.take(compressed_length)),
+                            Cursor::new(&jpeg_tables[..jpeg_tables.len() - 2]).chain(reader),
                         ) as Box<dyn Read>
                     }
-                    None => Box::new(reader.take(compressed_length)),
+                    None => Box::new(reader),
                 };
 
                 let mut decoder = jpeg::Decoder::new(jpeg_reader);
@@ -435,6 +719,11 @@ impl Image {
                     PhotometricInterpretation::CMYK => {
                         decoder.set_color_transform(jpeg::ColorTransform::CMYK)
                     }
+                    // The decoder only transforms YCbCr without alpha, the color samples of YCbCrA
+                    // are converted to RGB after decoding.
+                    PhotometricInterpretation::YCbCr if samples == 4 => {
+                        decoder.set_color_transform(jpeg::ColorTransform::None)
+                    }
                     PhotometricInterpretation::YCbCr => {
                         decoder.set_color_transform(jpeg::ColorTransform::YCbCr)
                     }
@@ -447,7 +736,10 @@ impl Image {
                     }
                 }
 
-                let data = decoder.decode()?;
+                let mut data = decoder.decode()?;
+                if photometric_interpretation == PhotometricInterpretation::YCbCr && samples == 4 {
+                    data.chunks_exact_mut(4).for_each(ycbcr_to_rgb);
+                }
 
                 Box::new(Cursor::new(data))
             }
@@ -481,22 +773,16 @@ impl Image {
         }
     }
 
-    pub(crate) fn chunk_file_range(&self, chunk: u32) -> TiffResult<(u64, u64)> {
-        let file_offset = self
-            .chunk_offsets
-            .get(chunk as usize)
-            .ok_or(TiffError::FormatError(
-                TiffFormatError::InconsistentSizesEncountered,
-            ))?;
-
-        let compressed_bytes =
-            self.chunk_bytes
-                .get(chunk as usize)
-                .ok_or(TiffError::FormatError(
-                    TiffFormatError::InconsistentSizesEncountered,
-                ))?;
-
-        Ok((*file_offset, *compressed_bytes))
+    /// Returns the offset and the byte count of a chunk, reading them from the file if the chunk
+    /// tables are lazy.
+    pub(crate) fn chunk_file_range<R: Read + Seek>(
+        &self,
+        chunk: u32,
+        reader: &mut SmartReader<R>,
+    ) -> TiffResult<(u64, u64)> {
+        let file_offset = self.chunk_offsets.get(chunk as usize, reader)?;
+        let compressed_bytes = self.chunk_bytes.get(chunk as usize, reader)?;
+        Ok((file_offset, compressed_bytes))
     }
 
     pub(crate) fn chunk_dimensions(&self) -> TiffResult<(u32, u32)> {
@@ -516,13 +802,29 @@ impl Image {
     }
 
     pub(crate) fn chunk_data_dimensions(&self, chunk_index: u32) -> TiffResult<(u32, u32)> {
+        // No chunk of an empty image holds any data, and there is no data past the last chunk.
+        if self.width == 0
+            || self.height == 0
+            || chunk_index as usize >= self.chunk_offsets.len()
+        {
+            return Err(TiffError::UsageError(UsageError::InvalidChunkIndex(
+                chunk_index,
+            )));
+        }
+
         let dims = self.chunk_dimensions()?;
 
         match self.chunk_type {
             ChunkType::Strip => {
                 let strip_attrs = self.strip_decoder.as_ref().unwrap();
-                let strips_per_band =
-                    self.height.saturating_sub(1) / strip_attrs.rows_per_strip + 1;
+                let strips_per_band = self
+                    .height
+                    .saturating_sub(1)
+                    .checked_div(strip_attrs.rows_per_strip)
+                    .ok_or(TiffError::FormatError(
+                        TiffFormatError::InconsistentSizesEncountered,
+                    ))?
+                    + 1;
                 let strip_height_without_padding = (chunk_index % strips_per_band)
                     .checked_mul(dims.1)
                     .and_then(|x| self.height.checked_sub(x))
@@ -533,42 +835,61 @@ impl Image {
                 // Ignore potential vertical padding on the bottommost strip
                 let strip_height = dims.1.min(strip_height_without_padding);
 
-                Ok((dims.0, strip_height))
+                Self::nonempty_chunk(dims.0, strip_height)
             }
             ChunkType::Tile => {
                 let tile_attrs = self.tile_attributes.as_ref().unwrap();
                 let (padding_right, padding_down) = tile_attrs.get_padding(chunk_index as usize);
 
-                let tile_width = tile_attrs.tile_width - padding_right;
-                let tile_length = tile_attrs.tile_length - padding_down;
+                let tile_width = tile_attrs.tile_width.saturating_sub(padding_right);
+                let tile_length = tile_attrs.tile_length.saturating_sub(padding_down);
 
-                Ok((u32::try_from(tile_width)?, u32::try_from(tile_length)?))
+                Self::nonempty_chunk(u32::try_from(tile_width)?, u32::try_from(tile_length)?)
             }
         }
     }
 
-    pub(crate) fn expand_chunk(
-        &self,
-        reader: impl Read,
-        buf: &mut [u8],
-        output_row_stride: usize,
-        byte_order: ByteOrder,
-        chunk_index: u32,
-        limits: &Limits,
-    ) -> TiffResult<()> {
-        // Validate that the color type is supported.
+    /// Reject degenerate chunk geometry, which would otherwise underflow the row arithmetic.
+    fn nonempty_chunk(width: u32, height: u32) -> TiffResult<(u32, u32)> {
+        if width == 0 || height == 0 {
+            return Err(TiffError::FormatError(TiffFormatError::InvalidDimensions(
+                width, height,
+            )));
+        }
+
+        Ok((width, height))
+    }
+
+    /// Returns the color type of the image, checking that its samples and predictor can be
+    /// decoded.
+    pub(crate) fn decodable_color_type(&self) -> TiffResult<ColorType> {
         let color_type = self.colortype()?;
         match color_type {
             ColorType::RGB(n)
             | ColorType::RGBA(n)
             | ColorType::CMYK(n)
+            | ColorType::CMYKA(n)
             | ColorType::YCbCr(n)
+            | ColorType::YCbCrA(n)
             | ColorType::Gray(n)
+            | ColorType::GrayA(n)
+            | ColorType::Palette(n)
             | ColorType::Multiband {
                 bit_depth: n,
                 num_samples: _,
             } if n == 8 || n == 16 || n == 32 || n == 64 => {}
+            // Packed samples of more than 8 bits, which are only returned unpacked.
+            ColorType::Gray(n)
+            | ColorType::GrayA(n)
+            | ColorType::RGB(n)
+            | ColorType::RGBA(n)
+            | ColorType::Multiband {
+                bit_depth: n,
+                num_samples: _,
+            } if n > 8 && n < 16 && self.predictor == Predictor::None => {}
             ColorType::Gray(n)
+            | ColorType::GrayA(n)
+            | ColorType::Palette(n)
             | ColorType::Multiband {
                 bit_depth: n,
                 num_samples: _,
@@ -609,13 +930,85 @@ impl Image {
             _ => {}
         }
 
-        let compressed_bytes =
-            self.chunk_bytes
-                .get(chunk_index as usize)
-                .ok_or(TiffError::FormatError(
-                    TiffFormatError::InconsistentSizesEncountered,
-                ))?;
-        if *compressed_bytes > limits.intermediate_buffer_size as u64 {
+        Ok(color_type)
+    }
+
+    /// Decodes the chunk at `file_range`, its offset and declared byte count, into `buf`,
+    /// returning the number of bytes it takes up in the file.
+    ///
+    /// The reader is moved to the offset of the chunk first, so the result never depends on where
+    /// earlier reads left it.
+    ///
+    /// The bytes taken up are the number of bytes consumed by the decompressor, which may read
+    /// ahead up to the declared byte count. Uncompressed chunks take up all of their rows, even
+    /// those beyond the image. The chunk is never read beyond its declared byte count, running out
+    /// of the declared bytes is reported as [`TiffFormatError::ChunkByteCountMismatch`].
+    pub(crate) fn expand_chunk<R: Read + Seek>(
+        &self,
+        reader: &mut SmartReader<R>,
+        buf: &mut [u8],
+        output_row_stride: usize,
+        chunk_index: u32,
+        file_range: (u64, u64),
+        limits: &Limits,
+    ) -> TiffResult<u64> {
+        let (offset, declared) = file_range;
+        reader.goto_offset(offset)?;
+        let byte_order = reader.byte_order();
+        let mut reader = CountingReader::new(reader);
+        let result = self.expand_chunk_from(
+            &mut reader,
+            buf,
+            output_row_stride,
+            byte_order,
+            chunk_index,
+            declared,
+            limits,
+        );
+        let consumed = if self.compression_method == CompressionMethod::None {
+            let (chunk_width, chunk_rows) = match self.chunk_type {
+                ChunkType::Strip => self.chunk_data_dimensions(chunk_index)?,
+                ChunkType::Tile => self.chunk_dimensions()?,
+            };
+            let row_bits = u64::from(chunk_width)
+                * u64::from(self.bits_per_sample)
+                * self.samples_per_pixel() as u64;
+            (row_bits + 7) / 8 * u64::from(chunk_rows)
+        } else {
+            reader.count()
+        };
+
+        match result {
+            Ok(()) => Ok(consumed),
+            Err(TiffError::IoError(ref err))
+                if err.kind() == io::ErrorKind::UnexpectedEof && consumed >= declared =>
+            {
+                Err(TiffError::FormatError(
+                    TiffFormatError::ChunkByteCountMismatch {
+                        chunk: chunk_index,
+                        declared,
+                        consumed,
+                    },
+                ))
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn expand_chunk_from(
+        &self,
+        reader: impl Read,
+        buf: &mut [u8],
+        output_row_stride: usize,
+        byte_order: ByteOrder,
+        chunk_index: u32,
+        compressed_bytes: u64,
+        limits: &Limits,
+    ) -> TiffResult<()> {
+        let color_type = self.decodable_color_type()?;
+
+        if compressed_bytes > limits.intermediate_buffer_size as u64 {
             return Err(TiffError::LimitsExceeded);
         }
 
@@ -627,6 +1020,18 @@ impl Image {
         let chunk_dims = self.chunk_dimensions()?;
         let data_dims = self.chunk_data_dimensions(chunk_index)?;
 
+        // Decoded SGI log samples are little endian floats, independent of the file byte order.
+        let byte_order = match photometric_interpretation {
+            PhotometricInterpretation::LogL | PhotometricInterpretation::LogLuv => {
+                ByteOrder::LittleEndian
+            }
+            _ => byte_order,
+        };
+        // Samples that are stored as they are returned are used without any further pass.
+        let is_stored_as_decoded = predictor == Predictor::None
+            && (byte_order == ByteOrder::NATIVE || color_type.bit_depth() <= 8)
+            && photometric_interpretation != PhotometricInterpretation::WhiteIsZero;
+
         let chunk_row_bits = (u64::from(chunk_dims.0) * u64::from(self.bits_per_sample))
             .checked_mul(samples as u64)
             .ok_or(TiffError::LimitsExceeded)?;
@@ -637,33 +1042,47 @@ impl Image {
             .ok_or(TiffError::LimitsExceeded)?;
         let data_row_bytes: usize = ((data_row_bits + 7) / 8).try_into()?;
 
-        // TODO: Should these return errors instead?
-        assert!(output_row_stride >= data_row_bytes);
-        assert!(buf.len() >= output_row_stride * (data_dims.1 as usize - 1) + data_row_bytes);
+        let required_bytes = output_row_stride
+            .checked_mul(data_dims.1 as usize - 1)
+            .and_then(|bytes| bytes.checked_add(data_row_bytes))
+            .ok_or(TiffError::LimitsExceeded)?;
+        if output_row_stride < data_row_bytes || buf.len() < required_bytes {
+            return Err(TiffError::UsageError(UsageError::InsufficientBuffer(
+                required_bytes,
+                buf.len(),
+            )));
+        }
 
         let mut reader = Self::create_reader(
             reader,
             photometric_interpretation,
+            samples,
             compression_method,
-            *compressed_bytes,
+            compressed_bytes,
             self.jpeg_tables.as_deref().map(|a| &**a),
+            self.bits_per_sample,
+            chunk_dims.0,
+            data_dims.1,
+            self.log_luv_output,
+            limits,
         )?;
 
         if output_row_stride == chunk_row_bytes as usize {
             let tile = &mut buf[..chunk_row_bytes * data_dims.1 as usize];
             reader.read_exact(tile)?;
-
-            for row in tile.chunks_mut(chunk_row_bytes as usize) {
-                super::fix_endianness_and_predict(
-                    row,
-                    color_type.bit_depth(),
-                    samples,
-                    byte_order,
-                    predictor,
-                );
-            }
-            if photometric_interpretation == PhotometricInterpretation::WhiteIsZero {
-                super::invert_colors(tile, color_type, self.sample_format);
+            if !is_stored_as_decoded {
+                for row in tile.chunks_mut(chunk_row_bytes as usize) {
+                    super::fix_endianness_and_predict(
+                        row,
+                        color_type.bit_depth(),
+                        samples,
+                        byte_order,
+                        predictor,
+                    );
+                }
+                if photometric_interpretation == PhotometricInterpretation::WhiteIsZero {
+                    super::invert_colors(tile, color_type, self.sample_format);
+                }
             }
         } else if chunk_row_bytes > data_row_bytes && self.predictor == Predictor::FloatingPoint {
             // The floating point predictor shuffles the padding bytes into the encoded output, so
@@ -674,6 +1093,7 @@ impl Image {
 
                 let row = &mut row[..data_row_bytes];
                 match color_type.bit_depth() {
+                    16 => predict_f16(&mut encoded, row, samples),
                     32 => predict_f32(&mut encoded, row, samples),
                     64 => predict_f64(&mut encoded, row, samples),
                     _ => unreachable!(),
@@ -696,6 +1116,9 @@ impl Image {
                     let len = u64::try_from(chunk_row_bytes - data_row_bytes)?;
                     io::copy(&mut reader.by_ref().take(len), &mut io::sink())?;
                 }
+                if is_stored_as_decoded {
+                    continue;
+                }
 
                 super::fix_endianness_and_predict(
                     row,
@@ -710,6 +1133,55 @@ impl Image {
             }
         }
 
+        // A corrupt LZW stream can go on beyond the chunk, which must not be silently cut off.
+        // Tiles hold the rows beyond the image as well, and so may the last strip if it is padded
+        // to the full rows per strip. These are skipped first.
+        if compression_method == CompressionMethod::LZW {
+            let stored_rows = chunk_dims.1;
+            let padding = u64::from(stored_rows - data_dims.1) * chunk_row_bytes as u64;
+            if has_trailing_data(&mut reader, padding)? {
+                return Err(TiffError::FormatError(
+                    TiffFormatError::DecompressedChunkTooLarge {
+                        chunk: chunk_index,
+                        expected_bytes: u64::from(stored_rows) * chunk_row_bytes as u64,
+                    },
+                ));
+            }
+        }
+
         Ok(())
     }
 }
+
+/// Converts the YCbCr samples at the start of `pixel` to RGB in place, as the JPEG decoder does
+/// for images without alpha.
+fn ycbcr_to_rgb(pixel: &mut [u8]) {
+    const SHIFT: i32 = 20;
+    let fixed = |x: f32| (x * (1 << SHIFT) as f32 + 0.5) as i32;
+    let clamp = |value: i32| (value >> SHIFT).clamp(0, 255) as u8;
+
+    let y = i32::from(pixel[0]) * (1 << SHIFT) + (1 << (SHIFT - 1));
+    let cb = i32::from(pixel[1]) - 128;
+    let cr = i32::from(pixel[2]) - 128;
+    pixel[0] = clamp(y + fixed(1.402) * cr);
+    pixel[1] = clamp(y - fixed(0.34414) * cb - fixed(0.71414) * cr);
+    pixel[2] = clamp(y + fixed(1.772) * cb);
+}
+
+/// Whether `reader` yields any data beyond the next `skip` bytes.
+///
+/// A stream that ends early, e.g. an LZW stream without end code, has no trailing data.
+fn has_trailing_data(reader: &mut impl Read, skip: u64) -> TiffResult<bool> {
+    let ended = |err: &io::Error| err.kind() == io::ErrorKind::UnexpectedEof;
+    match io::copy(&mut reader.by_ref().take(skip), &mut io::sink()) {
+        Ok(skipped) if skipped < skip => return Ok(false),
+        Ok(_) => {}
+        Err(ref err) if ended(err) => return Ok(false),
+        Err(err) => return Err(err.into()),
+    }
+    match reader.read(&mut [0]) {
+        Ok(read) => Ok(read > 0),
+        Err(ref err) if ended(err) => Ok(false),
+        Err(err) => Err(err.into()),
+    }
+}
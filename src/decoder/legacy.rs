@@ -0,0 +1,152 @@
+//! Decoders for the run-length encodings of early scanning software.
+//!
+//! Both encodings work on single rows of grayscale samples and are decoded one chunk at a time.
+use std::io;
+
+fn corrupt(message: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn apply_delta(value: u8, delta: i8) -> u8 {
+    (value as i8).wrapping_add(delta) as u8 & 0xf
+}
+
+/// Decodes `rows` rows of `width` 4-bit samples compressed with ThunderScan (32809).
+///
+/// Every row starts at a sample value of zero and is built from runs of the previous value,
+/// small deltas to it and literal values.
+pub(crate) fn decode_thunderscan(data: &[u8], width: usize, rows: usize) -> io::Result<Vec<u8>> {
+    const TWO_BIT_DELTAS: [i8; 4] = [0, 1, 0, -1];
+    const TWO_BIT_SKIP: u8 = 2;
+    const THREE_BIT_DELTAS: [i8; 8] = [0, 1, 2, 3, 0, -3, -2, -1];
+    const THREE_BIT_SKIP: u8 = 4;
+
+    let row_bytes = (width + 1) / 2;
+    let mut output = vec![0; row_bytes * rows];
+    let mut input = data.iter();
+
+    for row in output.chunks_mut(row_bytes.max(1)).take(rows) {
+        let mut last = 0u8;
+        let mut pixels = 0;
+
+        while pixels < width {
+            let code = *input
+                .next()
+                .ok_or_else(|| corrupt("ThunderScan data ends within a row"))?;
+            let mut push = |value: u8| {
+                if pixels < width {
+                    row[pixels / 2] |= if pixels % 2 == 0 { value << 4 } else { value };
+                }
+                pixels += 1;
+            };
+            match code >> 6 {
+                // A run of the previous value.
+                0 => {
+                    for _ in 0..code & 0x3f {
+                        push(last);
+                    }
+                }
+                // Three 2-bit deltas.
+                1 => {
+                    for shift in [4, 2, 0] {
+                        let index = code >> shift & 3;
+                        if index != TWO_BIT_SKIP {
+                            last = apply_delta(last, TWO_BIT_DELTAS[usize::from(index)]);
+                            push(last);
+                        }
+                    }
+                }
+                // Two 3-bit deltas.
+                2 => {
+                    for shift in [3, 0] {
+                        let index = code >> shift & 7;
+                        if index != THREE_BIT_SKIP {
+                            last = apply_delta(last, THREE_BIT_DELTAS[usize::from(index)]);
+                            push(last);
+                        }
+                    }
+                }
+                // A literal value.
+                _ => {
+                    last = code & 0xf;
+                    push(last);
+                }
+            }
+        }
+
+        if pixels > width {
+            return Err(corrupt("ThunderScan row holds too many samples"));
+        }
+    }
+
+    Ok(output)
+}
+
+/// Decodes `rows` rows of `width` 2-bit samples compressed with the NeXT encoding (32766).
+///
+/// Every row is either stored literally, as a literal span within a white row, or as runs of a
+/// single value. Rows missing at the end of the data are white.
+pub(crate) fn decode_next(data: &[u8], width: usize, rows: usize) -> io::Result<Vec<u8>> {
+    const LITERAL_ROW: u8 = 0x00;
+    const LITERAL_SPAN: u8 = 0x40;
+
+    let row_bytes = (width + 3) / 4;
+    let mut output = vec![0xff; row_bytes * rows];
+    let mut input = data;
+
+    for row in output.chunks_mut(row_bytes.max(1)).take(rows) {
+        let (&code, rest) = match input.split_first() {
+            Some(split) => split,
+            None => break,
+        };
+        input = rest;
+
+        match code {
+            LITERAL_ROW => {
+                if input.len() < row_bytes {
+                    return Err(corrupt("NeXT literal row is truncated"));
+                }
+                row.copy_from_slice(&input[..row_bytes]);
+                input = &input[row_bytes..];
+            }
+            LITERAL_SPAN => {
+                if input.len() < 4 {
+                    return Err(corrupt("NeXT literal span is truncated"));
+                }
+                let offset = usize::from(u16::from_be_bytes([input[0], input[1]]));
+                let length = usize::from(u16::from_be_bytes([input[2], input[3]]));
+                input = &input[4..];
+                if input.len() < length || offset + length > row_bytes {
+                    return Err(corrupt("NeXT literal span exceeds the row"));
+                }
+                row[offset..offset + length].copy_from_slice(&input[..length]);
+                input = &input[length..];
+            }
+            _ => {
+                // Runs of <value><count> codes until the row is filled.
+                let mut code = code;
+                let mut pixels = 0;
+                loop {
+                    let value = code >> 6;
+                    let count = usize::from(code & 0x3f).min(width - pixels);
+                    for pixel in pixels..pixels + count {
+                        let shift = 6 - 2 * (pixel % 4);
+                        row[pixel / 4] = row[pixel / 4] & !(3 << shift) | value << shift;
+                    }
+                    pixels += count;
+                    if pixels >= width {
+                        break;
+                    }
+
+                    let (&next, rest) = input
+                        .split_first()
+                        .ok_or_else(|| corrupt("NeXT data ends within a row"))?;
+                    code = next;
+                    input = rest;
+                }
+            }
+        }
+    }
+
+    Ok(output)
+}
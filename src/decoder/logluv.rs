@@ -0,0 +1,124 @@
+//! Decoding of the SGI log luminance encodings used for high dynamic range images.
+//!
+//! `LogL` images store a signed 16-bit logarithm of the luminance `Y`, `LogLuv` images add the
+//! CIE 1976 `u'` and `v'` chromaticity coordinates as one byte each. The SGILog compression
+//! run-length encodes every byte plane of a row separately. The SGILog24 compression, which packs
+//! the chromaticity into a table index, is not supported.
+use std::io;
+
+use super::LogLuvOutput;
+
+/// Scale of the 8-bit `u'` and `v'` coordinates.
+const UV_SCALE: f64 = 410.0;
+
+fn corrupt(message: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Decodes `rows` rows of `width` SGILog (34676) compressed pixels to little endian `f32` samples.
+///
+/// `samples` is 1 for `LogL` images, which decode to the luminance `Y`, and 3 for `LogLuv` images,
+/// which decode as selected by `output`.
+pub(crate) fn decode_sgilog(
+    data: &[u8],
+    width: usize,
+    rows: usize,
+    samples: usize,
+    output: LogLuvOutput,
+) -> io::Result<Vec<u8>> {
+    // The number of byte planes of the encoded pixels.
+    let planes = if samples == 1 { 2 } else { 4 };
+    let mut pixels = vec![0u32; width];
+    let mut decoded = Vec::with_capacity(width * rows * samples * 4);
+    let mut input = data;
+
+    for _ in 0..rows {
+        pixels.iter_mut().for_each(|pixel| *pixel = 0);
+        for plane in 0..planes {
+            let shift = 8 * (planes - 1 - plane);
+            input = decode_plane(input, &mut pixels, shift)?;
+        }
+
+        for &pixel in &pixels {
+            if samples == 1 {
+                decoded.extend_from_slice(&(log_l16_to_y(pixel as u16) as f32).to_le_bytes());
+            } else {
+                for value in log_luv32_to(pixel, output) {
+                    decoded.extend_from_slice(&(value as f32).to_le_bytes());
+                }
+            }
+        }
+    }
+
+    Ok(decoded)
+}
+
+/// Decodes the run-length encoded byte plane at `shift` of a single row, returns the rest of the
+/// input.
+fn decode_plane<'a>(mut input: &'a [u8], pixels: &mut [u32], shift: usize) -> io::Result<&'a [u8]> {
+    let mut i = 0;
+    while i < pixels.len() {
+        let (&count, rest) = input
+            .split_first()
+            .ok_or_else(|| corrupt("SGILog data ends within a row"))?;
+        if count >= 128 {
+            // A run of a single byte, repeated at least twice.
+            let (&value, rest) = rest
+                .split_first()
+                .ok_or_else(|| corrupt("SGILog data ends within a row"))?;
+            let count = usize::from(count) + 2 - 128;
+            for pixel in pixels.iter_mut().skip(i).take(count) {
+                *pixel |= u32::from(value) << shift;
+            }
+            i += count;
+            input = rest;
+        } else {
+            let count = usize::from(count).min(pixels.len() - i);
+            if rest.len() < count {
+                return Err(corrupt("SGILog data ends within a row"));
+            }
+            for (pixel, &value) in pixels[i..i + count].iter_mut().zip(rest) {
+                *pixel |= u32::from(value) << shift;
+            }
+            i += count;
+            input = &rest[count..];
+        }
+    }
+
+    Ok(input)
+}
+
+/// Converts the 16-bit log encoding of a luminance to its linear value.
+fn log_l16_to_y(encoded: u16) -> f64 {
+    let exponent = encoded & 0x7fff;
+    if exponent == 0 {
+        return 0.0;
+    }
+
+    let y = (std::f64::consts::LN_2 / 256.0 * (f64::from(exponent) + 0.5)
+        - std::f64::consts::LN_2 * 64.0)
+        .exp();
+    if encoded & 0x8000 != 0 {
+        -y
+    } else {
+        y
+    }
+}
+
+/// Converts a 32-bit `LogLuv` pixel to `XYZ` or to `Y`, `u'` and `v'`.
+fn log_luv32_to(encoded: u32, output: LogLuvOutput) -> [f64; 3] {
+    let luminance = log_l16_to_y((encoded >> 16) as u16);
+    let u = (f64::from(encoded >> 8 & 0xff) + 0.5) / UV_SCALE;
+    let v = (f64::from(encoded & 0xff) + 0.5) / UV_SCALE;
+
+    match output {
+        LogLuvOutput::Luv => [luminance, u, v],
+        LogLuvOutput::Xyz if luminance <= 0.0 => [0.0; 3],
+        LogLuvOutput::Xyz => {
+            let s = 1.0 / (6.0 * u - 16.0 * v + 12.0);
+            let x = 9.0 * u * s;
+            let y = 4.0 * v * s;
+            [x / y * luminance, luminance, (1.0 - x - y) / y * luminance]
+        }
+    }
+}
@@ -0,0 +1,133 @@
+//! A decoded copy of all directories of a file, for cataloging and comparing metadata.
+use std::collections::HashSet;
+use std::io::{Read, Seek};
+
+use super::ifd::Value;
+use super::raw::MAX_DIRECTORY_DEPTH;
+use super::Decoder;
+use crate::tags::{Tag, Type};
+use crate::{TiffError, TiffFormatError, TiffResult};
+
+/// The tags pointing to directories outside of the chain of images.
+const POINTER_TAGS: [Tag; 4] = [
+    Tag::SubIFDs,
+    Tag::ExifDirectory,
+    Tag::GpsDirectory,
+    Tag::InteropDirectory,
+];
+
+/// All directories of a file with their decoded values, see [`Decoder::dump_metadata`].
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct MetadataTree {
+    /// The directories of the images, in the order of the file.
+    pub images: Vec<MetadataDirectory>,
+}
+
+/// A directory and the directories it points to.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct MetadataDirectory {
+    /// The location of the directory in the file.
+    pub offset: u64,
+    /// The entries in ascending tag order.
+    pub entries: Vec<MetadataEntry>,
+    /// The directories pointed to by the `SubIFDs`, EXIF, GPS and interoperability tags, along
+    /// with the tag pointing to them.
+    pub sub_directories: Vec<(Tag, MetadataDirectory)>,
+}
+
+/// A single entry of a directory.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct MetadataEntry {
+    pub tag: Tag,
+    /// The field type as stored in the file.
+    pub field_type: Type,
+    /// The number of values as stored in the file.
+    pub count: u64,
+    pub value: Value,
+}
+
+impl MetadataDirectory {
+    /// Returns the entry of `tag`, if the directory has one.
+    pub fn entry(&self, tag: Tag) -> Option<&MetadataEntry> {
+        self.entries.iter().find(|entry| entry.tag == tag)
+    }
+}
+
+impl<R: Read + Seek> Decoder<R> {
+    /// Reads and decodes all directories of the file.
+    ///
+    /// This covers the directories of all images as well as the directories they point to with
+    /// the `SubIFDs`, EXIF, GPS and interoperability tags. The values are decoded within the
+    /// decoding limits, and directories nested more than 16 deep are rejected as exceeding them.
+    /// The current image is not changed.
+    pub fn dump_metadata(&mut self) -> TiffResult<MetadataTree> {
+        let mut seen = HashSet::new();
+        let mut images = Vec::new();
+        let mut next = self.ifd_offsets.first().copied();
+        while let Some(offset) = next {
+            if !seen.insert(offset) {
+                return Err(TiffError::FormatError(TiffFormatError::CycleInOffsets));
+            }
+            let (directory, next_ifd) = self.dump_directory(offset, &mut Vec::new())?;
+            images.push(directory);
+            next = next_ifd;
+        }
+
+        Ok(MetadataTree { images })
+    }
+
+    /// Dumps the directory at `offset` and the directories it points to.
+    ///
+    /// `path` holds the directories pointing to this one. Only these form a cycle, a directory
+    /// may still be pointed to from several others.
+    fn dump_directory(
+        &mut self,
+        offset: u64,
+        path: &mut Vec<u64>,
+    ) -> TiffResult<(MetadataDirectory, Option<u64>)> {
+        if path.contains(&offset) {
+            return Err(TiffError::FormatError(TiffFormatError::CycleInOffsets));
+        }
+        if path.len() >= MAX_DIRECTORY_DEPTH {
+            return Err(TiffError::LimitsExceeded);
+        }
+
+        let (ifd, next_ifd) = self.read_ifd_at(offset)?;
+
+        path.push(offset);
+
+        let mut entries = Vec::with_capacity(ifd.len());
+        let mut sub_directories = Vec::new();
+        for (tag, entry) in ifd {
+            let value = entry.tag_val(tag, &self.limits, self.bigtiff, &mut self.reader)?;
+            if POINTER_TAGS.contains(&tag) {
+                for offset in value.clone().into_ifd_vec()? {
+                    let (directory, _) = self.dump_directory(offset, path)?;
+                    sub_directories.push((tag, directory));
+                }
+            }
+
+            entries.push(MetadataEntry {
+                tag,
+                field_type: entry.field_type(),
+                count: entry.count(),
+                value,
+            });
+        }
+
+        path.pop();
+
+        let directory = MetadataDirectory {
+            offset,
+            entries,
+            sub_directories,
+        };
+        Ok((directory, next_ifd))
+    }
+}
@@ -1,20 +1,41 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::HashSet;
 use std::io::{self, Read, Seek};
+use std::ops::Range;
+use std::sync::Arc;
+use std::{fmt, mem};
 
+use crate::error::IoLimitsExceeded;
+use crate::pixel_format::copy_samples;
 use crate::tags::{
-    CompressionMethod, PhotometricInterpretation, PlanarConfiguration, Predictor, SampleFormat,
-    Tag, Type,
+    CompressionMethod, ExtraSample, NewSubfileType, PhotometricInterpretation, PlanarConfiguration,
+    Predictor, SampleFormat, Tag, Type,
 };
 use crate::{
-    bytecast, ColorType, TiffError, TiffFormatError, TiffResult, TiffUnsupportedError, UsageError,
+    bytecast, ColorType, OffsetSource, PixelFormat, TiffError, TiffFormatError, TiffResult,
+    TiffUnsupportedError, UsageError,
 };
 
-use self::ifd::Directory;
-use self::image::Image;
-use self::stream::{ByteOrder, EndianReader, SmartReader};
+use self::ifd::{Directory, Value};
+use self::image::{ChunkTable, Image};
+use self::stream::{EndianReader, SmartReader};
+use self::tag_reader::TagReader;
 
+pub use self::bands::{BandInfo, BandMetadataParser, BandText, GdalMetadataParser};
+pub use self::chunks::Chunks;
+pub use self::metadata::{MetadataDirectory, MetadataEntry, MetadataTree};
+pub(crate) use self::raw::RawEntry;
+pub use self::stream::ByteOrder;
+
+mod bands;
+mod chunks;
+mod exif;
 pub mod ifd;
 mod image;
+#[cfg(feature = "legacy-compressions")]
+mod legacy;
+mod logluv;
+mod metadata;
+mod raw;
 mod stream;
 mod tag_reader;
 
@@ -23,7 +44,7 @@ mod tag_reader;
 pub enum DecodingResult {
     /// A vector of unsigned bytes
     U8(Vec<u8>),
-    /// A vector of unsigned words
+    /// A vector of unsigned words, or of the bits of 16 bit IEEE floats
     U16(Vec<u16>),
     /// A vector of 32 bit unsigned ints
     U32(Vec<u32>),
@@ -138,8 +159,149 @@ impl DecodingResult {
             DecodingResult::I64(ref mut buf) => DecodingBuffer::I64(&mut buf[start..]),
         }
     }
+
+    /// Converts the samples to a single sample type.
+    ///
+    /// How values that do not fit the target type are handled depends on `conversion`, see
+    /// [`SampleConversion`]. Scaling between 64-bit integer types of different ranges goes
+    /// through `f64` and may lose precision for large values.
+    pub fn convert_to<T: ConvertSample>(&self, conversion: SampleConversion) -> Vec<T> {
+        fn convert<S: ConvertSample, T: ConvertSample>(
+            buf: &[S],
+            conversion: SampleConversion,
+        ) -> Vec<T> {
+            // Scaling between types of the same range is exact when done by saturating.
+            let scale = conversion == SampleConversion::Scale && S::RANGE_MAX != T::RANGE_MAX;
+
+            buf.iter()
+                .map(|&sample| match sample.to_i128() {
+                    _ if scale => T::from_normalized(sample.to_normalized()),
+                    Some(value) => T::saturate_from_i128(value),
+                    None => T::saturate_from_f64(sample.to_f64()),
+                })
+                .collect()
+        }
+
+        match self {
+            DecodingResult::U8(buf) => convert(buf, conversion),
+            DecodingResult::U16(buf) => convert(buf, conversion),
+            DecodingResult::U32(buf) => convert(buf, conversion),
+            DecodingResult::U64(buf) => convert(buf, conversion),
+            DecodingResult::F32(buf) => convert(buf, conversion),
+            DecodingResult::F64(buf) => convert(buf, conversion),
+            DecodingResult::I8(buf) => convert(buf, conversion),
+            DecodingResult::I16(buf) => convert(buf, conversion),
+            DecodingResult::I32(buf) => convert(buf, conversion),
+            DecodingResult::I64(buf) => convert(buf, conversion),
+        }
+    }
+}
+
+/// How [`DecodingResult::convert_to`] maps values between sample types.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SampleConversion {
+    /// Keep the numeric value, clamping it to the range of the target type.
+    ///
+    /// Floating point values are truncated towards zero when converted to integers and `NaN`
+    /// becomes zero.
+    Saturate,
+    /// Map the full range of the source type onto the full range of the target type.
+    ///
+    /// Unsigned integers cover `0.0..=1.0` and signed integers `-1.0..=1.0`, with their maximum
+    /// mapping to `1.0`. Floating point samples are taken to be normalized to that range already.
+    /// Negative values become zero when scaled to an unsigned type.
+    Scale,
+}
+
+/// A sample type that [`DecodingResult::convert_to`] can produce.
+///
+/// This is implemented for all sample types of [`DecodingResult`].
+pub trait ConvertSample: Copy {
+    #[doc(hidden)]
+    const RANGE_MAX: f64;
+    #[doc(hidden)]
+    fn to_i128(self) -> Option<i128>;
+    #[doc(hidden)]
+    fn to_f64(self) -> f64;
+    #[doc(hidden)]
+    fn to_normalized(self) -> f64;
+    #[doc(hidden)]
+    fn saturate_from_i128(value: i128) -> Self;
+    #[doc(hidden)]
+    fn saturate_from_f64(value: f64) -> Self;
+    #[doc(hidden)]
+    fn from_normalized(value: f64) -> Self;
+}
+
+macro_rules! convert_sample_int {
+    ($($ty:ty),*) => {$(
+        impl ConvertSample for $ty {
+            const RANGE_MAX: f64 = <$ty>::MAX as f64;
+
+            fn to_i128(self) -> Option<i128> {
+                Some(self.into())
+            }
+
+            fn to_f64(self) -> f64 {
+                self as f64
+            }
+
+            fn to_normalized(self) -> f64 {
+                self as f64 / Self::RANGE_MAX
+            }
+
+            fn saturate_from_i128(value: i128) -> Self {
+                value.clamp(<$ty>::MIN.into(), <$ty>::MAX.into()) as $ty
+            }
+
+            fn saturate_from_f64(value: f64) -> Self {
+                // Float to integer casts saturate and map NaN to zero.
+                value as $ty
+            }
+
+            fn from_normalized(value: f64) -> Self {
+                let min = if <$ty>::MIN == 0 { 0.0 } else { -1.0 };
+                (value.clamp(min, 1.0) * Self::RANGE_MAX).round() as $ty
+            }
+        }
+    )*};
+}
+
+macro_rules! convert_sample_float {
+    ($($ty:ty),*) => {$(
+        impl ConvertSample for $ty {
+            const RANGE_MAX: f64 = <$ty>::MAX as f64;
+
+            fn to_i128(self) -> Option<i128> {
+                None
+            }
+
+            fn to_f64(self) -> f64 {
+                self.into()
+            }
+
+            fn to_normalized(self) -> f64 {
+                self.into()
+            }
+
+            fn saturate_from_i128(value: i128) -> Self {
+                value as $ty
+            }
+
+            fn saturate_from_f64(value: f64) -> Self {
+                value as $ty
+            }
+
+            fn from_normalized(value: f64) -> Self {
+                value as $ty
+            }
+        }
+    )*};
 }
 
+convert_sample_int!(u8, u16, u32, u64, i8, i16, i32, i64);
+convert_sample_float!(f32, f64);
+
 // A buffer for image decoding
 pub enum DecodingBuffer<'a> {
     /// A slice of unsigned bytes
@@ -165,6 +327,15 @@ pub enum DecodingBuffer<'a> {
 }
 
 impl<'a> DecodingBuffer<'a> {
+    fn sample_bytes(&self) -> usize {
+        match self {
+            DecodingBuffer::U8(_) | DecodingBuffer::I8(_) => 1,
+            DecodingBuffer::U16(_) | DecodingBuffer::I16(_) => 2,
+            DecodingBuffer::U32(_) | DecodingBuffer::I32(_) | DecodingBuffer::F32(_) => 4,
+            DecodingBuffer::U64(_) | DecodingBuffer::I64(_) | DecodingBuffer::F64(_) => 8,
+        }
+    }
+
     fn as_bytes_mut(&mut self) -> &mut [u8] {
         match self {
             DecodingBuffer::U8(ref mut buf) => buf,
@@ -181,7 +352,7 @@ impl<'a> DecodingBuffer<'a> {
     }
 }
 
-#[derive(Debug, Copy, Clone, PartialEq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 /// Chunk type of the internal representation
 pub enum ChunkType {
     Strip,
@@ -189,6 +360,9 @@ pub enum ChunkType {
 }
 
 /// Decoding limits
+///
+/// Besides the defaults and [`Limits::unlimited`], there are presets for common environments,
+/// such as [`Limits::for_untrusted_input`].
 #[derive(Clone, Debug)]
 pub struct Limits {
     /// The maximum size of any `DecodingResult` in bytes, the default is
@@ -202,6 +376,12 @@ pub struct Limits {
     /// Maximum size for intermediate buffer which may be used to limit the amount of data read per
     /// segment even if the entire image is decoded at once.
     pub intermediate_buffer_size: usize,
+    /// The maximum number of bytes that the low-level `read_*` methods of the decoder, such as
+    /// [`Decoder::read_string`], may read in total. These methods are not otherwise limited, which
+    /// matters when they are driven by lengths taken from the file. The methods returning an
+    /// [`io::Error`] fail with one that converts into [`TiffError::LimitsExceeded`] once the budget
+    /// is exhausted. The default is no limit.
+    pub io_budget: Option<u64>,
     /// The purpose of this is to prevent all the fields of the struct from
     /// being public, as this would make adding new fields a major version
     /// bump.
@@ -221,6 +401,52 @@ impl Limits {
             decoding_buffer_size: usize::max_value(),
             ifd_value_size: usize::max_value(),
             intermediate_buffer_size: usize::max_value(),
+            io_budget: None,
+            _non_exhaustive: (),
+        }
+    }
+
+    /// Tighter limits for files from untrusted sources, such as uploads to a web service.
+    ///
+    /// Results are limited to 64MiB, compressed chunks to 16MiB, tag values to 256KiB and the
+    /// low-level `read_*` methods to 16MiB in total. This fits images of up to about 4096x4096 RGBA
+    /// pixels of 8 bits decoded at once, larger images must be decoded by chunk.
+    pub fn for_untrusted_input() -> Limits {
+        Limits {
+            decoding_buffer_size: 64 * 1024 * 1024,
+            intermediate_buffer_size: 16 * 1024 * 1024,
+            ifd_value_size: 256 * 1024,
+            io_budget: Some(16 * 1024 * 1024),
+            _non_exhaustive: (),
+        }
+    }
+
+    /// Generous limits for large rasters of trusted sources, such as GeoTIFF elevation models and
+    /// satellite scenes.
+    ///
+    /// Results are limited to 2GiB, compressed chunks to 256MiB and tag values to 64MiB, which
+    /// holds the offsets and byte counts of millions of tiles as well as large `GdalMetadata`.
+    /// The low-level `read_*` methods are not limited.
+    pub fn for_large_geotiff() -> Limits {
+        Limits {
+            decoding_buffer_size: 2 * 1024 * 1024 * 1024,
+            intermediate_buffer_size: 256 * 1024 * 1024,
+            ifd_value_size: 64 * 1024 * 1024,
+            io_budget: None,
+            _non_exhaustive: (),
+        }
+    }
+
+    /// Small limits for devices with little memory.
+    ///
+    /// Results are limited to 16MiB, compressed chunks to 4MiB, tag values to 64KiB and the
+    /// low-level `read_*` methods to 1MiB in total.
+    pub fn for_embedded() -> Limits {
+        Limits {
+            decoding_buffer_size: 16 * 1024 * 1024,
+            intermediate_buffer_size: 4 * 1024 * 1024,
+            ifd_value_size: 64 * 1024,
+            io_budget: Some(1024 * 1024),
             _non_exhaustive: (),
         }
     }
@@ -232,11 +458,252 @@ impl Default for Limits {
             decoding_buffer_size: 256 * 1024 * 1024,
             intermediate_buffer_size: 128 * 1024 * 1024,
             ifd_value_size: 1024 * 1024,
+            io_budget: None,
             _non_exhaustive: (),
         }
     }
 }
 
+/// The sizes of the largest buffers a decoder allocated, see [`Decoder::memory_usage`].
+///
+/// Each counter holds the size in bytes of the largest single buffer of its kind, so that the
+/// corresponding [`Limits`] can be calibrated against the files a service actually decodes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct MemoryUsage {
+    /// The largest `DecodingResult`, bounded by [`Limits::decoding_buffer_size`].
+    pub result_bytes: usize,
+    /// The largest compressed chunk, bounded by [`Limits::intermediate_buffer_size`].
+    pub intermediate_bytes: usize,
+    /// The largest tag value in the directories of the images loaded, as stored in the file.
+    pub tag_value_bytes: usize,
+}
+
+impl MemoryUsage {
+    /// Raises every counter to the one of `other`, if it is larger.
+    fn record(&mut self, other: MemoryUsage) {
+        self.result_bytes = self.result_bytes.max(other.result_bytes);
+        self.intermediate_bytes = self.intermediate_bytes.max(other.intermediate_bytes);
+        self.tag_value_bytes = self.tag_value_bytes.max(other.tag_value_bytes);
+    }
+}
+
+/// Options to create a [`Decoder`] with, see [`Decoder::with_options`].
+#[derive(Clone, Debug, Default)]
+pub struct DecoderOptions {
+    limits: Limits,
+    validate: bool,
+    collect_warnings: bool,
+    log_luv_output: LogLuvOutput,
+    lenient: bool,
+    skip_stream_length: bool,
+    duplicate_tags: DuplicateTagPolicy,
+    allow_unsorted_tags: bool,
+    lazy_chunk_tables: bool,
+}
+
+impl DecoderOptions {
+    /// Create the default options, equivalent to those of [`Decoder::new`].
+    pub fn new() -> DecoderOptions {
+        DecoderOptions::default()
+    }
+
+    /// Set the decoding limits.
+    pub fn with_limits(mut self, limits: Limits) -> DecoderOptions {
+        self.limits = limits;
+        self
+    }
+
+    /// Enable or disable validation mode, see [`Decoder::with_validation`].
+    pub fn with_validation(mut self, validate: bool) -> DecoderOptions {
+        self.validate = validate;
+        self
+    }
+
+    /// Collect non-fatal anomalies found while decoding, see [`Decoder::warnings`].
+    pub fn with_warnings(mut self, collect_warnings: bool) -> DecoderOptions {
+        self.collect_warnings = collect_warnings;
+        self
+    }
+
+    /// Enable or disable lenient mode, which accepts recoverable deviations from the specification
+    /// found in files of minimal writers instead of failing.
+    ///
+    /// In lenient mode a missing `PhotometricInterpretation` is taken to be `BlackIsZero`, as
+    /// libtiff does. Well-known tags stored with a wrong field type, such as an `ImageWidth` of
+    /// type `SSHORT` or an `XResolution` of type `LONG`, are converted to the type the
+    /// specification gives them, as long as their values fit. Each such image or tag is reported
+    /// as a [`DecodeWarning`].
+    pub fn with_lenient(mut self, lenient: bool) -> DecoderOptions {
+        self.lenient = lenient;
+        self
+    }
+
+    /// Select which entry is used for a tag that appears more than once in a directory.
+    ///
+    /// Validation mode rejects such directories with [`TiffFormatError::DuplicateTag`] regardless
+    /// of the policy.
+    pub fn with_duplicate_tags(mut self, policy: DuplicateTagPolicy) -> DecoderOptions {
+        self.duplicate_tags = policy;
+        self
+    }
+
+    /// Accept directories whose entries are not sorted in ascending tag order in validation mode.
+    ///
+    /// Some writers are known to store tags out of order, which does not affect decoding. Such
+    /// directories are still reported as a [`DecodeWarning`].
+    pub fn with_unsorted_tags(mut self, allow: bool) -> DecoderOptions {
+        self.allow_unsorted_tags = allow;
+        self
+    }
+
+    /// Enable or disable checking offsets against the length of the stream, enabled by default.
+    ///
+    /// The length is determined by seeking to the end of the stream when the decoder is created.
+    /// Offsets of directories, tag values and chunks that point beyond it are then reported as
+    /// [`TiffFormatError::OffsetOutOfBounds`] rather than failing later with an unexpected end
+    /// of file. Chunks whose byte count runs past the end are still decoded from the bytes before
+    /// it, outside validation mode. Disable this for streams that grow while being decoded or
+    /// whose end is expensive to seek to.
+    pub fn with_stream_length_check(mut self, check: bool) -> DecoderOptions {
+        self.skip_stream_length = !check;
+        self
+    }
+
+    /// Select the samples `LogLuv` images decode to, see [`LogLuvOutput`].
+    pub fn with_log_luv_output(mut self, log_luv_output: LogLuvOutput) -> DecoderOptions {
+        self.log_luv_output = log_luv_output;
+        self
+    }
+
+    /// Enable or disable lazy chunk tables, disabled by default.
+    ///
+    /// The `StripOffsets` and `StripByteCounts`, or `TileOffsets` and `TileByteCounts`, of an
+    /// image are read as a whole when it becomes the current image. Images of millions of chunks
+    /// thus cost tens of megabytes and the time to read them before a single chunk is decoded.
+    /// With lazy tables only the locations of the tables are kept, and the offset and byte count
+    /// of a chunk are read from the file whenever the chunk is accessed. This suits reading a few
+    /// chunks of a large image, at the cost of an additional read per chunk. Tables that are not
+    /// stored as `SHORT`, `LONG` or `LONG8` values are always read as a whole.
+    pub fn with_lazy_chunk_tables(mut self, lazy: bool) -> DecoderOptions {
+        self.lazy_chunk_tables = lazy;
+        self
+    }
+}
+
+/// The samples SGI `LogLuv` encoded images decode to.
+///
+/// `LogLuv` images always decode to three `f32` samples per pixel, `LogL` images to the single
+/// luminance sample `Y`. Luminances are in the absolute units of the file, usually candela per
+/// square meter.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LogLuvOutput {
+    /// CIE 1931 `X`, `Y` and `Z` tristimulus values, the default.
+    Xyz,
+    /// The luminance `Y` followed by the CIE 1976 `u'` and `v'` chromaticity coordinates.
+    Luv,
+}
+
+impl Default for LogLuvOutput {
+    fn default() -> Self {
+        LogLuvOutput::Xyz
+    }
+}
+
+/// The entry used for a tag that appears more than once in a directory.
+///
+/// The specification does not allow duplicated tags, but some writers produce them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DuplicateTagPolicy {
+    /// Reject the directory with [`TiffFormatError::DuplicateTag`].
+    Error,
+    /// Use the first entry of the tag.
+    FirstWins,
+    /// Use the last entry of the tag, the default.
+    LastWins,
+}
+
+impl Default for DuplicateTagPolicy {
+    fn default() -> Self {
+        DuplicateTagPolicy::LastWins
+    }
+}
+
+/// A non-fatal anomaly the decoder tolerated.
+///
+/// Warnings are only collected if enabled with [`DecoderOptions::with_warnings`]. The `ifd` field
+/// is the file offset of the image file directory the anomaly was found in.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum DecodeWarning {
+    /// An entry with a field type unknown to the decoder was skipped.
+    UnknownFieldType { ifd: u64, tag: u16, field_type: u16 },
+    /// A tag appeared more than once in a directory, see [`DuplicateTagPolicy`] for the entry
+    /// that is used.
+    DuplicateTag { ifd: u64, tag: u16 },
+    /// The entries of a directory are not sorted in ascending tag order.
+    UnsortedTags { ifd: u64 },
+    /// The `PhotometricInterpretation` of an image is missing and assumed to be `BlackIsZero`.
+    MissingPhotometricInterpretation { ifd: u64 },
+    /// A well-known tag is stored with a wrong field type and its value converted, see
+    /// [`DecoderOptions::with_lenient`].
+    CoercedFieldType { ifd: u64, tag: u16, field_type: u16 },
+    /// The byte count of a chunk does not match the bytes the chunk takes up, see
+    /// [`TiffFormatError::ChunkByteCountMismatch`].
+    ChunkByteCountMismatch {
+        ifd: u64,
+        chunk: u32,
+        declared: u64,
+        consumed: u64,
+    },
+}
+
+impl fmt::Display for DecodeWarning {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            DecodeWarning::UnknownFieldType {
+                ifd,
+                tag,
+                field_type,
+            } => write!(
+                fmt,
+                "Skipped tag {} with unknown field type {} in IFD at {}",
+                tag, field_type, ifd
+            ),
+            DecodeWarning::DuplicateTag { ifd, tag } => {
+                write!(fmt, "Tag {} appears more than once in IFD at {}", tag, ifd)
+            }
+            DecodeWarning::UnsortedTags { ifd } => {
+                write!(fmt, "Entries of IFD at {} are not sorted by tag", ifd)
+            }
+            DecodeWarning::MissingPhotometricInterpretation { ifd } => write!(
+                fmt,
+                "PhotometricInterpretation of IFD at {} is missing, assuming BlackIsZero",
+                ifd
+            ),
+            DecodeWarning::CoercedFieldType {
+                ifd,
+                tag,
+                field_type,
+            } => write!(
+                fmt,
+                "Converted tag {} of wrong field type {} in IFD at {}",
+                tag, field_type, ifd
+            ),
+            DecodeWarning::ChunkByteCountMismatch {
+                ifd,
+                chunk,
+                declared,
+                consumed,
+            } => write!(
+                fmt,
+                "Chunk {} of IFD at {} takes up {} bytes, but its byte count is {}",
+                chunk, ifd, consumed, declared
+            ),
+        }
+    }
+}
+
 /// The representation of a TIFF decoder
 ///
 /// Currently does not support decoding of interlaced images
@@ -252,6 +719,230 @@ where
     ifd_offsets: Vec<u64>,
     seen_ifds: HashSet<u64>,
     image: Image,
+    overviews: Option<Box<Decoder<R>>>,
+    validate: bool,
+    io_budget_used: u64,
+    warnings: Option<Vec<DecodeWarning>>,
+    /// The warnings in `warnings`, to report every anomaly once.
+    reported_warnings: HashSet<DecodeWarning>,
+    /// The location of the directory of the current image.
+    current_ifd: Option<u64>,
+    log_luv_output: LogLuvOutput,
+    lenient: bool,
+    duplicate_tags: DuplicateTagPolicy,
+    allow_unsorted_tags: bool,
+    lazy_chunk_tables: bool,
+    memory_usage: MemoryUsage,
+    /// The memory usage while each image was the current one, by the location of its directory.
+    #[cfg(feature = "memory-stats")]
+    image_memory_usage: Vec<(u64, MemoryUsage)>,
+}
+
+/// The location of a resolution level within a pyramid.
+///
+/// A pyramid consists of the images of the primary file and, if one was attached with
+/// [`Decoder::with_external_overviews`], the images of an external overview (`.ovr`) file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Overview {
+    /// The image with the given IFD index in the primary file.
+    Main(usize),
+    /// The image with the given IFD index in the external overview file.
+    External(usize),
+}
+
+/// The kind of image layer in a TIFF/IT (ISO 12639) prepress file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TiffItImageType {
+    /// Continuous tone picture.
+    CT,
+    /// Line work, run-length encoded with a color table.
+    LW,
+    /// High resolution continuous tone.
+    HC,
+    /// Monochrome continuous tone picture.
+    MP,
+    /// Binary picture.
+    BP,
+    /// Binary line art, run-length encoded.
+    BL,
+}
+
+/// The conformance level of a TIFF/IT (ISO 12639) file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TiffItProfile {
+    /// The restricted profile that every TIFF/IT reader must support.
+    P1,
+    /// The extended profile, which allows more compression methods and data layouts.
+    P2,
+}
+
+/// Assessment of the layout of a file against the Cloud Optimized GeoTIFF (COG) conventions.
+///
+/// Created by [`Decoder::cog_report`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct CogReport {
+    /// Every image of the file is tiled.
+    pub tiled: bool,
+    /// All image file directories are stored before any image data.
+    pub ifds_before_data: bool,
+    /// The number of reduced resolution images (overviews).
+    pub overview_count: usize,
+    /// The full resolution image comes first, followed by overviews of decreasing size.
+    pub overviews_ordered: bool,
+    /// The chunks of every image are stored in order, and the data of the smallest overview comes
+    /// first, followed by increasingly larger images.
+    pub data_ordered: bool,
+    /// The file starts with the structural metadata ("ghost header") written by GDAL.
+    pub ghost_header: bool,
+    /// The ghost header announces block leaders and every chunk is preceded by its size.
+    pub block_leaders: bool,
+}
+
+impl CogReport {
+    /// Whether the layout satisfies the requirements of a COG.
+    ///
+    /// Overviews and the GDAL specific ghost header and block leaders are recommended but not
+    /// required.
+    pub fn is_compliant(&self) -> bool {
+        self.tiled && self.ifds_before_data && self.overviews_ordered && self.data_ordered
+    }
+}
+
+/// The placement of the chunks of an image within the file.
+///
+/// Created by [`Decoder::chunk_placement`]. Chunks without any data are not considered. The
+/// placement of the chunks within the image is described by [`ChunkLayout`] instead.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ChunkPlacement {
+    /// Every chunk starts at or after the end of the previous chunk, so the chunks can be read
+    /// front to back.
+    pub ascending: bool,
+    /// Every chunk starts right where the previous chunk ends.
+    pub contiguous: bool,
+}
+
+/// The geometry of the chunks of an image, as used by the decoder.
+///
+/// Created by [`Decoder::chunk_layout`]. Chunk indices run through the chunks of a plane row by
+/// row, and through the planes one after another. Where the chunks are stored in the file is
+/// described by [`ChunkPlacement`] instead.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ChunkLayout {
+    /// Whether the image is divided into strips or tiles.
+    pub chunk_type: ChunkType,
+    /// The width of every chunk in pixels. Strips span the width of the image.
+    pub chunk_width: u32,
+    /// The height of every chunk in pixels.
+    pub chunk_height: u32,
+    /// The number of chunks in a row of chunks, always 1 for strips.
+    pub chunks_across: u32,
+    /// The number of rows of chunks.
+    pub chunks_down: u32,
+    /// The number of separately stored planes, the samples per pixel for planar images and 1
+    /// otherwise.
+    pub planes: u16,
+    /// The number of columns by which the rightmost chunks extend past the image.
+    pub padding_right: u32,
+    /// The number of rows by which the bottom chunks extend past the image. Tiles store these
+    /// rows, the last strip simply holds fewer rows.
+    pub padding_down: u32,
+    /// The `RowsPerStrip` of striped images, which may exceed the image height.
+    pub rows_per_strip: Option<u32>,
+}
+
+impl ChunkLayout {
+    /// The number of chunks of a single plane.
+    pub fn chunks_per_plane(&self) -> u32 {
+        self.chunks_across * self.chunks_down
+    }
+
+    /// The index of the chunk at `coord` in the grid of chunks of `plane`.
+    ///
+    /// Returns `None` if the plane or the coordinates lie outside of the image.
+    pub fn chunk_index(&self, plane: PlaneIndex, coord: TileCoord) -> Option<ChunkIndex> {
+        if plane.0 >= self.planes || coord.x >= self.chunks_across || coord.y >= self.chunks_down {
+            return None;
+        }
+
+        let index =
+            u32::from(plane.0) * self.chunks_per_plane() + coord.y * self.chunks_across + coord.x;
+        Some(ChunkIndex(index))
+    }
+
+    /// The plane of a chunk and its coordinates in the grid of chunks of that plane.
+    ///
+    /// Returns `None` if there is no chunk of that index.
+    pub fn chunk_position(&self, index: ChunkIndex) -> Option<(PlaneIndex, TileCoord)> {
+        let per_plane = self.chunks_per_plane();
+        if per_plane == 0 || index.0 / per_plane >= u32::from(self.planes) {
+            return None;
+        }
+
+        let plane = PlaneIndex((index.0 / per_plane) as u16);
+        let within = index.0 % per_plane;
+        let coord = TileCoord {
+            x: within % self.chunks_across,
+            y: within / self.chunks_across,
+        };
+        Some((plane, coord))
+    }
+}
+
+/// The index of a chunk of the current image, as used by the decoding functions.
+///
+/// Chunk indices run through the chunks of a plane row by row, and through the planes one after
+/// another, see [`ChunkLayout::chunk_index`] to address a chunk by its plane and position.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ChunkIndex(pub u32);
+
+/// The index of a separately stored sample plane of a planar image.
+///
+/// Images that are not planar only have the plane 0.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PlaneIndex(pub u16);
+
+/// The position of a chunk in the grid of chunks of a plane, in chunks.
+///
+/// Strips always have the column 0.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TileCoord {
+    pub x: u32,
+    pub y: u32,
+}
+
+/// The roles of the images of a whole-slide image in the Aperio SVS layout.
+///
+/// Created by [`Decoder::svs_layout`]. The images are referred to by their IFD index, to be loaded
+/// with [`Decoder::seek_to_image`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct SvsLayout {
+    /// The `ImageDescription` of the full resolution image, holding the slide metadata such as
+    /// the microns per pixel.
+    pub description: String,
+    /// The resolution levels of the slide, starting with the full resolution image.
+    pub levels: Vec<usize>,
+    /// A small, downsampled version of the whole slide.
+    pub thumbnail: Option<usize>,
+    /// A photo of the label of the slide.
+    pub label: Option<usize>,
+    /// A low resolution photo of the entire glass slide.
+    pub macro_image: Option<usize>,
+}
+
+/// Dimensions and subfile type of an image, read without decoding the image itself.
+#[derive(Clone, Copy, Debug)]
+struct IfdSummary {
+    width: u32,
+    height: u32,
+    new_subfile_type: NewSubfileType,
+}
+
+fn is_reduced_resolution(new_subfile_type: NewSubfileType) -> bool {
+    new_subfile_type.contains(NewSubfileType::REDUCED_RESOLUTION)
 }
 
 fn rev_hpredict_nsamp(buf: &mut [u8], bit_depth: u8, samples: usize) {
@@ -288,6 +979,19 @@ fn rev_hpredict_nsamp(buf: &mut [u8], bit_depth: u8, samples: usize) {
     }
 }
 
+fn predict_f16(input: &mut [u8], output: &mut [u8], samples: usize) {
+    for i in samples..input.len() {
+        input[i] = input[i].wrapping_add(input[i - samples]);
+    }
+
+    for (i, chunk) in output.chunks_mut(2).enumerate() {
+        chunk.copy_from_slice(&u16::to_ne_bytes(u16::from_be_bytes([
+            input[i],
+            input[input.len() / 2 + i],
+        ])));
+    }
+}
+
 fn predict_f32(input: &mut [u8], output: &mut [u8], samples: usize) {
     for i in samples..input.len() {
         input[i] = input[i].wrapping_add(input[i - samples]);
@@ -340,6 +1044,7 @@ fn fix_endianness_and_predict(
         Predictor::FloatingPoint => {
             let mut buffer_copy = buf.to_vec();
             match bit_depth {
+                16 => predict_f16(&mut buffer_copy, buf, samples),
                 32 => predict_f32(&mut buffer_copy, buf, samples),
                 64 => predict_f64(&mut buffer_copy, buf, samples),
                 _ => unreachable!("Caller should have validated arguments. Please file a bug."),
@@ -349,40 +1054,47 @@ fn fix_endianness_and_predict(
 }
 
 fn invert_colors(buf: &mut [u8], color_type: ColorType, sample_format: SampleFormat) {
-    match (color_type, sample_format) {
-        (ColorType::Gray(8), SampleFormat::Uint) => {
-            for x in buf {
-                *x = 0xff - *x;
+    let (bit_depth, samples) = match color_type {
+        ColorType::Gray(n) => (n, 1),
+        ColorType::GrayA(n) => (n, 2),
+        _ => return,
+    };
+    // Only the gray sample is inverted, an alpha sample keeps its meaning.
+    let pixel_bytes = usize::from(bit_depth / 8) * samples;
+    match (bit_depth, sample_format) {
+        (8, SampleFormat::Uint) => {
+            for x in buf.chunks_exact_mut(pixel_bytes) {
+                x[0] = 0xff - x[0];
             }
         }
-        (ColorType::Gray(16), SampleFormat::Uint) => {
-            for x in buf.chunks_mut(2) {
-                let v = u16::from_ne_bytes(x.try_into().unwrap());
-                x.copy_from_slice(&(0xffff - v).to_ne_bytes());
+        (16, SampleFormat::Uint) => {
+            for x in buf.chunks_exact_mut(pixel_bytes) {
+                let v = u16::from_ne_bytes(x[..2].try_into().unwrap());
+                x[..2].copy_from_slice(&(0xffff - v).to_ne_bytes());
             }
         }
-        (ColorType::Gray(32), SampleFormat::Uint) => {
-            for x in buf.chunks_mut(4) {
-                let v = u32::from_ne_bytes(x.try_into().unwrap());
-                x.copy_from_slice(&(0xffff_ffff - v).to_ne_bytes());
+        (32, SampleFormat::Uint) => {
+            for x in buf.chunks_exact_mut(pixel_bytes) {
+                let v = u32::from_ne_bytes(x[..4].try_into().unwrap());
+                x[..4].copy_from_slice(&(0xffff_ffff - v).to_ne_bytes());
             }
         }
-        (ColorType::Gray(64), SampleFormat::Uint) => {
-            for x in buf.chunks_mut(8) {
-                let v = u64::from_ne_bytes(x.try_into().unwrap());
-                x.copy_from_slice(&(0xffff_ffff_ffff_ffff - v).to_ne_bytes());
+        (64, SampleFormat::Uint) => {
+            for x in buf.chunks_exact_mut(pixel_bytes) {
+                let v = u64::from_ne_bytes(x[..8].try_into().unwrap());
+                x[..8].copy_from_slice(&(0xffff_ffff_ffff_ffff - v).to_ne_bytes());
             }
         }
-        (ColorType::Gray(32), SampleFormat::IEEEFP) => {
-            for x in buf.chunks_mut(4) {
-                let v = f32::from_ne_bytes(x.try_into().unwrap());
-                x.copy_from_slice(&(1.0 - v).to_ne_bytes());
+        (32, SampleFormat::IEEEFP) => {
+            for x in buf.chunks_exact_mut(pixel_bytes) {
+                let v = f32::from_ne_bytes(x[..4].try_into().unwrap());
+                x[..4].copy_from_slice(&(1.0 - v).to_ne_bytes());
             }
         }
-        (ColorType::Gray(64), SampleFormat::IEEEFP) => {
-            for x in buf.chunks_mut(8) {
-                let v = f64::from_ne_bytes(x.try_into().unwrap());
-                x.copy_from_slice(&(1.0 - v).to_ne_bytes());
+        (64, SampleFormat::IEEEFP) => {
+            for x in buf.chunks_exact_mut(pixel_bytes) {
+                let v = f64::from_ne_bytes(x[..8].try_into().unwrap());
+                x[..8].copy_from_slice(&(1.0 - v).to_ne_bytes());
             }
         }
         _ => {}
@@ -391,6 +1103,11 @@ fn invert_colors(buf: &mut [u8], color_type: ColorType, sample_format: SampleFor
 
 /// Fix endianness. If `byte_order` matches the host, then conversion is a no-op.
 fn fix_endianness(buf: &mut [u8], byte_order: ByteOrder, bit_depth: u8) {
+    // Samples that do not fill whole bytes are packed into a stream of bits, which is stored
+    // independently of the byte order.
+    if bit_depth % 8 != 0 {
+        return;
+    }
     match byte_order {
         ByteOrder::LittleEndian => match bit_depth {
             0..=8 => {}
@@ -419,9 +1136,70 @@ fn fix_endianness(buf: &mut [u8], byte_order: ByteOrder, bit_depth: u8) {
     };
 }
 
+/// Whether an error decoding a chunk is caused by malformed or truncated chunk data.
+fn is_corrupt_chunk_error(err: &TiffError) -> bool {
+    match err {
+        TiffError::FormatError(_) => true,
+        TiffError::IoError(err) => matches!(
+            err.kind(),
+            io::ErrorKind::InvalidData | io::ErrorKind::UnexpectedEof
+        ),
+        _ => false,
+    }
+}
+
+impl<R: Read + Seek + Clone> Decoder<R> {
+    /// Duplicate the decoder, including the directories parsed so far and the current image.
+    ///
+    /// The copy continues from the same image with a clone of the reader, so several threads can
+    /// decode different images of the same file without parsing the header and directories again.
+    /// For that, the clones of the reader must not share a cursor: each read seeks to its offset
+    /// first, and the reads of another thread could move a shared position in between. Readers
+    /// sharing their position, such as handles to one open file, are only fine for clones used
+    /// one after the other. External overviews are duplicated as well.
+    pub fn try_clone(&self) -> TiffResult<Decoder<R>> {
+        let overviews = match &self.overviews {
+            Some(overviews) => Some(Box::new(overviews.try_clone()?)),
+            None => None,
+        };
+
+        Ok(Decoder {
+            reader: self.reader.clone(),
+            bigtiff: self.bigtiff,
+            limits: self.limits.clone(),
+            next_ifd: self.next_ifd,
+            ifd_offsets: self.ifd_offsets.clone(),
+            seen_ifds: self.seen_ifds.clone(),
+            image: self.image.clone(),
+            overviews,
+            validate: self.validate,
+            io_budget_used: self.io_budget_used,
+            warnings: self.warnings.clone(),
+            reported_warnings: self.reported_warnings.clone(),
+            current_ifd: self.current_ifd,
+            log_luv_output: self.log_luv_output,
+            lenient: self.lenient,
+            duplicate_tags: self.duplicate_tags,
+            allow_unsorted_tags: self.allow_unsorted_tags,
+            lazy_chunk_tables: self.lazy_chunk_tables,
+            memory_usage: self.memory_usage,
+            #[cfg(feature = "memory-stats")]
+            image_memory_usage: self.image_memory_usage.clone(),
+        })
+    }
+}
+
 impl<R: Read + Seek> Decoder<R> {
     /// Create a new decoder that decodes from the stream ```r```
-    pub fn new(mut r: R) -> TiffResult<Decoder<R>> {
+    pub fn new(r: R) -> TiffResult<Decoder<R>> {
+        Decoder::with_options(r, DecoderOptions::default())
+    }
+
+    /// Create a new decoder that decodes from the stream ```r``` with the given options.
+    ///
+    /// Unlike configuring an existing decoder, the options already apply to reading the first
+    /// image file directory.
+    pub fn with_options(mut r: R, options: DecoderOptions) -> TiffResult<Decoder<R>> {
         let mut endianess = Vec::with_capacity(2);
         (&mut r).take(2).read_to_end(&mut endianess)?;
         let byte_order = match &*endianess {
@@ -434,6 +1212,9 @@ impl<R: Read + Seek> Decoder<R> {
             }
         };
         let mut reader = SmartReader::wrap(r, byte_order);
+        if !options.skip_stream_length {
+            reader.measure_stream_length()?;
+        }
 
         let bigtiff = match reader.read_u16()? {
             42 => false,
@@ -471,7 +1252,7 @@ impl<R: Read + Seek> Decoder<R> {
         let mut decoder = Decoder {
             reader,
             bigtiff,
-            limits: Default::default(),
+            limits: options.limits,
             next_ifd,
             ifd_offsets,
             seen_ifds,
@@ -490,63 +1271,428 @@ impl<R: Read + Seek> Decoder<R> {
                 planar_config: PlanarConfiguration::Chunky,
                 strip_decoder: None,
                 tile_attributes: None,
-                chunk_offsets: Vec::new(),
-                chunk_bytes: Vec::new(),
+                chunk_offsets: ChunkTable::Loaded(Arc::new(Vec::new())),
+                chunk_bytes: ChunkTable::Loaded(Arc::new(Vec::new())),
+                log_luv_output: options.log_luv_output,
+            },
+            overviews: None,
+            validate: options.validate,
+            io_budget_used: 0,
+            warnings: if options.collect_warnings {
+                Some(Vec::new())
+            } else {
+                None
             },
+            reported_warnings: HashSet::new(),
+            current_ifd: None,
+            log_luv_output: options.log_luv_output,
+            lenient: options.lenient,
+            duplicate_tags: options.duplicate_tags,
+            allow_unsorted_tags: options.allow_unsorted_tags,
+            lazy_chunk_tables: options.lazy_chunk_tables,
+            memory_usage: MemoryUsage::default(),
+            #[cfg(feature = "memory-stats")]
+            image_memory_usage: Vec::new(),
         };
         decoder.next_image()?;
         Ok(decoder)
     }
 
+    /// Set the decoding limits.
+    ///
+    /// The first image file directory has already been read with the default limits, use
+    /// [`Decoder::with_options`] to apply the limits to it as well.
     pub fn with_limits(mut self, limits: Limits) -> Decoder<R> {
         self.limits = limits;
         self
     }
 
-    pub fn dimensions(&mut self) -> TiffResult<(u32, u32)> {
-        Ok((self.image().width, self.image().height))
-    }
+    /// Identify the current image as a TIFF/IT (ISO 12639) layer.
+    ///
+    /// Returns `None` if the image is not marked with any of the TIFF/IT specific tags or
+    /// compression methods. This only looks at the directory, the image data is not inspected.
+    pub fn tiff_it_image_type(&self) -> Option<TiffItImageType> {
+        let image = self.image();
+        match image.compression_method {
+            CompressionMethod::IT8CTPAD => return Some(TiffItImageType::CT),
+            CompressionMethod::IT8LW => return Some(TiffItImageType::LW),
+            CompressionMethod::IT8MP => return Some(TiffItImageType::MP),
+            CompressionMethod::IT8BL => return Some(TiffItImageType::BL),
+            _ => {}
+        }
 
-    pub fn colortype(&mut self) -> TiffResult<ColorType> {
-        self.image().colortype()
-    }
+        let ifd = image.ifd.as_ref().unwrap();
+        if ifd.contains_key(&Tag::HCUsage) {
+            return Some(TiffItImageType::HC);
+        }
 
-    fn image(&self) -> &Image {
-        &self.image
+        let is_tiff_it = ifd
+            .keys()
+            .any(|tag| (34016..=34032).contains(&tag.to_u16()));
+        if !is_tiff_it {
+            return None;
+        }
+
+        match (image.photometric_interpretation, image.bits_per_sample) {
+            (_, 1) => Some(TiffItImageType::BP),
+            (PhotometricInterpretation::CMYK, _) => Some(TiffItImageType::CT),
+            (
+                PhotometricInterpretation::BlackIsZero | PhotometricInterpretation::WhiteIsZero,
+                _,
+            ) => Some(TiffItImageType::MP),
+            _ => None,
+        }
     }
 
-    /// Loads the IFD at the specified index in the list, if one exists
-    pub fn seek_to_image(&mut self, ifd_index: usize) -> TiffResult<()> {
-        // Check whether we have seen this IFD before, if so then the index will be less than the length of the list of ifd offsets
-        if ifd_index >= self.ifd_offsets.len() {
-            // We possibly need to load in the next IFD
-            if self.next_ifd.is_none() {
-                return Err(TiffError::FormatError(
-                    TiffFormatError::ImageFileDirectoryNotFound,
-                ));
+    /// Determine the TIFF/IT (ISO 12639) profile the current image conforms to.
+    ///
+    /// An image conforms to P1 if it uses the only data layout P1 allows for its layer type:
+    /// uncompressed, chunky 8-bit CMYK for CT, uncompressed 8-bit for MP, uncompressed for BP and
+    /// the TIFF/IT run-length encoding for LW. Any other TIFF/IT layer is reported as P2.
+    pub fn tiff_it_profile(&self) -> Option<TiffItProfile> {
+        let image = self.image();
+        let uncompressed = image.compression_method == CompressionMethod::None;
+        let p1 = match self.tiff_it_image_type()? {
+            TiffItImageType::CT => {
+                uncompressed
+                    && image.planar_config == PlanarConfiguration::Chunky
+                    && image.photometric_interpretation == PhotometricInterpretation::CMYK
+                    && image.samples == 4
+                    && image.bits_per_sample == 8
             }
+            TiffItImageType::MP => uncompressed && image.bits_per_sample == 8,
+            TiffItImageType::BP => uncompressed,
+            TiffItImageType::LW => true,
+            TiffItImageType::HC | TiffItImageType::BL => false,
+        };
 
-            loop {
-                // Follow the list until we find the one we want, or we reach the end, whichever happens first
-                let (_ifd, next_ifd) = self.next_ifd()?;
-
-                if next_ifd.is_none() {
-                    break;
-                }
+        Some(if p1 {
+            TiffItProfile::P1
+        } else {
+            TiffItProfile::P2
+        })
+    }
 
-                if ifd_index < self.ifd_offsets.len() {
-                    break;
-                }
-            }
+    /// Read the chromaticity of the white point of the current image, as `[x, y]`.
+    ///
+    /// Returns `None` if the image has no `WhitePoint` tag.
+    pub fn white_point(&mut self) -> TiffResult<Option<[f64; 2]>> {
+        let mut white_point = [0.0; 2];
+        if !self.find_rationals(Tag::WhitePoint, &mut white_point)? {
+            return Ok(None);
         }
+        Ok(Some(white_point))
+    }
 
-        // If the index is within the list of ifds then we can load the selected image/IFD
-        if let Some(ifd_offset) = self.ifd_offsets.get(ifd_index) {
-            let (ifd, _next_ifd) = Self::read_ifd(&mut self.reader, self.bigtiff, *ifd_offset)?;
+    /// Read the chromaticities of the primaries of the current image.
+    ///
+    /// The values are ordered `[red x, red y, green x, green y, blue x, blue y]`. Returns `None`
+    /// if the image has no `PrimaryChromaticities` tag.
+    pub fn primary_chromaticities(&mut self) -> TiffResult<Option<[f64; 6]>> {
+        let mut primaries = [0.0; 6];
+        if !self.find_rationals(Tag::PrimaryChromaticities, &mut primaries)? {
+            return Ok(None);
+        }
+        Ok(Some(primaries))
+    }
 
-            self.image = Image::from_reader(&mut self.reader, ifd, &self.limits, self.bigtiff)?;
+    /// Read the palette of the current image, an `RGBPalette` image whose samples are indices
+    /// into it.
+    ///
+    /// The palette has `2**BitsPerSample` colors, each of a red, green and blue value of 16 bits.
+    /// Returns `None` if the image has no `ColorMap` tag.
+    pub fn palette(&mut self) -> TiffResult<Option<Vec<[u16; 3]>>> {
+        let values = match self.find_tag(Tag::ColorMap)? {
+            Some(value) => value.into_u16_vec()?,
+            None => return Ok(None),
+        };
 
-            Ok(())
+        let bits_per_sample = self.image().bits_per_sample;
+        if bits_per_sample > 16 {
+            return Err(TiffFormatError::InvalidTagValueType(Tag::ColorMap).into());
+        }
+
+        // The map holds all red values, then all green values, then all blue values.
+        let colors = 1usize << bits_per_sample;
+        if values.len() != 3 * colors {
+            return Err(TiffFormatError::InconsistentSizesEncountered.into());
+        }
+        let (red, rest) = values.split_at(colors);
+        let (green, blue) = rest.split_at(colors);
+
+        Ok(Some(
+            (0..colors)
+                .map(|index| [red[index], green[index], blue[index]])
+                .collect(),
+        ))
+    }
+
+    /// Read the transfer function tables of the current image.
+    ///
+    /// Each table has `2**BitsPerSample` entries. There is either a single table that applies to
+    /// all color channels, or one table per channel. Extra samples such as alpha have no table.
+    /// Returns `None` if the image has no `TransferFunction` tag.
+    pub fn transfer_function(&mut self) -> TiffResult<Option<Vec<Vec<u16>>>> {
+        let values = match self.find_tag(Tag::TransferFunction)? {
+            Some(value) => value.into_u16_vec()?,
+            None => return Ok(None),
+        };
+
+        let bits_per_sample = self.image().bits_per_sample;
+        if bits_per_sample > 16 {
+            return Err(TiffFormatError::InvalidTagValueType(Tag::TransferFunction).into());
+        }
+
+        let color_channels = usize::from(self.image().samples) - self.extra_samples()?.len();
+        let table_len = 1usize << bits_per_sample;
+        let tables = values.len() / table_len;
+        if values.len() % table_len != 0 || !(tables == 1 || tables == color_channels) {
+            return Err(TiffFormatError::InvalidTagValueType(Tag::TransferFunction).into());
+        }
+
+        Ok(Some(
+            values
+                .chunks_exact(table_len)
+                .map(<[u16]>::to_vec)
+                .collect(),
+        ))
+    }
+
+    /// Read the Photoshop layer data (`ImageSourceData`) of the current image as a raw blob.
+    ///
+    /// The blob is not interpreted. Its size is bounded by [`Limits::decoding_buffer_size`].
+    /// Returns `None` if the image has no `ImageSourceData` tag.
+    pub fn image_source_data(&mut self) -> TiffResult<Option<Vec<u8>>> {
+        let entry = match self
+            .image()
+            .ifd
+            .as_ref()
+            .unwrap()
+            .get(&Tag::ImageSourceData)
+        {
+            None => return Ok(None),
+            Some(entry) => entry.clone(),
+        };
+
+        match entry.field_type() {
+            Type::UNDEFINED | Type::BYTE => {}
+            _ => return Err(TiffFormatError::InvalidTagValueType(Tag::ImageSourceData).into()),
+        }
+
+        Ok(Some(entry.bytes_val(
+            Tag::ImageSourceData,
+            &self.limits,
+            self.bigtiff,
+            &mut self.reader,
+        )?))
+    }
+
+    /// Read the ICC color profile (`IccProfile`) of the current image.
+    ///
+    /// The profile is not interpreted. Its size is bounded by [`Limits::decoding_buffer_size`].
+    /// Returns `None` if the image has no `IccProfile` tag.
+    pub fn icc_profile(&mut self) -> TiffResult<Option<Vec<u8>>> {
+        let entry = match self.image().ifd.as_ref().unwrap().get(&Tag::IccProfile) {
+            None => return Ok(None),
+            Some(entry) => entry.clone(),
+        };
+
+        match entry.field_type() {
+            Type::UNDEFINED | Type::BYTE => {}
+            _ => return Err(TiffFormatError::InvalidTagValueType(Tag::IccProfile).into()),
+        }
+
+        Ok(Some(entry.bytes_val(
+            Tag::IccProfile,
+            &self.limits,
+            self.bigtiff,
+            &mut self.reader,
+        )?))
+    }
+
+    /// Read a tag with `values.len()` rational values into `values`.
+    ///
+    /// Returns `false` if the tag is not present.
+    fn find_rationals(&mut self, tag: Tag, values: &mut [f64]) -> TiffResult<bool> {
+        let list = match self.find_tag(tag)? {
+            Some(Value::List(list)) => list,
+            Some(value) => vec![value],
+            None => return Ok(false),
+        };
+
+        if list.len() != values.len() {
+            return Err(TiffFormatError::InvalidTagValueType(tag).into());
+        }
+
+        for (value, out) in list.into_iter().zip(values) {
+            let (numerator, denominator) = match value {
+                Value::Rational(n, d) => (f64::from(n), f64::from(d)),
+                Value::RationalBig(n, d) => (n as f64, d as f64),
+                _ => return Err(TiffFormatError::InvalidTagValueType(tag).into()),
+            };
+            if denominator == 0.0 {
+                return Err(TiffFormatError::InvalidTagValueType(tag).into());
+            }
+            *out = numerator / denominator;
+        }
+
+        Ok(true)
+    }
+
+    /// Enable validation mode.
+    ///
+    /// In validation mode every image file directory is checked for structures that contradict the
+    /// file header before it is read, such as entries that only make sense in the opposite byte
+    /// order or an embedded TIFF header in place of a directory. These are reported as
+    /// [`TiffFormatError::InconsistentByteOrder`] instead of being decoded as garbage.
+    ///
+    /// The chunks of every image are also checked when the image is loaded, instead of when they
+    /// are read, for offsets that wrapped around the 4 GiB limit of a non-BigTIFF file. Tiles whose
+    /// width or length is not a multiple of 16, which the specification requires, are reported as
+    /// [`TiffFormatError::InvalidTileSize`]. Such tiles are decoded normally otherwise.
+    ///
+    /// Directories whose entries are not sorted in ascending tag order, as the specification
+    /// requires, are rejected with [`TiffFormatError::UnsortedTags`] unless permitted with
+    /// [`DecoderOptions::with_unsorted_tags`]. Outside of validation mode they are read regardless,
+    /// see [`Decoder::tag_order`] for the order stored in the file. Directories with a tag that
+    /// appears more than once are rejected with [`TiffFormatError::DuplicateTag`].
+    ///
+    /// The directories and the image that were read before validation was enabled are checked
+    /// immediately.
+    pub fn with_validation(mut self) -> TiffResult<Decoder<R>> {
+        self.validate = true;
+        self.check_image()?;
+        let pending = self.next_ifd;
+        for &offset in self.ifd_offsets.iter().filter(|&&o| Some(o) != pending) {
+            Self::check_ifd_byte_order(&mut self.reader, self.bigtiff, offset)?;
+            let tags = Self::read_tag_order(&mut self.reader, self.bigtiff, offset)?;
+            if !self.allow_unsorted_tags
+                && tags
+                    .windows(2)
+                    .any(|pair| pair[0].to_u16() > pair[1].to_u16())
+            {
+                return Err(TiffFormatError::UnsortedTags(offset).into());
+            }
+            let mut seen = HashSet::new();
+            if let Some(&tag) = tags.iter().find(|&&tag| !seen.insert(tag)) {
+                return Err(TiffFormatError::DuplicateTag(tag, offset).into());
+            }
+        }
+        Ok(self)
+    }
+
+    /// Attach a decoder for an external overview file, such as the `.ovr` sibling written by GIS
+    /// tools.
+    ///
+    /// The images of that file are then considered as additional resolution levels by
+    /// [`best_overview_for_scale`][Decoder::best_overview_for_scale] and can be accessed with
+    /// [`seek_to_overview`][Decoder::seek_to_overview].
+    pub fn with_external_overviews(mut self, overviews: Decoder<R>) -> Decoder<R> {
+        self.overviews = Some(Box::new(overviews));
+        self
+    }
+
+    /// Returns the decoder of the attached external overview file, if any.
+    pub fn external_overviews(&mut self) -> Option<&mut Decoder<R>> {
+        self.overviews.as_deref_mut()
+    }
+
+    pub fn dimensions(&mut self) -> TiffResult<(u32, u32)> {
+        Ok((self.image().width, self.image().height))
+    }
+
+    /// Returns the color type of the current image.
+    ///
+    /// An image with a single sample beyond those of its color model has the matching color type
+    /// with alpha, e.g. [`ColorType::RGBA`], whatever its `ExtraSamples` tag says. An image with
+    /// more of them, such as alpha and spot color channels, is [`ColorType::Multiband`] with all
+    /// of its samples, the color samples first. The meaning of the other samples is given by
+    /// [`extra_samples`][Decoder::extra_samples].
+    pub fn colortype(&mut self) -> TiffResult<ColorType> {
+        self.image().colortype()
+    }
+
+    /// Returns the meaning of the samples following the color samples of the current image, as
+    /// given by its `ExtraSamples` tag.
+    ///
+    /// There is one entry for each of the last samples of a pixel, e.g. an alpha channel followed
+    /// by [`ExtraSample::Unspecified`] spot color channels. Values the specification does not
+    /// define are returned as [`ExtraSample::Unknown`]. The vector is empty if the tag is missing.
+    pub fn extra_samples(&mut self) -> TiffResult<Vec<ExtraSample>> {
+        let extra_samples = self
+            .find_tag_unsigned_vec::<u16>(Tag::ExtraSamples)?
+            .unwrap_or_default()
+            .into_iter()
+            .map(ExtraSample::from_u16_exhaustive)
+            .collect::<Vec<_>>();
+        if extra_samples.len() > usize::from(self.image().samples) {
+            return Err(TiffFormatError::InvalidTagValueType(Tag::ExtraSamples).into());
+        }
+
+        Ok(extra_samples)
+    }
+
+    /// Returns the kind of the current image as given by its `NewSubfileType` tag, e.g. whether
+    /// it is a reduced resolution version or a mask of another image.
+    ///
+    /// The flags are empty if the tag is missing.
+    pub fn new_subfile_type(&mut self) -> TiffResult<NewSubfileType> {
+        Ok(self
+            .find_tag_unsigned(Tag::NewSubfileType)?
+            .map(NewSubfileType::from_bits)
+            .unwrap_or_default())
+    }
+
+    /// Describes the samples of the current image as decoded, see [`PixelFormat`].
+    ///
+    /// Unlike [`colortype`][Decoder::colortype] this also describes images whose samples have no
+    /// matching color type, such as palette images.
+    pub fn pixel_format(&mut self) -> TiffResult<PixelFormat> {
+        let extra_samples = self.extra_samples()?;
+        let image = self.image();
+        let samples = usize::from(image.samples);
+        Ok(PixelFormat::new(
+            image.photometric_interpretation,
+            vec![image.bits_per_sample; samples],
+            vec![image.sample_format; samples],
+        )
+        .with_extra_samples(extra_samples)
+        .with_planar_config(image.planar_config))
+    }
+
+    fn image(&self) -> &Image {
+        &self.image
+    }
+
+    /// Loads the IFD at the specified index in the list, if one exists
+    pub fn seek_to_image(&mut self, ifd_index: usize) -> TiffResult<()> {
+        // Check whether we have seen this IFD before, if so then the index will be less than the length of the list of ifd offsets
+        if ifd_index >= self.ifd_offsets.len() {
+            // We possibly need to load in the next IFD
+            if self.next_ifd.is_none() {
+                return Err(TiffError::FormatError(
+                    TiffFormatError::ImageFileDirectoryNotFound,
+                ));
+            }
+
+            loop {
+                // Follow the list until we find the one we want, or we reach the end, whichever happens first
+                let (_ifd, next_ifd) = self.next_ifd()?;
+
+                if next_ifd.is_none() {
+                    break;
+                }
+
+                if ifd_index < self.ifd_offsets.len() {
+                    break;
+                }
+            }
+        }
+
+        // If the index is within the list of ifds then we can load the selected image/IFD
+        if let Some(&ifd_offset) = self.ifd_offsets.get(ifd_index) {
+            let (ifd, _next_ifd) = self.read_ifd_at(ifd_offset)?;
+            self.load_image(ifd, Some(ifd_offset))
         } else {
             Err(TiffError::FormatError(
                 TiffFormatError::ImageFileDirectoryNotFound,
@@ -561,11 +1707,8 @@ impl<R: Read + Seek> Decoder<R> {
             ));
         }
 
-        let (ifd, next_ifd) = Self::read_ifd(
-            &mut self.reader,
-            self.bigtiff,
-            self.next_ifd.take().unwrap(),
-        )?;
+        let ifd_location = self.next_ifd.take().unwrap();
+        let (ifd, next_ifd) = self.read_ifd_at(ifd_location)?;
 
         if let Some(next) = next_ifd {
             if !self.seen_ifds.insert(next) {
@@ -582,12 +1725,134 @@ impl<R: Read + Seek> Decoder<R> {
     /// If there is no further image in the TIFF file a format error is returned.
     /// To determine whether there are more images call `TIFFDecoder::more_images` instead.
     pub fn next_image(&mut self) -> TiffResult<()> {
+        let ifd_location = self.next_ifd;
         let (ifd, _next_ifd) = self.next_ifd()?;
+        self.load_image(ifd, ifd_location)
+    }
+
+    /// Returns the offsets of the directories listed in the `SubIFDs` tag of the current image.
+    ///
+    /// These are typically the reduced resolution levels of a pyramid. The list is empty if the
+    /// image has no such tag.
+    pub fn sub_ifds(&mut self) -> TiffResult<Vec<u64>> {
+        match self.find_tag(Tag::SubIFDs)? {
+            Some(value) => value.into_ifd_vec(),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Loads the image at `index` in the `SubIFDs` of the current image.
+    ///
+    /// The directories of sub images are not part of the main chain, so this does not change
+    /// which image [`next_image`][Decoder::next_image] reads. Use
+    /// [`seek_to_image`][Decoder::seek_to_image] to return to the parent image.
+    pub fn seek_to_sub_image(&mut self, index: usize) -> TiffResult<()> {
+        let ifd_location = *self.sub_ifds()?.get(index).ok_or(TiffError::FormatError(
+            TiffFormatError::ImageFileDirectoryNotFound,
+        ))?;
+        let (ifd, _next_ifd) = self.read_ifd_at(ifd_location)?;
+        self.load_image(ifd, Some(ifd_location))
+    }
+
+    /// Makes the image of the directory `ifd` read from `ifd_location` the current image.
+    fn load_image(&mut self, ifd: Directory, ifd_location: Option<u64>) -> TiffResult<()> {
+        let photometric_missing = !ifd.contains_key(&Tag::PhotometricInterpretation);
+        let mut coerced: Vec<_> = ifd
+            .iter()
+            .filter(|(&tag, entry)| {
+                self.lenient && tag_reader::coerced_field_type(tag, entry.field_type())
+            })
+            .map(|(tag, entry)| (tag.to_u16(), entry.field_type().to_u16()))
+            .collect();
+        coerced.sort_unstable();
+        let tag_value_bytes = ifd.values().map(ifd::Entry::value_bytes).max().unwrap_or(0);
+
+        self.image = Image::from_reader(
+            &mut self.reader,
+            ifd,
+            &self.limits,
+            self.bigtiff,
+            self.log_luv_output,
+            self.lenient,
+            self.lazy_chunk_tables,
+        )?;
+        self.current_ifd = ifd_location;
+        self.record_memory_usage(MemoryUsage {
+            tag_value_bytes: usize::try_from(tag_value_bytes).unwrap_or(usize::MAX),
+            ..MemoryUsage::default()
+        });
+        if self.validate {
+            self.check_image()?;
+        }
+
+        if let Some(ifd) = ifd_location {
+            if photometric_missing {
+                self.record_warning(DecodeWarning::MissingPhotometricInterpretation { ifd });
+            }
+            for (tag, field_type) in coerced {
+                self.record_warning(DecodeWarning::CoercedFieldType {
+                    ifd,
+                    tag,
+                    field_type,
+                });
+            }
+        }
 
-        self.image = Image::from_reader(&mut self.reader, ifd, &self.limits, self.bigtiff)?;
         Ok(())
     }
 
+    /// Returns the sizes of the largest buffers allocated since the decoder was created or the
+    /// counters were reset with [`reset_memory_usage`][Decoder::reset_memory_usage].
+    pub fn memory_usage(&self) -> MemoryUsage {
+        self.memory_usage
+    }
+
+    /// Resets the counters returned by [`memory_usage`][Decoder::memory_usage].
+    pub fn reset_memory_usage(&mut self) {
+        self.memory_usage = MemoryUsage::default();
+    }
+
+    /// Returns the largest buffers allocated for each image, by the location of its directory.
+    ///
+    /// Images are listed in the order they were first loaded. Unlike
+    /// [`memory_usage`][Decoder::memory_usage] these are not reset.
+    #[cfg(feature = "memory-stats")]
+    pub fn image_memory_usage(&self) -> &[(u64, MemoryUsage)] {
+        &self.image_memory_usage
+    }
+
+    fn record_memory_usage(&mut self, usage: MemoryUsage) {
+        self.memory_usage.record(usage);
+
+        #[cfg(feature = "memory-stats")]
+        {
+            if let Some(ifd) = self.current_ifd {
+                let images = &mut self.image_memory_usage;
+                match images.iter_mut().find(|(location, _)| *location == ifd) {
+                    Some((_, image)) => image.record(usage),
+                    None => images.push((ifd, usage)),
+                }
+            }
+        }
+    }
+
+    /// Records the compressed size `bytes` of a chunk as an intermediate buffer.
+    fn record_chunk_memory_usage(&mut self, bytes: u64) {
+        self.record_memory_usage(MemoryUsage {
+            intermediate_bytes: usize::try_from(bytes).unwrap_or(usize::MAX),
+            ..MemoryUsage::default()
+        });
+    }
+
+    /// Records the size of a result buffer.
+    fn record_result_memory_usage(&mut self, result: &mut DecodingResult) {
+        let bytes = result.as_buffer(0).as_bytes_mut().len();
+        self.record_memory_usage(MemoryUsage {
+            result_bytes: bytes,
+            ..MemoryUsage::default()
+        });
+    }
+
     /// Returns `true` if there is at least one more image available.
     pub fn more_images(&self) -> bool {
         self.next_ifd.is_some()
@@ -598,6 +1863,358 @@ impl<R: Read + Seek> Decoder<R> {
         self.reader.byte_order
     }
 
+    /// Returns `true` if the file is a BigTIFF file, with 64-bit offsets.
+    pub fn is_bigtiff(&self) -> bool {
+        self.bigtiff
+    }
+
+    /// Returns the version number of the file header, 42 for TIFF and 43 for BigTIFF files.
+    pub fn header_version(&self) -> u16 {
+        if self.bigtiff {
+            43
+        } else {
+            42
+        }
+    }
+
+    /// Reads the dimensions and subfile type of every image in the file.
+    ///
+    /// Only the directories are visited, no image data is decoded and the current image is left
+    /// unchanged.
+    fn ifd_summaries(&mut self) -> TiffResult<Vec<IfdSummary>> {
+        self.visit_ifds(|_, tag_reader| {
+            Ok(IfdSummary {
+                width: tag_reader.require_tag(Tag::ImageWidth)?.into_u32()?,
+                height: tag_reader.require_tag(Tag::ImageLength)?.into_u32()?,
+                new_subfile_type: tag_reader.new_subfile_type()?,
+            })
+        })
+    }
+
+    /// Calls `visit` with the offset and the tags of every IFD in the file, in order.
+    ///
+    /// The current image is left unchanged.
+    fn visit_ifds<T>(
+        &mut self,
+        mut visit: impl FnMut(u64, &mut TagReader<R>) -> TiffResult<T>,
+    ) -> TiffResult<Vec<T>> {
+        let mut results = Vec::new();
+        let mut seen = HashSet::new();
+        let mut next = self.ifd_offsets.first().copied();
+
+        while let Some(offset) = next {
+            if !seen.insert(offset) {
+                return Err(TiffError::FormatError(TiffFormatError::CycleInOffsets));
+            }
+
+            let (ifd, next_ifd) = self.read_ifd_at(offset)?;
+            let mut tag_reader = TagReader {
+                reader: &mut self.reader,
+                ifd: &ifd,
+                limits: &self.limits,
+                bigtiff: self.bigtiff,
+                lenient: self.lenient,
+            };
+
+            results.push(visit(offset, &mut tag_reader)?);
+            next = next_ifd;
+        }
+
+        Ok(results)
+    }
+
+    /// Assesses whether the file is laid out as a Cloud Optimized GeoTIFF (COG).
+    ///
+    /// Only the directories, the GDAL ghost header and the block leaders are read, no image data
+    /// is decoded and the current image is left unchanged. See [`CogReport`] for the individual
+    /// criteria.
+    pub fn cog_report(&mut self) -> TiffResult<CogReport> {
+        struct Layout {
+            offset: u64,
+            width: u32,
+            new_subfile_type: NewSubfileType,
+            tiled: bool,
+            chunk_offsets: Vec<u64>,
+            chunk_bytes: Vec<u64>,
+        }
+
+        let layouts = self.visit_ifds(|offset, tag_reader| {
+            let tiled = tag_reader.ifd.contains_key(&Tag::TileOffsets);
+            let (offsets_tag, bytes_tag) = if tiled {
+                (Tag::TileOffsets, Tag::TileByteCounts)
+            } else {
+                (Tag::StripOffsets, Tag::StripByteCounts)
+            };
+
+            Ok(Layout {
+                offset,
+                width: tag_reader.require_tag(Tag::ImageWidth)?.into_u32()?,
+                new_subfile_type: tag_reader.new_subfile_type()?,
+                tiled,
+                chunk_offsets: tag_reader.require_tag(offsets_tag)?.into_u64_vec()?,
+                chunk_bytes: tag_reader.require_tag(bytes_tag)?.into_u64_vec()?,
+            })
+        })?;
+
+        // Sparse chunks have no data and do not count for the data layout.
+        let data = |layout: &Layout| {
+            layout
+                .chunk_offsets
+                .iter()
+                .zip(&layout.chunk_bytes)
+                .filter(|&(_, &bytes)| bytes > 0)
+                .map(|(&offset, &bytes)| (offset, bytes))
+                .collect::<Vec<_>>()
+        };
+
+        let first_data = layouts
+            .iter()
+            .flat_map(&data)
+            .map(|(offset, _)| offset)
+            .min();
+        let ifds_before_data = match first_data {
+            Some(first_data) => layouts.iter().all(|layout| layout.offset < first_data),
+            None => true,
+        };
+
+        // Masks accompany the image levels and are not considered for the order of the levels.
+        let levels: Vec<&Layout> = layouts
+            .iter()
+            .filter(|layout| !layout.new_subfile_type.contains(NewSubfileType::MASK))
+            .collect();
+        let overview_count = levels
+            .iter()
+            .filter(|layout| is_reduced_resolution(layout.new_subfile_type))
+            .count();
+        let overviews_ordered = levels.windows(2).all(|pair| {
+            is_reduced_resolution(pair[1].new_subfile_type) && pair[1].width < pair[0].width
+        });
+
+        // Within a level the chunks are stored in order, and the data of every level precedes
+        // the data of the next larger level. Chunks ending beyond `u64::MAX` are out of order.
+        let precedes = |(offset, length): (u64, u64), next: u64| {
+            offset.checked_add(length).map_or(false, |end| end <= next)
+        };
+        let tiles_ordered = layouts.iter().all(|layout| {
+            data(layout)
+                .windows(2)
+                .all(|pair| precedes(pair[0], pair[1].0))
+        });
+        let levels_ordered = levels.windows(2).all(|pair| {
+            let larger = data(pair[0]);
+            let smaller = data(pair[1]);
+            match (larger.first(), smaller.last()) {
+                (Some(larger), Some(&smaller)) => precedes(smaller, larger.0),
+                _ => true,
+            }
+        });
+
+        let block_leader = self.read_ghost_header()?.map(|header| {
+            header
+                .lines()
+                .any(|line| line.trim() == "BLOCK_LEADER=SIZE_AS_UINT4")
+        });
+        let block_leaders = match block_leader {
+            Some(true) => {
+                let mut valid = true;
+                for (offset, bytes) in layouts.iter().flat_map(&data) {
+                    if offset < 4 {
+                        valid = false;
+                        break;
+                    }
+                    self.goto_offset_u64(offset - 4)?;
+                    if u64::from(self.reader.read_u32()?) != bytes {
+                        valid = false;
+                        break;
+                    }
+                }
+                valid
+            }
+            _ => false,
+        };
+
+        Ok(CogReport {
+            tiled: layouts.iter().all(|layout| layout.tiled),
+            ifds_before_data,
+            overview_count,
+            overviews_ordered,
+            data_ordered: tiles_ordered && levels_ordered,
+            ghost_header: block_leader.is_some(),
+            block_leaders,
+        })
+    }
+
+    /// Identifies the images of a whole-slide image written in the Aperio SVS layout.
+    ///
+    /// Returns `None` unless the description of the first image starts with `Aperio`. That image
+    /// is the full resolution level and the second one is the thumbnail, unless it is tiled. The
+    /// label and macro images are recognized by their description, all remaining images are
+    /// considered reduced resolution levels. Only the directories are read, no image data is
+    /// decoded and the current image is left unchanged.
+    pub fn svs_layout(&mut self) -> TiffResult<Option<SvsLayout>> {
+        let images = self.visit_ifds(|_, tag_reader| {
+            let description = match tag_reader.find_tag(Tag::ImageDescription)? {
+                Some(description) => Some(description.into_string()?),
+                None => None,
+            };
+            let tiled = tag_reader.ifd.contains_key(&Tag::TileOffsets);
+            Ok((description, tiled))
+        })?;
+
+        let description = match images.first() {
+            Some((Some(description), _)) if description.starts_with("Aperio") => {
+                description.clone()
+            }
+            _ => return Ok(None),
+        };
+
+        // E.g. "Aperio Image Library v10.0.50\nlabel 415x422", the role follows the first line.
+        let role = |description: &Option<String>, role: &str| {
+            description.as_deref().map_or(false, |description| {
+                description
+                    .lines()
+                    .skip(1)
+                    .any(|line| line.trim_start().starts_with(role))
+            })
+        };
+
+        let mut layout = SvsLayout {
+            description,
+            levels: vec![0],
+            thumbnail: None,
+            label: None,
+            macro_image: None,
+        };
+        for (index, (description, tiled)) in images.iter().enumerate().skip(1) {
+            if role(description, "label") {
+                layout.label = Some(index);
+            } else if role(description, "macro") {
+                layout.macro_image = Some(index);
+            } else if index == 1 && !tiled {
+                layout.thumbnail = Some(index);
+            } else {
+                layout.levels.push(index);
+            }
+        }
+
+        Ok(Some(layout))
+    }
+
+    /// Reads the structural metadata GDAL writes right after the file header, if present.
+    fn read_ghost_header(&mut self) -> TiffResult<Option<String>> {
+        const PREFIX: &[u8] = b"GDAL_STRUCTURAL_METADATA_SIZE=";
+
+        let start = if self.bigtiff { 16 } else { 8 };
+        self.goto_offset_u64(start)?;
+
+        // The prefix is followed by a six digit size and " bytes\n".
+        let mut line = [0; 43];
+        if self.reader.read_exact(&mut line).is_err() || !line.starts_with(PREFIX) {
+            return Ok(None);
+        }
+        let size = match std::str::from_utf8(&line[PREFIX.len()..PREFIX.len() + 6])
+            .ok()
+            .and_then(|size| size.trim().parse::<usize>().ok())
+        {
+            Some(size) if size <= self.limits.ifd_value_size => size,
+            _ => return Ok(None),
+        };
+
+        let mut header = vec![0; size];
+        self.reader.read_exact(&mut header)?;
+        Ok(Some(String::from_utf8_lossy(&header).into_owned()))
+    }
+
+    /// Selects the resolution level best suited to render the image at `scale`.
+    ///
+    /// The `scale` is the ratio of the desired output width to the full resolution width, e.g.
+    /// `0.25` for a quarter-size rendering. Candidates are the first image of this file, the
+    /// reduced-resolution images of this file (bit 0 of `NewSubfileType` set) and all images of the
+    /// external overview file. The smallest level that is at least as wide as requested is chosen,
+    /// levels of the primary file win ties. If no level is large enough, the full resolution image
+    /// is returned.
+    pub fn best_overview_for_scale(&mut self, scale: f64) -> TiffResult<Overview> {
+        if !(scale.is_finite() && scale > 0.0) {
+            return Err(TiffError::UsageError(UsageError::InvalidOverviewScale));
+        }
+
+        let main = self.ifd_summaries()?;
+        let target_width = f64::from(main[0].width) * scale;
+
+        let mut levels: Vec<(Overview, u32)> = main
+            .iter()
+            .enumerate()
+            .filter(|(index, summary)| {
+                *index == 0 || is_reduced_resolution(summary.new_subfile_type)
+            })
+            .map(|(index, summary)| (Overview::Main(index), summary.width))
+            .collect();
+        if let Some(overviews) = self.overviews.as_mut() {
+            let external = overviews.ifd_summaries()?;
+            levels.extend(
+                external
+                    .iter()
+                    .enumerate()
+                    .map(|(index, summary)| (Overview::External(index), summary.width)),
+            );
+        }
+
+        Ok(levels
+            .into_iter()
+            .filter(|&(_, width)| f64::from(width) >= target_width)
+            .min_by_key(|&(_, width)| width)
+            .map_or(Overview::Main(0), |(overview, _)| overview))
+    }
+
+    /// Seeks to the resolution level of a pyramid that best matches the requested dimensions.
+    ///
+    /// All images of the file are considered as levels. Their dimensions are read from the
+    /// directories only, without decoding any image data. The smallest level that covers the
+    /// target in both dimensions is chosen, so that no upsampling is required. If no level is large
+    /// enough, the largest level is chosen instead. Returns the index of the selected image.
+    pub fn select_level(&mut self, target_width: u32, target_height: u32) -> TiffResult<usize> {
+        let levels = self.ifd_summaries()?;
+        let area = |summary: &IfdSummary| u64::from(summary.width) * u64::from(summary.height);
+
+        let covering = levels
+            .iter()
+            .enumerate()
+            .filter(|(_, summary)| summary.width >= target_width && summary.height >= target_height)
+            .min_by_key(|(_, summary)| area(summary));
+        let index = match covering {
+            Some((index, _)) => index,
+            None => levels
+                .iter()
+                .enumerate()
+                .rev()
+                .max_by_key(|(_, summary)| area(summary))
+                .map_or(0, |(index, _)| index),
+        };
+
+        self.seek_to_image(index)?;
+        Ok(index)
+    }
+
+    /// Loads the image of the given resolution level and returns the decoder to read it from.
+    ///
+    /// This is either `self` or the decoder of the external overview file.
+    pub fn seek_to_overview(&mut self, overview: Overview) -> TiffResult<&mut Decoder<R>> {
+        match overview {
+            Overview::Main(index) => {
+                self.seek_to_image(index)?;
+                Ok(self)
+            }
+            Overview::External(index) => {
+                let overviews = self
+                    .overviews
+                    .as_deref_mut()
+                    .ok_or(TiffError::UsageError(UsageError::NoExternalOverviews))?;
+                overviews.seek_to_image(index)?;
+                Ok(overviews)
+            }
+        }
+    }
+
     #[inline]
     pub fn read_ifd_offset(&mut self) -> Result<u64, io::Error> {
         if self.bigtiff {
@@ -607,9 +2224,23 @@ impl<R: Read + Seek> Decoder<R> {
         }
     }
 
+    /// Accounts for a low-level read of `bytes` bytes against [`Limits::io_budget`].
+    fn consume_io_budget(&mut self, bytes: u64) -> io::Result<()> {
+        if let Some(budget) = self.limits.io_budget {
+            let used = self.io_budget_used.saturating_add(bytes);
+            if used > budget {
+                return Err(IoLimitsExceeded::into_io_error());
+            }
+            self.io_budget_used = used;
+        }
+
+        Ok(())
+    }
+
     /// Reads a TIFF byte value
     #[inline]
     pub fn read_byte(&mut self) -> Result<u8, io::Error> {
+        self.consume_io_budget(1)?;
         let mut buf = [0; 1];
         self.reader.read_exact(&mut buf)?;
         Ok(buf[0])
@@ -618,52 +2249,61 @@ impl<R: Read + Seek> Decoder<R> {
     /// Reads a TIFF short value
     #[inline]
     pub fn read_short(&mut self) -> Result<u16, io::Error> {
+        self.consume_io_budget(2)?;
         self.reader.read_u16()
     }
 
     /// Reads a TIFF sshort value
     #[inline]
     pub fn read_sshort(&mut self) -> Result<i16, io::Error> {
+        self.consume_io_budget(2)?;
         self.reader.read_i16()
     }
 
     /// Reads a TIFF long value
     #[inline]
     pub fn read_long(&mut self) -> Result<u32, io::Error> {
+        self.consume_io_budget(4)?;
         self.reader.read_u32()
     }
 
     /// Reads a TIFF slong value
     #[inline]
     pub fn read_slong(&mut self) -> Result<i32, io::Error> {
+        self.consume_io_budget(4)?;
         self.reader.read_i32()
     }
 
     /// Reads a TIFF float value
     #[inline]
     pub fn read_float(&mut self) -> Result<f32, io::Error> {
+        self.consume_io_budget(4)?;
         self.reader.read_f32()
     }
 
     /// Reads a TIFF double value
     #[inline]
     pub fn read_double(&mut self) -> Result<f64, io::Error> {
+        self.consume_io_budget(8)?;
         self.reader.read_f64()
     }
 
     #[inline]
     pub fn read_long8(&mut self) -> Result<u64, io::Error> {
+        self.consume_io_budget(8)?;
         self.reader.read_u64()
     }
 
     #[inline]
     pub fn read_slong8(&mut self) -> Result<i64, io::Error> {
+        self.consume_io_budget(8)?;
         self.reader.read_i64()
     }
 
     /// Reads a string
     #[inline]
     pub fn read_string(&mut self, length: usize) -> TiffResult<String> {
+        self.consume_io_budget(length as u64)?;
         let mut out = vec![0; length];
         self.reader.read_exact(&mut out)?;
         // Strings may be null-terminated, so we trim anything downstream of the null byte
@@ -681,6 +2321,7 @@ impl<R: Read + Seek> Decoder<R> {
                 TiffFormatError::InconsistentSizesEncountered,
             ));
         }
+        self.consume_io_budget(4)?;
         let mut val = [0; 4];
         self.reader.read_exact(&mut val)?;
         Ok(val)
@@ -689,6 +2330,7 @@ impl<R: Read + Seek> Decoder<R> {
     /// Reads a TIFF IFA offset/value field
     #[inline]
     pub fn read_offset_u64(&mut self) -> Result<[u8; 8], io::Error> {
+        self.consume_io_budget(8)?;
         let mut val = [0; 8];
         self.reader.read_exact(&mut val)?;
         Ok(val)
@@ -700,11 +2342,36 @@ impl<R: Read + Seek> Decoder<R> {
         self.goto_offset_u64(offset.into())
     }
 
+    /// Moves the cursor to the specified offset
+    ///
+    /// Fails with [`io::ErrorKind::UnexpectedEof`] if the offset lies beyond the end of the stream,
+    /// see [`DecoderOptions::with_stream_length_check`].
     #[inline]
     pub fn goto_offset_u64(&mut self, offset: u64) -> io::Result<()> {
+        if let Some(stream_length) = self.reader.stream_length() {
+            if offset > stream_length {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    format!(
+                        "Offset {} lies beyond the end of the file at {}",
+                        offset, stream_length
+                    ),
+                ));
+            }
+        }
+
         self.reader.seek(io::SeekFrom::Start(offset)).map(|_| ())
     }
 
+    /// The size of the number of entries at the start of an IFD.
+    fn ifd_count_size(bigtiff: bool) -> u64 {
+        if bigtiff {
+            8
+        } else {
+            2
+        }
+    }
+
     /// Reads a IFD entry.
     // An IFD entry has four fields:
     //
@@ -715,15 +2382,16 @@ impl<R: Read + Seek> Decoder<R> {
     fn read_entry(
         reader: &mut SmartReader<R>,
         bigtiff: bool,
-    ) -> TiffResult<Option<(Tag, ifd::Entry)>> {
+    ) -> TiffResult<(Tag, Result<ifd::Entry, u16>)> {
         let tag = Tag::from_u16_exhaustive(reader.read_u16()?);
-        let type_ = match Type::from_u16(reader.read_u16()?) {
+        let field_type = reader.read_u16()?;
+        let type_ = match Type::from_u16(field_type) {
             Some(t) => t,
             None => {
                 // Unknown type. Skip this entry according to spec.
                 reader.read_u32()?;
                 reader.read_u32()?;
-                return Ok(None);
+                return Ok((tag, Err(field_type)));
             }
         };
         let entry = if bigtiff {
@@ -739,61 +2407,387 @@ impl<R: Read + Seek> Decoder<R> {
             reader.read_exact(&mut offset)?;
             ifd::Entry::new(type_, count, offset)
         };
-        Ok(Some((tag, entry)))
+        Ok((tag, Ok(entry)))
+    }
+
+    /// Reads the tags of the IFD starting at the indicated location, in the order they are stored.
+    fn read_tag_order(
+        reader: &mut SmartReader<R>,
+        bigtiff: bool,
+        ifd_location: u64,
+    ) -> TiffResult<Vec<Tag>> {
+        reader.goto_range(
+            ifd_location,
+            Self::ifd_count_size(bigtiff),
+            OffsetSource::Ifd,
+        )?;
+
+        let num_tags = if bigtiff {
+            reader.read_u64()?
+        } else {
+            reader.read_u16()?.into()
+        };
+
+        let mut tags = Vec::new();
+        for _ in 0..num_tags {
+            let (tag, _entry) = Self::read_entry(reader, bigtiff)?;
+            tags.push(tag);
+        }
+
+        Ok(tags)
     }
 
     /// Reads the IFD starting at the indicated location.
+    ///
+    /// Anomalies that do not prevent reading the directory are pushed to `warnings`.
     fn read_ifd(
         reader: &mut SmartReader<R>,
         bigtiff: bool,
         ifd_location: u64,
+        duplicate_tags: DuplicateTagPolicy,
+        warnings: &mut Vec<DecodeWarning>,
     ) -> TiffResult<(Directory, Option<u64>)> {
-        reader.goto_offset(ifd_location)?;
+        reader.goto_range(
+            ifd_location,
+            Self::ifd_count_size(bigtiff),
+            OffsetSource::Ifd,
+        )?;
+
+        let mut entries = Vec::new();
+        let mut previous_tag = None;
+        let mut unsorted = false;
+
+        let num_tags = if bigtiff {
+            reader.read_u64()?
+        } else {
+            reader.read_u16()?.into()
+        };
+        for _ in 0..num_tags {
+            let (tag, entry) = Self::read_entry(reader, bigtiff)?;
+            let tag_id = tag.to_u16();
+
+            if !unsorted && previous_tag.map_or(false, |previous| previous > tag_id) {
+                warnings.push(DecodeWarning::UnsortedTags { ifd: ifd_location });
+                unsorted = true;
+            }
+            previous_tag = Some(tag_id);
+
+            let entry = match entry {
+                Ok(entry) => entry,
+                // Unknown data type in tag, skip
+                Err(field_type) => {
+                    warnings.push(DecodeWarning::UnknownFieldType {
+                        ifd: ifd_location,
+                        tag: tag_id,
+                        field_type,
+                    });
+                    continue;
+                }
+            };
+            entries.push((tag, entry));
+        }
+
+        // Sorting once is cheaper than inserting every entry in order. The sort is stable, so the
+        // entries of a tag that is stored more than once remain in the order of the file.
+        entries.sort_by_key(|(tag, _)| tag.to_u16());
+        let mut duplicate = None;
+        entries.dedup_by(|later, earlier| {
+            if later.0 != earlier.0 {
+                return false;
+            }
+
+            if duplicate_tags == DuplicateTagPolicy::LastWins {
+                mem::swap(later, earlier);
+            }
+            duplicate.get_or_insert(later.0);
+            warnings.push(DecodeWarning::DuplicateTag {
+                ifd: ifd_location,
+                tag: later.0.to_u16(),
+            });
+            true
+        });
+
+        if let (DuplicateTagPolicy::Error, Some(tag)) = (duplicate_tags, duplicate) {
+            return Err(TiffFormatError::DuplicateTag(tag, ifd_location).into());
+        }
+        let dir = entries.into_iter().collect();
+
+        let next_ifd = if bigtiff {
+            reader.read_u64()?
+        } else {
+            reader.read_u32()?.into()
+        };
+
+        let next_ifd = match next_ifd {
+            0 => None,
+            _ => Some(next_ifd),
+        };
+
+        Ok((dir, next_ifd))
+    }
+
+    /// Tries to retrieve a tag.
+    /// Return `Ok(None)` if the tag is not present.
+    pub fn find_tag(&mut self, tag: Tag) -> TiffResult<Option<ifd::Value>> {
+        let entry = match self.image().ifd.as_ref().unwrap().get(&tag) {
+            None => return Ok(None),
+            Some(entry) => entry.clone(),
+        };
+
+        let value = entry.tag_val(tag, &self.limits, self.bigtiff, &mut self.reader)?;
+        if self.lenient && tag_reader::coerced_field_type(tag, entry.field_type()) {
+            Ok(Some(tag_reader::coerce(tag, value)))
+        } else {
+            Ok(Some(value))
+        }
+    }
+
+    /// Reads the IFD at the indicated location, checking it first if validation is enabled.
+    fn read_ifd_at(&mut self, ifd_location: u64) -> TiffResult<(Directory, Option<u64>)> {
+        if self.validate {
+            Self::check_ifd_byte_order(&mut self.reader, self.bigtiff, ifd_location)?;
+        }
+
+        let mut warnings = Vec::new();
+        let ifd = Self::read_ifd(
+            &mut self.reader,
+            self.bigtiff,
+            ifd_location,
+            self.duplicate_tags,
+            &mut warnings,
+        )?;
+
+        if self.validate {
+            for warning in &warnings {
+                match *warning {
+                    DecodeWarning::UnsortedTags { ifd } if !self.allow_unsorted_tags => {
+                        return Err(TiffFormatError::UnsortedTags(ifd).into());
+                    }
+                    DecodeWarning::DuplicateTag { ifd, tag } => {
+                        let tag = Tag::from_u16_exhaustive(tag);
+                        return Err(TiffFormatError::DuplicateTag(tag, ifd).into());
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        // The same directory may be read more than once, e.g. when seeking between images.
+        for warning in warnings {
+            self.record_warning(warning);
+        }
+
+        Ok(ifd)
+    }
+
+    /// The non-fatal anomalies found in the directories read so far.
+    ///
+    /// This is always empty unless warnings were enabled with [`DecoderOptions::with_warnings`].
+    pub fn warnings(&self) -> &[DecodeWarning] {
+        self.warnings.as_deref().unwrap_or(&[])
+    }
+
+    /// Take the warnings collected so far, see [`Decoder::warnings`].
+    pub fn take_warnings(&mut self) -> Vec<DecodeWarning> {
+        self.reported_warnings.clear();
+        self.warnings.as_mut().map(mem::take).unwrap_or_default()
+    }
+
+    /// Collects `warning` if warnings are enabled and it was not collected before.
+    fn record_warning(&mut self, warning: DecodeWarning) {
+        if let Some(collected) = &mut self.warnings {
+            if self.reported_warnings.insert(warning.clone()) {
+                collected.push(warning);
+            }
+        }
+    }
+
+    /// Returns the offset and the byte count of a chunk, along with the number of its bytes that
+    /// can be read, after checking that it lies within the range addressable by the file.
+    ///
+    /// Writers commonly overstate the byte count of the last chunk, so outside validation mode
+    /// only the offset has to lie within the stream and the readable bytes end with the stream.
+    /// Whether the chunk fits into them is left to [`Decoder::check_chunk_bytes`].
+    fn check_chunk_range(&mut self, chunk_index: u32) -> TiffResult<(u64, u64, u64)> {
+        let (offset, declared) = self.image.chunk_file_range(chunk_index, &mut self.reader)?;
+        if self.validate {
+            self.check_file_range(chunk_index, offset, declared)?;
+            return Ok((offset, declared, declared));
+        }
+
+        if !self.bigtiff {
+            ifd::check_classic_range(offset, declared)?;
+        }
+
+        self.reader
+            .check_range(offset, 0, OffsetSource::Chunk(chunk_index))?;
+        let readable = match self.reader.stream_length() {
+            Some(stream_length) => declared.min(stream_length - offset),
+            None => declared,
+        };
+        Ok((offset, declared, readable))
+    }
+
+    /// Reports a chunk that could not be decoded from the bytes before the end of the stream as
+    /// lying beyond the end of the stream.
+    fn truncated_chunk_error(
+        &self,
+        chunk_index: u32,
+        (offset, declared, readable): (u64, u64, u64),
+        err: TiffError,
+    ) -> TiffError {
+        let truncated = match &err {
+            TiffError::FormatError(TiffFormatError::ChunkByteCountMismatch { .. }) => true,
+            TiffError::IoError(err) => err.kind() == io::ErrorKind::UnexpectedEof,
+            _ => false,
+        };
+
+        if readable < declared && truncated {
+            if let Err(err) =
+                self.reader
+                    .check_range(offset, declared, OffsetSource::Chunk(chunk_index))
+            {
+                return err;
+            }
+        }
 
-        let mut dir: Directory = HashMap::new();
+        err
+    }
+
+    fn check_file_range(&self, chunk_index: u32, offset: u64, length: u64) -> TiffResult<()> {
+        if !self.bigtiff {
+            ifd::check_classic_range(offset, length)?;
+        }
+
+        self.reader
+            .check_range(offset, length, OffsetSource::Chunk(chunk_index))
+    }
+
+    /// Returns the offsets and byte counts of the chunks in `range` of the current image.
+    fn chunk_file_ranges(&mut self, range: Range<usize>) -> TiffResult<Vec<(u64, u64)>> {
+        let offsets = self
+            .image
+            .chunk_offsets
+            .values(range.clone(), &mut self.reader)?;
+        let bytes = self.image.chunk_bytes.values(range, &mut self.reader)?;
+        Ok(offsets.into_iter().zip(bytes).collect())
+    }
+
+    /// Checks the current image for violations of the specification that do not prevent decoding.
+    fn check_image(&mut self) -> TiffResult<()> {
+        if let Some(tile) = &self.image.tile_attributes {
+            if tile.tile_width % 16 != 0 || tile.tile_length % 16 != 0 {
+                return Err(TiffFormatError::InvalidTileSize(
+                    u32::try_from(tile.tile_width)?,
+                    u32::try_from(tile.tile_length)?,
+                )
+                .into());
+            }
+        }
+
+        self.check_image_ranges()
+    }
+
+    /// Checks that all chunks of the current image lie within the range addressable by the file
+    /// and within the stream.
+    fn check_image_ranges(&mut self) -> TiffResult<()> {
+        let chunks = self.image.chunk_offsets.len();
+        for start in (0..chunks).step_by(ChunkTable::BATCH) {
+            let ranges = self.chunk_file_ranges(start..chunks.min(start + ChunkTable::BATCH))?;
+            for (chunk_index, (offset, length)) in (start..).zip(ranges) {
+                self.check_file_range(u32::try_from(chunk_index)?, offset, length)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks that the IFD at the indicated location is consistent with the file's byte order.
+    fn check_ifd_byte_order(
+        reader: &mut SmartReader<R>,
+        bigtiff: bool,
+        ifd_location: u64,
+    ) -> TiffResult<()> {
+        // Limits the work spent on directories with an implausible number of entries.
+        const MAX_CHECKED_ENTRIES: u64 = 512;
+
+        reader.goto_range(
+            ifd_location,
+            Self::ifd_count_size(bigtiff),
+            OffsetSource::Ifd,
+        )?;
+
+        // An offset pointing to a TIFF header instead of a directory, e.g. of an embedded file.
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if let b"II*\0" | b"MM\0*" | b"II+\0" | b"MM\0+" = &magic {
+            return Err(TiffError::FormatError(
+                TiffFormatError::InconsistentByteOrder,
+            ));
+        }
 
+        reader.goto_offset(ifd_location)?;
         let num_tags = if bigtiff {
             reader.read_u64()?
         } else {
             reader.read_u16()?.into()
         };
-        for _ in 0..num_tags {
-            let (tag, entry) = match Self::read_entry(reader, bigtiff)? {
-                Some(val) => val,
-                None => {
-                    continue;
-                } // Unknown data type in tag, skip
+
+        // Entries whose field type is only known when reading it in the opposite byte order.
+        let mut swapped = 0;
+        let mut valid = 0;
+        for _ in 0..num_tags.min(MAX_CHECKED_ENTRIES) {
+            // A count in the wrong byte order may well run past the end of the file.
+            let type_ = match reader.read_u16().and_then(|_tag| reader.read_u16()) {
+                Ok(type_) => type_,
+                Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => break,
+                Err(err) => return Err(err.into()),
             };
-            dir.insert(tag, entry);
-        }
+            if Type::from_u16(type_).is_some() {
+                valid += 1;
+            } else if Type::from_u16(type_.swap_bytes()).is_some() {
+                swapped += 1;
+            }
 
-        let next_ifd = if bigtiff {
-            reader.read_u64()?
-        } else {
-            reader.read_u32()?.into()
-        };
+            // Skip the count and the offset/value field.
+            let remainder = if bigtiff { 16 } else { 8 };
+            reader.seek(io::SeekFrom::Current(remainder))?;
+        }
 
-        let next_ifd = match next_ifd {
-            0 => None,
-            _ => Some(next_ifd),
-        };
+        if swapped > valid {
+            return Err(TiffError::FormatError(
+                TiffFormatError::InconsistentByteOrder,
+            ));
+        }
 
-        Ok((dir, next_ifd))
+        Ok(())
     }
 
-    /// Tries to retrieve a tag.
-    /// Return `Ok(None)` if the tag is not present.
-    pub fn find_tag(&mut self, tag: Tag) -> TiffResult<Option<ifd::Value>> {
-        let entry = match self.image().ifd.as_ref().unwrap().get(&tag) {
-            None => return Ok(None),
-            Some(entry) => entry.clone(),
-        };
+    /// Tries to retrieve a single tag value without allocating or reading from the file.
+    ///
+    /// This succeeds for scalar values stored inline in the directory entry, which covers common
+    /// numeric tags such as `ImageWidth` or `Compression`. Returns `None` if the tag is not present,
+    /// holds more than one value, or its value is stored elsewhere in the file. Use
+    /// [`find_tag`][Decoder::find_tag] for those.
+    pub fn find_inline_tag(&self, tag: Tag) -> Option<ifd::Value> {
+        self.image()
+            .ifd
+            .as_ref()?
+            .get(&tag)?
+            .inline_val(self.reader.byte_order, self.bigtiff)
+    }
 
-        Ok(Some(entry.val(
-            &self.limits,
-            self.bigtiff,
-            &mut self.reader,
-        )?))
+    /// Tries to retrieve an inline tag value and convert it to the desired unsigned type.
+    ///
+    /// See [`find_inline_tag`][Decoder::find_inline_tag] for the values that can be retrieved.
+    pub fn find_inline_tag_unsigned<T: TryFrom<u64>>(&self, tag: Tag) -> TiffResult<Option<T>> {
+        self.find_inline_tag(tag)
+            .map(|v| v.into_u64())
+            .transpose()?
+            .map(|value| {
+                T::try_from(value).map_err(|_| TiffFormatError::InvalidTagValueType(tag).into())
+            })
+            .transpose()
     }
 
     /// Tries to retrieve a tag and convert it to the desired unsigned type.
@@ -898,11 +2892,50 @@ impl<R: Read + Seek> Decoder<R> {
     pub fn tag_iter(&mut self) -> impl Iterator<Item = TiffResult<(Tag, ifd::Value)>> + '_ {
         self.image.ifd.as_ref().unwrap().iter().map(|(tag, entry)| {
             entry
-                .val(&self.limits, self.bigtiff, &mut self.reader)
+                .tag_val(*tag, &self.limits, self.bigtiff, &mut self.reader)
                 .map(|value| (*tag, value))
         })
     }
 
+    /// Returns the directory of the current image, as it was read from the file.
+    ///
+    /// The entries report the field type and number of values of each tag without reading the
+    /// values, which can be retrieved with [`Decoder::find_tag`]. Tags that appear more than once
+    /// hold the entry selected by the [`DuplicateTagPolicy`].
+    pub fn current_directory(&self) -> &Directory {
+        self.image().ifd.as_ref().unwrap()
+    }
+
+    /// Returns the tags of the current image's IFD in the order they are stored in the file.
+    ///
+    /// The specification requires entries to be sorted in ascending tag order, but some writers do
+    /// not comply. Unlike the directory this was decoded into, the returned list also includes
+    /// duplicated entries and entries with a field type unknown to this crate.
+    pub fn tag_order(&mut self) -> TiffResult<Vec<Tag>> {
+        match self.current_ifd {
+            Some(ifd_location) => {
+                Self::read_tag_order(&mut self.reader, self.bigtiff, ifd_location)
+            }
+            None => Err(TiffFormatError::ImageFileDirectoryNotFound.into()),
+        }
+    }
+
+    /// Returns an iterator over all entries of the current image's IFD in ascending tag order.
+    ///
+    /// Each item holds the numeric tag id, the field type and the number of values as stored in
+    /// the file, along with the decoded value. Unlike [`Decoder::tag_iter`] this is suitable for
+    /// dumping metadata, as tags unknown to this crate are reported by their id. Entries with a
+    /// field type unknown to this crate are skipped when the IFD is read and not included.
+    pub fn raw_tag_iter(
+        &mut self,
+    ) -> impl Iterator<Item = TiffResult<(u16, Type, u64, ifd::Value)>> + '_ {
+        self.image.ifd.as_ref().unwrap().iter().map(|(tag, entry)| {
+            entry
+                .tag_val(*tag, &self.limits, self.bigtiff, &mut self.reader)
+                .map(|value| (tag.to_u16(), entry.field_type(), entry.count(), value))
+        })
+    }
+
     fn check_chunk_type(&self, expected: ChunkType) -> TiffResult<()> {
         if expected != self.image().chunk_type {
             return Err(TiffError::UsageError(UsageError::InvalidChunkType(
@@ -914,6 +2947,71 @@ impl<R: Read + Seek> Decoder<R> {
         Ok(())
     }
 
+    /// Reports how the chunks of the current image are placed in the file, in the order of their
+    /// indices.
+    ///
+    /// No data is read, but lazy chunk tables are, see [`DecoderOptions::with_lazy_chunk_tables`].
+    pub fn chunk_placement(&mut self) -> TiffResult<ChunkPlacement> {
+        let mut placement = ChunkPlacement {
+            ascending: true,
+            contiguous: true,
+        };
+        let mut previous_end = None;
+        let chunks = self.image.chunk_offsets.len();
+        for start in (0..chunks).step_by(ChunkTable::BATCH) {
+            let ranges = self.chunk_file_ranges(start..chunks.min(start + ChunkTable::BATCH))?;
+            for (offset, bytes) in ranges.into_iter().filter(|&(_, bytes)| bytes > 0) {
+                if let Some(end) = previous_end {
+                    placement.ascending &= end <= offset;
+                    placement.contiguous &= end == offset;
+                }
+                previous_end = Some(offset.saturating_add(bytes));
+            }
+        }
+
+        Ok(placement)
+    }
+
+    /// Reports the chunk geometry of the current image, following the decoder's own chunk math.
+    ///
+    /// Applications splitting the decoding of an image into their own units of work can use this
+    /// to map chunk indices to pixel regions.
+    pub fn chunk_layout(&self) -> TiffResult<ChunkLayout> {
+        let image = self.image();
+        let (chunk_width, chunk_height) = image.chunk_dimensions()?;
+        let rows_per_strip = image
+            .strip_decoder
+            .as_ref()
+            .map(|strip| strip.rows_per_strip);
+        // Strips are never padded, so a strip is at most as high as the image.
+        let chunk_height = match rows_per_strip {
+            Some(_) => chunk_height.min(image.height),
+            None => chunk_height,
+        };
+
+        let count = |length: u32, chunk: u32| match chunk {
+            0 => 0,
+            chunk => (u64::from(length) + u64::from(chunk) - 1) / u64::from(chunk),
+        };
+        let chunks_across = count(image.width, chunk_width);
+        let chunks_down = count(image.height, chunk_height);
+
+        Ok(ChunkLayout {
+            chunk_type: image.chunk_type,
+            chunk_width,
+            chunk_height,
+            chunks_across: u32::try_from(chunks_across)?,
+            chunks_down: u32::try_from(chunks_down)?,
+            planes: match image.planar_config {
+                PlanarConfiguration::Chunky => 1,
+                PlanarConfiguration::Planar => image.samples,
+            },
+            padding_right: u32::try_from(chunks_across * u64::from(chunk_width))? - image.width,
+            padding_down: u32::try_from(chunks_down * u64::from(chunk_height))? - image.height,
+            rows_per_strip,
+        })
+    }
+
     /// The chunk type (Strips / Tiles) of the image
     pub fn get_chunk_type(&self) -> ChunkType {
         self.image().chunk_type
@@ -948,49 +3046,185 @@ impl<R: Read + Seek> Decoder<R> {
         Ok(u32::try_from(self.image().chunk_offsets.len())?)
     }
 
+    /// Decodes a chunk into the provided buffer, placing the rows `output_width` pixels apart.
+    #[deprecated(note = "use `read_chunk_into`, which takes the row stride instead of a width")]
     pub fn read_chunk_to_buffer(
         &mut self,
         mut buffer: DecodingBuffer,
         chunk_index: u32,
         output_width: usize,
     ) -> TiffResult<()> {
-        let offset = self.image.chunk_file_range(chunk_index)?.0;
-        self.goto_offset_u64(offset)?;
-
-        let byte_order = self.reader.byte_order;
-
         let output_row_stride = (output_width as u64)
             .saturating_mul(self.image.samples_per_pixel() as u64)
             .saturating_mul(self.image.bits_per_sample as u64)
             / 8;
 
-        self.image.expand_chunk(
-            &mut self.reader,
+        self.read_chunk_bytes(
+            chunk_index,
             buffer.as_bytes_mut(),
             output_row_stride.try_into()?,
-            byte_order,
-            chunk_index,
-            &self.limits,
-        )?;
+        )
+    }
+
+    /// Decodes a chunk into the provided buffer.
+    ///
+    /// The rows of the chunk are placed `stride` samples apart, starting at the beginning of the
+    /// buffer. Use the chunk width to get a compact chunk, or the width of a larger image to decode
+    /// the chunk in place. Only the data of the chunk is written, without the padding of tiles at
+    /// the right and bottom edges of the image, see [`Decoder::chunk_data_dimensions_at`].
+    ///
+    /// A row holds all samples of each pixel, unless the planar configuration is
+    /// [`PlanarConfiguration::Planar`]. Then every chunk holds a single sample plane, the chunks of
+    /// the first plane are followed by the chunks of the second one and so on, and a row holds one
+    /// sample per pixel.
+    ///
+    /// The buffer must be of the sample type of the image, as returned by [`Decoder::read_chunk_at`].
+    /// For images of less than 8 bits per sample the rows are packed and `stride` counts bytes.
+    /// If `stride` is shorter than a row of the chunk or the buffer can not hold all of its rows,
+    /// [`UsageError::InsufficientBuffer`] is returned, a buffer of another sample type is rejected
+    /// with [`UsageError::InvalidBufferType`].
+    pub fn read_chunk_into(
+        &mut self,
+        chunk_index: ChunkIndex,
+        mut buffer: DecodingBuffer,
+        stride: usize,
+    ) -> TiffResult<()> {
+        self.check_buffer_type(&buffer)?;
+        let output_row_stride = stride
+            .checked_mul(buffer.sample_bytes())
+            .ok_or(TiffError::LimitsExceeded)?;
+
+        self.read_chunk_bytes(chunk_index.0, buffer.as_bytes_mut(), output_row_stride)
+    }
+
+    /// Checks that `buffer` is of the sample type that the samples of the image are decoded to,
+    /// as chosen for the results of [`Decoder::read_image`], for decoding chunks and regions into
+    /// buffers of the caller.
+    fn check_buffer_type(&self, buffer: &DecodingBuffer) -> TiffResult<()> {
+        let image = self.image();
+        let (format, bits) = (image.sample_format, image.bits_per_sample);
+        let matches = match (format, buffer) {
+            (SampleFormat::Uint, DecodingBuffer::U8(_)) => bits <= 8,
+            (SampleFormat::Uint, DecodingBuffer::U16(_)) => (9..=16).contains(&bits),
+            (SampleFormat::Uint, DecodingBuffer::U32(_)) => (17..=32).contains(&bits),
+            (SampleFormat::Uint, DecodingBuffer::U64(_)) => (33..=64).contains(&bits),
+            (SampleFormat::Int, DecodingBuffer::I8(_)) => bits <= 8,
+            (SampleFormat::Int, DecodingBuffer::I16(_)) => (9..=16).contains(&bits),
+            (SampleFormat::Int, DecodingBuffer::I32(_)) => (17..=32).contains(&bits),
+            (SampleFormat::Int, DecodingBuffer::I64(_)) => (33..=64).contains(&bits),
+            // There is no 16 bit float type, such samples are returned as their bits.
+            (SampleFormat::IEEEFP, DecodingBuffer::U16(_)) => bits == 16,
+            (SampleFormat::IEEEFP, DecodingBuffer::F32(_)) => bits == 32,
+            (SampleFormat::IEEEFP, DecodingBuffer::F64(_)) => bits == 64,
+            _ => false,
+        };
+
+        if matches {
+            Ok(())
+        } else {
+            Err(TiffError::UsageError(UsageError::InvalidBufferType(
+                format, bits,
+            )))
+        }
+    }
+
+    fn read_chunk_bytes(
+        &mut self,
+        chunk_index: u32,
+        buffer: &mut [u8],
+        output_row_stride: usize,
+    ) -> TiffResult<()> {
+        let range = self.check_chunk_range(chunk_index)?;
+        let (offset, declared, readable) = range;
+        self.record_chunk_memory_usage(readable);
+
+        let consumed = self
+            .image
+            .expand_chunk(
+                &mut self.reader,
+                buffer,
+                output_row_stride,
+                chunk_index,
+                (offset, readable),
+                &self.limits,
+            )
+            .map_err(|err| self.truncated_chunk_error(chunk_index, range, err))?;
+
+        self.check_chunk_bytes(chunk_index, declared, consumed)
+    }
+
+    /// Checks the bytes a decoded chunk took up against its byte count.
+    ///
+    /// An uncompressed chunk whose byte count exceeds its size, e.g. the padding of a truncated
+    /// file, or an uncompressed tile whose byte count lacks the rows beyond the image, is rejected
+    /// in validation mode and reported as a warning otherwise. Chunks are never read beyond their
+    /// byte count, and decompressors read ahead, so compressed chunks that end before their byte
+    /// count are not detected.
+    fn check_chunk_bytes(
+        &mut self,
+        chunk_index: u32,
+        declared: u64,
+        consumed: u64,
+    ) -> TiffResult<()> {
+        let uncompressed = self.image.compression_method == CompressionMethod::None;
+        if consumed == declared || consumed < declared && !uncompressed {
+            return Ok(());
+        }
+
+        if self.validate {
+            return Err(TiffFormatError::ChunkByteCountMismatch {
+                chunk: chunk_index,
+                declared,
+                consumed,
+            }
+            .into());
+        }
+
+        if let Some(ifd) = self.current_ifd {
+            self.record_warning(DecodeWarning::ChunkByteCountMismatch {
+                ifd,
+                chunk: chunk_index,
+                declared,
+                consumed,
+            });
+        }
 
         Ok(())
     }
 
-    fn result_buffer(&self, width: usize, height: usize) -> TiffResult<DecodingResult> {
+    fn result_buffer(&mut self, width: usize, height: usize) -> TiffResult<DecodingResult> {
+        let mut result = self.new_result_buffer(width, height)?;
+        self.record_result_memory_usage(&mut result);
+        Ok(result)
+    }
+
+    fn new_result_buffer(&self, width: usize, height: usize) -> TiffResult<DecodingResult> {
         let bits_per_sample = self.image().bits_per_sample;
+        let samples = self.image().samples_per_pixel();
 
-        let row_samples = if bits_per_sample >= 8 {
+        let buffer_size = if bits_per_sample >= 8 {
             width
+                .checked_mul(height)
+                .and_then(|x| x.checked_mul(samples))
         } else {
-            ((((width as u64) * bits_per_sample as u64) + 7) / 8)
-                .try_into()
-                .map_err(|_| TiffError::LimitsExceeded)?
-        };
+            // Packed rows are padded to whole bytes, per plane if the samples are planar.
+            let (row_samples, planes) = match self.image().planar_config {
+                PlanarConfiguration::Planar => (width as u64, samples),
+                _ => ((width as u64) * samples as u64, 1),
+            };
+            usize::try_from((row_samples * bits_per_sample as u64 + 7) / 8)
+                .ok()
+                .and_then(|x| x.checked_mul(height))
+                .and_then(|x| x.checked_mul(planes))
+        }
+        .ok_or(TiffError::LimitsExceeded)?;
 
-        let buffer_size = row_samples
-            .checked_mul(height)
-            .and_then(|x| x.checked_mul(self.image().samples_per_pixel()))
-            .ok_or(TiffError::LimitsExceeded)?;
+        // Samples of more than 8 bits that do not fill whole bytes are only returned unpacked.
+        if (9..16).contains(&bits_per_sample) {
+            return Err(
+                TiffUnsupportedError::UnsupportedColorType(self.image().colortype()?).into(),
+            );
+        }
 
         let max_sample_bits = self.image().bits_per_sample;
         match self.image().sample_format {
@@ -1004,6 +3238,8 @@ impl<R: Read + Seek> Decoder<R> {
                 )),
             },
             SampleFormat::IEEEFP => match max_sample_bits {
+                // There is no 16 bit float type, such samples are returned as their bits.
+                16 => DecodingResult::new_u16(buffer_size, &self.limits),
                 32 => DecodingResult::new_f32(buffer_size, &self.limits),
                 64 => DecodingResult::new_f64(buffer_size, &self.limits),
                 n => Err(TiffError::UnsupportedError(
@@ -1024,12 +3260,27 @@ impl<R: Read + Seek> Decoder<R> {
     }
 
     /// Read the specified chunk (at index `chunk_index`) and return the binary data as a Vector.
+    #[deprecated(note = "use `read_chunk_at`, which takes a typed `ChunkIndex`")]
     pub fn read_chunk(&mut self, chunk_index: u32) -> TiffResult<DecodingResult> {
-        let data_dims = self.image().chunk_data_dimensions(chunk_index)?;
+        self.read_chunk_at(ChunkIndex(chunk_index))
+    }
+
+    /// Read the specified chunk and return the binary data as a Vector.
+    pub fn read_chunk_at(&mut self, chunk_index: ChunkIndex) -> TiffResult<DecodingResult> {
+        let data_dims = self.image().chunk_data_dimensions(chunk_index.0)?;
 
         let mut result = self.result_buffer(data_dims.0 as usize, data_dims.1 as usize)?;
 
-        self.read_chunk_to_buffer(result.as_buffer(0), chunk_index, data_dims.0 as usize)?;
+        let stride = if self.image().bits_per_sample >= 8 {
+            data_dims.0 as usize * self.image().samples_per_pixel()
+        } else {
+            (data_dims.0 as usize
+                * self.image().samples_per_pixel()
+                * self.image().bits_per_sample as usize
+                + 7)
+                / 8
+        };
+        self.read_chunk_into(chunk_index, result.as_buffer(0), stride)?;
 
         Ok(result)
     }
@@ -1042,19 +3293,262 @@ impl<R: Read + Seek> Decoder<R> {
 
     /// Returns the size of the data in the chunk with the specified index. This is the default size of the chunk,
     /// minus any padding.
+    #[deprecated(note = "use `chunk_data_dimensions_at`, which takes a typed `ChunkIndex`")]
     pub fn chunk_data_dimensions(&self, chunk_index: u32) -> (u32, u32) {
         self.image()
             .chunk_data_dimensions(chunk_index)
             .expect("invalid chunk_index")
     }
 
+    /// Returns the size of the data in the chunk with the specified index. This is the default
+    /// size of the chunk, minus any padding. Indices past the last chunk are an
+    /// [`UsageError::InvalidChunkIndex`].
+    pub fn chunk_data_dimensions_at(&self, chunk_index: ChunkIndex) -> TiffResult<(u32, u32)> {
+        self.image().chunk_data_dimensions(chunk_index.0)
+    }
+
     /// Decodes the entire image and return it as a Vector
+    ///
+    /// An image with a width or height of zero decodes to an empty buffer, its chunks are not read.
+    ///
+    /// Samples of less than 8 bits are returned packed as stored in the file, with the first
+    /// sample in the most significant bits and each row padded to a whole byte. Use
+    /// [`Decoder::read_image_unpacked`] to get one byte per sample instead.
+    ///
+    /// The samples are interleaved pixel by pixel for both chunky and planar images, so the planes
+    /// of a planar image are all read and merged. Use [`Decoder::read_plane`] to read a single
+    /// plane instead.
     pub fn read_image(&mut self) -> TiffResult<DecodingResult> {
+        let (result, _) = self.read_image_inner(false)?;
+        Ok(result)
+    }
+
+    /// Decodes a single sample plane of the image.
+    ///
+    /// Every plane of a [`PlanarConfiguration::Planar`] image holds one sample of each pixel, e.g.
+    /// the red, green or blue band of an RGB image, so bands can be read one at a time. Other
+    /// images store all samples in their only plane, `PlaneIndex(0)`, which decodes like
+    /// [`Decoder::read_image`]. Fails with [`UsageError::InvalidPlaneIndex`] for a plane the image
+    /// does not have, see [`ChunkLayout::planes`].
+    pub fn read_plane(&mut self, plane: PlaneIndex) -> TiffResult<DecodingResult> {
+        if usize::from(plane.0) >= self.image().strips_per_pixel() {
+            return Err(TiffError::UsageError(UsageError::InvalidPlaneIndex(
+                plane.0,
+            )));
+        }
+
+        let width = self.image().width as usize;
+        let height = self.image().height as usize;
+        let mut result = self.result_buffer(width, height)?;
+        self.read_plane_bytes(result.as_buffer(0).as_bytes_mut(), plane, false)?;
+        Ok(result)
+    }
+
+    /// Decodes the entire image like [`Decoder::read_image`], but with every sample in a whole
+    /// number of bytes.
+    ///
+    /// Images of less than 8 bits per sample are returned with one byte per sample, and unsigned
+    /// images of 9 to 15 bits per sample, such as the 12 bits of many cameras and scanners, with
+    /// a `u16` per sample. With [`SampleConversion::Saturate`] the stored sample values are kept,
+    /// e.g. `0` and `1` for a bilevel image or up to `4095` for 12 bits. With
+    /// [`SampleConversion::Scale`] they are mapped onto the full range of the returned type, which
+    /// is only meaningful for samples that are not palette indices. The original bit depth is
+    /// given by [`Decoder::colortype`]. The photometric interpretation is not applied in either
+    /// case. Images of 8, 16, 32 or 64 bits per sample are returned unchanged.
+    pub fn read_image_unpacked(
+        &mut self,
+        conversion: SampleConversion,
+    ) -> TiffResult<DecodingResult> {
+        let bits_per_sample = self.image().bits_per_sample;
+        if !(9..16).contains(&bits_per_sample) {
+            let packed = match self.read_image()? {
+                DecodingResult::U8(packed) if bits_per_sample < 8 => packed,
+                other => return Ok(other),
+            };
+            return self.unpack_samples(&packed, conversion);
+        }
+        if self.image().sample_format != SampleFormat::Uint {
+            let color_type = self.image().colortype()?;
+            return Err(TiffUnsupportedError::UnsupportedColorType(color_type).into());
+        }
+
+        // The packed samples are decoded to bytes, as `read_image` does for fewer bits.
+        let row_bits = u64::from(self.image().width)
+            .checked_mul(u64::from(bits_per_sample) * u64::from(self.image().samples))
+            .ok_or(TiffError::LimitsExceeded)?;
+        let packed_len = usize::try_from((row_bits + 7) / 8)?
+            .checked_mul(self.image().height as usize)
+            .ok_or(TiffError::LimitsExceeded)?;
+        let mut packed = DecodingResult::new_u8(packed_len, &self.limits)?;
+        self.record_result_memory_usage(&mut packed);
+        let packed = match packed {
+            DecodingResult::U8(mut packed) => {
+                self.read_image_bytes(&mut packed, false)?;
+                packed
+            }
+            _ => unreachable!(),
+        };
+        self.unpack_samples(&packed, conversion)
+    }
+
+    /// Unpacks the samples of an image of less than 16 bits per sample that do not fill whole
+    /// bytes, with the layout of [`Decoder::read_image`].
+    fn unpack_samples(
+        &mut self,
+        packed: &[u8],
+        conversion: SampleConversion,
+    ) -> TiffResult<DecodingResult> {
+        let bits_per_sample = self.image().bits_per_sample;
+        let row_samples = (self.image().width as usize)
+            .checked_mul(self.image().samples.into())
+            .ok_or(TiffError::LimitsExceeded)?;
+        let samples = row_samples
+            .checked_mul(self.image().height as usize)
+            .ok_or(TiffError::LimitsExceeded)?;
+
+        let bits = usize::from(bits_per_sample);
+        let row_bytes = (row_samples * bits + 7) / 8;
+        let max = (1u32 << bits) - 1;
+        // A sample may span up to three bytes unless the bit depth is a power of two.
+        let sample_value = |row: &[u8], index: usize| {
+            let bit = index * bits;
+            let window = (bit / 8..bit / 8 + 3).fold(0u32, |window, byte| {
+                window << 8 | row.get(byte).copied().map_or(0, u32::from)
+            });
+            (window >> (24 - bits - bit % 8)) & max
+        };
+        let rows = packed.chunks(row_bytes.max(1));
+
+        let mut unpacked = if bits < 8 {
+            DecodingResult::new_u8(samples, &self.limits)?
+        } else {
+            DecodingResult::new_u16(samples, &self.limits)?
+        };
+        self.record_result_memory_usage(&mut unpacked);
+        if row_samples > 0 {
+            match &mut unpacked {
+                DecodingResult::U8(buffer) => {
+                    for (row, out) in rows.zip(buffer.chunks_mut(row_samples)) {
+                        for (index, sample) in out.iter_mut().enumerate() {
+                            let value = sample_value(row, index);
+                            *sample = match conversion {
+                                SampleConversion::Saturate => value as u8,
+                                SampleConversion::Scale => ((value * 255 + max / 2) / max) as u8,
+                            };
+                        }
+                    }
+                }
+                DecodingResult::U16(buffer) => {
+                    for (row, out) in rows.zip(buffer.chunks_mut(row_samples)) {
+                        for (index, sample) in out.iter_mut().enumerate() {
+                            let value = sample_value(row, index);
+                            *sample = match conversion {
+                                SampleConversion::Saturate => value as u16,
+                                SampleConversion::Scale => {
+                                    ((value * 0xffff + max / 2) / max) as u16
+                                }
+                            };
+                        }
+                    }
+                }
+                _ => unreachable!(),
+            }
+        }
+
+        Ok(unpacked)
+    }
+
+    /// Decodes the entire image, skipping chunks whose data is corrupt.
+    ///
+    /// Unlike [`Decoder::read_image`], a chunk that fails to decode because its data is malformed
+    /// or truncated does not abort the read. Its region of the image is filled with zeros instead
+    /// and its index is included in the returned list of failed chunks. Errors that are not caused
+    /// by the chunk data, such as an unsupported image format or exceeded limits, are still
+    /// returned.
+    pub fn read_image_skipping_corrupt_chunks(&mut self) -> TiffResult<(DecodingResult, Vec<u32>)> {
+        self.read_image_inner(true)
+    }
+
+    fn read_image_inner(&mut self, skip_corrupt: bool) -> TiffResult<(DecodingResult, Vec<u32>)> {
+        let width = self.image().width as usize;
+        let height = self.image().height as usize;
+        // Sized for all samples of a pixel, even if the chunks hold a single plane.
+        let planes = self.image().strips_per_pixel();
+        let mut result = self.result_buffer(
+            width.checked_mul(planes).ok_or(TiffError::LimitsExceeded)?,
+            height,
+        )?;
+        let failed_chunks =
+            self.read_image_bytes(result.as_buffer(0).as_bytes_mut(), skip_corrupt)?;
+        Ok((result, failed_chunks))
+    }
+
+    /// Decodes the entire image into `buffer`, with the layout of [`Decoder::read_image`],
+    /// returning the chunks that were skipped as corrupt.
+    fn read_image_bytes(&mut self, buffer: &mut [u8], skip_corrupt: bool) -> TiffResult<Vec<u32>> {
+        let image = self.image();
+        let planes = image.strips_per_pixel();
+        let (width, height) = (image.width as usize, image.height as usize);
+        if planes == 1 || width == 0 || height == 0 {
+            return self.read_plane_bytes(buffer, PlaneIndex(0), skip_corrupt);
+        }
+
+        // Every plane is decoded on its own, and its samples are interleaved into the pixels.
+        let bits = usize::from(image.bits_per_sample);
+        let plane_row_bits = width.checked_mul(bits).ok_or(TiffError::LimitsExceeded)?;
+        let plane_row_bytes = (plane_row_bits + 7) / 8;
+        let row_bytes = plane_row_bits
+            .checked_mul(planes)
+            .map(|row_bits| (row_bits + 7) / 8)
+            .ok_or(TiffError::LimitsExceeded)?;
+        let plane_len = plane_row_bytes
+            .checked_mul(height)
+            .ok_or(TiffError::LimitsExceeded)?;
+        if plane_len > self.limits.intermediate_buffer_size {
+            return Err(TiffError::LimitsExceeded);
+        }
+
+        let mut plane_buffer = vec![0u8; plane_len];
+        let mut failed_chunks = Vec::new();
+        for plane in 0..planes {
+            let plane_index = PlaneIndex(u16::try_from(plane)?);
+            failed_chunks.extend(self.read_plane_bytes(
+                &mut plane_buffer,
+                plane_index,
+                skip_corrupt,
+            )?);
+            for (src, dst) in plane_buffer
+                .chunks(plane_row_bytes)
+                .zip(buffer.chunks_mut(row_bytes))
+            {
+                copy_samples(
+                    (src, 0, bits),
+                    (dst, plane * bits, planes * bits),
+                    bits,
+                    width,
+                );
+            }
+        }
+
+        Ok(failed_chunks)
+    }
+
+    /// Decodes a single plane of the image into `buffer`, with the layout of
+    /// [`Decoder::read_plane`], returning the chunks that were skipped as corrupt.
+    fn read_plane_bytes(
+        &mut self,
+        buffer: &mut [u8],
+        plane: PlaneIndex,
+        skip_corrupt: bool,
+    ) -> TiffResult<Vec<u32>> {
         let width = self.image().width;
         let height = self.image().height;
-        let mut result = self.result_buffer(width as usize, height as usize)?;
+        let mut failed_chunks = Vec::new();
         if width == 0 || height == 0 {
-            return Ok(result);
+            return Ok(failed_chunks);
+        }
+        if !skip_corrupt && self.read_single_strip(buffer)? {
+            return Ok(failed_chunks);
         }
 
         let chunk_dimensions = self.image().chunk_dimensions()?;
@@ -1094,28 +3588,276 @@ impl<R: Read + Seek> Decoder<R> {
         }
 
         let image_chunks = self.image().chunk_offsets.len() / self.image().strips_per_pixel();
-        // For multi-band images, only the first band is read.
-        // Possible improvements:
-        // * pass requested band as parameter
-        // * collect bands to a RGB encoding result in case of RGB bands
-        for chunk in 0..image_chunks {
-            self.goto_offset_u64(self.image().chunk_offsets[chunk])?;
-
-            let x = chunk % chunks_across;
-            let y = chunk / chunks_across;
+        let first_chunk = usize::from(plane.0) * image_chunks;
+        for chunk in first_chunk..first_chunk + image_chunks {
+            let x = (chunk - first_chunk) % chunks_across;
+            let y = (chunk - first_chunk) / chunks_across;
             let buffer_offset =
                 y * output_row_stride * chunk_dimensions.1 as usize + x * chunk_row_bytes;
-            let byte_order = self.reader.byte_order;
-            self.image.expand_chunk(
-                &mut self.reader,
-                &mut result.as_buffer(0).as_bytes_mut()[buffer_offset..],
-                output_row_stride,
-                byte_order,
-                chunk as u32,
-                &self.limits,
-            )?;
+            let expanded = self.check_chunk_range(chunk as u32).and_then(|range| {
+                let (offset, declared, readable) = range;
+                self.record_chunk_memory_usage(readable);
+                self.image
+                    .expand_chunk(
+                        &mut self.reader,
+                        &mut buffer[buffer_offset..],
+                        output_row_stride,
+                        chunk as u32,
+                        (offset, readable),
+                        &self.limits,
+                    )
+                    .map(|consumed| (declared, consumed))
+                    .map_err(|err| self.truncated_chunk_error(chunk as u32, range, err))
+            });
+
+            match expanded {
+                Ok((declared, consumed)) => {
+                    self.check_chunk_bytes(chunk as u32, declared, consumed)?
+                }
+                Err(err) if skip_corrupt && is_corrupt_chunk_error(&err) => {
+                    // Discard whatever was decoded before the error was encountered.
+                    let data_dims = self.image().chunk_data_dimensions(chunk as u32)?;
+                    let data_row_bytes = ((u64::from(data_dims.0)
+                        * u64::from(self.image.bits_per_sample)
+                        * samples as u64
+                        + 7)
+                        / 8) as usize;
+                    for row in 0..data_dims.1 as usize {
+                        let start = buffer_offset + row * output_row_stride;
+                        buffer[start..start + data_row_bytes].fill(0);
+                    }
+                    failed_chunks.push(chunk as u32);
+                }
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok(failed_chunks)
+    }
+
+    /// Reads an image stored as a single uncompressed strip directly into `buffer`.
+    ///
+    /// The strip holds the image exactly as it is returned, so it is read with a single read and
+    /// converted in one pass instead of chunk by chunk and row by row. Returns `false` without
+    /// reading anything if the image is stored otherwise, or if the byte count of the strip does
+    /// not match the size of the image, which is left to the checks of the generic path.
+    fn read_single_strip(&mut self, buffer: &mut [u8]) -> TiffResult<bool> {
+        let image = self.image();
+        let rows_per_strip = match &image.strip_decoder {
+            Some(strip) => strip.rows_per_strip,
+            None => return Ok(false),
+        };
+        if image.compression_method != CompressionMethod::None
+            || image.predictor != Predictor::None
+            || image.planar_config != PlanarConfiguration::Chunky
+            || rows_per_strip < image.height
+            || image.chunk_offsets.len() != 1
+            || matches!(
+                image.photometric_interpretation,
+                PhotometricInterpretation::LogL | PhotometricInterpretation::LogLuv
+            )
+        {
+            return Ok(false);
+        }
+
+        let color_type = image.decodable_color_type()?;
+        let (_, declared) = self.image.chunk_file_range(0, &mut self.reader)?;
+        if declared != buffer.len() as u64 {
+            return Ok(false);
+        }
+
+        let (offset, _, readable) = self.check_chunk_range(0)?;
+        if readable != declared {
+            return Ok(false);
+        }
+
+        self.record_chunk_memory_usage(declared);
+        self.goto_offset_u64(offset)?;
+        self.reader.read_exact(buffer)?;
+
+        fix_endianness(buffer, self.reader.byte_order, color_type.bit_depth());
+        let image = self.image();
+        if image.photometric_interpretation == PhotometricInterpretation::WhiteIsZero {
+            invert_colors(buffer, color_type, image.sample_format);
         }
+        Ok(true)
+    }
+
+    /// Decodes a rectangular region of the image.
+    ///
+    /// Only the chunks overlapping the region are read. The samples are returned interleaved,
+    /// pixel by pixel, for both chunky and planar images, so for a planar image the
+    /// corresponding chunk of every plane is read and merged.
+    pub fn read_region(
+        &mut self,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> TiffResult<DecodingResult> {
+        self.check_region(x, y, width, height)?;
+
+        // Sized for all samples of a pixel, even if the chunks hold a single plane.
+        let planes = self.image().strips_per_pixel();
+        let mut result = self.result_buffer(
+            (width as usize)
+                .checked_mul(planes)
+                .ok_or(TiffError::LimitsExceeded)?,
+            height as usize,
+        )?;
+        let row_stride = width as usize
+            * usize::from(self.image().samples)
+            * usize::from(self.image().bits_per_sample / 8);
+        self.read_region_bytes(
+            x,
+            y,
+            width,
+            height,
+            result.as_buffer(0).as_bytes_mut(),
+            row_stride,
+        )?;
 
         Ok(result)
     }
+
+    /// Decodes a rectangular region of the image into `buffer`, placing the rows `stride` samples
+    /// apart.
+    ///
+    /// This is like [`read_region`][Decoder::read_region], for decoding windows of a large image
+    /// into a buffer that is reused or part of a larger image. A row holds all samples of each
+    /// pixel, and the buffer must be of the sample type of the image, as returned by
+    /// [`Decoder::read_region`]. If `stride` is shorter than a row of the region or the buffer
+    /// can not hold all of its rows, [`UsageError::InsufficientBuffer`] is returned, a buffer of
+    /// another sample type is rejected with [`UsageError::InvalidBufferType`].
+    pub fn read_region_into(
+        &mut self,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+        mut buffer: DecodingBuffer,
+        stride: usize,
+    ) -> TiffResult<()> {
+        self.check_region(x, y, width, height)?;
+        self.check_buffer_type(&buffer)?;
+
+        let sample_bytes = usize::from(self.image().bits_per_sample / 8);
+        let row_bytes = (width as usize)
+            .checked_mul(usize::from(self.image().samples) * sample_bytes)
+            .ok_or(TiffError::LimitsExceeded)?;
+        let row_stride = stride
+            .checked_mul(sample_bytes)
+            .ok_or(TiffError::LimitsExceeded)?;
+        let buffer = buffer.as_bytes_mut();
+        let required_bytes = row_stride
+            .checked_mul(height as usize - 1)
+            .and_then(|bytes| bytes.checked_add(row_bytes))
+            .ok_or(TiffError::LimitsExceeded)?;
+        if row_stride < row_bytes || buffer.len() < required_bytes {
+            return Err(TiffError::UsageError(UsageError::InsufficientBuffer(
+                required_bytes,
+                buffer.len(),
+            )));
+        }
+
+        self.read_region_bytes(x, y, width, height, buffer, row_stride)
+    }
+
+    /// Checks that a region is not empty and lies within the image, and that its samples can be
+    /// cut out of the chunks.
+    fn check_region(&self, x: u32, y: u32, width: u32, height: u32) -> TiffResult<()> {
+        let image = self.image();
+        let in_region = |start: u32, len: u32, max: u32| {
+            len > 0 && start.checked_add(len).map_or(false, |end| end <= max)
+        };
+        if !in_region(x, width, image.width) || !in_region(y, height, image.height) {
+            return Err(TiffError::UsageError(UsageError::InvalidRegion(
+                x, y, width, height,
+            )));
+        }
+
+        if image.bits_per_sample % 8 != 0 {
+            return Err(TiffError::UnsupportedError(
+                TiffUnsupportedError::UnsupportedBitsPerChannel(image.bits_per_sample),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Decodes a region checked by [`check_region`][Decoder::check_region] into `out`, placing the
+    /// rows `row_stride` bytes apart.
+    fn read_region_bytes(
+        &mut self,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+        out: &mut [u8],
+        row_stride: usize,
+    ) -> TiffResult<()> {
+        let image = self.image();
+        let sample_bytes = usize::from(image.bits_per_sample / 8);
+        let samples = usize::from(image.samples);
+        let chunk_samples = image.samples_per_pixel();
+        let planes = image.strips_per_pixel();
+
+        let (chunk_width, chunk_height) = image.chunk_dimensions()?;
+        if chunk_width == 0 || chunk_height == 0 {
+            return Err(TiffError::FormatError(
+                TiffFormatError::InconsistentSizesEncountered,
+            ));
+        }
+        let chunks_across = (image.width - 1) / chunk_width + 1;
+        let chunks_down = (image.height - 1) / chunk_height + 1;
+
+        let (width, height) = (width as usize, height as usize);
+        let (x, y) = (x as usize, y as usize);
+        for plane in 0..planes {
+            for chunk_y in y / chunk_height as usize..=(y + height - 1) / chunk_height as usize {
+                for chunk_x in x / chunk_width as usize..=(x + width - 1) / chunk_width as usize {
+                    let chunk_index = u32::try_from(
+                        (plane * chunks_down as usize + chunk_y) * chunks_across as usize + chunk_x,
+                    )?;
+                    let (data_width, data_height) =
+                        self.image().chunk_data_dimensions(chunk_index)?;
+                    let mut chunk = self.read_chunk_at(ChunkIndex(chunk_index))?;
+                    let mut chunk = chunk.as_buffer(0);
+                    let chunk = chunk.as_bytes_mut();
+
+                    // The part of the region covered by this chunk, in image coordinates.
+                    let origin_x = chunk_x * chunk_width as usize;
+                    let origin_y = chunk_y * chunk_height as usize;
+                    let start_x = x.max(origin_x);
+                    let end_x = (x + width).min(origin_x + data_width as usize);
+                    let start_y = y.max(origin_y);
+                    let end_y = (y + height).min(origin_y + data_height as usize);
+
+                    for row in start_y..end_y {
+                        let src = ((row - origin_y) * data_width as usize + start_x - origin_x)
+                            * chunk_samples
+                            * sample_bytes;
+                        let dst = (row - y) * row_stride + (start_x - x) * samples * sample_bytes;
+                        let len = end_x - start_x;
+
+                        if chunk_samples == samples {
+                            let len = len * samples * sample_bytes;
+                            out[dst..dst + len].copy_from_slice(&chunk[src..src + len]);
+                        } else {
+                            // Interleave the single sample of this plane into the pixels.
+                            let sample_bits = sample_bytes * 8;
+                            copy_samples(
+                                (&chunk[src..], 0, sample_bits),
+                                (&mut out[dst..], plane * sample_bits, samples * sample_bits),
+                                sample_bits,
+                                len,
+                            );
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
 }
@@ -0,0 +1,124 @@
+//! Access to directories and data as stored in the file, for copying them to another file.
+use std::io::{Read, Seek};
+
+use super::stream::ByteOrder;
+use super::Decoder;
+use crate::tags::{Tag, Type};
+use crate::{OffsetSource, TiffError, TiffFormatError, TiffResult};
+
+/// The deepest nesting of directories pointed to from an image.
+pub(crate) const MAX_DIRECTORY_DEPTH: usize = 16;
+
+/// An entry of a directory with the bytes of its values in native byte order.
+pub(crate) struct RawEntry {
+    pub tag: Tag,
+    pub field_type: Type,
+    pub count: u64,
+    pub data: Vec<u8>,
+}
+
+impl RawEntry {
+    /// Decodes the values of an entry holding offsets or byte counts.
+    pub fn unsigned_values(&self) -> TiffResult<Vec<u64>> {
+        let values = match self.field_type {
+            Type::BYTE => self.data.iter().map(|&byte| u64::from(byte)).collect(),
+            Type::SHORT => self
+                .data
+                .chunks_exact(2)
+                .map(|bytes| u64::from(u16::from_ne_bytes([bytes[0], bytes[1]])))
+                .collect(),
+            Type::LONG | Type::IFD => self
+                .data
+                .chunks_exact(4)
+                .map(|bytes| u64::from(u32::from_ne_bytes(bytes.try_into().unwrap())))
+                .collect(),
+            Type::LONG8 | Type::IFD8 => self
+                .data
+                .chunks_exact(8)
+                .map(|bytes| u64::from_ne_bytes(bytes.try_into().unwrap()))
+                .collect(),
+            _ => {
+                return Err(TiffError::FormatError(
+                    TiffFormatError::InvalidTagValueType(self.tag),
+                ))
+            }
+        };
+
+        Ok(values)
+    }
+}
+
+impl<R: Read + Seek> Decoder<R> {
+    /// The location of the directory of the first image.
+    pub(crate) fn first_ifd_offset(&self) -> Option<u64> {
+        self.ifd_offsets.first().copied()
+    }
+
+    /// Reads the directory at `ifd_location` with the values of its entries in native byte order,
+    /// in ascending tag order, along with the location of the next directory.
+    ///
+    /// `path` holds the directories pointing to this one, reading one of them again fails with
+    /// [`TiffFormatError::CycleInOffsets`]. Directories nested deeper than
+    /// [`MAX_DIRECTORY_DEPTH`] exceed the limits.
+    pub(crate) fn read_raw_ifd_at(
+        &mut self,
+        ifd_location: u64,
+        path: &[u64],
+    ) -> TiffResult<(Vec<RawEntry>, Option<u64>)> {
+        if path.contains(&ifd_location) {
+            return Err(TiffError::FormatError(TiffFormatError::CycleInOffsets));
+        }
+        if path.len() >= MAX_DIRECTORY_DEPTH {
+            return Err(TiffError::LimitsExceeded);
+        }
+
+        let (ifd, next_ifd) = self.read_ifd_at(ifd_location)?;
+
+        let swap = match self.byte_order() {
+            ByteOrder::LittleEndian => cfg!(target_endian = "big"),
+            ByteOrder::BigEndian => cfg!(target_endian = "little"),
+        };
+        let mut entries = Vec::with_capacity(ifd.len());
+        for (tag, entry) in ifd {
+            let mut data = entry.bytes_val(tag, &self.limits, self.bigtiff, &mut self.reader)?;
+            let value_len = match entry.field_type() {
+                Type::SHORT | Type::SSHORT => 2,
+                // Rationals are pairs of 4 byte integers.
+                Type::LONG | Type::SLONG | Type::FLOAT | Type::IFD => 4,
+                Type::RATIONAL | Type::SRATIONAL => 4,
+                Type::LONG8 | Type::SLONG8 | Type::DOUBLE | Type::IFD8 => 8,
+                Type::BYTE | Type::SBYTE | Type::ASCII | Type::UNDEFINED => 1,
+            };
+            if swap {
+                data.chunks_exact_mut(value_len).for_each(<[u8]>::reverse);
+            }
+
+            entries.push(RawEntry {
+                tag,
+                field_type: entry.field_type(),
+                count: entry.count(),
+                data,
+            });
+        }
+
+        Ok((entries, next_ifd))
+    }
+
+    /// Reads `length` bytes at `offset`, such as the data of a chunk.
+    pub(crate) fn read_raw_data(
+        &mut self,
+        offset: u64,
+        length: u64,
+        source: OffsetSource,
+    ) -> TiffResult<Vec<u8>> {
+        let length = usize::try_from(length)?;
+        if length > self.limits.decoding_buffer_size {
+            return Err(TiffError::LimitsExceeded);
+        }
+
+        self.reader.goto_range(offset, length as u64, source)?;
+        let mut data = vec![0; length];
+        self.reader.read_exact(&mut data)?;
+        Ok(data)
+    }
+}
@@ -2,8 +2,10 @@
 
 use std::io::{self, BufRead, BufReader, Read, Seek, Take};
 
+use crate::{OffsetSource, TiffFormatError, TiffResult};
+
 /// Byte order of the TIFF file.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum ByteOrder {
     /// little endian byte order
     LittleEndian,
@@ -11,6 +13,15 @@ pub enum ByteOrder {
     BigEndian,
 }
 
+impl ByteOrder {
+    /// The byte order of the target, which decoded samples are returned in.
+    pub(crate) const NATIVE: ByteOrder = if cfg!(target_endian = "little") {
+        ByteOrder::LittleEndian
+    } else {
+        ByteOrder::BigEndian
+    };
+}
+
 /// Reader that is aware of the byte order.
 pub trait EndianReader: Read {
     /// Byte order that should be adhered to
@@ -185,6 +196,7 @@ impl<R: Read> Read for LZWReader<R> {
 /// ## PackBits Reader
 ///
 
+#[derive(Clone, Copy)]
 enum PackBitsReaderState {
     Header,
     Literal,
@@ -196,59 +208,101 @@ pub struct PackBitsReader<R: Read> {
     reader: Take<R>,
     state: PackBitsReaderState,
     count: usize,
+    /// Compressed bytes read ahead, runs are decoded from here instead of byte by byte.
+    input: Box<[u8]>,
+    input_start: usize,
+    input_end: usize,
 }
 
 impl<R: Read> PackBitsReader<R> {
+    /// Size of the read-ahead buffer for the compressed data.
+    const INPUT_BUFFER_SIZE: u64 = 8192;
+
     /// Wraps a reader
     pub fn new(reader: R, length: u64) -> Self {
+        let input_size = length.min(Self::INPUT_BUFFER_SIZE) as usize;
         Self {
             reader: reader.take(length),
             state: PackBitsReaderState::Header,
             count: 0,
+            input: vec![0; input_size].into_boxed_slice(),
+            input_start: 0,
+            input_end: 0,
+        }
+    }
+
+    /// Make sure there are compressed bytes available, returns `false` at the end of the data.
+    fn fill_input(&mut self) -> io::Result<bool> {
+        if self.input_start == self.input_end {
+            self.input_start = 0;
+            self.input_end = self.reader.read(&mut self.input)?;
+        }
+        Ok(self.input_start < self.input_end)
+    }
+
+    fn next_byte(&mut self) -> io::Result<Option<u8>> {
+        if !self.fill_input()? {
+            return Ok(None);
         }
+        let byte = self.input[self.input_start];
+        self.input_start += 1;
+        Ok(Some(byte))
     }
 }
 
 impl<R: Read> Read for PackBitsReader<R> {
     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
-        while let PackBitsReaderState::Header = self.state {
-            if self.reader.limit() == 0 {
-                return Ok(0);
-            }
-            let mut header: [u8; 1] = [0];
-            self.reader.read_exact(&mut header)?;
-            let h = header[0] as i8;
-            if (-127..=-1).contains(&h) {
-                let mut data: [u8; 1] = [0];
-                self.reader.read_exact(&mut data)?;
-                self.state = PackBitsReaderState::Repeat { value: data[0] };
-                self.count = (1 - h as isize) as usize;
-            } else if h >= 0 {
-                self.state = PackBitsReaderState::Literal;
-                self.count = h as usize + 1;
-            } else {
-                // h = -128 is a no-op.
+        let mut written = 0;
+
+        while written < buf.len() {
+            if let PackBitsReaderState::Header = self.state {
+                let h = match self.next_byte()? {
+                    Some(header) => header as i8,
+                    None => break,
+                };
+                if (-127..=-1).contains(&h) {
+                    let value = self
+                        .next_byte()?
+                        .ok_or_else(|| io::Error::from(io::ErrorKind::UnexpectedEof))?;
+                    self.state = PackBitsReaderState::Repeat { value };
+                    self.count = (1 - h as isize) as usize;
+                } else if h >= 0 {
+                    self.state = PackBitsReaderState::Literal;
+                    self.count = h as usize + 1;
+                } else {
+                    // h = -128 is a no-op.
+                }
+                continue;
             }
-        }
 
-        let length = buf.len().min(self.count);
-        let actual = match self.state {
-            PackBitsReaderState::Literal => self.reader.read(&mut buf[..length])?,
-            PackBitsReaderState::Repeat { value } => {
-                for b in &mut buf[..length] {
-                    *b = value;
+            let out = &mut buf[written..];
+            let length = out.len().min(self.count);
+            let actual = match self.state {
+                PackBitsReaderState::Literal => {
+                    if !self.fill_input()? {
+                        break;
+                    }
+                    let available = &self.input[self.input_start..self.input_end];
+                    let actual = length.min(available.len());
+                    out[..actual].copy_from_slice(&available[..actual]);
+                    self.input_start += actual;
+                    actual
                 }
+                PackBitsReaderState::Repeat { value } => {
+                    out[..length].fill(value);
+                    length
+                }
+                PackBitsReaderState::Header => unreachable!(),
+            };
 
-                length
+            written += actual;
+            self.count -= actual;
+            if self.count == 0 {
+                self.state = PackBitsReaderState::Header;
             }
-            PackBitsReaderState::Header => unreachable!(),
-        };
-
-        self.count -= actual;
-        if self.count == 0 {
-            self.state = PackBitsReaderState::Header;
         }
-        Ok(actual)
+
+        Ok(written)
     }
 }
 
@@ -257,13 +311,15 @@ impl<R: Read> Read for PackBitsReader<R> {
 ///
 
 /// Reader that is aware of the byte order.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct SmartReader<R>
 where
     R: Read,
 {
     reader: R,
     pub byte_order: ByteOrder,
+    /// The length of the stream, if offsets are checked against it.
+    stream_length: Option<u64>,
 }
 
 impl<R> SmartReader<R>
@@ -272,7 +328,11 @@ where
 {
     /// Wraps a reader
     pub fn wrap(reader: R, byte_order: ByteOrder) -> SmartReader<R> {
-        SmartReader { reader, byte_order }
+        SmartReader {
+            reader,
+            byte_order,
+            stream_length: None,
+        }
     }
     pub fn into_inner(self) -> R {
         self.reader
@@ -282,6 +342,43 @@ impl<R: Read + Seek> SmartReader<R> {
     pub fn goto_offset(&mut self, offset: u64) -> io::Result<()> {
         self.seek(io::SeekFrom::Start(offset)).map(|_| ())
     }
+
+    /// Determines the length of the stream, which offsets are checked against from then on.
+    pub fn measure_stream_length(&mut self) -> io::Result<()> {
+        let position = self.stream_position()?;
+        let length = self.seek(io::SeekFrom::End(0))?;
+        self.seek(io::SeekFrom::Start(position))?;
+        self.stream_length = Some(length);
+        Ok(())
+    }
+
+    pub fn stream_length(&self) -> Option<u64> {
+        self.stream_length
+    }
+
+    /// Checks that `length` bytes at `offset` lie within the stream, if its length is known.
+    pub fn check_range(&self, offset: u64, length: u64, source: OffsetSource) -> TiffResult<()> {
+        let stream_length = match self.stream_length {
+            Some(stream_length) => stream_length,
+            None => return Ok(()),
+        };
+
+        match offset.checked_add(length) {
+            Some(end) if end <= stream_length => Ok(()),
+            _ => Err(TiffFormatError::OffsetOutOfBounds {
+                offset,
+                stream_length,
+                source,
+            }
+            .into()),
+        }
+    }
+
+    /// Moves to `offset` after checking that `length` bytes there lie within the stream.
+    pub fn goto_range(&mut self, offset: u64, length: u64, source: OffsetSource) -> TiffResult<()> {
+        self.check_range(offset, length, source)?;
+        Ok(self.goto_offset(offset)?)
+    }
 }
 
 impl<R> EndianReader for SmartReader<R>
@@ -308,6 +405,65 @@ impl<R: Read + Seek> Seek for SmartReader<R> {
     }
 }
 
+/// Reader that counts the bytes read from the wrapped reader.
+pub struct CountingReader<R: Read> {
+    reader: R,
+    count: u64,
+}
+
+impl<R: Read> CountingReader<R> {
+    pub fn new(reader: R) -> Self {
+        CountingReader { reader, count: 0 }
+    }
+
+    /// The number of bytes read so far.
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.reader.read(buf)?;
+        self.count += read as u64;
+        Ok(read)
+    }
+}
+
+/// Writer that keeps the first `limit` bytes written to it and discards the rest.
+#[cfg(feature = "lzma")]
+pub(crate) struct TruncatingWriter {
+    data: Vec<u8>,
+    limit: usize,
+}
+
+#[cfg(feature = "lzma")]
+impl TruncatingWriter {
+    pub fn new(limit: usize) -> Self {
+        TruncatingWriter {
+            data: Vec::new(),
+            limit,
+        }
+    }
+
+    pub fn into_inner(self) -> Vec<u8> {
+        self.data
+    }
+}
+
+#[cfg(feature = "lzma")]
+impl io::Write for TruncatingWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let kept = buf.len().min(self.limit - self.data.len());
+        self.data.extend_from_slice(&buf[..kept]);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -332,4 +488,35 @@ mod test {
         ];
         assert_eq!(decoded, expected);
     }
+
+    #[test]
+    fn test_packbits_split_reads() {
+        // Runs that straddle both the read-ahead buffer and the output buffers.
+        let mut encoded = Vec::new();
+        let mut expected = Vec::new();
+        for i in 0..2000u32 {
+            let literal: Vec<u8> = (0..=(i % 128) as u8).collect();
+            encoded.push(literal.len() as u8 - 1);
+            encoded.extend_from_slice(&literal);
+            expected.extend_from_slice(&literal);
+
+            let repeat = (i % 127) as u8 + 2;
+            encoded.push((1 - i16::from(repeat)) as u8);
+            encoded.push(i as u8);
+            expected.extend(std::iter::repeat(i as u8).take(repeat.into()));
+        }
+
+        for chunk_size in [1, 7, 4096] {
+            let mut decoder = PackBitsReader::new(io::Cursor::new(&encoded), encoded.len() as u64);
+            let mut decoded = Vec::new();
+            let mut chunk = vec![0; chunk_size];
+            loop {
+                match decoder.read(&mut chunk).unwrap() {
+                    0 => break,
+                    n => decoded.extend_from_slice(&chunk[..n]),
+                }
+            }
+            assert_eq!(decoded, expected);
+        }
+    }
 }
@@ -1,6 +1,7 @@
 use std::io::{Read, Seek};
 
-use crate::tags::Tag;
+use crate::baseline;
+use crate::tags::{NewSubfileType, Tag, Type};
 use crate::{TiffError, TiffFormatError, TiffResult};
 
 use super::ifd::{Directory, Value};
@@ -12,11 +13,21 @@ pub(crate) struct TagReader<'a, R: Read + Seek> {
     pub ifd: &'a Directory,
     pub limits: &'a Limits,
     pub bigtiff: bool,
+    /// Whether values of well-known tags stored with a wrong field type are coerced, see
+    /// [`coerced_field_type`].
+    pub lenient: bool,
 }
 impl<'a, R: Read + Seek> TagReader<'a, R> {
     pub(crate) fn find_tag(&mut self, tag: Tag) -> TiffResult<Option<Value>> {
         Ok(match self.ifd.get(&tag) {
-            Some(entry) => Some(entry.clone().val(self.limits, self.bigtiff, self.reader)?),
+            Some(entry) => {
+                let value = entry.tag_val(tag, self.limits, self.bigtiff, self.reader)?;
+                if self.lenient && coerced_field_type(tag, entry.field_type()) {
+                    Some(coerce(tag, value))
+                } else {
+                    Some(value)
+                }
+            }
             None => None,
         })
     }
@@ -28,6 +39,19 @@ impl<'a, R: Read + Seek> TagReader<'a, R> {
             )),
         }
     }
+    /// Like `require_tag`, but falls back to the baseline default value of the tag.
+    pub(crate) fn require_tag_or_default(&mut self, tag: Tag) -> TiffResult<Value> {
+        match self.find_tag(tag)? {
+            Some(val) => Ok(val),
+            None => {
+                baseline::default_value(tag)
+                    .map(Value::Unsigned)
+                    .ok_or(TiffError::FormatError(
+                        TiffFormatError::RequiredTagNotFound(tag),
+                    ))
+            }
+        }
+    }
     pub fn find_tag_uint_vec<T: TryFrom<u64>>(&mut self, tag: Tag) -> TiffResult<Option<Vec<T>>> {
         self.find_tag(tag)?
             .map(|v| v.into_u64_vec())
@@ -41,4 +65,153 @@ impl<'a, R: Read + Seek> TagReader<'a, R> {
             })
             .transpose()
     }
+    /// Reads the `NewSubfileType`, which is empty if the tag is missing.
+    pub(crate) fn new_subfile_type(&mut self) -> TiffResult<NewSubfileType> {
+        Ok(self
+            .find_tag(Tag::NewSubfileType)?
+            .map(Value::into_u32)
+            .transpose()?
+            .map(NewSubfileType::from_bits)
+            .unwrap_or_default())
+    }
+}
+
+/// The kind of value a well-known tag holds.
+#[derive(Clone, Copy)]
+enum ValueKind {
+    Unsigned,
+    Rational,
+}
+
+impl ValueKind {
+    fn of(tag: Tag) -> Option<ValueKind> {
+        Some(match tag {
+            Tag::NewSubfileType
+            | Tag::ImageWidth
+            | Tag::ImageLength
+            | Tag::BitsPerSample
+            | Tag::Compression
+            | Tag::PhotometricInterpretation
+            | Tag::FillOrder
+            | Tag::StripOffsets
+            | Tag::Orientation
+            | Tag::SamplesPerPixel
+            | Tag::RowsPerStrip
+            | Tag::StripByteCounts
+            | Tag::PlanarConfiguration
+            | Tag::ResolutionUnit
+            | Tag::Predictor
+            | Tag::TileWidth
+            | Tag::TileLength
+            | Tag::TileOffsets
+            | Tag::TileByteCounts
+            | Tag::ExtraSamples
+            | Tag::SampleFormat => ValueKind::Unsigned,
+            Tag::XResolution | Tag::YResolution => ValueKind::Rational,
+            _ => return None,
+        })
+    }
+
+    /// Converts a value of another type without loss, if possible.
+    fn convert(self, value: &Value) -> Option<Value> {
+        match (self, value) {
+            (_, Value::List(values)) => values
+                .iter()
+                .map(|value| self.convert(value))
+                .collect::<Option<_>>()
+                .map(Value::List),
+            (ValueKind::Unsigned, value) => integral(value).map(|value| {
+                if let Ok(value) = u16::try_from(value) {
+                    Value::Short(value)
+                } else if let Ok(value) = u32::try_from(value) {
+                    Value::Unsigned(value)
+                } else {
+                    Value::UnsignedBig(value)
+                }
+            }),
+            (ValueKind::Rational, Value::SRational(n, d)) => Some(Value::Rational(
+                u32::try_from(*n).ok()?,
+                u32::try_from(*d).ok()?,
+            )),
+            (ValueKind::Rational, value) => {
+                let value = match value {
+                    Value::Byte(value) => u64::from(*value),
+                    Value::Short(value) => u64::from(*value),
+                    Value::Unsigned(value) => u64::from(*value),
+                    Value::UnsignedBig(value) => *value,
+                    _ => return None,
+                };
+                Some(match u32::try_from(value) {
+                    Ok(value) => Value::Rational(value, 1),
+                    Err(_) => Value::RationalBig(value, 1),
+                })
+            }
+        }
+    }
+}
+
+/// The value of an integer, a fraction or a floating point number, if it is a whole number that
+/// is not negative.
+fn integral(value: &Value) -> Option<u64> {
+    match *value {
+        Value::Byte(value) => Some(value.into()),
+        Value::Short(value) => Some(value.into()),
+        Value::Unsigned(value) | Value::Ifd(value) => Some(value.into()),
+        Value::UnsignedBig(value) | Value::IfdBig(value) => Some(value),
+        Value::SignedByte(value) => u64::try_from(value).ok(),
+        Value::SignedShort(value) => u64::try_from(value).ok(),
+        Value::Signed(value) => u64::try_from(value).ok(),
+        Value::SignedBig(value) => u64::try_from(value).ok(),
+        Value::Rational(n, d) if d != 0 && n % d == 0 => Some((n / d).into()),
+        Value::RationalBig(n, d) if d != 0 && n % d == 0 => Some(n / d),
+        Value::SRational(n, d) if d != 0 && n % d == 0 => u64::try_from(n / d).ok(),
+        Value::SRationalBig(n, d) if d != 0 && n % d == 0 => u64::try_from(n / d).ok(),
+        Value::Float(value) => integral_float(value.into()),
+        Value::Double(value) => integral_float(value),
+        _ => None,
+    }
+}
+
+fn integral_float(value: f64) -> Option<u64> {
+    // Every whole number below 2^53 is exact, larger ones are too large for any image anyway.
+    if (0.0..9_007_199_254_740_992.0).contains(&value) && value.fract() == 0.0 {
+        Some(value as u64)
+    } else {
+        None
+    }
+}
+
+/// Whether the value of a well-known `tag` is stored with a field type other than the ones the
+/// specification allows, but one that lenient mode converts.
+pub(crate) fn coerced_field_type(tag: Tag, field_type: Type) -> bool {
+    match ValueKind::of(tag) {
+        Some(ValueKind::Unsigned) => matches!(
+            field_type,
+            Type::BYTE
+                | Type::SBYTE
+                | Type::SSHORT
+                | Type::SLONG
+                | Type::SLONG8
+                | Type::RATIONAL
+                | Type::SRATIONAL
+                | Type::FLOAT
+                | Type::DOUBLE
+        ),
+        Some(ValueKind::Rational) => matches!(
+            field_type,
+            Type::BYTE | Type::SHORT | Type::LONG | Type::LONG8 | Type::SRATIONAL
+        ),
+        None => false,
+    }
+}
+
+/// Converts the value of a well-known `tag` to the type the specification gives it.
+///
+/// Values that cannot be converted without loss, such as negative or fractional numbers, are
+/// returned as they are and fail when they are interpreted.
+pub(crate) fn coerce(tag: Tag, value: Value) -> Value {
+    match ValueKind::of(tag) {
+        Some(kind) => kind.convert(&value).unwrap_or(value),
+        None => value,
+    }
 }
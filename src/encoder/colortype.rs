@@ -1,4 +1,5 @@
-use crate::tags::{PhotometricInterpretation, SampleFormat};
+use crate::tags::{ExtraSample, PhotometricInterpretation, SampleFormat};
+use crate::{TiffError, TiffResult, UsageError};
 
 macro_rules! integer_horizontal_predict {
     () => {
@@ -26,6 +27,32 @@ macro_rules! integer_horizontal_predict {
     };
 }
 
+macro_rules! float_floating_point_predict {
+    () => {
+        fn floating_point_predict(row: &[Self::Inner], result: &mut Vec<u8>) -> TiffResult<()> {
+            let sample_size = Self::SAMPLE_FORMAT.len();
+            let byte_len = std::mem::size_of::<Self::Inner>();
+
+            let start = result.len();
+            result.resize(start + row.len() * byte_len, 0);
+            let shuffled = &mut result[start..];
+
+            // Big endian bytes of equal significance are grouped, the most significant first.
+            for (i, sample) in row.iter().enumerate() {
+                for (plane, byte) in sample.to_be_bytes().into_iter().enumerate() {
+                    shuffled[plane * row.len() + i] = byte;
+                }
+            }
+
+            for i in (sample_size..shuffled.len()).rev() {
+                shuffled[i] = shuffled[i].wrapping_sub(shuffled[i - sample_size]);
+            }
+
+            Ok(())
+        }
+    };
+}
+
 /// Trait for different colortypes that can be encoded.
 pub trait ColorType {
     /// The type of each sample of this colortype
@@ -36,8 +63,19 @@ pub trait ColorType {
     const BITS_PER_SAMPLE: &'static [u16];
     /// The value of the tiff tag `SampleFormat`
     const SAMPLE_FORMAT: &'static [SampleFormat];
+    /// The value of the tiff tag `ExtraSamples`, the tag is not written if this is empty
+    const EXTRA_SAMPLES: &'static [ExtraSample] = &[];
 
     fn horizontal_predict(row: &[Self::Inner], result: &mut Vec<Self::Inner>);
+
+    /// Appends a row predicted with [`Predictor::FloatingPoint`][crate::tags::Predictor] to
+    /// `result`, only called for `IEEEFP` samples.
+    ///
+    /// Color types without an implementation reject the predictor with
+    /// [`UsageError::PredictorUnavailable`].
+    fn floating_point_predict(_row: &[Self::Inner], _result: &mut Vec<u8>) -> TiffResult<()> {
+        Err(TiffError::UsageError(UsageError::PredictorUnavailable))
+    }
 }
 
 pub struct Gray8;
@@ -80,6 +118,28 @@ impl ColorType for GrayI16 {
     integer_horizontal_predict!();
 }
 
+pub struct GrayA8;
+impl ColorType for GrayA8 {
+    type Inner = u8;
+    const TIFF_VALUE: PhotometricInterpretation = PhotometricInterpretation::BlackIsZero;
+    const BITS_PER_SAMPLE: &'static [u16] = &[8, 8];
+    const SAMPLE_FORMAT: &'static [SampleFormat] = &[SampleFormat::Uint; 2];
+    const EXTRA_SAMPLES: &'static [ExtraSample] = &[ExtraSample::UnassociatedAlpha];
+
+    integer_horizontal_predict!();
+}
+
+pub struct GrayA16;
+impl ColorType for GrayA16 {
+    type Inner = u16;
+    const TIFF_VALUE: PhotometricInterpretation = PhotometricInterpretation::BlackIsZero;
+    const BITS_PER_SAMPLE: &'static [u16] = &[16, 16];
+    const SAMPLE_FORMAT: &'static [SampleFormat] = &[SampleFormat::Uint; 2];
+    const EXTRA_SAMPLES: &'static [ExtraSample] = &[ExtraSample::UnassociatedAlpha];
+
+    integer_horizontal_predict!();
+}
+
 pub struct Gray32;
 impl ColorType for Gray32 {
     type Inner = u32;
@@ -110,6 +170,8 @@ impl ColorType for Gray32Float {
     fn horizontal_predict(_: &[Self::Inner], _: &mut Vec<Self::Inner>) {
         unreachable!()
     }
+
+    float_floating_point_predict!();
 }
 
 pub struct Gray64;
@@ -142,6 +204,8 @@ impl ColorType for Gray64Float {
     fn horizontal_predict(_: &[Self::Inner], _: &mut Vec<Self::Inner>) {
         unreachable!()
     }
+
+    float_floating_point_predict!();
 }
 
 pub struct RGB8;
@@ -183,6 +247,8 @@ impl ColorType for RGB32Float {
     fn horizontal_predict(_: &[Self::Inner], _: &mut Vec<Self::Inner>) {
         unreachable!()
     }
+
+    float_floating_point_predict!();
 }
 
 pub struct RGB64;
@@ -204,6 +270,8 @@ impl ColorType for RGB64Float {
     fn horizontal_predict(_: &[Self::Inner], _: &mut Vec<Self::Inner>) {
         unreachable!()
     }
+
+    float_floating_point_predict!();
 }
 
 pub struct RGBA8;
@@ -212,6 +280,7 @@ impl ColorType for RGBA8 {
     const TIFF_VALUE: PhotometricInterpretation = PhotometricInterpretation::RGB;
     const BITS_PER_SAMPLE: &'static [u16] = &[8, 8, 8, 8];
     const SAMPLE_FORMAT: &'static [SampleFormat] = &[SampleFormat::Uint; 4];
+    const EXTRA_SAMPLES: &'static [ExtraSample] = &[ExtraSample::UnassociatedAlpha];
 
     integer_horizontal_predict!();
 }
@@ -222,6 +291,7 @@ impl ColorType for RGBA16 {
     const TIFF_VALUE: PhotometricInterpretation = PhotometricInterpretation::RGB;
     const BITS_PER_SAMPLE: &'static [u16] = &[16, 16, 16, 16];
     const SAMPLE_FORMAT: &'static [SampleFormat] = &[SampleFormat::Uint; 4];
+    const EXTRA_SAMPLES: &'static [ExtraSample] = &[ExtraSample::UnassociatedAlpha];
 
     integer_horizontal_predict!();
 }
@@ -232,6 +302,7 @@ impl ColorType for RGBA32 {
     const TIFF_VALUE: PhotometricInterpretation = PhotometricInterpretation::RGB;
     const BITS_PER_SAMPLE: &'static [u16] = &[32, 32, 32, 32];
     const SAMPLE_FORMAT: &'static [SampleFormat] = &[SampleFormat::Uint; 4];
+    const EXTRA_SAMPLES: &'static [ExtraSample] = &[ExtraSample::UnassociatedAlpha];
 
     integer_horizontal_predict!();
 }
@@ -242,9 +313,12 @@ impl ColorType for RGBA32Float {
     const TIFF_VALUE: PhotometricInterpretation = PhotometricInterpretation::RGB;
     const BITS_PER_SAMPLE: &'static [u16] = &[32, 32, 32, 32];
     const SAMPLE_FORMAT: &'static [SampleFormat] = &[SampleFormat::IEEEFP; 4];
+    const EXTRA_SAMPLES: &'static [ExtraSample] = &[ExtraSample::UnassociatedAlpha];
     fn horizontal_predict(_: &[Self::Inner], _: &mut Vec<Self::Inner>) {
         unreachable!()
     }
+
+    float_floating_point_predict!();
 }
 
 pub struct RGBA64;
@@ -253,6 +327,7 @@ impl ColorType for RGBA64 {
     const TIFF_VALUE: PhotometricInterpretation = PhotometricInterpretation::RGB;
     const BITS_PER_SAMPLE: &'static [u16] = &[64, 64, 64, 64];
     const SAMPLE_FORMAT: &'static [SampleFormat] = &[SampleFormat::Uint; 4];
+    const EXTRA_SAMPLES: &'static [ExtraSample] = &[ExtraSample::UnassociatedAlpha];
 
     integer_horizontal_predict!();
 }
@@ -263,9 +338,12 @@ impl ColorType for RGBA64Float {
     const TIFF_VALUE: PhotometricInterpretation = PhotometricInterpretation::RGB;
     const BITS_PER_SAMPLE: &'static [u16] = &[64, 64, 64, 64];
     const SAMPLE_FORMAT: &'static [SampleFormat] = &[SampleFormat::IEEEFP; 4];
+    const EXTRA_SAMPLES: &'static [ExtraSample] = &[ExtraSample::UnassociatedAlpha];
     fn horizontal_predict(_: &[Self::Inner], _: &mut Vec<Self::Inner>) {
         unreachable!()
     }
+
+    float_floating_point_predict!();
 }
 
 pub struct CMYK8;
@@ -308,6 +386,8 @@ impl ColorType for CMYK32Float {
     fn horizontal_predict(_: &[Self::Inner], _: &mut Vec<Self::Inner>) {
         unreachable!()
     }
+
+    float_floating_point_predict!();
 }
 
 pub struct CMYK64;
@@ -330,6 +410,30 @@ impl ColorType for CMYK64Float {
     fn horizontal_predict(_: &[Self::Inner], _: &mut Vec<Self::Inner>) {
         unreachable!()
     }
+
+    float_floating_point_predict!();
+}
+
+pub struct CMYKA8;
+impl ColorType for CMYKA8 {
+    type Inner = u8;
+    const TIFF_VALUE: PhotometricInterpretation = PhotometricInterpretation::CMYK;
+    const BITS_PER_SAMPLE: &'static [u16] = &[8, 8, 8, 8, 8];
+    const SAMPLE_FORMAT: &'static [SampleFormat] = &[SampleFormat::Uint; 5];
+    const EXTRA_SAMPLES: &'static [ExtraSample] = &[ExtraSample::UnassociatedAlpha];
+
+    integer_horizontal_predict!();
+}
+
+pub struct CMYKA16;
+impl ColorType for CMYKA16 {
+    type Inner = u16;
+    const TIFF_VALUE: PhotometricInterpretation = PhotometricInterpretation::CMYK;
+    const BITS_PER_SAMPLE: &'static [u16] = &[16, 16, 16, 16, 16];
+    const SAMPLE_FORMAT: &'static [SampleFormat] = &[SampleFormat::Uint; 5];
+    const EXTRA_SAMPLES: &'static [ExtraSample] = &[ExtraSample::UnassociatedAlpha];
+
+    integer_horizontal_predict!();
 }
 
 pub struct YCbCr8;
@@ -341,3 +445,14 @@ impl ColorType for YCbCr8 {
 
     integer_horizontal_predict!();
 }
+
+pub struct YCbCrA8;
+impl ColorType for YCbCrA8 {
+    type Inner = u8;
+    const TIFF_VALUE: PhotometricInterpretation = PhotometricInterpretation::YCbCr;
+    const BITS_PER_SAMPLE: &'static [u16] = &[8, 8, 8, 8];
+    const SAMPLE_FORMAT: &'static [SampleFormat] = &[SampleFormat::Uint; 4];
+    const EXTRA_SAMPLES: &'static [ExtraSample] = &[ExtraSample::UnassociatedAlpha];
+
+    integer_horizontal_predict!();
+}
@@ -0,0 +1,221 @@
+//! Rewriting files without the space that no directory refers to.
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::{Read, Seek, Write};
+
+use super::{DirectoryEncoder, DirectoryEntry, IfdPointer, TiffEncoder, TiffKind};
+use crate::decoder::{Decoder, RawEntry};
+use crate::tags::Tag;
+use crate::{OffsetSource, TiffError, TiffFormatError, TiffResult};
+
+/// The tags holding the offsets of data, along with the tags holding its byte counts.
+const DATA_TAGS: [(Tag, Tag); 3] = [
+    (Tag::StripOffsets, Tag::StripByteCounts),
+    (Tag::TileOffsets, Tag::TileByteCounts),
+    (Tag::JPEGInterchangeFormat, Tag::JPEGInterchangeFormatLength),
+];
+
+/// The tags pointing to private directories, which are not images.
+const POINTER_TAGS: [Tag; 3] = [Tag::ExifDirectory, Tag::GpsDirectory, Tag::InteropDirectory];
+
+/// Rewrites the file read by `decoder` to `writer`, leaving out all space that none of its
+/// directories refer to.
+///
+/// Such space is left behind when a file is edited in place, e.g. by writers appending a changed
+/// directory or chunk instead of overwriting the old one. The rewritten file holds the same
+/// images with the same tags, see [`TiffEncoder::copy_images`]. BigTiff files stay BigTiff.
+pub fn compact<R: Read + Seek, W: Write + Seek>(
+    decoder: &mut Decoder<R>,
+    writer: W,
+) -> TiffResult<()> {
+    if decoder.is_bigtiff() {
+        TiffEncoder::new_big(writer)?.copy_images(decoder)
+    } else {
+        TiffEncoder::new(writer)?.copy_images(decoder)
+    }
+}
+
+impl<W: Write + Seek, K: TiffKind> TiffEncoder<W, K> {
+    /// Copies all images of `decoder` to this file, without decoding their data.
+    ///
+    /// Every entry of the directories is copied as it is, except for the tags locating data in
+    /// the file. The strips, tiles and JPEG interchange stream of each image are written right
+    /// before its directory and their offsets rewritten, chunks shared between several entries
+    /// stay shared. The `SubIFDs` and the EXIF, GPS and interoperability directories are copied
+    /// along with the image referring to them. `FreeOffsets` and `FreeByteCounts` are dropped, as
+    /// the space they describe is not copied.
+    ///
+    /// Offsets within other values, such as those of a maker note, are not rewritten. Entries are
+    /// copied with their field types, so the decoder should read a file of the same kind as this
+    /// encoder writes.
+    pub fn copy_images<R: Read + Seek>(&mut self, decoder: &mut Decoder<R>) -> TiffResult<()> {
+        let mut seen = HashSet::new();
+        let mut next = decoder.first_ifd_offset();
+        while let Some(offset) = next {
+            if !seen.insert(offset) {
+                return Err(TiffError::FormatError(TiffFormatError::CycleInOffsets));
+            }
+            let mut encoder = self.new_directory()?;
+            next = encoder.copy_directory(decoder, offset, &mut Vec::new())?;
+            encoder.finish()?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<'a, W: 'a + Write + Seek, K: TiffKind> DirectoryEncoder<'a, W, K> {
+    /// Copies the entries of the directory at `offset` of `decoder` along with the data and the
+    /// directories they refer to, returning the location of the next directory.
+    ///
+    /// `path` holds the directories pointing to this one, a directory pointed to from several
+    /// others is copied for each of them.
+    fn copy_directory<R: Read + Seek>(
+        &mut self,
+        decoder: &mut Decoder<R>,
+        offset: u64,
+        path: &mut Vec<u64>,
+    ) -> TiffResult<Option<u64>> {
+        let (entries, next_ifd) = decoder.read_raw_ifd_at(offset, path)?;
+        path.push(offset);
+        let by_tag: HashMap<_, _> = entries.iter().map(|entry| (entry.tag, entry)).collect();
+
+        for entry in &entries {
+            match entry.tag {
+                Tag::SubIFDs => {
+                    for sub_ifd in entry.unsigned_values()? {
+                        let mut sub_directory = self.new_sub_directory()?;
+                        sub_directory.copy_directory(decoder, sub_ifd, path)?;
+                        sub_directory.finish()?;
+                    }
+                }
+                tag if POINTER_TAGS.contains(&tag) => {
+                    let target = match entry.unsigned_values()?[..] {
+                        [target] => target,
+                        _ => {
+                            return Err(TiffError::FormatError(
+                                TiffFormatError::InvalidTagValueType(tag),
+                            ))
+                        }
+                    };
+
+                    let mut offsets = Vec::new();
+                    let mut private = self.new_private_directory(&mut offsets)?;
+                    private.copy_directory(decoder, target, path)?;
+                    private.finish()?;
+                    self.write_tag(tag, IfdPointer::<K>::new(offsets[0])?)?;
+                }
+                Tag::FreeOffsets | Tag::FreeByteCounts => {}
+                tag => match DATA_TAGS.iter().find(|&&(offsets, _)| offsets == tag) {
+                    Some(&(_, byte_counts_tag)) => {
+                        let byte_counts = match by_tag.get(&byte_counts_tag) {
+                            Some(byte_counts) => byte_counts.unsigned_values()?,
+                            None => {
+                                return Err(TiffError::FormatError(
+                                    TiffFormatError::RequiredTagNotFound(byte_counts_tag),
+                                ))
+                            }
+                        };
+                        let offsets = entry.unsigned_values()?;
+                        if offsets.len() != byte_counts.len() {
+                            return Err(TiffError::FormatError(
+                                TiffFormatError::InconsistentSizesEncountered,
+                            ));
+                        }
+
+                        let offsets = self.copy_data(decoder, tag, &offsets, &byte_counts)?;
+                        if tag == Tag::JPEGInterchangeFormat {
+                            let offsets: Vec<u32> = offsets
+                                .into_iter()
+                                .map(u32::try_from)
+                                .collect::<Result<_, _>>()?;
+                            self.write_tag(tag, &offsets[..])?;
+                        } else {
+                            let offsets: Vec<K::OffsetType> = offsets
+                                .into_iter()
+                                .map(K::convert_offset)
+                                .collect::<TiffResult<_>>()?;
+                            self.write_tag(tag, K::convert_slice(&offsets))?;
+                        }
+                    }
+                    None => self.write_raw_entry(entry)?,
+                },
+            }
+        }
+
+        path.pop();
+        Ok(next_ifd)
+    }
+
+    /// Copies the data at the `offsets` of `decoder` given by `tag`, returning the offsets of the
+    /// copies.
+    ///
+    /// Data without any bytes is placed at offset 0, data referred to more than once is copied
+    /// once.
+    fn copy_data<R: Read + Seek>(
+        &mut self,
+        decoder: &mut Decoder<R>,
+        tag: Tag,
+        offsets: &[u64],
+        byte_counts: &[u64],
+    ) -> TiffResult<Vec<u64>> {
+        let mut copies = BTreeMap::new();
+        let mut new_offsets = Vec::with_capacity(offsets.len());
+        for (index, (&offset, &byte_count)) in offsets.iter().zip(byte_counts).enumerate() {
+            if byte_count == 0 {
+                new_offsets.push(0);
+                continue;
+            }
+
+            let new_offset = match copies.get(&(offset, byte_count)) {
+                Some(&new_offset) => new_offset,
+                None => {
+                    let source = match tag {
+                        Tag::JPEGInterchangeFormat => OffsetSource::Tag(tag),
+                        _ => OffsetSource::Chunk(u32::try_from(index)?),
+                    };
+                    let data = decoder.read_raw_data(offset, byte_count, source)?;
+                    let new_offset = self.write_data(&data[..])?;
+                    copies.insert((offset, byte_count), new_offset);
+                    new_offset
+                }
+            };
+            new_offsets.push(new_offset);
+        }
+
+        Ok(new_offsets)
+    }
+
+    /// Writes an entry as read from another file.
+    fn write_raw_entry(&mut self, entry: &RawEntry) -> TiffResult<()> {
+        let tag = entry.tag.to_u16();
+        self.reserved.remove(&tag);
+        self.ifd.insert(
+            tag,
+            DirectoryEntry {
+                data_type: entry.field_type.to_u16(),
+                count: K::convert_offset(entry.count)?,
+                data: entry.data.clone(),
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Create a [`DirectoryEncoder`] for a directory outside of the chain that is not a sub
+    /// image, such as an EXIF directory. Its location is pushed to `offsets` once it is finished.
+    fn new_private_directory<'b>(
+        &'b mut self,
+        offsets: &'b mut Vec<u64>,
+    ) -> TiffResult<DirectoryEncoder<'b, W, K>> {
+        self.writer.pad_word_boundary()?;
+        Ok(DirectoryEncoder {
+            writer: &mut *self.writer,
+            dropped: false,
+            ifd_pointer_pos: 0,
+            ifd: BTreeMap::new(),
+            reserved: BTreeMap::new(),
+            sub_ifds: Vec::new(),
+            parent: Some(offsets),
+        })
+    }
+}
@@ -1,3 +1,4 @@
+pub use compact::compact;
 pub use tiff_value::*;
 
 use std::{
@@ -7,15 +8,21 @@ use std::{
     marker::PhantomData,
     mem,
     num::TryFromIntError,
+    ops::{self, ControlFlow},
 };
 
 use crate::{
+    decoder::DecodingResult,
     error::{TiffResult, UsageError},
-    tags::{CompressionMethod, ResolutionUnit, SampleFormat, Tag},
-    TiffError, TiffFormatError,
+    tags::{
+        CompressionMethod, NewSubfileType, PhotometricInterpretation, PlanarConfiguration,
+        ResolutionUnit, SampleFormat, Tag, Type,
+    },
+    PixelFormat, TiffError, TiffFormatError, TiffUnsupportedError,
 };
 
 pub mod colortype;
+mod compact;
 pub mod compression;
 mod tiff_value;
 mod writer;
@@ -32,7 +39,8 @@ use self::writer::*;
 /// compression, where using [Predictor::Horizontal] we see a 35% improvement in compression
 /// ratio over the unpredicted compression !
 ///
-/// [Predictor::FloatingPoint] is currently not supported.
+/// [Predictor::FloatingPoint] is the counterpart for floating point samples, which shuffles the
+/// bytes of each row by significance before differencing them.
 pub type Predictor = crate::tags::Predictor;
 pub type DeflateLevel = compression::DeflateLevel;
 
@@ -70,6 +78,35 @@ impl Compression {
     }
 }
 
+/// A writer the encoder can use, which is object safe unlike `Write + Seek`.
+///
+/// This is implemented for every seekable writer, see [`DynTiffEncoder`].
+pub trait WriteSeek: Write + Seek {}
+
+impl<T: Write + Seek + ?Sized> WriteSeek for T {}
+
+/// An encoder writing to a type-erased writer.
+///
+/// Encoders of files, buffers and other sinks all share this type, so that many of them can be
+/// managed together, e.g. by a plugin system. Create one from a boxed writer:
+///
+/// ```
+/// # extern crate tiff;
+/// # fn main() {
+/// use tiff::encoder::*;
+///
+/// let mut buffer = std::io::Cursor::new(Vec::new());
+/// let mut encoders: Vec<DynTiffEncoder> = vec![
+///     DynTiffEncoder::new(Box::new(std::io::Cursor::new(Vec::new()))).unwrap(),
+///     DynTiffEncoder::new(Box::new(&mut buffer)).unwrap(),
+/// ];
+/// for encoder in &mut encoders {
+///     encoder.write_image::<colortype::Gray8>(1, 1, &[0]).unwrap();
+/// }
+/// # }
+/// ```
+pub type DynTiffEncoder<'a, K = TiffKindStandard> = TiffEncoder<Box<dyn WriteSeek + 'a>, K>;
+
 /// Encoder for Tiff and BigTiff files.
 ///
 /// With this type you can get a `DirectoryEncoder` or a `ImageEncoder`
@@ -111,6 +148,37 @@ impl<W: Write + Seek> TiffEncoder<W> {
     pub fn new(writer: W) -> TiffResult<TiffEncoder<W, TiffKindStandard>> {
         TiffEncoder::new_generic(writer)
     }
+
+    /// Creates a new encoder for standard Tiff files, or for BigTiff files if `expected_size`
+    /// bytes may not fit into a standard Tiff file.
+    ///
+    /// Pass the uncompressed size of all images to be written, BigTiff is chosen if it comes
+    /// close to 4 GiB, leaving room for the directories and tag values. The sizes can be computed
+    /// with [`image_size`] or [`PixelFormat::image_size`]. Compression can make incompressible
+    /// data slightly larger, callers expecting such data should add a margin.
+    ///
+    /// The kind is chosen once, a standard Tiff file is not promoted to BigTiff while it is
+    /// written. Standard Tiff files can not address data beyond 4 GiB, writing past that limit
+    /// fails with [`UsageError::BigTiffRequired`] and the file has to be written again with
+    /// [`new_big`][TiffEncoder::new_big].
+    pub fn new_auto(writer: W, expected_size: u64) -> TiffResult<AutoTiffEncoder<W>> {
+        // Leave 1/64 of the address space for directories, tag values and compression overhead.
+        let limit = u64::from(u32::MAX) - u64::from(u32::MAX) / 64;
+        Ok(if expected_size > limit {
+            AutoTiffEncoder::Big(TiffEncoder::new_big(writer)?)
+        } else {
+            AutoTiffEncoder::Standard(TiffEncoder::new(writer)?)
+        })
+    }
+}
+
+/// The size in bytes of an uncompressed image of color type `C`, e.g. to pass to
+/// [`TiffEncoder::new_auto`].
+///
+/// The size saturates at `u64::MAX` instead of overflowing.
+pub fn image_size<C: ColorType>(width: u32, height: u32) -> u64 {
+    let bits = C::BITS_PER_SAMPLE.iter().map(|&bits| u64::from(bits)).sum();
+    crate::pixel_format::saturating_image_size(width, height, [bits])
 }
 
 /// Constructor functions to create BigTiff files.
@@ -124,6 +192,134 @@ impl<W: Write + Seek> TiffEncoder<W, TiffKindBig> {
     }
 }
 
+/// A Tiff or BigTiff encoder, chosen at runtime by [`TiffEncoder::new_auto`].
+///
+/// The kind does not change after the encoder was created.
+///
+/// The functions writing entire images are available for either kind, all others by matching on
+/// the variants.
+pub enum AutoTiffEncoder<W> {
+    /// An encoder for standard Tiff files.
+    Standard(TiffEncoder<W, TiffKindStandard>),
+    /// An encoder for BigTiff files.
+    Big(TiffEncoder<W, TiffKindBig>),
+}
+
+/// Calls a function of the encoder of either kind.
+macro_rules! with_auto_encoder {
+    ($auto:expr, $encoder:ident => $call:expr) => {
+        match $auto {
+            AutoTiffEncoder::Standard($encoder) => $call,
+            AutoTiffEncoder::Big($encoder) => $call,
+        }
+    };
+}
+
+impl<W: Write + Seek> AutoTiffEncoder<W> {
+    /// Whether a BigTiff file is written.
+    pub fn is_big(&self) -> bool {
+        matches!(self, AutoTiffEncoder::Big(_))
+    }
+
+    /// Set the predictor to use, see [`TiffEncoder::with_predictor`].
+    pub fn with_predictor(self, predictor: Predictor) -> Self {
+        match self {
+            AutoTiffEncoder::Standard(encoder) => {
+                AutoTiffEncoder::Standard(encoder.with_predictor(predictor))
+            }
+            AutoTiffEncoder::Big(encoder) => {
+                AutoTiffEncoder::Big(encoder.with_predictor(predictor))
+            }
+        }
+    }
+
+    /// Set the compression method to use, see [`TiffEncoder::with_compression`].
+    pub fn with_compression(self, compression: Compression) -> Self {
+        match self {
+            AutoTiffEncoder::Standard(encoder) => {
+                AutoTiffEncoder::Standard(encoder.with_compression(compression))
+            }
+            AutoTiffEncoder::Big(encoder) => {
+                AutoTiffEncoder::Big(encoder.with_compression(compression))
+            }
+        }
+    }
+
+    /// Write an entire image, see [`TiffEncoder::write_image`].
+    pub fn write_image<C: ColorType>(
+        &mut self,
+        width: u32,
+        height: u32,
+        data: &[C::Inner],
+    ) -> TiffResult<()>
+    where
+        [C::Inner]: TiffValue,
+    {
+        with_auto_encoder!(self, encoder => encoder.write_image::<C>(width, height, data))
+    }
+
+    /// Write an entire image with its own compression and predictor, see
+    /// [`TiffEncoder::write_image_with`].
+    pub fn write_image_with<C: ColorType>(
+        &mut self,
+        width: u32,
+        height: u32,
+        data: &[C::Inner],
+        compression: Option<Compression>,
+        predictor: Option<Predictor>,
+    ) -> TiffResult<()>
+    where
+        [C::Inner]: TiffValue,
+    {
+        with_auto_encoder!(self, encoder => {
+            encoder.write_image_with::<C>(width, height, data, compression, predictor)
+        })
+    }
+
+    /// Write an entire image as returned by the decoder, see
+    /// [`TiffEncoder::write_decoding_result`].
+    pub fn write_decoding_result(
+        &mut self,
+        width: u32,
+        height: u32,
+        color_type: crate::ColorType,
+        data: &DecodingResult,
+    ) -> TiffResult<()> {
+        with_auto_encoder!(self, encoder => {
+            encoder.write_decoding_result(width, height, color_type, data)
+        })
+    }
+
+    /// Write an entire image described by a [`PixelFormat`], see
+    /// [`TiffEncoder::write_image_with_format`].
+    pub fn write_image_with_format(
+        &mut self,
+        width: u32,
+        height: u32,
+        format: &PixelFormat,
+        data: &[u8],
+    ) -> TiffResult<()> {
+        with_auto_encoder!(self, encoder => {
+            encoder.write_image_with_format(width, height, format, data)
+        })
+    }
+
+    /// Write an entire image described by a [`PixelFormat`] in tiles, see
+    /// [`TiffEncoder::write_tiled_image_with_format`].
+    pub fn write_tiled_image_with_format(
+        &mut self,
+        width: u32,
+        height: u32,
+        tile_size: (u32, u32),
+        format: &PixelFormat,
+        data: &[u8],
+    ) -> TiffResult<()> {
+        with_auto_encoder!(self, encoder => {
+            encoder.write_tiled_image_with_format(width, height, tile_size, format, data)
+        })
+    }
+}
+
 /// Generic functions that are available for both Tiff and BigTiff encoders.
 impl<W: Write + Seek, K: TiffKind> TiffEncoder<W, K> {
     /// Creates a new Tiff or BigTiff encoder, inferred from the return type.
@@ -157,36 +353,688 @@ impl<W: Write + Seek, K: TiffKind> TiffEncoder<W, K> {
         self
     }
 
+    /// Hold back the directories to write them contiguously, instead of after each image.
+    ///
+    /// By default every directory is linked to the previous one as soon as it is finished, which
+    /// seeks back to the pointer written by the previous directory. When writing many pages this
+    /// random access can be slow, e.g. with writers backed by an object store. With this option
+    /// the values of each directory are still written with its image, but its entries are kept in
+    /// memory until [`write_deferred_directories`][TiffEncoder::write_deferred_directories] writes
+    /// all of them as a single chain, with one seek to link it to the file.
+    ///
+    /// The file is incomplete until the directories were written. Reserved tags whose value is
+    /// small enough to be stored in its entry can only be patched afterwards.
+    pub fn with_deferred_directories(mut self) -> Self {
+        let pointer_pos = self
+            .writer
+            .take_ifd_pointer_pos(mem::size_of::<K::OffsetType>());
+        self.writer.defer_directories(pointer_pos);
+
+        self
+    }
+
+    /// Hold back the tag values that do not fit into their entries as well, to write them after
+    /// the directories.
+    ///
+    /// This enables [`with_deferred_directories`][TiffEncoder::with_deferred_directories]. By
+    /// default the large values of a directory, such as long strings or the strip offsets, are
+    /// written right after the image data. With this option
+    /// [`write_deferred_directories`][TiffEncoder::write_deferred_directories] writes them in a
+    /// single region following the chain of directories, so that the directories are not
+    /// interleaved with metadata. Reserved tags stored out of line can only be patched once the
+    /// directories were written.
+    pub fn with_deferred_values(self) -> Self {
+        let mut encoder = self.with_deferred_directories();
+        encoder.writer.deferred_directories().unwrap().hold_values = true;
+
+        encoder
+    }
+
+    /// Store identical tag values that do not fit into their entries only once.
+    ///
+    /// Multipage files often repeat the same values on every page, such as long `Software` or
+    /// `Artist` strings. With this option such a value is only written the first time it occurs,
+    /// and the entries of later directories point to that copy. Every distinct value is kept in
+    /// memory to find the repetitions. Values of reserved tags are always written on their own,
+    /// so that patching them does not change other tags.
+    pub fn with_shared_values(mut self) -> Self {
+        self.writer.share_values();
+
+        self
+    }
+
+    /// Write the directories held back since they were deferred, see
+    /// [`with_deferred_directories`][TiffEncoder::with_deferred_directories].
+    ///
+    /// Directories finished later are again held back until this is called another time. Does
+    /// nothing if directories are not deferred.
+    pub fn write_deferred_directories(&mut self) -> TiffResult<()> {
+        let pointer_size = mem::size_of::<K::OffsetType>();
+        let (pointer_pos, mut directories) = match self.writer.deferred_directories() {
+            Some(deferred) if !deferred.directories.is_empty() => {
+                (deferred.pointer_pos, mem::take(&mut deferred.directories))
+            }
+            _ => return Ok(()),
+        };
+
+        self.writer.pad_word_boundary()?;
+        let start = self.writer.offset();
+
+        // Link the directories and point their held back values into the region following them.
+        let mut offset = start;
+        let mut value_offset = start
+            + directories
+                .iter()
+                .map(|dir| dir.block.len() as u64)
+                .sum::<u64>();
+        let mut last_pointer = 0;
+        // Whether each held back value is written, rather than shared with an earlier one.
+        let mut written = Vec::new();
+        let count = directories.len();
+        for (index, directory) in directories.iter_mut().enumerate() {
+            let block = &mut directory.block;
+            let next = offset + block.len() as u64;
+            let pointer = block.len() - pointer_size;
+            if index + 1 < count {
+                K::write_offset(&mut TiffWriter::new(&mut block[pointer..]), next)?;
+            } else {
+                last_pointer = offset + pointer as u64;
+            }
+            for &(id, position) in &directory.reserved {
+                self.writer.set_reserved_tag_position(id, offset + position);
+            }
+            for value in &directory.values {
+                let shared = match value.reserved {
+                    Some(_) => None,
+                    None => self.writer.shared_value(&value.data),
+                };
+                let field = &mut block[value.pointer..value.pointer + pointer_size];
+                K::write_offset(&mut TiffWriter::new(field), shared.unwrap_or(value_offset))?;
+                written.push(shared.is_none());
+                if shared.is_some() {
+                    continue;
+                }
+
+                match value.reserved {
+                    Some(id) => self.writer.set_reserved_tag_position(id, value_offset),
+                    None => self.writer.share_value(&value.data, value_offset),
+                }
+                value_offset += value.data.len() as u64;
+            }
+
+            offset = next;
+        }
+
+        for directory in &directories {
+            self.writer.write_bytes(&directory.block)?;
+        }
+        let values = directories.iter().flat_map(|dir| &dir.values);
+        for (value, _) in values.zip(written).filter(|&(_, written)| written) {
+            self.writer.write_bytes(&value.data)?;
+        }
+        let end = self.writer.offset();
+
+        self.writer.goto_offset(pointer_pos)?;
+        K::write_offset(&mut self.writer, start)?;
+        self.writer.goto_offset(end)?;
+
+        // Directories finished later are linked to the last one.
+        self.writer.deferred_directories().unwrap().pointer_pos = last_pointer;
+
+        Ok(())
+    }
+
     /// Create a [`DirectoryEncoder`] to encode an ifd directory.
     pub fn new_directory(&mut self) -> TiffResult<DirectoryEncoder<W, K>> {
         DirectoryEncoder::new(&mut self.writer)
     }
 
     /// Create an [`ImageEncoder`] to encode an image one slice at a time.
+    ///
+    /// Images without any pixels can not be written, a zero `width` or `height` is rejected with
+    /// [`TiffFormatError::InvalidDimensions`].
     pub fn new_image<C: ColorType>(
         &mut self,
         width: u32,
         height: u32,
     ) -> TiffResult<ImageEncoder<W, C, K>> {
+        self.new_image_with::<C>(width, height, None, None)
+    }
+
+    /// Create an [`ImageEncoder`] like [`new_image`][TiffEncoder::new_image], with a compression
+    /// and predictor for this image only.
+    ///
+    /// Settings that are `None` fall back to those of the encoder, see
+    /// [`with_compression`][TiffEncoder::with_compression] and
+    /// [`with_predictor`][TiffEncoder::with_predictor].
+    pub fn new_image_with<C: ColorType>(
+        &mut self,
+        width: u32,
+        height: u32,
+        compression: Option<Compression>,
+        predictor: Option<Predictor>,
+    ) -> TiffResult<ImageEncoder<'_, W, C, K>> {
         let encoder = DirectoryEncoder::new(&mut self.writer)?;
-        ImageEncoder::new(encoder, width, height, self.compression, self.predictor)
+        let compression = compression.unwrap_or(self.compression);
+        let predictor = predictor.unwrap_or(self.predictor);
+        ImageEncoder::new(encoder, width, height, compression, predictor)
     }
 
-    /// Convenience function to write an entire image from memory.
-    pub fn write_image<C: ColorType>(
+    /// Write an entire image from memory like [`write_image`][TiffEncoder::write_image], calling
+    /// `progress` after every strip, see [`ImageEncoder::write_data_with_progress`].
+    pub fn write_image_with_progress<C: ColorType>(
         &mut self,
         width: u32,
         height: u32,
         data: &[C::Inner],
-    ) -> TiffResult<()>
+        progress: impl FnMut(EncodeProgress) -> ControlFlow<()>,
+    ) -> TiffResult<ControlFlow<()>>
     where
         [C::Inner]: TiffValue,
     {
         let encoder = DirectoryEncoder::new(&mut self.writer)?;
         let image: ImageEncoder<W, C, K> =
             ImageEncoder::new(encoder, width, height, self.compression, self.predictor)?;
+        image.write_data_with_progress(data, progress)
+    }
+
+    /// Convenience function to write an entire image from memory.
+    pub fn write_image<C: ColorType>(
+        &mut self,
+        width: u32,
+        height: u32,
+        data: &[C::Inner],
+    ) -> TiffResult<()>
+    where
+        [C::Inner]: TiffValue,
+    {
+        self.write_image_with::<C>(width, height, data, None, None)
+    }
+
+    /// Write an entire image from memory like [`write_image`][TiffEncoder::write_image], with a
+    /// compression and predictor for this image only, see [`new_image_with`][Self::new_image_with].
+    pub fn write_image_with<C: ColorType>(
+        &mut self,
+        width: u32,
+        height: u32,
+        data: &[C::Inner],
+        compression: Option<Compression>,
+        predictor: Option<Predictor>,
+    ) -> TiffResult<()>
+    where
+        [C::Inner]: TiffValue,
+    {
+        let image = self.new_image_with::<C>(width, height, compression, predictor)?;
         image.write_data(data)
     }
+
+    /// Convenience function to write an image along with reduced resolution versions of it.
+    ///
+    /// Each of the `overviews` is given by its width, height and samples, usually in order of
+    /// decreasing size. They are written as sub images of the image, see
+    /// [`ImageEncoder::new_sub_image`], so that the `SubIFDs` of the image point to them and they
+    /// are marked with `NewSubfileType`. The overviews are not part of the chain of images of the
+    /// file.
+    pub fn write_pyramid<C: ColorType>(
+        &mut self,
+        width: u32,
+        height: u32,
+        data: &[C::Inner],
+        overviews: &[(u32, u32, &[C::Inner])],
+    ) -> TiffResult<()>
+    where
+        [C::Inner]: TiffValue,
+    {
+        let mut image = self.new_image::<C>(width, height)?;
+        for &(width, height, data) in overviews {
+            image.new_sub_image::<C>(width, height)?.write_data(data)?;
+        }
+        image.write_data(data)
+    }
+
+    /// Convenience function to write an entire image as returned by the decoder.
+    ///
+    /// The encoder color type is chosen from `color_type` and the sample type of `data`, which
+    /// eases copying images without naming the color type statically. Combinations without a
+    /// matching encoder color type, such as palette images, are rejected with
+    /// [`TiffUnsupportedError::UnsupportedColorType`].
+    pub fn write_decoding_result(
+        &mut self,
+        width: u32,
+        height: u32,
+        color_type: crate::ColorType,
+        data: &DecodingResult,
+    ) -> TiffResult<()> {
+        use crate::ColorType::*;
+
+        match (color_type, data) {
+            (Gray(8), DecodingResult::U8(data)) => self.write_image::<Gray8>(width, height, data),
+            (Gray(8), DecodingResult::I8(data)) => self.write_image::<GrayI8>(width, height, data),
+            (Gray(16), DecodingResult::U16(data)) => {
+                self.write_image::<Gray16>(width, height, data)
+            }
+            (Gray(16), DecodingResult::I16(data)) => {
+                self.write_image::<GrayI16>(width, height, data)
+            }
+            (Gray(32), DecodingResult::U32(data)) => {
+                self.write_image::<Gray32>(width, height, data)
+            }
+            (Gray(32), DecodingResult::I32(data)) => {
+                self.write_image::<GrayI32>(width, height, data)
+            }
+            (Gray(32), DecodingResult::F32(data)) => {
+                self.write_image::<Gray32Float>(width, height, data)
+            }
+            (Gray(64), DecodingResult::U64(data)) => {
+                self.write_image::<Gray64>(width, height, data)
+            }
+            (Gray(64), DecodingResult::I64(data)) => {
+                self.write_image::<GrayI64>(width, height, data)
+            }
+            (Gray(64), DecodingResult::F64(data)) => {
+                self.write_image::<Gray64Float>(width, height, data)
+            }
+            (GrayA(8), DecodingResult::U8(data)) => self.write_image::<GrayA8>(width, height, data),
+            (GrayA(16), DecodingResult::U16(data)) => {
+                self.write_image::<GrayA16>(width, height, data)
+            }
+            (RGB(8), DecodingResult::U8(data)) => self.write_image::<RGB8>(width, height, data),
+            (RGB(16), DecodingResult::U16(data)) => self.write_image::<RGB16>(width, height, data),
+            (RGB(32), DecodingResult::U32(data)) => self.write_image::<RGB32>(width, height, data),
+            (RGB(32), DecodingResult::F32(data)) => {
+                self.write_image::<RGB32Float>(width, height, data)
+            }
+            (RGB(64), DecodingResult::U64(data)) => self.write_image::<RGB64>(width, height, data),
+            (RGB(64), DecodingResult::F64(data)) => {
+                self.write_image::<RGB64Float>(width, height, data)
+            }
+            (RGBA(8), DecodingResult::U8(data)) => self.write_image::<RGBA8>(width, height, data),
+            (RGBA(16), DecodingResult::U16(data)) => {
+                self.write_image::<RGBA16>(width, height, data)
+            }
+            (RGBA(32), DecodingResult::U32(data)) => {
+                self.write_image::<RGBA32>(width, height, data)
+            }
+            (RGBA(32), DecodingResult::F32(data)) => {
+                self.write_image::<RGBA32Float>(width, height, data)
+            }
+            (RGBA(64), DecodingResult::U64(data)) => {
+                self.write_image::<RGBA64>(width, height, data)
+            }
+            (RGBA(64), DecodingResult::F64(data)) => {
+                self.write_image::<RGBA64Float>(width, height, data)
+            }
+            (CMYK(8), DecodingResult::U8(data)) => self.write_image::<CMYK8>(width, height, data),
+            (CMYK(16), DecodingResult::U16(data)) => {
+                self.write_image::<CMYK16>(width, height, data)
+            }
+            (CMYK(32), DecodingResult::U32(data)) => {
+                self.write_image::<CMYK32>(width, height, data)
+            }
+            (CMYK(32), DecodingResult::F32(data)) => {
+                self.write_image::<CMYK32Float>(width, height, data)
+            }
+            (CMYK(64), DecodingResult::U64(data)) => {
+                self.write_image::<CMYK64>(width, height, data)
+            }
+            (CMYK(64), DecodingResult::F64(data)) => {
+                self.write_image::<CMYK64Float>(width, height, data)
+            }
+            (CMYKA(8), DecodingResult::U8(data)) => self.write_image::<CMYKA8>(width, height, data),
+            (CMYKA(16), DecodingResult::U16(data)) => {
+                self.write_image::<CMYKA16>(width, height, data)
+            }
+            (YCbCr(8), DecodingResult::U8(data)) => self.write_image::<YCbCr8>(width, height, data),
+            (YCbCrA(8), DecodingResult::U8(data)) => {
+                self.write_image::<YCbCrA8>(width, height, data)
+            }
+            _ => Err(TiffError::UnsupportedError(
+                TiffUnsupportedError::UnsupportedColorType(color_type),
+            )),
+        }
+    }
+
+    /// Write an entire image whose samples are described at runtime by a [`PixelFormat`].
+    ///
+    /// This is a low level alternative to [`write_image`][TiffEncoder::write_image] for formats
+    /// without an encoder color type. `data` holds the samples in native byte order, with every
+    /// row starting at a byte boundary. The planes of planar images follow each other. No tags
+    /// specific to the interpretation, such as a `ColorMap`, are written. Predictors are not
+    /// supported and rejected with [`UsageError::PredictorUnavailable`].
+    pub fn write_image_with_format(
+        &mut self,
+        width: u32,
+        height: u32,
+        format: &PixelFormat,
+        data: &[u8],
+    ) -> TiffResult<()> {
+        self.write_format_chunks(width, height, None, format, data)
+    }
+
+    /// Write an entire image described by a [`PixelFormat`] in tiles of `tile_width` by
+    /// `tile_length` pixels.
+    ///
+    /// The data is given as for [`write_image_with_format`][TiffEncoder::write_image_with_format].
+    /// The tile dimensions must be multiples of 16 and are otherwise rejected with
+    /// [`TiffFormatError::InvalidTileSize`]. Tiles extending past the image are padded with zeros.
+    /// Samples that do not fill whole bytes are rejected with
+    /// [`TiffUnsupportedError::UnsupportedBitsPerChannel`].
+    pub fn write_tiled_image_with_format(
+        &mut self,
+        width: u32,
+        height: u32,
+        (tile_width, tile_length): (u32, u32),
+        format: &PixelFormat,
+        data: &[u8],
+    ) -> TiffResult<()> {
+        if tile_width == 0 || tile_length == 0 || tile_width % 16 != 0 || tile_length % 16 != 0 {
+            return Err(TiffError::FormatError(TiffFormatError::InvalidTileSize(
+                tile_width,
+                tile_length,
+            )));
+        }
+        if let Some(&bits) = format.bits_per_sample.iter().find(|&&bits| bits % 8 != 0) {
+            return Err(TiffError::UnsupportedError(
+                TiffUnsupportedError::UnsupportedBitsPerChannel(bits),
+            ));
+        }
+        self.write_format_chunks(width, height, Some((tile_width, tile_length)), format, data)
+    }
+
+    /// Writes an image described by a [`PixelFormat`] in strips, or in tiles of the given size.
+    fn write_format_chunks(
+        &mut self,
+        width: u32,
+        height: u32,
+        tile_size: Option<(u32, u32)>,
+        format: &PixelFormat,
+        data: &[u8],
+    ) -> TiffResult<()> {
+        if width == 0 || height == 0 {
+            return Err(TiffError::FormatError(TiffFormatError::InvalidDimensions(
+                width, height,
+            )));
+        }
+        if self.predictor != Predictor::None {
+            return Err(TiffError::UsageError(UsageError::PredictorUnavailable));
+        }
+        let samples = format.samples();
+        if samples == 0
+            || format.sample_format.len() != samples
+            || format.extra_samples.len() > samples
+            || format.bits_per_sample.contains(&0)
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "pixel format is inconsistent",
+            )
+            .into());
+        }
+
+        // The bits of a row of each plane.
+        let plane_bits: Vec<u64> = match format.planar_config {
+            PlanarConfiguration::Planar => format
+                .bits_per_sample
+                .iter()
+                .map(|&bits| bits.into())
+                .collect(),
+            _ => vec![format
+                .bits_per_sample
+                .iter()
+                .map(|&bits| u64::from(bits))
+                .sum()],
+        };
+        let row_bytes: Vec<u64> = plane_bits
+            .iter()
+            .map(|bits| (u64::from(width) * bits + 7) / 8)
+            .collect();
+        if u64::try_from(data.len())? != row_bytes.iter().sum::<u64>() * u64::from(height) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Input data slice does not match the pixel format and dimensions",
+            )
+            .into());
+        }
+
+        // Limit the strip size as `ImageEncoder` does.
+        let max_row_bytes = *row_bytes.iter().max().unwrap();
+        let rows_per_strip = (1_000_000 + max_row_bytes - 1) / max_row_bytes;
+
+        let mut encoder = DirectoryEncoder::<W, K>::new(&mut self.writer)?;
+        encoder.write_tag(Tag::ImageWidth, width)?;
+        encoder.write_tag(Tag::ImageLength, height)?;
+        encoder.write_tag(Tag::Compression, self.compression.tag().to_u16())?;
+        let bits_per_sample: Vec<_> = format
+            .bits_per_sample
+            .iter()
+            .map(|&b| u16::from(b))
+            .collect();
+        encoder.write_tag(Tag::BitsPerSample, &bits_per_sample[..])?;
+        let sample_format: Vec<_> = format.sample_format.iter().map(|s| s.to_u16()).collect();
+        encoder.write_tag(Tag::SampleFormat, &sample_format[..])?;
+        encoder.write_tag(
+            Tag::PhotometricInterpretation,
+            format.interpretation.to_u16(),
+        )?;
+        encoder.write_tag(Tag::SamplesPerPixel, u16::try_from(samples)?)?;
+        if !format.extra_samples.is_empty() {
+            let extra_samples: Vec<_> = format.extra_samples.iter().map(|s| s.to_u16()).collect();
+            encoder.write_tag(Tag::ExtraSamples, &extra_samples[..])?;
+        }
+        encoder.write_tag(Tag::PlanarConfiguration, format.planar_config.to_u16())?;
+        match tile_size {
+            Some((tile_width, tile_length)) => {
+                encoder.write_tag(Tag::TileWidth, tile_width)?;
+                encoder.write_tag(Tag::TileLength, tile_length)?;
+            }
+            None => encoder.write_tag(Tag::RowsPerStrip, u32::try_from(rows_per_strip)?)?,
+        }
+        encoder.write_tag(Tag::XResolution, Rational { n: 1, d: 1 })?;
+        encoder.write_tag(Tag::YResolution, Rational { n: 1, d: 1 })?;
+        encoder.write_tag(Tag::ResolutionUnit, ResolutionUnit::None.to_u16())?;
+
+        let mut chunk_offsets = Vec::new();
+        let mut chunk_byte_counts = Vec::new();
+        let packbits = self.compression == Compression::Packbits;
+        encoder
+            .writer
+            .set_compression(self.compression.get_algorithm());
+        let mut planes = data;
+        for &row_bytes in &row_bytes {
+            let row_bytes = usize::try_from(row_bytes)?;
+            let (plane, rest) = planes.split_at(row_bytes * usize::try_from(height)?);
+            planes = rest;
+
+            let mut write_chunk =
+                |encoder: &mut DirectoryEncoder<W, K>, chunk: &[u8], row_bytes| {
+                    let offset = encoder.write_chunk_rows(chunk, row_bytes, packbits)?;
+                    chunk_offsets.push(K::convert_offset(offset)?);
+                    chunk_byte_counts.push(K::convert_offset(encoder.writer.offset() - offset)?);
+                    TiffResult::Ok(())
+                };
+
+            match tile_size {
+                Some((tile_width, tile_length)) => {
+                    let (tile_width, tile_length) =
+                        (usize::try_from(tile_width)?, usize::try_from(tile_length)?);
+                    let pixel_bytes = row_bytes / usize::try_from(width)?;
+                    let tile_row_bytes = tile_width * pixel_bytes;
+                    let mut tile = vec![0; tile_row_bytes * tile_length];
+                    for rows in plane.chunks(row_bytes * tile_length) {
+                        for left in (0..row_bytes).step_by(tile_row_bytes) {
+                            let right = (left + tile_row_bytes).min(row_bytes);
+                            tile.fill(0);
+                            for (tile_row, row) in
+                                tile.chunks_mut(tile_row_bytes).zip(rows.chunks(row_bytes))
+                            {
+                                tile_row[..right - left].copy_from_slice(&row[left..right]);
+                            }
+                            write_chunk(&mut encoder, &tile, tile_row_bytes)?;
+                        }
+                    }
+                }
+                None => {
+                    for strip in plane.chunks(row_bytes * usize::try_from(rows_per_strip)?) {
+                        write_chunk(&mut encoder, strip, row_bytes)?;
+                    }
+                }
+            }
+        }
+        encoder.writer.reset_compression();
+
+        let (offsets_tag, byte_counts_tag) = match tile_size {
+            Some(_) => (Tag::TileOffsets, Tag::TileByteCounts),
+            None => (Tag::StripOffsets, Tag::StripByteCounts),
+        };
+        encoder.write_tag(offsets_tag, K::convert_slice(&chunk_offsets))?;
+        encoder.write_tag(byte_counts_tag, K::convert_slice(&chunk_byte_counts))?;
+        encoder.finish()
+    }
+
+    /// Overwrite the value of a tag reserved with [`DirectoryEncoder::reserve_tag`].
+    ///
+    /// The directory holding the tag must have been written already, and `value` must have the
+    /// same type and number of values as the placeholder it replaces.
+    pub fn patch_tag<T: TiffValue>(&mut self, tag: &ReservedTag, value: T) -> TiffResult<()> {
+        let position = self
+            .writer
+            .reserved_tag_position(tag.id)
+            .ok_or(TiffError::UsageError(UsageError::ReservedTagNotWritten))?;
+        if T::FIELD_TYPE.to_u16() != tag.field_type
+            || value.count() != tag.count
+            || value.bytes() != tag.bytes
+        {
+            return Err(TiffError::UsageError(UsageError::ReservedTagMismatch));
+        }
+
+        let mut bytes = Vec::with_capacity(value.bytes());
+        {
+            let mut writer = TiffWriter::new(&mut bytes);
+            value.write(&mut writer)?;
+        }
+
+        let curr_pos = self.writer.offset();
+        self.writer.goto_offset(position)?;
+        self.writer.write_bytes(&bytes)?;
+        self.writer.goto_offset(curr_pos)?;
+
+        Ok(())
+    }
+
+    /// Write a JPEG compressed thumbnail directory.
+    ///
+    /// This follows the layout of the EXIF thumbnail directory (IFD1): the complete JPEG stream is
+    /// stored as a single block referenced by `JPEGInterchangeFormat` and
+    /// `JPEGInterchangeFormatLength`, and the directory is marked as a reduced resolution version
+    /// of the previous image. To produce a thumbnail that file browsers pick up, call this right
+    /// after writing the main image so that it becomes the second directory of the file.
+    ///
+    /// The `width` and `height` must be the dimensions of the JPEG image, its contents are not
+    /// inspected.
+    pub fn write_jpeg_thumbnail(&mut self, width: u32, height: u32, jpeg: &[u8]) -> TiffResult<()> {
+        if !jpeg.starts_with(&[0xff, 0xd8]) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "thumbnail is not a JPEG stream",
+            )
+            .into());
+        }
+
+        let mut encoder = DirectoryEncoder::<W, K>::new(&mut self.writer)?;
+        let offset = encoder.write_data(jpeg)?;
+
+        let new_subfile_type = NewSubfileType::REDUCED_RESOLUTION;
+        encoder.write_tag(Tag::NewSubfileType, new_subfile_type.bits())?;
+        encoder.write_tag(Tag::ImageWidth, width)?;
+        encoder.write_tag(Tag::ImageLength, height)?;
+        encoder.write_tag(Tag::BitsPerSample, &[8u16, 8, 8][..])?;
+        encoder.write_tag(Tag::Compression, CompressionMethod::JPEG.to_u16())?;
+        encoder.write_tag(
+            Tag::PhotometricInterpretation,
+            PhotometricInterpretation::YCbCr.to_u16(),
+        )?;
+        encoder.write_tag(Tag::SamplesPerPixel, 3u16)?;
+        encoder.write_tag(Tag::XResolution, Rational { n: 72, d: 1 })?;
+        encoder.write_tag(Tag::YResolution, Rational { n: 72, d: 1 })?;
+        encoder.write_tag(Tag::ResolutionUnit, ResolutionUnit::Inch.to_u16())?;
+        encoder.write_tag(Tag::JPEGInterchangeFormat, u32::try_from(offset)?)?;
+        encoder.write_tag(Tag::JPEGInterchangeFormatLength, u32::try_from(jpeg.len())?)?;
+        // Readers that ignore the interchange format still find the stream as a single strip.
+        encoder.write_tag(Tag::StripOffsets, K::convert_offset(offset)?)?;
+        encoder.write_tag(Tag::RowsPerStrip, height)?;
+        encoder.write_tag(Tag::StripByteCounts, K::convert_offset(jpeg.len() as u64)?)?;
+
+        encoder.finish()
+    }
+
+    /// Write a directory of pre-encoded entries, such as a private IFD or a vendor makernote.
+    ///
+    /// Each entry is given as its tag, field type, count and the bytes of its values. Values that
+    /// fit into the entry are padded with zeros, larger ones are written in front of the
+    /// directory, in the order of the entries. The directory is not part of the chain of
+    /// directories of the file, the returned pointer is meant to be written as the value of the
+    /// tag referencing it, e.g. with [`DirectoryEncoder::write_tag`].
+    ///
+    /// # Correctness
+    ///
+    /// The entries are written as given, bypassing [`TiffValue`] and every check of the encoder.
+    /// It is up to the caller to ensure that:
+    /// - the entries are sorted by ascending tag, as readers may rely on the order,
+    /// - the bytes hold exactly `count` values of the field type, in the byte order of the file,
+    /// - offsets stored in the values point to data that is or will be written to the file.
+    ///
+    /// Otherwise the file is corrupt, but can still be written.
+    pub fn write_ifd(&mut self, entries: &[(u16, Type, u64, &[u8])]) -> TiffResult<IfdPointer<K>> {
+        let data_bytes = mem::size_of::<K::OffsetType>();
+        // The next directory of the chain is still linked to the previous one.
+        let ifd_pointer_pos = self.writer.take_ifd_pointer_pos(data_bytes);
+
+        self.writer.pad_word_boundary()?;
+        let mut fields = Vec::with_capacity(entries.len());
+        for &(_, _, _, bytes) in entries {
+            let mut field = bytes.to_vec();
+            if bytes.len() > data_bytes {
+                field = vec![0; data_bytes];
+                K::write_offset(&mut TiffWriter::new(&mut field[..]), self.writer.offset())?;
+                self.writer.write_bytes(bytes)?;
+            } else {
+                field.resize(data_bytes, 0);
+            }
+            fields.push(field);
+        }
+
+        self.writer.pad_word_boundary()?;
+        let offset = self.writer.offset();
+        K::write_entry_count(&mut self.writer, entries.len())?;
+        for (&(tag, field_type, count, _), field) in entries.iter().zip(&fields) {
+            self.writer.write_u16(tag)?;
+            self.writer.write_u16(field_type.to_u16())?;
+            K::write_offset(&mut self.writer, count)?;
+            self.writer.write_bytes(field)?;
+        }
+        K::write_offset(&mut self.writer, 0)?;
+        self.writer.set_ifd_pointer_pos(ifd_pointer_pos);
+
+        IfdPointer::new(offset)
+    }
+}
+
+/// How much of an image was written, passed to the callback of
+/// [`ImageEncoder::write_data_with_progress`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct EncodeProgress {
+    pub strips_written: u64,
+    pub strip_count: u64,
+}
+
+/// Handle to a tag value that can be overwritten later, see [`DirectoryEncoder::reserve_tag`].
+#[derive(Debug)]
+pub struct ReservedTag {
+    id: usize,
+    field_type: u16,
+    count: usize,
+    bytes: usize,
 }
 
 /// Low level interface to encode ifd directories.
@@ -199,23 +1047,53 @@ pub struct DirectoryEncoder<'a, W: 'a + Write + Seek, K: TiffKind> {
     // We use BTreeMap to make sure tags are written in correct order
     ifd_pointer_pos: u64,
     ifd: BTreeMap<u16, DirectoryEntry<K::OffsetType>>,
+    // Reservation ids of the tags whose values can be patched later
+    reserved: BTreeMap<u16, usize>,
+    // Offsets of the finished sub directories, written as `SubIFDs`
+    sub_ifds: Vec<u64>,
+    // The sub directories of the parent, if this is a sub directory
+    parent: Option<&'a mut Vec<u64>>,
 }
 
 impl<'a, W: 'a + Write + Seek, K: TiffKind> DirectoryEncoder<'a, W, K> {
     fn new(writer: &'a mut TiffWriter<W>) -> TiffResult<Self> {
-        // the previous word is the IFD offset position
-        let ifd_pointer_pos = writer.offset() - mem::size_of::<K::OffsetType>() as u64;
+        // usually the previous word is the IFD offset position
+        let ifd_pointer_pos = writer.take_ifd_pointer_pos(mem::size_of::<K::OffsetType>());
         writer.pad_word_boundary()?; // TODO: Do we need to adjust this for BigTiff?
         Ok(DirectoryEncoder {
             writer,
             dropped: false,
             ifd_pointer_pos,
             ifd: BTreeMap::new(),
+            reserved: BTreeMap::new(),
+            sub_ifds: Vec::new(),
+            parent: None,
+        })
+    }
+
+    /// Create a [`DirectoryEncoder`] for a directory referenced by the `SubIFDs` tag of this one.
+    ///
+    /// Sub directories are not part of the chain of directories of the file. Such directories
+    /// usually hold the reduced resolution levels of a pyramid. Once it is finished, the sub
+    /// directory is appended to the `SubIFDs` of this directory, which are written as `IFD` or
+    /// `IFD8` values depending on the file kind.
+    pub fn new_sub_directory(&mut self) -> TiffResult<DirectoryEncoder<'_, W, K>> {
+        self.writer.pad_word_boundary()?;
+        Ok(DirectoryEncoder {
+            writer: &mut *self.writer,
+            dropped: false,
+            ifd_pointer_pos: 0,
+            ifd: BTreeMap::new(),
+            reserved: BTreeMap::new(),
+            sub_ifds: Vec::new(),
+            parent: Some(&mut self.sub_ifds),
         })
     }
 
     /// Write a single ifd tag.
     pub fn write_tag<T: TiffValue>(&mut self, tag: Tag, value: T) -> TiffResult<()> {
+        self.reserved.remove(&tag.to_u16());
+
         let mut bytes = Vec::with_capacity(value.bytes());
         {
             let mut writer = TiffWriter::new(&mut bytes);
@@ -234,18 +1112,87 @@ impl<'a, W: 'a + Write + Seek, K: TiffKind> DirectoryEncoder<'a, W, K> {
         Ok(())
     }
 
-    fn write_directory(&mut self) -> TiffResult<u64> {
-        // Start by writing out all values
-        for &mut DirectoryEntry {
-            data: ref mut bytes,
-            ..
-        } in self.ifd.values_mut()
+    /// Write a tag whose value can be overwritten after the directory has been written.
+    ///
+    /// The `placeholder` is written like any other tag value and determines the type and the
+    /// number of values of the tag. Once the directory is finished, the returned handle can be
+    /// passed to [`TiffEncoder::patch_tag`] to replace the placeholder with a value of the same
+    /// type and count, without rewriting the file. This is useful for metadata that is only
+    /// known once the image data was written, such as checksums or statistics.
+    pub fn reserve_tag<T: TiffValue>(
+        &mut self,
+        tag: Tag,
+        placeholder: T,
+    ) -> TiffResult<ReservedTag> {
+        self.write_tag(tag, &placeholder)?;
+
+        let id = self.writer.reserve_tag();
+        self.reserved.insert(tag.to_u16(), id);
+
+        Ok(ReservedTag {
+            id,
+            field_type: T::FIELD_TYPE.to_u16(),
+            count: placeholder.count(),
+            bytes: placeholder.bytes(),
+        })
+    }
+
+    /// Write a tag whose value is stored at `offset`, in room reserved for it before.
+    ///
+    /// Values that fit into their entry are stored there instead.
+    fn write_tag_at<T: TiffValue>(&mut self, tag: Tag, value: T, offset: u64) -> TiffResult<()> {
+        let data_bytes = mem::size_of::<K::OffsetType>();
+        if value.bytes() <= data_bytes {
+            return self.write_tag(tag, value);
+        }
+        self.reserved.remove(&tag.to_u16());
+
+        let curr_pos = self.writer.offset();
+        self.writer.goto_offset(offset)?;
+        value.write(self.writer)?;
+        self.writer.goto_offset(curr_pos)?;
+
+        let mut data = Vec::with_capacity(data_bytes);
+        K::write_offset(&mut TiffWriter::new(&mut data), offset)?;
+        self.ifd.insert(
+            tag.to_u16(),
+            DirectoryEntry {
+                data_type: <T>::FIELD_TYPE.to_u16(),
+                count: value.count().try_into()?,
+                data,
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Writes the values that do not fit into their entries, replacing them by their offsets.
+    fn write_values(&mut self) -> TiffResult<()> {
+        for (
+            tag,
+            &mut DirectoryEntry {
+                data: ref mut bytes,
+                ..
+            },
+        ) in self.ifd.iter_mut()
         {
             let data_bytes = mem::size_of::<K::OffsetType>();
 
             if bytes.len() > data_bytes {
-                let offset = self.writer.offset();
-                self.writer.write_bytes(bytes)?;
+                // Reserved values are never shared, as patching them must not change other tags.
+                let reserved = self.reserved.get(tag).copied();
+                let offset = match self.writer.shared_value(bytes) {
+                    Some(offset) if reserved.is_none() => offset,
+                    _ => {
+                        let offset = self.writer.offset();
+                        match reserved {
+                            Some(id) => self.writer.set_reserved_tag_position(id, offset),
+                            None => self.writer.share_value(bytes, offset),
+                        }
+                        self.writer.write_bytes(bytes)?;
+                        offset
+                    }
+                };
                 *bytes = vec![0; data_bytes];
                 let mut writer = TiffWriter::new(bytes as &mut [u8]);
                 K::write_offset(&mut writer, offset)?;
@@ -256,9 +1203,36 @@ impl<'a, W: 'a + Write + Seek, K: TiffKind> DirectoryEncoder<'a, W, K> {
             }
         }
 
-        let offset = self.writer.offset();
+        Ok(())
+    }
+
+    /// Takes the values that do not fit into their entries to write them later, leaving blank
+    /// offsets in their place.
+    fn take_values(&mut self) -> Vec<(u16, Vec<u8>)> {
+        let data_bytes = mem::size_of::<K::OffsetType>();
 
-        K::write_entry_count(self.writer, self.ifd.len())?;
+        let mut values = Vec::new();
+        for (&tag, entry) in self.ifd.iter_mut() {
+            if entry.data.len() > data_bytes {
+                values.push((tag, mem::replace(&mut entry.data, vec![0; data_bytes])));
+            } else {
+                entry.data.resize(data_bytes, 0);
+            }
+        }
+
+        values
+    }
+
+    /// Writes the entry count and the entries, once their values were written.
+    ///
+    /// Returns the position of the value field of every entry.
+    fn write_entries<V: Write>(
+        &self,
+        writer: &mut TiffWriter<V>,
+    ) -> TiffResult<BTreeMap<u16, u64>> {
+        let mut fields = BTreeMap::new();
+
+        K::write_entry_count(writer, self.ifd.len())?;
         for (
             tag,
             DirectoryEntry {
@@ -268,10 +1242,41 @@ impl<'a, W: 'a + Write + Seek, K: TiffKind> DirectoryEncoder<'a, W, K> {
             },
         ) in self.ifd.iter()
         {
-            self.writer.write_u16(*tag)?;
-            self.writer.write_u16(*field_type)?;
-            (*count).write(self.writer)?;
-            self.writer.write_bytes(offset)?;
+            writer.write_u16(*tag)?;
+            writer.write_u16(*field_type)?;
+            (*count).write(writer)?;
+            fields.insert(*tag, writer.offset());
+            writer.write_bytes(offset)?;
+        }
+
+        Ok(fields)
+    }
+
+    /// The reservation ids of the tags whose values are stored in their entries, along with the
+    /// position of the value given the value `fields` returned by `write_entries`.
+    fn reserved_in_entries(&self, fields: &BTreeMap<u16, u64>) -> Vec<(usize, u64)> {
+        self.reserved
+            .iter()
+            // Values stored out of line were located when they were written
+            .filter(|&(_, &id)| self.writer.reserved_tag_position(id).is_none())
+            .map(|(tag, &id)| (id, fields[tag]))
+            .collect()
+    }
+
+    fn write_directory(&mut self) -> TiffResult<u64> {
+        // Start by writing out all values
+        self.write_values()?;
+
+        let offset = self.writer.offset();
+
+        let mut block = Vec::new();
+        let fields = {
+            let mut writer = TiffWriter::new(&mut block);
+            self.write_entries(&mut writer)?
+        };
+        self.writer.write_bytes(&block)?;
+        for (id, position) in self.reserved_in_entries(&fields) {
+            self.writer.set_reserved_tag_position(id, offset + position);
         }
 
         Ok(offset)
@@ -286,12 +1291,89 @@ impl<'a, W: 'a + Write + Seek, K: TiffKind> DirectoryEncoder<'a, W, K> {
         Ok(offset)
     }
 
-    /// Provides the number of bytes written by the underlying TiffWriter during the last call.
-    fn last_written(&self) -> u64 {
-        self.writer.last_written()
+    /// Writes the (possibly compressed) rows of a strip or tile, returning its offset.
+    fn write_chunk_rows<V>(&mut self, data: &[V], row_len: usize, packbits: bool) -> TiffResult<u64>
+    where
+        [V]: TiffValue,
+    {
+        if packbits && !data.is_empty() {
+            // PackBits runs must not cross row boundaries, so each row is packed on its own.
+            let offset = self.writer.offset();
+            for row in data.chunks(row_len) {
+                self.write_data(row)?;
+            }
+            Ok(offset)
+        } else {
+            self.write_data(data)
+        }
+    }
+
+    /// Holds the entries back to be written with the other directories, see
+    /// [`TiffEncoder::with_deferred_directories`].
+    fn defer_directory(&mut self) -> TiffResult<()> {
+        let values = if self.writer.deferred_directories().unwrap().hold_values {
+            self.take_values()
+        } else {
+            self.write_values()?;
+            Vec::new()
+        };
+
+        let mut block = Vec::new();
+        let fields = {
+            let mut writer = TiffWriter::new(&mut block);
+            let fields = self.write_entries(&mut writer)?;
+            K::write_offset(&mut writer, 0)?;
+            fields
+        };
+        let mut reserved = self.reserved_in_entries(&fields);
+
+        let values: Vec<_> = values
+            .into_iter()
+            .map(|(tag, data)| DeferredValue {
+                pointer: fields[&tag] as usize,
+                data,
+                reserved: self.reserved.get(&tag).copied(),
+            })
+            .collect();
+        // Reserved values held back are located once they are written.
+        reserved.retain(|&(id, _)| values.iter().all(|value| value.reserved != Some(id)));
+
+        let deferred = self.writer.deferred_directories().unwrap();
+        deferred.directories.push(DeferredDirectory {
+            block,
+            reserved,
+            values,
+        });
+
+        self.dropped = true;
+
+        Ok(())
     }
 
     fn finish_internal(&mut self) -> TiffResult<()> {
+        if !self.sub_ifds.is_empty() {
+            let pointers = self
+                .sub_ifds
+                .iter()
+                .map(|&offset| IfdPointer::<K>::new(offset))
+                .collect::<TiffResult<Vec<_>>>()?;
+            self.write_tag(Tag::SubIFDs, &pointers[..])?;
+        }
+
+        // Sub directories are written right away, their parent links to them.
+        if self.parent.is_some() {
+            let offset = self.write_directory()?;
+            K::write_offset(self.writer, 0)?;
+            self.parent.as_mut().unwrap().push(offset);
+            self.dropped = true;
+
+            return Ok(());
+        }
+
+        if self.writer.deferred_directories().is_some() {
+            return self.defer_directory();
+        }
+
         let ifd_pointer = self.write_directory()?;
         let curr_pos = self.writer.offset();
 
@@ -319,6 +1401,9 @@ impl<'a, W: Write + Seek, K: TiffKind> Drop for DirectoryEncoder<'a, W, K> {
     }
 }
 
+/// A function transforming the samples of a strip.
+type MapSamples<T> = fn(&[T]) -> Vec<T>;
+
 /// Type to encode images strip by strip.
 ///
 /// You should call `finish` on this when you are finished with it.
@@ -352,9 +1437,16 @@ impl<'a, W: Write + Seek, K: TiffKind> Drop for DirectoryEncoder<'a, W, K> {
 /// # }
 /// ```
 /// You can also call write_data function wich will encode by strip and finish
+///
+/// The strips are stored in the order they are written, each one after the previous one, so that
+/// the `StripOffsets` ascend. Readers that process the file front to back, such as streaming
+/// hardware readers, can rely on this. Data written with [`DirectoryEncoder::write_data`] between
+/// strips is placed in between.
 pub struct ImageEncoder<'a, W: 'a + Write + Seek, C: ColorType, K: TiffKind> {
     encoder: DirectoryEncoder<'a, W, K>,
     strip_idx: u64,
+    /// File position after the last strip written, which the next strip must not precede.
+    strip_end: u64,
     strip_count: u64,
     row_samples: u64,
     width: u32,
@@ -362,9 +1454,15 @@ pub struct ImageEncoder<'a, W: 'a + Write + Seek, C: ColorType, K: TiffKind> {
     rows_per_strip: u64,
     strip_offsets: Vec<K::OffsetType>,
     strip_byte_count: Vec<K::OffsetType>,
+    /// File position and number of strips of the room reserved for the strip tables, see
+    /// [`reserve_strip_tables`][ImageEncoder::reserve_strip_tables].
+    reserved_strips: Option<(u64, usize)>,
     dropped: bool,
     compression: Compression,
     predictor: Predictor,
+    /// Inverts the samples of `WhiteIsZero` images, see
+    /// [`photometric_interpretation`][ImageEncoder::photometric_interpretation].
+    invert: Option<MapSamples<C::Inner>>,
     _phantom: ::std::marker::PhantomData<C>,
 }
 
@@ -374,8 +1472,8 @@ impl<'a, W: 'a + Write + Seek, T: ColorType, K: TiffKind> ImageEncoder<'a, W, T,
             (Predictor::Horizontal, _, SampleFormat::IEEEFP | SampleFormat::Void) => {
                 Err(TiffError::UsageError(UsageError::PredictorIncompatible))
             }
-            (Predictor::FloatingPoint, _, _) => {
-                Err(TiffError::UsageError(UsageError::PredictorUnavailable))
+            (Predictor::FloatingPoint, _, format) if format != SampleFormat::IEEEFP => {
+                Err(TiffError::UsageError(UsageError::PredictorIncompatible))
             }
             _ => Ok(()),
         }
@@ -401,12 +1499,7 @@ impl<'a, W: 'a + Write + Seek, T: ColorType, K: TiffKind> ImageEncoder<'a, W, T,
 
         // Limit the strip size to prevent potential memory and security issues.
         // Also keep the multiple strip handling 'oiled'
-        let rows_per_strip = {
-            match compression.tag() {
-                CompressionMethod::PackBits => 1, // Each row must be packed separately. Do not compress across row boundaries
-                _ => (1_000_000 + row_bytes - 1) / row_bytes,
-            }
-        };
+        let rows_per_strip = (1_000_000 + row_bytes - 1) / row_bytes;
 
         let strip_count = (u64::from(height) + rows_per_strip - 1) / rows_per_strip;
 
@@ -419,6 +1512,9 @@ impl<'a, W: 'a + Write + Seek, T: ColorType, K: TiffKind> ImageEncoder<'a, W, T,
         let sample_format: Vec<_> = <T>::SAMPLE_FORMAT.iter().map(|s| s.to_u16()).collect();
         encoder.write_tag(Tag::SampleFormat, &sample_format[..])?;
         encoder.write_tag(Tag::PhotometricInterpretation, <T>::TIFF_VALUE.to_u16())?;
+        if <T>::TIFF_VALUE == PhotometricInterpretation::YCbCr {
+            Self::write_ycbcr_tags(&mut encoder)?;
+        }
 
         encoder.write_tag(Tag::RowsPerStrip, u32::try_from(rows_per_strip)?)?;
 
@@ -426,6 +1522,10 @@ impl<'a, W: 'a + Write + Seek, T: ColorType, K: TiffKind> ImageEncoder<'a, W, T,
             Tag::SamplesPerPixel,
             u16::try_from(<T>::BITS_PER_SAMPLE.len())?,
         )?;
+        if !<T>::EXTRA_SAMPLES.is_empty() {
+            let extra_samples: Vec<_> = <T>::EXTRA_SAMPLES.iter().map(|s| s.to_u16()).collect();
+            encoder.write_tag(Tag::ExtraSamples, &extra_samples[..])?;
+        }
         encoder.write_tag(Tag::XResolution, Rational { n: 1, d: 1 })?;
         encoder.write_tag(Tag::YResolution, Rational { n: 1, d: 1 })?;
         encoder.write_tag(Tag::ResolutionUnit, ResolutionUnit::None.to_u16())?;
@@ -434,19 +1534,42 @@ impl<'a, W: 'a + Write + Seek, T: ColorType, K: TiffKind> ImageEncoder<'a, W, T,
             encoder,
             strip_count,
             strip_idx: 0,
+            strip_end: 0,
             row_samples,
             rows_per_strip,
             width,
             height,
             strip_offsets: Vec::new(),
             strip_byte_count: Vec::new(),
+            reserved_strips: None,
             dropped: false,
             compression,
             predictor,
+            invert: None,
             _phantom: ::std::marker::PhantomData,
         })
     }
 
+    /// Write the YCbCr tags whose defaults do not describe the samples we write.
+    ///
+    /// The samples are not subsampled, while readers assume 2x2 subsampling if the tag is
+    /// missing. The coefficients and reference values are those of ITU-R BT.601 with the full
+    /// 8-bit range, as used by JFIF.
+    fn write_ycbcr_tags(encoder: &mut DirectoryEncoder<'a, W, K>) -> TiffResult<()> {
+        encoder.write_tag(Tag::YCbCrSubSampling, &[1u16, 1][..])?;
+        encoder.write_tag(
+            Tag::YCbCrCoefficients,
+            &[
+                Rational { n: 299, d: 1000 },
+                Rational { n: 587, d: 1000 },
+                Rational { n: 114, d: 1000 },
+            ][..],
+        )?;
+        let reference = [0, 255, 128, 255, 128, 255].map(|n| Rational { n, d: 1 });
+        encoder.write_tag(Tag::ReferenceBlackWhite, &reference[..])?;
+        Ok(())
+    }
+
     /// Number of samples the next strip should have.
     pub fn next_strip_sample_count(&self) -> u64 {
         if self.strip_idx >= self.strip_count {
@@ -460,6 +1583,54 @@ impl<'a, W: 'a + Write + Seek, T: ColorType, K: TiffKind> ImageEncoder<'a, W, T,
         (end_row - start_row) * self.row_samples
     }
 
+    /// Reserve room for the offsets and byte counts of at least `additional` more strips.
+    ///
+    /// When streaming a large number of strips this avoids growing the offset tables repeatedly
+    /// and reallocating them at the end. This is only a hint, it does not limit the number of
+    /// strips that can be written. See [`reserve_strip_tables`][ImageEncoder::reserve_strip_tables]
+    /// to also reserve room for the tables in the file.
+    pub fn reserve_strips(&mut self, additional: usize) {
+        self.strip_offsets.reserve(additional);
+        self.strip_byte_count.reserve(additional);
+    }
+
+    /// Reserve room in the file for the offsets and byte counts of `strips` strips.
+    ///
+    /// The room for the `StripOffsets` and `StripByteCounts` tables is written to the file right
+    /// away, ahead of the strips, and the tables are stored there when the image is finished
+    /// instead of after the strips. The tables are also allocated once.
+    ///
+    /// Writing more than `strips` strips fails. This function needs to be called before any
+    /// strip is written and will return an error otherwise.
+    pub fn reserve_strip_tables(&mut self, strips: usize) -> TiffResult<()> {
+        if self.strip_idx != 0 || self.reserved_strips.is_some() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Cannot reserve strip tables after data was written or reserved",
+            )
+            .into());
+        }
+        let table_bytes = strips
+            .checked_mul(mem::size_of::<K::OffsetType>())
+            .ok_or(TiffError::LimitsExceeded)?;
+        self.strip_offsets.reserve_exact(strips);
+        self.strip_byte_count.reserve_exact(strips);
+
+        let writer = &mut *self.encoder.writer;
+        writer.pad_word_boundary()?;
+        let offset = writer.offset();
+        let zeros = [0; 4096];
+        let mut remaining = 2 * table_bytes as u64;
+        while remaining > 0 {
+            let len = cmp::min(remaining, zeros.len() as u64) as usize;
+            writer.write_bytes(&zeros[..len])?;
+            remaining -= len as u64;
+        }
+
+        self.reserved_strips = Some((offset, strips));
+        Ok(())
+    }
+
     /// Write a single strip.
     pub fn write_strip(&mut self, value: &[T::Inner]) -> TiffResult<()>
     where
@@ -473,21 +1644,54 @@ impl<'a, W: 'a + Write + Seek, T: ColorType, K: TiffKind> ImageEncoder<'a, W, T,
             )
             .into());
         }
+        if let Some((_, reserved)) = self.reserved_strips {
+            if self.strip_offsets.len() >= reserved {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "More strips written than reserved",
+                )
+                .into());
+            }
+        }
+
+        let inverted;
+        let value = match self.invert {
+            Some(invert) => {
+                inverted = invert(value);
+                inverted.as_slice()
+            }
+            None => value,
+        };
 
-        // Write the (possible compressed) data to the encoder.
+        let row_samples = self.row_samples as usize;
+        let packbits = self.compression == Compression::Packbits;
         let offset = match self.predictor {
-            Predictor::None => self.encoder.write_data(value)?,
+            Predictor::None => self
+                .encoder
+                .write_chunk_rows(value, row_samples, packbits)?,
             Predictor::Horizontal => {
-                let mut row_result = Vec::with_capacity(value.len());
-                for row in value.chunks_exact(self.row_samples as usize) {
-                    T::horizontal_predict(row, &mut row_result);
+                let mut predicted = Vec::with_capacity(value.len());
+                for row in value.chunks_exact(row_samples) {
+                    T::horizontal_predict(row, &mut predicted);
+                }
+                self.encoder
+                    .write_chunk_rows(&predicted, row_samples, packbits)?
+            }
+            Predictor::FloatingPoint => {
+                let byte_len = usize::from(<T::Inner>::BYTE_LEN);
+                let mut predicted = Vec::with_capacity(value.len() * byte_len);
+                for row in value.chunks_exact(row_samples) {
+                    T::floating_point_predict(row, &mut predicted)?;
                 }
-                self.encoder.write_data(row_result.as_slice())?
+                self.encoder
+                    .write_chunk_rows::<u8>(&predicted, row_samples * byte_len, packbits)?
             }
-            _ => unimplemented!(),
         };
 
-        let byte_count = self.encoder.last_written() as usize;
+        // Strips must be stored in ascending order, see the type level documentation.
+        debug_assert!(offset >= self.strip_end);
+        self.strip_end = self.encoder.writer.offset();
+        let byte_count = (self.strip_end - offset) as usize;
 
         self.strip_offsets.push(K::convert_offset(offset)?);
         self.strip_byte_count.push(byte_count.try_into()?);
@@ -497,7 +1701,25 @@ impl<'a, W: 'a + Write + Seek, T: ColorType, K: TiffKind> ImageEncoder<'a, W, T,
     }
 
     /// Write strips from data
-    pub fn write_data(mut self, data: &[T::Inner]) -> TiffResult<()>
+    pub fn write_data(self, data: &[T::Inner]) -> TiffResult<()>
+    where
+        [T::Inner]: TiffValue,
+    {
+        match self.write_data_with_progress(data, |_| ControlFlow::Continue(()))? {
+            ControlFlow::Continue(()) => Ok(()),
+            ControlFlow::Break(()) => unreachable!("progress never breaks"),
+        }
+    }
+
+    /// Write strips from data, calling `progress` after every strip.
+    ///
+    /// When `progress` breaks, the image is abandoned as with [`abort`][ImageEncoder::abort] and
+    /// `ControlFlow::Break` is returned. Otherwise the image is finished.
+    pub fn write_data_with_progress(
+        mut self,
+        data: &[T::Inner],
+        mut progress: impl FnMut(EncodeProgress) -> ControlFlow<()>,
+    ) -> TiffResult<ControlFlow<()>>
     where
         [T::Inner]: TiffValue,
     {
@@ -526,13 +1748,105 @@ impl<'a, W: 'a + Write + Seek, T: ColorType, K: TiffKind> ImageEncoder<'a, W, T,
             let sample_count = usize::try_from(self.next_strip_sample_count())?;
             self.write_strip(&data[idx..idx + sample_count])?;
             idx += sample_count;
+
+            let written = EncodeProgress {
+                strips_written: self.strip_idx,
+                strip_count: self.strip_count,
+            };
+            if progress(written).is_break() {
+                self.encoder.writer.reset_compression();
+                self.abort();
+                return Ok(ControlFlow::Break(()));
+            }
         }
 
         self.encoder.writer.reset_compression();
         self.finish()?;
+        Ok(ControlFlow::Continue(()))
+    }
+
+    /// Write strips from rows produced one after the other, then finish the image.
+    ///
+    /// Each row holds the samples of `width` pixels. The rows are taken from `rows` only as
+    /// needed and collected until they fill a strip of [`rows_per_strip`] rows, which is then
+    /// written. Only a single strip is held in memory, so callers producing rows on the fly, such
+    /// as renderers, neither materialize the whole image nor align their rows to strips.
+    ///
+    /// Exactly `height` rows are taken. If a row is of the wrong size or `rows` ends early, the
+    /// image is abandoned as with [`abort`][ImageEncoder::abort] and an error is returned.
+    ///
+    /// [`rows_per_strip`]: ImageEncoder::rows_per_strip
+    pub fn write_rows<'r, I>(mut self, rows: I) -> TiffResult<()>
+    where
+        I: IntoIterator<Item = &'r [T::Inner]>,
+        T::Inner: Copy + 'r,
+        [T::Inner]: TiffValue,
+    {
+        self.encoder
+            .writer
+            .set_compression(self.compression.get_algorithm());
+        let written = self.write_strips_of_rows(rows.into_iter());
+        self.encoder.writer.reset_compression();
+
+        match written {
+            Ok(()) => self.finish(),
+            Err(err) => {
+                self.abort();
+                Err(err)
+            }
+        }
+    }
+
+    fn write_strips_of_rows<'r>(
+        &mut self,
+        mut rows: impl Iterator<Item = &'r [T::Inner]>,
+    ) -> TiffResult<()>
+    where
+        T::Inner: Copy + 'r,
+        [T::Inner]: TiffValue,
+    {
+        let row_samples = usize::try_from(self.row_samples)?;
+        let mut strip = Vec::new();
+        while self.next_strip_sample_count() > 0 {
+            let sample_count = usize::try_from(self.next_strip_sample_count())?;
+            strip.clear();
+            while strip.len() < sample_count {
+                let row = rows.next().ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "Fewer rows than the image height",
+                    )
+                })?;
+                if row.len() != row_samples {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "Row is wrong size for image",
+                    )
+                    .into());
+                }
+                strip.extend_from_slice(row);
+            }
+            self.write_strip(&strip)?;
+        }
+
         Ok(())
     }
 
+    /// Abandon the image without writing its directory.
+    ///
+    /// The strips written so far stay in the file without being referenced. The directories of
+    /// the previous images are not changed, so the file holds them as if this image was never
+    /// started.
+    pub fn abort(mut self) {
+        self.dropped = true;
+        self.encoder.dropped = true;
+        // The next directory links to the pointer this one would have been linked to.
+        if self.encoder.parent.is_none() {
+            let ifd_pointer_pos = self.encoder.ifd_pointer_pos;
+            self.encoder.writer.set_ifd_pointer_pos(ifd_pointer_pos);
+        }
+    }
+
     /// Set image resolution
     pub fn resolution(&mut self, unit: ResolutionUnit, value: Rational) {
         self.encoder
@@ -561,6 +1875,83 @@ impl<'a, W: 'a + Write + Seek, T: ColorType, K: TiffKind> ImageEncoder<'a, W, T,
         self.encoder.write_tag(Tag::YResolution, value).unwrap();
     }
 
+    /// Set the horizontal offset of the image, in `ResolutionUnit`s from the left side of the page
+    ///
+    /// Returns an error if the denominator of `value` is zero.
+    pub fn x_position(&mut self, value: Rational) -> TiffResult<()> {
+        if value.d == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "XPosition denominator must not be zero",
+            )
+            .into());
+        }
+        self.encoder.write_tag(Tag::XPosition, value)
+    }
+
+    /// Set the vertical offset of the image, in `ResolutionUnit`s from the top of the page
+    ///
+    /// Returns an error if the denominator of `value` is zero.
+    pub fn y_position(&mut self, value: Rational) -> TiffResult<()> {
+        if value.d == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "YPosition denominator must not be zero",
+            )
+            .into());
+        }
+        self.encoder.write_tag(Tag::YPosition, value)
+    }
+
+    /// Set a clipping path outlining the visible part of the image
+    ///
+    /// The `path` is the binary path data as defined in TIFF Supplement 1. `x_units` and `y_units`
+    /// give the number of path units spanning the image width and height respectively. Returns an
+    /// error if the path is empty or one of the unit counts is zero.
+    pub fn clip_path(&mut self, path: &[u8], x_units: u32, y_units: u32) -> TiffResult<()> {
+        if path.is_empty() {
+            return Err(
+                io::Error::new(io::ErrorKind::InvalidInput, "ClipPath must not be empty").into(),
+            );
+        }
+        if x_units == 0 || y_units == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Clip path units must not be zero",
+            )
+            .into());
+        }
+
+        self.encoder.write_tag(Tag::ClipPath, path)?;
+        self.encoder.write_tag(Tag::XClipPathUnits, x_units)?;
+        self.encoder.write_tag(Tag::YClipPathUnits, y_units)
+    }
+
+    /// Embed an ICC color profile in the image.
+    ///
+    /// The profile is written as it is, after checking that it starts with an ICC profile header
+    /// whose size field matches the length of `profile`.
+    pub fn icc_profile(&mut self, profile: &[u8]) -> TiffResult<()> {
+        // The header is 128 bytes, with the size at its start and the signature at byte 36.
+        if profile.len() < 128 || &profile[36..40] != b"acsp" {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "ICC profile does not start with a profile header",
+            )
+            .into());
+        }
+        let size = u32::from_be_bytes([profile[0], profile[1], profile[2], profile[3]]);
+        if u64::from(size) != profile.len() as u64 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "ICC profile size does not match its length",
+            )
+            .into());
+        }
+
+        self.encoder.write_tag(Tag::IccProfile, Undefined(profile))
+    }
+
     /// Set image number of lines per strip
     ///
     /// This function needs to be called before any calls to `write_data` or
@@ -583,18 +1974,105 @@ impl<'a, W: 'a + Write + Seek, T: ColorType, K: TiffKind> ImageEncoder<'a, W, T,
         Ok(())
     }
 
-    fn finish_internal(&mut self) -> TiffResult<()> {
+    /// Set the photometric interpretation of a grayscale image.
+    ///
+    /// Grayscale images are written as `BlackIsZero` by default. With `WhiteIsZero`, as required
+    /// for fax compatible bilevel documents, the samples passed to the encoder are still taken
+    /// to be `BlackIsZero` and are inverted when written. Other interpretations and other color
+    /// types, including grayscale with extra samples such as alpha and signed grayscale, whose
+    /// inversion is not the bitwise complement, are rejected.
+    ///
+    /// This function needs to be called before any calls to `write_data` or
+    /// `write_strip` and will return an error otherwise.
+    pub fn photometric_interpretation(&mut self, value: PhotometricInterpretation) -> TiffResult<()>
+    where
+        T::Inner: Copy + ops::Not<Output = T::Inner>,
+    {
+        if self.strip_idx != 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Cannot change photometric interpretation after data was written",
+            )
+            .into());
+        }
+
+        let invert = match value {
+            PhotometricInterpretation::BlackIsZero => false,
+            PhotometricInterpretation::WhiteIsZero => true,
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "Only BlackIsZero and WhiteIsZero can be selected",
+                )
+                .into())
+            }
+        };
+        let single_sample = <T>::BITS_PER_SAMPLE.len() == 1 && <T>::EXTRA_SAMPLES.is_empty();
+        let invertible = <T>::SAMPLE_FORMAT
+            .iter()
+            .all(|&format| matches!(format, SampleFormat::Uint | SampleFormat::IEEEFP));
+        if <T>::TIFF_VALUE != PhotometricInterpretation::BlackIsZero
+            || !single_sample
+            || !invertible
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "The photometric interpretation can only be selected for grayscale images",
+            )
+            .into());
+        }
+
         self.encoder
-            .write_tag(Tag::StripOffsets, K::convert_slice(&self.strip_offsets))?;
-        self.encoder.write_tag(
-            Tag::StripByteCounts,
-            K::convert_slice(&self.strip_byte_count),
-        )?;
+            .write_tag(Tag::PhotometricInterpretation, value.to_u16())?;
+        self.invert = if invert {
+            Some(|samples| samples.iter().map(|&sample| !sample).collect())
+        } else {
+            None
+        };
+
+        Ok(())
+    }
+
+    fn finish_internal(&mut self) -> TiffResult<()> {
+        let offsets = K::convert_slice(&self.strip_offsets);
+        let byte_counts = K::convert_slice(&self.strip_byte_count);
+        match self.reserved_strips {
+            Some((offset, reserved)) => {
+                let table_bytes = (reserved * mem::size_of::<K::OffsetType>()) as u64;
+                self.encoder
+                    .write_tag_at(Tag::StripOffsets, offsets, offset)?;
+                self.encoder.write_tag_at(
+                    Tag::StripByteCounts,
+                    byte_counts,
+                    offset + table_bytes,
+                )?;
+            }
+            None => {
+                self.encoder.write_tag(Tag::StripOffsets, offsets)?;
+                self.encoder.write_tag(Tag::StripByteCounts, byte_counts)?;
+            }
+        }
         self.dropped = true;
 
         self.encoder.finish_internal()
     }
 
+    /// Create an [`ImageEncoder`] for a reduced resolution version of this image.
+    ///
+    /// The image is written to a sub directory, see [`DirectoryEncoder::new_sub_directory`], and
+    /// marked as a reduced resolution image. It uses the compression and predictor of this image
+    /// and must be finished before any further strip of this image is written.
+    pub fn new_sub_image<C: ColorType>(
+        &mut self,
+        width: u32,
+        height: u32,
+    ) -> TiffResult<ImageEncoder<'_, W, C, K>> {
+        let mut encoder = self.encoder.new_sub_directory()?;
+        let new_subfile_type = NewSubfileType::REDUCED_RESOLUTION;
+        encoder.write_tag(Tag::NewSubfileType, new_subfile_type.bits())?;
+        ImageEncoder::new(encoder, width, height, self.compression, self.predictor)
+    }
+
     /// Get a reference of the underlying `DirectoryEncoder`
     pub fn encoder(&mut self) -> &mut DirectoryEncoder<'a, W, K> {
         &mut self.encoder
@@ -678,11 +2156,12 @@ impl TiffKind for TiffKindStandard {
     }
 
     fn convert_offset(offset: u64) -> TiffResult<Self::OffsetType> {
-        Ok(Self::OffsetType::try_from(offset)?)
+        Self::OffsetType::try_from(offset)
+            .map_err(|_| TiffError::UsageError(UsageError::BigTiffRequired))
     }
 
     fn write_offset<W: Write>(writer: &mut TiffWriter<W>, offset: u64) -> TiffResult<()> {
-        writer.write_u32(u32::try_from(offset)?)?;
+        writer.write_u32(Self::convert_offset(offset)?)?;
         Ok(())
     }
 
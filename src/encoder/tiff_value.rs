@@ -2,7 +2,7 @@ use std::{borrow::Cow, io::Write, slice::from_ref};
 
 use crate::{bytecast, tags::Type, TiffError, TiffFormatError, TiffResult};
 
-use super::writer::TiffWriter;
+use super::{writer::TiffWriter, TiffKind};
 
 /// Trait for types that can be encoded in a tiff file
 pub trait TiffValue {
@@ -374,6 +374,64 @@ impl TiffValue for Ifd8 {
     }
 }
 
+impl<K: TiffKind> TiffValue for IfdPointer<K> {
+    const BYTE_LEN: u8 = <K::OffsetType as TiffValue>::BYTE_LEN;
+    // Offsets are `LONG` in standard tiff and `LONG8` in BigTiff, pointers follow the same width.
+    const FIELD_TYPE: Type = match <K::OffsetType as TiffValue>::FIELD_TYPE {
+        Type::LONG8 => Type::IFD8,
+        _ => Type::IFD,
+    };
+
+    fn count(&self) -> usize {
+        1
+    }
+
+    fn write<W: Write>(&self, writer: &mut TiffWriter<W>) -> TiffResult<()> {
+        self.0.write(writer)
+    }
+
+    fn data(&self) -> Cow<'_, [u8]> {
+        self.0.data()
+    }
+}
+
+impl<K: TiffKind> TiffValue for [IfdPointer<K>] {
+    const BYTE_LEN: u8 = IfdPointer::<K>::BYTE_LEN;
+    const FIELD_TYPE: Type = IfdPointer::<K>::FIELD_TYPE;
+
+    fn count(&self) -> usize {
+        self.len()
+    }
+
+    fn write<W: Write>(&self, writer: &mut TiffWriter<W>) -> TiffResult<()> {
+        for x in self {
+            x.write(writer)?;
+        }
+        Ok(())
+    }
+
+    fn data(&self) -> Cow<'_, [u8]> {
+        let mut buf: Vec<u8> = Vec::with_capacity(Self::BYTE_LEN as usize * self.len());
+        for x in self {
+            buf.extend_from_slice(&x.data());
+        }
+        Cow::Owned(buf)
+    }
+}
+
+impl<'a> TiffValue for Undefined<'a> {
+    const BYTE_LEN: u8 = 1;
+    const FIELD_TYPE: Type = Type::UNDEFINED;
+
+    fn count(&self) -> usize {
+        self.0.len()
+    }
+
+    fn data(&self) -> Cow<'_, [u8]> {
+        Cow::Borrowed(self.0)
+    }
+}
+
 impl TiffValue for Rational {
     const BYTE_LEN: u8 = 8;
     const FIELD_TYPE: Type = Type::RATIONAL;
@@ -508,6 +566,27 @@ pub struct Ifd(pub u32);
 #[derive(Clone)]
 pub struct Ifd8(pub u64);
 
+/// Type to represent a pointer to another IFD, e.g. the value of a `SubIFDs` or EXIF tag
+///
+/// The pointer is written with type `IFD` to standard tiff files and with type `IFD8` to BigTiff
+/// files, matching the offset size of the file kind `K`.
+pub struct IfdPointer<K: TiffKind>(K::OffsetType);
+
+impl<K: TiffKind> IfdPointer<K> {
+    /// Create a pointer to the IFD at `offset`.
+    ///
+    /// Errors if the offset cannot be represented in a standard tiff file.
+    pub fn new(offset: u64) -> TiffResult<Self> {
+        Ok(IfdPointer(K::convert_offset(offset)?))
+    }
+}
+
+/// Type to represent tiff values of type `UNDEFINED`, an opaque sequence of bytes
+///
+/// This is used for binary blobs such as Photoshop layer data (`ImageSourceData`).
+#[derive(Clone, Copy)]
+pub struct Undefined<'a>(pub &'a [u8]);
+
 /// Type to represent tiff values of type `RATIONAL`
 #[derive(Clone)]
 pub struct Rational {
@@ -1,5 +1,6 @@
 use crate::encoder::compression::*;
 use crate::error::TiffResult;
+use std::collections::HashMap;
 use std::io::{self, Seek, SeekFrom, Write};
 
 pub fn write_tiff_header<W: Write>(writer: &mut TiffWriter<W>) -> TiffResult<()> {
@@ -45,6 +46,41 @@ pub struct TiffWriter<W> {
     offset: u64,
     byte_count: u64,
     compressor: Compressor,
+    /// File position of the value of each reserved tag, once its directory was written.
+    reserved_tags: Vec<Option<u64>>,
+    /// Directories held back to be written contiguously, if enabled.
+    deferred: Option<DeferredDirectories>,
+    /// Position of the pointer to link the next directory to, if it is not the last word written.
+    ifd_pointer_pos: Option<u64>,
+    /// Offsets of the tag values written so far by their bytes, if identical values are shared.
+    shared_values: Option<HashMap<Vec<u8>, u64>>,
+}
+
+/// Directories whose values were written but whose entries are held back.
+pub struct DeferredDirectories {
+    /// File position of the pointer to the first held back directory.
+    pub pointer_pos: u64,
+    pub directories: Vec<DeferredDirectory>,
+    /// Whether the values that do not fit into their entries are held back as well.
+    pub hold_values: bool,
+}
+
+/// The entries of a directory, ending with a blank pointer to the next directory.
+pub struct DeferredDirectory {
+    pub block: Vec<u8>,
+    /// Reserved tags whose value is stored in the block, with their position within it.
+    pub reserved: Vec<(usize, u64)>,
+    /// Values held back to be written after the directories.
+    pub values: Vec<DeferredValue>,
+}
+
+/// A value that does not fit into its entry, held back with its directory.
+pub struct DeferredValue {
+    /// Position of the entry field to point to the value, within the directory block.
+    pub pointer: usize,
+    pub data: Vec<u8>,
+    /// Reservation id of the tag of the value, if it can be patched.
+    pub reserved: Option<usize>,
 }
 
 impl<W: Write> TiffWriter<W> {
@@ -54,9 +90,72 @@ impl<W: Write> TiffWriter<W> {
             offset: 0,
             byte_count: 0,
             compressor: Compressor::default(),
+            reserved_tags: Vec::new(),
+            deferred: None,
+            ifd_pointer_pos: None,
+            shared_values: None,
         }
     }
 
+    /// Takes the position of the pointer to link the next directory to.
+    ///
+    /// This is the last `pointer_size` bytes written, unless another position was set.
+    pub fn take_ifd_pointer_pos(&mut self, pointer_size: usize) -> u64 {
+        self.ifd_pointer_pos
+            .take()
+            .unwrap_or(self.offset - pointer_size as u64)
+    }
+
+    pub fn set_ifd_pointer_pos(&mut self, position: u64) {
+        self.ifd_pointer_pos = Some(position);
+    }
+
+    pub fn defer_directories(&mut self, pointer_pos: u64) {
+        if self.deferred.is_none() {
+            self.deferred = Some(DeferredDirectories {
+                pointer_pos,
+                directories: Vec::new(),
+                hold_values: false,
+            });
+        }
+    }
+
+    pub fn deferred_directories(&mut self) -> Option<&mut DeferredDirectories> {
+        self.deferred.as_mut()
+    }
+
+    pub fn share_values(&mut self) {
+        if self.shared_values.is_none() {
+            self.shared_values = Some(HashMap::new());
+        }
+    }
+
+    /// The offset of a tag value identical to `bytes` that was written before, if values are
+    /// shared.
+    pub fn shared_value(&self, bytes: &[u8]) -> Option<u64> {
+        self.shared_values.as_ref()?.get(bytes).copied()
+    }
+
+    /// Records the offset of a tag value, to be shared with identical values written later.
+    pub fn share_value(&mut self, bytes: &[u8], offset: u64) {
+        if let Some(shared_values) = &mut self.shared_values {
+            shared_values.entry(bytes.to_vec()).or_insert(offset);
+        }
+    }
+
+    pub fn reserve_tag(&mut self) -> usize {
+        self.reserved_tags.push(None);
+        self.reserved_tags.len() - 1
+    }
+
+    pub fn set_reserved_tag_position(&mut self, id: usize, position: u64) {
+        self.reserved_tags[id] = Some(position);
+    }
+
+    pub fn reserved_tag_position(&self, id: usize) -> Option<u64> {
+        self.reserved_tags.get(id).copied().flatten()
+    }
+
     pub fn set_compression(&mut self, compressor: Compressor) {
         self.compressor = compressor;
     }
@@ -78,6 +78,53 @@ pub enum TiffFormatError {
     CycleInOffsets,
     JpegDecoder(JpegDecoderError),
     SamplesPerPixelIsZero,
+    InconsistentByteOrder,
+    OffsetBeyondClassicTiff(u64, u64),
+    UnsortedTags(u64),
+    DuplicateTag(Tag, u64),
+    ChunkByteCountMismatch {
+        chunk: u32,
+        declared: u64,
+        consumed: u64,
+    },
+    /// A compressed chunk holds more data than its rows take up.
+    DecompressedChunkTooLarge {
+        chunk: u32,
+        expected_bytes: u64,
+    },
+    InvalidTileSize(u32, u32),
+    OffsetOutOfBounds {
+        offset: u64,
+        stream_length: u64,
+        source: OffsetSource,
+    },
+    /// The `GeoKeyDirectoryTag` is truncated or a key refers to values beyond its parameter tag.
+    InvalidGeoKeyDirectory,
+}
+
+/// What an offset that points outside of the file was read from.
+///
+/// The list of variants may grow. Matching against this exhaustively is not covered by interface
+/// stability guarantees.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum OffsetSource {
+    /// The location of an image file directory, from the header or a previous directory.
+    Ifd,
+    /// The location of the values of an entry with this tag.
+    Tag(Tag),
+    /// The location of the strip or tile with this index.
+    Chunk(u32),
+}
+
+impl fmt::Display for OffsetSource {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match *self {
+            OffsetSource::Ifd => write!(fmt, "image file directory"),
+            OffsetSource::Tag(tag) => write!(fmt, "values of tag `{:?}`", tag),
+            OffsetSource::Chunk(chunk) => write!(fmt, "chunk {}", chunk),
+        }
+    }
 }
 
 impl fmt::Display for TiffFormatError {
@@ -135,6 +182,57 @@ impl fmt::Display for TiffFormatError {
             CycleInOffsets => write!(fmt, "File contained a cycle in the list of IFDs"),
             JpegDecoder(ref error) => write!(fmt, "{}",  error),
             SamplesPerPixelIsZero => write!(fmt, "Samples per pixel is zero"),
+            InconsistentByteOrder => write!(
+                fmt,
+                "Image file directory contradicts the byte order of the file header"
+            ),
+            OffsetBeyondClassicTiff(offset, length) => write!(
+                fmt,
+                "Data at offset {} with length {} extends beyond the 4 GiB limit of a non-BigTIFF file",
+                offset, length
+            ),
+            UnsortedTags(offset) => write!(
+                fmt,
+                "Entries of the image file directory at offset {} are not sorted by tag",
+                offset
+            ),
+            DuplicateTag(ref tag, offset) => write!(
+                fmt,
+                "Tag `{:?}` appears more than once in the image file directory at offset {}",
+                tag, offset
+            ),
+            ChunkByteCountMismatch {
+                chunk,
+                declared,
+                consumed,
+            } => write!(
+                fmt,
+                "Chunk {} takes up {} bytes, but its byte count is {}",
+                chunk, consumed, declared
+            ),
+            DecompressedChunkTooLarge {
+                chunk,
+                expected_bytes,
+            } => write!(
+                fmt,
+                "Chunk {} decompresses to more than the expected {} bytes",
+                chunk, expected_bytes
+            ),
+            InvalidTileSize(width, length) => write!(
+                fmt,
+                "Tile size {}x{} is not a multiple of 16",
+                width, length
+            ),
+            OffsetOutOfBounds {
+                offset,
+                stream_length,
+                source,
+            } => write!(
+                fmt,
+                "Offset {} of the {} lies beyond the end of the file at {}",
+                offset, source, stream_length
+            ),
+            InvalidGeoKeyDirectory => write!(fmt, "The GeoTIFF key directory is malformed"),
         }
     }
 }
@@ -232,13 +330,23 @@ impl fmt::Display for TiffUnsupportedError {
 /// User attempted to use the Decoder in a way that is incompatible with a specific image.
 ///
 /// For example: attempting to read a tile from a stripped image.
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum UsageError {
     InvalidChunkType(ChunkType, ChunkType),
     InvalidChunkIndex(u32),
+    InvalidPlaneIndex(u16),
     PredictorCompressionMismatch,
     PredictorIncompatible,
     PredictorUnavailable,
+    InvalidOverviewScale,
+    NoExternalOverviews,
+    ReservedTagNotWritten,
+    ReservedTagMismatch,
+    InvalidRegion(u32, u32, u32, u32),
+    InsufficientBuffer(usize, usize),
+    BigTiffRequired,
+    InvalidBufferType(SampleFormat, u8),
 }
 
 impl fmt::Display for UsageError {
@@ -253,6 +361,7 @@ impl fmt::Display for UsageError {
                 )
             }
             InvalidChunkIndex(index) => write!(fmt, "Image chunk index ({}) requested.", index),
+            InvalidPlaneIndex(index) => write!(fmt, "Image plane index ({}) requested.", index),
             PredictorCompressionMismatch => write!(
                 fmt,
                 "The requested predictor is not compatible with the requested compression"
@@ -262,6 +371,35 @@ impl fmt::Display for UsageError {
                 "The requested predictor is not compatible with the image's format"
             ),
             PredictorUnavailable => write!(fmt, "The requested predictor is not available"),
+            InvalidOverviewScale => write!(fmt, "The overview scale is not a positive number"),
+            NoExternalOverviews => write!(fmt, "No external overview file is attached"),
+            ReservedTagNotWritten => write!(
+                fmt,
+                "The directory of the reserved tag has not been written yet"
+            ),
+            ReservedTagMismatch => write!(
+                fmt,
+                "The value does not match the type and count of the reserved tag"
+            ),
+            InvalidRegion(x, y, width, height) => write!(
+                fmt,
+                "Region of {}x{} pixels at ({}, {}) is empty or exceeds the image",
+                width, height, x, y
+            ),
+            InsufficientBuffer(required, actual) => write!(
+                fmt,
+                "Output buffer of {} bytes is too small, {} bytes are required",
+                actual, required
+            ),
+            BigTiffRequired => write!(
+                fmt,
+                "The file exceeds 4 GiB, which only BigTIFF files can address"
+            ),
+            InvalidBufferType(format, bits) => write!(
+                fmt,
+                "Output buffer type does not hold {:?} samples of {} bits",
+                format, bits
+            ),
         }
     }
 }
@@ -306,10 +444,32 @@ impl Error for TiffError {
 
 impl From<io::Error> for TiffError {
     fn from(err: io::Error) -> TiffError {
-        TiffError::IoError(err)
+        match err.get_ref() {
+            Some(inner) if inner.is::<IoLimitsExceeded>() => TiffError::LimitsExceeded,
+            _ => TiffError::IoError(err),
+        }
+    }
+}
+
+/// The error of decoder methods returning an [`io::Error`] when a limit is exceeded, which
+/// converts into [`TiffError::LimitsExceeded`].
+#[derive(Debug)]
+pub(crate) struct IoLimitsExceeded;
+
+impl IoLimitsExceeded {
+    pub(crate) fn into_io_error() -> io::Error {
+        io::Error::new(io::ErrorKind::Other, IoLimitsExceeded)
     }
 }
 
+impl Display for IoLimitsExceeded {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(fmt, "The IO budget of the decoder was exceeded")
+    }
+}
+
+impl Error for IoLimitsExceeded {}
+
 impl From<str::Utf8Error> for TiffError {
     fn from(_err: str::Utf8Error) -> TiffError {
         TiffError::FormatError(TiffFormatError::InvalidTag)
@@ -346,6 +506,30 @@ impl From<std::num::TryFromIntError> for TiffError {
     }
 }
 
+#[cfg(feature = "lzma")]
+impl From<lzma_rs::error::Error> for TiffError {
+    fn from(err: lzma_rs::error::Error) -> TiffError {
+        match err {
+            lzma_rs::error::Error::IoError(err) | lzma_rs::error::Error::HeaderTooShort(err) => {
+                TiffError::IoError(err)
+            }
+            lzma_rs::error::Error::LzmaError(message) | lzma_rs::error::Error::XzError(message) => {
+                TiffError::FormatError(TiffFormatError::Format(message))
+            }
+        }
+    }
+}
+
+#[cfg(feature = "webp")]
+impl From<image_webp::DecodingError> for TiffError {
+    fn from(err: image_webp::DecodingError) -> TiffError {
+        match err {
+            image_webp::DecodingError::IoError(err) => TiffError::IoError(err),
+            err => TiffError::FormatError(TiffFormatError::Format(err.to_string())),
+        }
+    }
+}
+
 impl From<LzwError> for TiffError {
     fn from(err: LzwError) -> TiffError {
         match err {
@@ -0,0 +1,193 @@
+//! Georeferencing of GeoTIFF images.
+//!
+//! GeoTIFF stores the coordinate system of an image as keys packed into the `GeoKeyDirectoryTag`,
+//! whose values may be held by the `GeoDoubleParamsTag` and `GeoAsciiParamsTag`. The placement of
+//! the image in that coordinate system is given by the model tags. See [`Decoder::geo_info`].
+use std::io::{Read, Seek};
+
+use crate::decoder::ifd::Value;
+use crate::decoder::Decoder;
+use crate::tags::{GeoKey, Tag};
+use crate::{TiffError, TiffFormatError, TiffResult};
+
+/// The georeferencing tags of an image, see [`Decoder::geo_info`].
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct GeoInfo {
+    /// The version of the key directory, followed by its key revision and minor revision.
+    pub version: [u16; 3],
+    /// The keys in the order of the directory, with their values resolved.
+    pub keys: Vec<(GeoKey, GeoKeyValue)>,
+    /// The size of a pixel in model space, from the `ModelPixelScaleTag`.
+    pub pixel_scale: Option<[f64; 3]>,
+    /// The points tying raster space to model space, from the `ModelTiepointTag`.
+    pub tiepoints: Vec<Tiepoint>,
+    /// The transformation from raster to model space as a 4x4 matrix in row major order, from
+    /// the `ModelTransformationTag`.
+    pub transformation: Option<[f64; 16]>,
+}
+
+/// The value of a key of the `GeoKeyDirectoryTag`.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum GeoKeyValue {
+    /// A single value stored in the key entry, such as a code.
+    Short(u16),
+    /// Values stored at the end of the key directory.
+    Shorts(Vec<u16>),
+    /// Values of the `GeoDoubleParamsTag`.
+    Doubles(Vec<f64>),
+    /// A string of the `GeoAsciiParamsTag`, without its `|` terminator.
+    Ascii(String),
+}
+
+/// A point of raster space along with the point of model space it corresponds to.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Tiepoint {
+    /// The raster coordinates `(i, j, k)`.
+    pub raster: [f64; 3],
+    /// The model coordinates `(x, y, z)`.
+    pub model: [f64; 3],
+}
+
+impl GeoInfo {
+    /// Returns the value of `key`, if the directory has it.
+    pub fn key(&self, key: GeoKey) -> Option<&GeoKeyValue> {
+        self.keys
+            .iter()
+            .find(|(other, _)| *other == key)
+            .map(|(_, value)| value)
+    }
+}
+
+impl GeoKeyValue {
+    /// Returns a value stored in the key entry, such as a code.
+    pub fn as_short(&self) -> Option<u16> {
+        match *self {
+            GeoKeyValue::Short(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Returns a single double value.
+    pub fn as_double(&self) -> Option<f64> {
+        match self {
+            GeoKeyValue::Doubles(values) if values.len() == 1 => Some(values[0]),
+            _ => None,
+        }
+    }
+
+    /// Returns a string value.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            GeoKeyValue::Ascii(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+impl<R: Read + Seek> Decoder<R> {
+    /// Reads the georeferencing tags of the current image.
+    ///
+    /// Returns `None` if the image has neither a `GeoKeyDirectoryTag` nor any of the model tags.
+    /// Keys referring to values beyond the end of their tag fail with
+    /// [`TiffFormatError::InvalidGeoKeyDirectory`].
+    pub fn geo_info(&mut self) -> TiffResult<Option<GeoInfo>> {
+        let directory = self.find_tag(Tag::GeoKeyDirectoryTag)?;
+        let pixel_scale = self.find_tag(Tag::ModelPixelScaleTag)?;
+        let tiepoints = self.find_tag(Tag::ModelTiepointTag)?;
+        let transformation = self.find_tag(Tag::ModelTransformationTag)?;
+        if directory.is_none()
+            && pixel_scale.is_none()
+            && tiepoints.is_none()
+            && transformation.is_none()
+        {
+            return Ok(None);
+        }
+
+        let mut info = GeoInfo::default();
+        if let Some(directory) = directory {
+            self.read_geo_keys(&directory.into_u16_vec()?, &mut info)?;
+        }
+        if let Some(pixel_scale) = pixel_scale {
+            info.pixel_scale = Some(fixed_size(Tag::ModelPixelScaleTag, pixel_scale)?);
+        }
+        if let Some(tiepoints) = tiepoints {
+            let values = tiepoints.into_f64_vec()?;
+            if values.len() % 6 != 0 {
+                return Err(TiffError::FormatError(
+                    TiffFormatError::InvalidTagValueType(Tag::ModelTiepointTag),
+                ));
+            }
+            info.tiepoints = values
+                .chunks_exact(6)
+                .map(|point| Tiepoint {
+                    raster: [point[0], point[1], point[2]],
+                    model: [point[3], point[4], point[5]],
+                })
+                .collect();
+        }
+        if let Some(transformation) = transformation {
+            info.transformation = Some(fixed_size(Tag::ModelTransformationTag, transformation)?);
+        }
+
+        Ok(Some(info))
+    }
+
+    /// Resolves the keys of the key `directory`.
+    fn read_geo_keys(&mut self, directory: &[u16], info: &mut GeoInfo) -> TiffResult<()> {
+        let invalid = || TiffError::FormatError(TiffFormatError::InvalidGeoKeyDirectory);
+        let (version, key_count, rest) = match directory {
+            [version, revision, minor_revision, key_count, rest @ ..] => {
+                ([*version, *revision, *minor_revision], *key_count, rest)
+            }
+            _ => return Err(invalid()),
+        };
+        let entries = rest.get(..usize::from(key_count) * 4).ok_or_else(invalid)?;
+        info.version = version;
+
+        let doubles = match self.find_tag(Tag::GeoDoubleParamsTag)? {
+            Some(doubles) => doubles.into_f64_vec()?,
+            None => Vec::new(),
+        };
+        let ascii = match self.find_tag(Tag::GeoAsciiParamsTag)? {
+            Some(ascii) => ascii.into_string()?,
+            None => String::new(),
+        };
+
+        for entry in entries.chunks_exact(4) {
+            let key = GeoKey::from_u16_exhaustive(entry[0]);
+            let (location, count, offset) = (entry[1], usize::from(entry[2]), entry[3]);
+            let values = usize::from(offset)..usize::from(offset) + count;
+            let value = match Tag::from_u16_exhaustive(location) {
+                // The value is stored in place of the offset.
+                Tag::Unknown(0) => GeoKeyValue::Short(offset),
+                Tag::GeoKeyDirectoryTag => {
+                    GeoKeyValue::Shorts(directory.get(values).ok_or_else(invalid)?.to_vec())
+                }
+                Tag::GeoDoubleParamsTag => {
+                    GeoKeyValue::Doubles(doubles.get(values).ok_or_else(invalid)?.to_vec())
+                }
+                Tag::GeoAsciiParamsTag => {
+                    let value = ascii.as_bytes().get(values).ok_or_else(invalid)?;
+                    let value = value.strip_suffix(b"|").unwrap_or(value);
+                    GeoKeyValue::Ascii(String::from_utf8_lossy(value).into_owned())
+                }
+                _ => return Err(invalid()),
+            };
+            info.keys.push((key, value));
+        }
+
+        Ok(())
+    }
+}
+
+/// Reads the values of a tag holding a fixed number of doubles.
+fn fixed_size<const N: usize>(tag: Tag, value: Value) -> TiffResult<[f64; N]> {
+    value
+        .into_f64_vec()?
+        .try_into()
+        .map_err(|_| TiffError::FormatError(TiffFormatError::InvalidTagValueType(tag)))
+}
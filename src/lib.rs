@@ -9,16 +9,29 @@
 extern crate jpeg;
 extern crate weezl;
 
+#[cfg(feature = "baseline-tables")]
+pub mod baseline;
+#[cfg(not(feature = "baseline-tables"))]
+#[allow(dead_code)]
+mod baseline;
 mod bytecast;
+#[cfg(feature = "image-interop")]
+pub mod convert;
 pub mod decoder;
 pub mod encoder;
 mod error;
+pub mod geotiff;
+mod pixel_format;
 pub mod tags;
 
-pub use self::error::{TiffError, TiffFormatError, TiffResult, TiffUnsupportedError, UsageError};
+pub use self::error::{
+    OffsetSource, TiffError, TiffFormatError, TiffResult, TiffUnsupportedError, UsageError,
+};
+pub use self::pixel_format::PixelFormat;
 
 /// An enumeration over supported color types and their bit depths
 #[derive(Copy, PartialEq, Eq, Debug, Clone, Hash)]
+#[non_exhaustive]
 pub enum ColorType {
     /// Pixel is grayscale
     Gray(u8),
@@ -26,7 +39,8 @@ pub enum ColorType {
     /// Pixel contains R, G and B channels
     RGB(u8),
 
-    /// Pixel is an index into a color palette
+    /// Pixel is an index into a color palette, see
+    /// [`Decoder::palette`](crate::decoder::Decoder::palette)
     Palette(u8),
 
     /// Pixel is grayscale with an alpha channel
@@ -38,9 +52,15 @@ pub enum ColorType {
     /// Pixel is CMYK
     CMYK(u8),
 
+    /// Pixel is CMYK with an alpha channel
+    CMYKA(u8),
+
     /// Pixel is YCbCr
     YCbCr(u8),
 
+    /// Pixel is YCbCr with an alpha channel
+    YCbCrA(u8),
+
     /// Pixel has multiple bands/channels
     Multiband { bit_depth: u8, num_samples: u16 },
 }
@@ -53,7 +73,9 @@ impl ColorType {
             | ColorType::GrayA(b)
             | ColorType::RGBA(b)
             | ColorType::CMYK(b)
+            | ColorType::CMYKA(b)
             | ColorType::YCbCr(b)
+            | ColorType::YCbCrA(b)
             | ColorType::Multiband { bit_depth: b, .. } => b,
         }
     }
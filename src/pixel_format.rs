@@ -0,0 +1,272 @@
+//! A description of the samples of a pixel, gathered in a single value.
+
+use std::io;
+
+use crate::tags::{ExtraSample, PhotometricInterpretation, PlanarConfiguration, SampleFormat};
+use crate::{TiffError, TiffResult};
+
+/// The samples making up every pixel of an image, and how they are stored.
+///
+/// This gathers what is otherwise spread over [`ColorType`](crate::ColorType), [`SampleFormat`]
+/// and the `BitsPerSample` of an image, with one entry per sample for the bits and the format.
+/// It is returned by [`Decoder::pixel_format`](crate::decoder::Decoder::pixel_format) and
+/// accepted by
+/// [`TiffEncoder::write_image_with_format`](crate::encoder::TiffEncoder::write_image_with_format),
+/// so code converting between pixel formats can work on plain values.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct PixelFormat {
+    /// How the color samples are to be interpreted.
+    pub interpretation: PhotometricInterpretation,
+    /// The number of bits of each sample.
+    pub bits_per_sample: Vec<u8>,
+    /// The data type of each sample.
+    pub sample_format: Vec<SampleFormat>,
+    /// The meaning of the samples following the color samples, as in the `ExtraSamples` tag.
+    pub extra_samples: Vec<ExtraSample>,
+    /// Whether the samples of a pixel are stored together or in separate planes.
+    pub planar_config: PlanarConfiguration,
+}
+
+impl PixelFormat {
+    /// A format of chunky pixels without extra samples, with one entry of `bits_per_sample` and
+    /// `sample_format` per sample.
+    pub fn new(
+        interpretation: PhotometricInterpretation,
+        bits_per_sample: Vec<u8>,
+        sample_format: Vec<SampleFormat>,
+    ) -> PixelFormat {
+        PixelFormat {
+            interpretation,
+            bits_per_sample,
+            sample_format,
+            extra_samples: Vec::new(),
+            planar_config: PlanarConfiguration::Chunky,
+        }
+    }
+
+    /// Set the meaning of the last `extra_samples.len()` samples.
+    pub fn with_extra_samples(mut self, extra_samples: Vec<ExtraSample>) -> PixelFormat {
+        self.extra_samples = extra_samples;
+        self
+    }
+
+    /// Set whether the samples are stored together or in separate planes.
+    pub fn with_planar_config(mut self, planar_config: PlanarConfiguration) -> PixelFormat {
+        self.planar_config = planar_config;
+        self
+    }
+
+    /// The number of samples of every pixel.
+    pub fn samples(&self) -> usize {
+        self.bits_per_sample.len()
+    }
+
+    /// The size in bytes of an uncompressed image of this format, e.g. to pass to
+    /// [`TiffEncoder::new_auto`](crate::encoder::TiffEncoder::new_auto).
+    ///
+    /// The rows of every plane start at a byte boundary. The size saturates at `u64::MAX` instead
+    /// of overflowing.
+    pub fn image_size(&self, width: u32, height: u32) -> u64 {
+        let bits = self.bits_per_sample.iter().map(|&bits| u64::from(bits));
+        match self.planar_config {
+            PlanarConfiguration::Planar => saturating_image_size(width, height, bits),
+            _ => saturating_image_size(width, height, [bits.sum()]),
+        }
+    }
+
+    /// Rearranges the samples of an image stored in separate planes into interleaved pixels.
+    ///
+    /// `planar` holds one plane per sample, one after another, as the chunks of a planar image
+    /// are ordered and as [`TiffEncoder::write_image_with_format`] takes them. The rows of every
+    /// plane and of the result start at a byte boundary, and samples of less than 8 bits are
+    /// packed starting at the most significant bit. The `planar_config` of the format is not
+    /// taken into account.
+    ///
+    /// [`TiffEncoder::write_image_with_format`]: crate::encoder::TiffEncoder::write_image_with_format
+    pub fn planar_to_chunky(&self, width: u32, height: u32, planar: &[u8]) -> TiffResult<Vec<u8>> {
+        let layout = RowLayout::new(self, width, height)?;
+        layout.check_len(planar, layout.planar_len())?;
+        let mut chunky = vec![0; layout.chunky_len()];
+        for row in 0..layout.height {
+            let mut plane_start = 0;
+            let mut pixel_bit = 0;
+            for (&bits, &plane_row) in self.bits_per_sample.iter().zip(&layout.plane_rows) {
+                let bits = usize::from(bits);
+                copy_samples(
+                    (&planar[plane_start + row * plane_row..], 0, bits),
+                    (
+                        &mut chunky[row * layout.chunky_row..],
+                        pixel_bit,
+                        layout.pixel_bits,
+                    ),
+                    bits,
+                    layout.width,
+                );
+                plane_start += plane_row * layout.height;
+                pixel_bit += bits;
+            }
+        }
+
+        Ok(chunky)
+    }
+
+    /// Splits the interleaved samples of an image into one plane per sample.
+    ///
+    /// This is the reverse of [`planar_to_chunky`][PixelFormat::planar_to_chunky], with the same
+    /// layout of rows and packed samples.
+    pub fn chunky_to_planar(&self, width: u32, height: u32, chunky: &[u8]) -> TiffResult<Vec<u8>> {
+        let layout = RowLayout::new(self, width, height)?;
+        layout.check_len(chunky, layout.chunky_len())?;
+        let mut planar = vec![0; layout.planar_len()];
+        for row in 0..layout.height {
+            let mut plane_start = 0;
+            let mut pixel_bit = 0;
+            for (&bits, &plane_row) in self.bits_per_sample.iter().zip(&layout.plane_rows) {
+                let bits = usize::from(bits);
+                copy_samples(
+                    (
+                        &chunky[row * layout.chunky_row..],
+                        pixel_bit,
+                        layout.pixel_bits,
+                    ),
+                    (&mut planar[plane_start + row * plane_row..], 0, bits),
+                    bits,
+                    layout.width,
+                );
+                plane_start += plane_row * layout.height;
+                pixel_bit += bits;
+            }
+        }
+
+        Ok(planar)
+    }
+
+    /// The kind of alpha, if the first extra sample is an alpha channel.
+    pub fn alpha(&self) -> Option<ExtraSample> {
+        match self.extra_samples.first() {
+            Some(&kind @ (ExtraSample::AssociatedAlpha | ExtraSample::UnassociatedAlpha)) => {
+                Some(kind)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// The size in bytes of an image with planes of `plane_bits` bits per pixel, saturating at
+/// `u64::MAX`.
+pub(crate) fn saturating_image_size(
+    width: u32,
+    height: u32,
+    plane_bits: impl IntoIterator<Item = u64>,
+) -> u64 {
+    plane_bits
+        .into_iter()
+        .map(|bits| u64::from(width).saturating_mul(bits).saturating_add(7) / 8)
+        .fold(0, u64::saturating_add)
+        .saturating_mul(u64::from(height))
+}
+
+/// The sizes of the rows of an image in chunky and in planar layout.
+struct RowLayout {
+    width: usize,
+    height: usize,
+    pixel_bits: usize,
+    chunky_row: usize,
+    plane_rows: Vec<usize>,
+}
+
+impl RowLayout {
+    fn new(format: &PixelFormat, width: u32, height: u32) -> TiffResult<RowLayout> {
+        if format.samples() == 0 || format.bits_per_sample.contains(&0) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "pixel format is inconsistent",
+            )
+            .into());
+        }
+
+        let row_bytes = |bits: u64| (u64::from(width) * bits + 7) / 8;
+        let pixel_bits: u64 = format.bits_per_sample.iter().map(|&b| u64::from(b)).sum();
+        let plane_rows: Vec<u64> = format
+            .bits_per_sample
+            .iter()
+            .map(|&bits| row_bytes(bits.into()))
+            .collect();
+        let chunky_row = row_bytes(pixel_bits);
+        // Both layouts must fit in memory, the planar one is the larger if samples are packed.
+        let planar_len = plane_rows
+            .iter()
+            .sum::<u64>()
+            .checked_mul(u64::from(height))
+            .ok_or(TiffError::LimitsExceeded)?;
+        usize::try_from(planar_len)?;
+
+        Ok(RowLayout {
+            width: usize::try_from(width)?,
+            height: usize::try_from(height)?,
+            pixel_bits: usize::try_from(pixel_bits)?,
+            chunky_row: usize::try_from(chunky_row)?,
+            plane_rows: plane_rows
+                .into_iter()
+                .map(usize::try_from)
+                .collect::<Result<_, _>>()?,
+        })
+    }
+
+    fn chunky_len(&self) -> usize {
+        self.chunky_row * self.height
+    }
+
+    fn planar_len(&self) -> usize {
+        self.plane_rows.iter().sum::<usize>() * self.height
+    }
+
+    fn check_len(&self, data: &[u8], len: usize) -> TiffResult<()> {
+        if data.len() != len {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Input data slice does not match the pixel format and dimensions",
+            )
+            .into());
+        }
+
+        Ok(())
+    }
+}
+
+/// Copies `count` samples of `bits` bits each, given a buffer with the bit offset of the first
+/// sample and the distance in bits between samples for both the source and the destination.
+///
+/// Bits are numbered starting at the most significant bit of each byte.
+pub(crate) fn copy_samples(
+    (src, src_bit, src_step): (&[u8], usize, usize),
+    (dst, dst_bit, dst_step): (&mut [u8], usize, usize),
+    bits: usize,
+    count: usize,
+) {
+    if (src_bit | src_step | dst_bit | dst_step | bits) % 8 == 0 {
+        let bytes = bits / 8;
+        for sample in 0..count {
+            let from = (src_bit + sample * src_step) / 8;
+            let to = (dst_bit + sample * dst_step) / 8;
+            dst[to..to + bytes].copy_from_slice(&src[from..from + bytes]);
+        }
+        return;
+    }
+
+    for sample in 0..count {
+        let src_start = src_bit + sample * src_step;
+        let dst_start = dst_bit + sample * dst_step;
+        for bit in 0..bits {
+            let (src_pos, dst_pos) = (src_start + bit, dst_start + bit);
+            let value = (src[src_pos / 8] >> (7 - src_pos % 8)) & 1;
+            let mask = 1 << (7 - dst_pos % 8);
+            if value == 1 {
+                dst[dst_pos / 8] |= mask;
+            } else {
+                dst[dst_pos / 8] &= !mask;
+            }
+        }
+    }
+}
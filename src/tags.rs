@@ -31,7 +31,7 @@ macro_rules! tags {
             fn __to_inner_type(&self) -> $ty {
                 match *self {
                     $( $name::$tag => $val, )*
-                    $( $name::Unknown(n) => { $unknown_doc; n }, )*
+                    $( $name::Unknown(n) => { let _ = $unknown_doc; n }, )*
                 }
             }
         }
@@ -49,7 +49,7 @@ macro_rules! tags {
             $(
             #[inline(always)]
             pub fn from_u16_exhaustive(val: u16) -> Self {
-                $unknown_doc;
+                let _ = $unknown_doc;
                 Self::__from_inner_type(val).unwrap_or_else(|_| $name::Unknown(val))
             }
             )*
@@ -68,6 +68,7 @@ macro_rules! tags {
 // Note: These tags appear in the order they are mentioned in the TIFF reference
 tags! {
 /// TIFF tags
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum Tag(u16) unknown("A private or extension tag") {
     // Baseline tags:
     Artist = 315,
@@ -109,17 +110,42 @@ pub enum Tag(u16) unknown("A private or extension tag") {
     XResolution = 282,
     YResolution = 283,
     // Advanced tags
+    XPosition = 286,
+    YPosition = 287,
     Predictor = 317,
     TileWidth = 322,
     TileLength = 323,
     TileOffsets = 324,
     TileByteCounts = 325,
+    // Reduced resolution images of a pyramid, or other images belonging to this one
+    SubIFDs = 330,
     // Data Sample Format
     SampleFormat = 339,
     SMinSampleValue = 340, // TODO add support
     SMaxSampleValue = 341, // TODO add support
+    // Clipping path (TIFF Supplement 1)
+    ClipPath = 343,
+    XClipPathUnits = 344,
+    YClipPathUnits = 345,
+    // Colorimetry
+    TransferFunction = 301,
+    WhitePoint = 318,
+    PrimaryChromaticities = 319,
+    // ICC color profile (InterColorProfile)
+    IccProfile = 34675,
+    // YCbCr
+    YCbCrCoefficients = 529,
+    YCbCrSubSampling = 530,
+    YCbCrPositioning = 531,
+    ReferenceBlackWhite = 532,
+    // Pointers to the EXIF private directories
+    ExifDirectory = 0x8769,
+    GpsDirectory = 0x8825,
+    InteropDirectory = 0xA005,
     // JPEG
     JPEGTables = 347,
+    JPEGInterchangeFormat = 513,
+    JPEGInterchangeFormatLength = 514,
     // GeoTIFF
     ModelPixelScaleTag = 33550, // (SoftDesk)
     ModelTransformationTag = 34264, // (JPL Carto Group)
@@ -127,12 +153,34 @@ pub enum Tag(u16) unknown("A private or extension tag") {
     GeoKeyDirectoryTag = 34735, // (SPOT)
     GeoDoubleParamsTag = 34736, // (SPOT)
     GeoAsciiParamsTag = 34737, // (SPOT)
+    GdalMetadata = 42112, // XML metadata of the dataset and its bands
     GdalNodata = 42113, // Contains areas with missing data
+    // Photoshop layers and their composite
+    ImageSourceData = 37724,
+    // TIFF/IT (ISO 12639)
+    Site = 34016,
+    ColorSequence = 34017,
+    IT8Header = 34018,
+    RasterPadding = 34019,
+    BitsPerRunLength = 34020,
+    BitsPerExtendedRunLength = 34021,
+    ColorTable = 34022,
+    ImageColorIndicator = 34023,
+    BackgroundColorIndicator = 34024,
+    ImageColorValue = 34025,
+    BackgroundColorValue = 34026,
+    PixelIntensityRange = 34027,
+    TransparencyIndicator = 34028,
+    ColorCharacterization = 34029,
+    HCUsage = 34030,
+    TrapIndicator = 34031,
+    CMYKEquivalent = 34032,
 }
 }
 
 tags! {
 /// The type of an IFD entry (a 2 byte field).
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum Type(u16) {
     /// 8-bit unsigned integer
     BYTE = 1,
@@ -184,9 +232,25 @@ pub enum CompressionMethod(u16) unknown("A custom compression method") {
     Deflate = 8,
     OldDeflate = 0x80B2,
     PackBits = 0x8005,
+    // Run-length encodings of early scanners, decoded with the `legacy-compressions` feature
+    NeXT = 0x7FFE,
+    ThunderScan = 0x8029,
+    // SGI log luminance encodings of high dynamic range images
+    SGILog = 0x8774,
+    SGILog24 = 0x8775,
+    // Decoded with the `lzma` feature
+    LZMA = 0x886D,
+    // Limited Error Raster Compression of ESRI, not supported for decoding
+    LERC = 0x8847,
+    // TIFF/IT (ISO 12639), not supported for decoding
+    IT8CTPAD = 0x807F,
+    IT8LW = 0x8080,
+    IT8MP = 0x8081,
+    IT8BL = 0x8082,
 
     // Self-assigned by libtiff
     ZSTD = 0xC350,
+    WebP = 0xC351,
 }
 }
 
@@ -200,6 +264,8 @@ pub enum PhotometricInterpretation(u16) {
     CMYK = 5,
     YCbCr = 6,
     CIELab = 8,
+    LogL = 0x804C,
+    LogLuv = 0x804D,
 }
 }
 
@@ -219,7 +285,8 @@ pub enum Predictor(u16) {
     /// This means that instead of having in order `[r1, g1. b1, r2, g2 ...]` you will find
     /// `[r1, g1, b1, r2-r1, g2-g1, b2-b1, r3-r2, g3-g2, ...]`
     Horizontal = 2,
-    /// Not currently supported
+    /// The bytes of each row of floating point samples were grouped by significance and then
+    /// differenced like [`Predictor::Horizontal`], see TIFF Technical Note 3.
     FloatingPoint = 3,
 }
 }
@@ -241,3 +308,265 @@ pub enum SampleFormat(u16) unknown("An unknown extension sample format") {
     Void = 4,
 }
 }
+
+tags! {
+/// The meaning of a sample beyond those of the photometric interpretation, see `ExtraSamples`.
+pub enum ExtraSample(u16) unknown("A value not defined by the specification") {
+    Unspecified = 0,
+    /// Opacity, with the color samples premultiplied by it.
+    AssociatedAlpha = 1,
+    /// Opacity, independent of the color samples.
+    UnassociatedAlpha = 2,
+}
+}
+
+tags! {
+/// The keys of the `GeoKeyDirectoryTag` of GeoTIFF, named without their `GeoKey` suffix.
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum GeoKey(u16) unknown("A private or extension key") {
+    // GeoTIFF configuration keys
+    GTModelType = 1024,
+    GTRasterType = 1025,
+    GTCitation = 1026,
+    // Geographic coordinate system keys
+    GeographicType = 2048,
+    GeogCitation = 2049,
+    GeogGeodeticDatum = 2050,
+    GeogPrimeMeridian = 2051,
+    GeogLinearUnits = 2052,
+    GeogLinearUnitSize = 2053,
+    GeogAngularUnits = 2054,
+    GeogAngularUnitSize = 2055,
+    GeogEllipsoid = 2056,
+    GeogSemiMajorAxis = 2057,
+    GeogSemiMinorAxis = 2058,
+    GeogInvFlattening = 2059,
+    GeogAzimuthUnits = 2060,
+    GeogPrimeMeridianLong = 2061,
+    GeogTOWGS84 = 2062,
+    // Projected coordinate system keys
+    ProjectedCSType = 3072,
+    PCSCitation = 3073,
+    Projection = 3074,
+    ProjCoordTrans = 3075,
+    ProjLinearUnits = 3076,
+    ProjLinearUnitSize = 3077,
+    ProjStdParallel1 = 3078,
+    ProjStdParallel2 = 3079,
+    ProjNatOriginLong = 3080,
+    ProjNatOriginLat = 3081,
+    ProjFalseEasting = 3082,
+    ProjFalseNorthing = 3083,
+    ProjFalseOriginLong = 3084,
+    ProjFalseOriginLat = 3085,
+    ProjFalseOriginEasting = 3086,
+    ProjFalseOriginNorthing = 3087,
+    ProjCenterLong = 3088,
+    ProjCenterLat = 3089,
+    ProjCenterEasting = 3090,
+    ProjCenterNorthing = 3091,
+    ProjScaleAtNatOrigin = 3092,
+    ProjScaleAtCenter = 3093,
+    ProjAzimuthAngle = 3094,
+    ProjStraightVertPoleLong = 3095,
+    // Vertical coordinate system keys
+    VerticalCSType = 4096,
+    VerticalCitation = 4097,
+    VerticalDatum = 4098,
+    VerticalUnits = 4099,
+}
+}
+
+tags! {
+/// The tags of the EXIF private directory, which the `ExifDirectory` tag points to.
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum ExifTag(u16) unknown("A private or extension tag") {
+    // Versions
+    ExifVersion = 0x9000,
+    FlashpixVersion = 0xA000,
+    // Image data characteristics
+    ColorSpace = 0xA001,
+    Gamma = 0xA500,
+    // Image configuration
+    ComponentsConfiguration = 0x9101,
+    CompressedBitsPerPixel = 0x9102,
+    PixelXDimension = 0xA002,
+    PixelYDimension = 0xA003,
+    // User information
+    MakerNote = 0x927C,
+    UserComment = 0x9286,
+    // Related files
+    RelatedSoundFile = 0xA004,
+    // Date and time
+    DateTimeOriginal = 0x9003,
+    DateTimeDigitized = 0x9004,
+    OffsetTime = 0x9010,
+    OffsetTimeOriginal = 0x9011,
+    OffsetTimeDigitized = 0x9012,
+    SubSecTime = 0x9290,
+    SubSecTimeOriginal = 0x9291,
+    SubSecTimeDigitized = 0x9292,
+    // Picture taking conditions
+    ExposureTime = 0x829A,
+    FNumber = 0x829D,
+    ExposureProgram = 0x8822,
+    SpectralSensitivity = 0x8824,
+    PhotographicSensitivity = 0x8827,
+    OECF = 0x8828,
+    SensitivityType = 0x8830,
+    StandardOutputSensitivity = 0x8831,
+    RecommendedExposureIndex = 0x8832,
+    ISOSpeed = 0x8833,
+    ShutterSpeedValue = 0x9201,
+    ApertureValue = 0x9202,
+    BrightnessValue = 0x9203,
+    ExposureBiasValue = 0x9204,
+    MaxApertureValue = 0x9205,
+    SubjectDistance = 0x9206,
+    MeteringMode = 0x9207,
+    LightSource = 0x9208,
+    Flash = 0x9209,
+    FocalLength = 0x920A,
+    SubjectArea = 0x9214,
+    FlashEnergy = 0xA20B,
+    SpatialFrequencyResponse = 0xA20C,
+    FocalPlaneXResolution = 0xA20E,
+    FocalPlaneYResolution = 0xA20F,
+    FocalPlaneResolutionUnit = 0xA210,
+    SubjectLocation = 0xA214,
+    ExposureIndex = 0xA215,
+    SensingMethod = 0xA217,
+    FileSource = 0xA300,
+    SceneType = 0xA301,
+    CFAPattern = 0xA302,
+    CustomRendered = 0xA401,
+    ExposureMode = 0xA402,
+    WhiteBalance = 0xA403,
+    DigitalZoomRatio = 0xA404,
+    FocalLengthIn35mmFilm = 0xA405,
+    SceneCaptureType = 0xA406,
+    GainControl = 0xA407,
+    Contrast = 0xA408,
+    Saturation = 0xA409,
+    Sharpness = 0xA40A,
+    DeviceSettingDescription = 0xA40B,
+    SubjectDistanceRange = 0xA40C,
+    // Other
+    ImageUniqueID = 0xA420,
+    CameraOwnerName = 0xA430,
+    BodySerialNumber = 0xA431,
+    LensSpecification = 0xA432,
+    LensMake = 0xA433,
+    LensModel = 0xA434,
+    LensSerialNumber = 0xA435,
+    // Pointer to the interoperability directory
+    InteropDirectory = 0xA005,
+}
+}
+
+tags! {
+/// The tags of the EXIF GPS directory, which the `GpsDirectory` tag points to, named without
+/// their `GPS` prefix.
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum GpsTag(u16) unknown("A private or extension tag") {
+    VersionID = 0,
+    LatitudeRef = 1,
+    Latitude = 2,
+    LongitudeRef = 3,
+    Longitude = 4,
+    AltitudeRef = 5,
+    Altitude = 6,
+    TimeStamp = 7,
+    Satellites = 8,
+    Status = 9,
+    MeasureMode = 10,
+    DOP = 11,
+    SpeedRef = 12,
+    Speed = 13,
+    TrackRef = 14,
+    Track = 15,
+    ImgDirectionRef = 16,
+    ImgDirection = 17,
+    MapDatum = 18,
+    DestLatitudeRef = 19,
+    DestLatitude = 20,
+    DestLongitudeRef = 21,
+    DestLongitude = 22,
+    DestBearingRef = 23,
+    DestBearing = 24,
+    DestDistanceRef = 25,
+    DestDistance = 26,
+    ProcessingMethod = 27,
+    AreaInformation = 28,
+    DateStamp = 29,
+    Differential = 30,
+    HPositioningError = 31,
+}
+}
+
+/// The kind of image flagged by the tag `NewSubfileType`, a set of bit flags.
+///
+/// Flags unknown to this type are kept, so that reading and writing the value back does not
+/// change it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct NewSubfileType(u32);
+
+impl NewSubfileType {
+    /// A reduced resolution version of another image in the file, such as a thumbnail or a level
+    /// of a pyramid.
+    pub const REDUCED_RESOLUTION: Self = NewSubfileType(1);
+    /// A single page of a multipage image.
+    pub const PAGE: Self = NewSubfileType(2);
+    /// A transparency mask for another image in the file.
+    pub const MASK: Self = NewSubfileType(4);
+
+    /// A full resolution image, without any flag set.
+    pub const fn empty() -> Self {
+        NewSubfileType(0)
+    }
+
+    /// Create the flags from the value of the tag.
+    pub const fn from_bits(bits: u32) -> Self {
+        NewSubfileType(bits)
+    }
+
+    /// The value of the tag.
+    pub const fn bits(self) -> u32 {
+        self.0
+    }
+
+    /// Whether no flag is set.
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// Whether all flags of `other` are set.
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Set the flags of `other`.
+    pub fn insert(&mut self, other: Self) {
+        self.0 |= other.0;
+    }
+
+    /// Clear the flags of `other`.
+    pub fn remove(&mut self, other: Self) {
+        self.0 &= !other.0;
+    }
+}
+
+impl std::ops::BitOr for NewSubfileType {
+    type Output = Self;
+
+    fn bitor(self, other: Self) -> Self {
+        NewSubfileType(self.0 | other.0)
+    }
+}
+
+impl std::ops::BitOrAssign for NewSubfileType {
+    fn bitor_assign(&mut self, other: Self) {
+        self.insert(other);
+    }
+}
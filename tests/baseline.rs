@@ -0,0 +1,57 @@
+#![cfg(feature = "baseline-tables")]
+extern crate tiff;
+
+use std::io::Cursor;
+
+use tiff::baseline::{self, ImageClass};
+use tiff::decoder::Decoder;
+use tiff::encoder::{colortype, TiffEncoder};
+use tiff::tags::Tag;
+
+#[test]
+fn encoded_images_contain_required_tags() {
+    let mut file = Cursor::new(Vec::new());
+    {
+        let mut tiff = TiffEncoder::new(&mut file).unwrap();
+        tiff.write_image::<colortype::Gray8>(2, 2, &[0; 4]).unwrap();
+        tiff.write_image::<colortype::RGB8>(2, 2, &[0; 12]).unwrap();
+    }
+
+    file.set_position(0);
+    let mut decoder = Decoder::new(&mut file).unwrap();
+    for class in [ImageClass::Grayscale, ImageClass::Rgb] {
+        for requirement in class.required_tags() {
+            if requirement.default.is_none() {
+                assert!(
+                    decoder.find_tag(requirement.tag).unwrap().is_some(),
+                    "{:?} image lacks {:?}",
+                    class,
+                    requirement.tag
+                );
+            }
+        }
+        if decoder.more_images() {
+            decoder.next_image().unwrap();
+        }
+    }
+}
+
+#[test]
+fn default_values() {
+    assert_eq!(baseline::default_value(Tag::Compression), Some(1));
+    assert_eq!(baseline::default_value(Tag::SamplesPerPixel), Some(1));
+    assert_eq!(baseline::default_value(Tag::BitsPerSample), Some(1));
+    assert_eq!(baseline::default_value(Tag::PlanarConfiguration), Some(1));
+    assert_eq!(baseline::default_value(Tag::ImageWidth), None);
+    assert!(baseline::PALETTE
+        .iter()
+        .any(|requirement| requirement.tag == Tag::ColorMap));
+
+    // RGB images have at least 3 samples of 8 bits, so the field defaults do not apply.
+    for tag in [Tag::BitsPerSample, Tag::SamplesPerPixel] {
+        assert!(ImageClass::Rgb
+            .required_tags()
+            .iter()
+            .any(|requirement| requirement.tag == tag && requirement.default.is_none()));
+    }
+}
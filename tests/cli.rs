@@ -0,0 +1,191 @@
+#![cfg(feature = "cli")]
+extern crate tiff;
+
+use tiff::decoder::{ChunkType, Decoder, SampleConversion};
+use tiff::tags::{CompressionMethod, Predictor, Tag};
+use tiff::ColorType;
+
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+const TEST_IMAGE_DIR: &str = "./tests/images/";
+
+fn tiff_cli(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_tiff-cli"))
+        .args(args)
+        .output()
+        .expect("Cannot run tiff-cli!")
+}
+
+fn image_path(file: &str) -> String {
+    PathBuf::from(TEST_IMAGE_DIR)
+        .join(file)
+        .to_string_lossy()
+        .into_owned()
+}
+
+fn output_path(file: &str) -> String {
+    Path::new(env!("CARGO_TARGET_TMPDIR"))
+        .join(file)
+        .to_string_lossy()
+        .into_owned()
+}
+
+fn decode(path: &str) -> (ColorType, Vec<f64>) {
+    let mut decoder = Decoder::new(File::open(path).unwrap()).unwrap();
+    let color_type = decoder.colortype().unwrap();
+    let samples = decoder
+        .read_image()
+        .unwrap()
+        .convert_to(SampleConversion::Saturate);
+    (color_type, samples)
+}
+
+#[test]
+fn test_inspect() {
+    let path = image_path("tiled-rgb-u8.tif");
+    let output = tiff_cli(&["inspect", &path]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("image 0: 374x499 RGB(8), LZW compression, Horizontal predictor"));
+    assert!(stdout.contains("192 tiles of 32x32"));
+    assert!(!stdout.contains("ImageWidth"));
+
+    let output = tiff_cli(&["inspect", "--tags", &image_path("geo-5b.tif")]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("georeferenced: 7 keys, 1 tiepoints"));
+    let tags: Vec<_> = stdout
+        .lines()
+        .skip_while(|&line| line != "  tags:")
+        .skip(1)
+        .collect();
+    assert_eq!(tags[0], "    ImageWidth: Unsigned(10)");
+    assert!(tags.contains(
+        &"    GeoAsciiParamsTag: Ascii(\"Bogota 1975 / Colombia Bogota zone|Bogota 1975|\")"
+    ));
+}
+
+#[test]
+fn test_validate() {
+    let valid = [image_path("rgb-3c-8b.tiff"), image_path("tiled-rgb-u8.tif")];
+    let output = tiff_cli(&["validate", &valid[0], &valid[1]]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(
+        stdout.lines().filter(|line| line.ends_with(": ok")).count(),
+        2
+    );
+
+    // The chunks beyond the end of a truncated file are reported.
+    let truncated = output_path("cli-truncated.tif");
+    let data = fs::read(image_path("int16.tif")).unwrap();
+    fs::write(&truncated, &data[..data.len() / 2]).unwrap();
+    let output = tiff_cli(&["validate", &valid[0], &truncated]);
+    assert_eq!(output.status.code(), Some(1));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("rgb-3c-8b.tiff: ok"));
+    assert!(stdout.contains("of the chunk 0 lies beyond the end of the file"));
+}
+
+#[test]
+fn test_convert_strips_to_tiles() {
+    let input = image_path("planar-rgb-u8.tif");
+    let output = output_path("cli-tiles.tif");
+    let status = tiff_cli(&[
+        "convert",
+        "--compression",
+        "deflate",
+        "--tile",
+        "64",
+        &input,
+        &output,
+    ])
+    .status;
+    assert!(status.success());
+
+    assert_eq!(decode(&output), decode(&input));
+    let mut decoder = Decoder::new(File::open(&output).unwrap()).unwrap();
+    let layout = decoder.chunk_layout().unwrap();
+    assert_eq!(layout.chunk_type, ChunkType::Tile);
+    assert_eq!((layout.chunk_width, layout.chunk_height), (64, 64));
+    assert_eq!(layout.planes, 1);
+    let compression = decoder.get_tag_u32(Tag::Compression).unwrap() as u16;
+    assert_eq!(compression, CompressionMethod::Deflate.to_u16());
+}
+
+#[test]
+fn test_convert_with_predictor() {
+    let input = image_path("gradient-1c-32b-float.tiff");
+    let output = output_path("cli-predictor.tif");
+    let status = tiff_cli(&[
+        "convert",
+        "--compression",
+        "lzw",
+        "--predictor",
+        "float",
+        "--bigtiff",
+        &input,
+        &output,
+    ])
+    .status;
+    assert!(status.success());
+
+    assert_eq!(decode(&output), decode(&input));
+    let mut decoder = Decoder::new(File::open(&output).unwrap()).unwrap();
+    assert!(decoder.is_bigtiff());
+    let predictor = decoder.get_tag_u32(Tag::Predictor).unwrap() as u16;
+    assert_eq!(predictor, Predictor::FloatingPoint.to_u16());
+}
+
+#[test]
+fn test_convert_white_is_zero() {
+    use tiff::encoder::{colortype, TiffEncoder};
+    use tiff::tags::PhotometricInterpretation;
+
+    let input = output_path("cli-white-is-zero-in.tif");
+    let output = output_path("cli-white-is-zero-out.tif");
+    let page: Vec<u8> = (0..64).map(|i| (i * 4) as u8).collect();
+    {
+        let mut tiff = TiffEncoder::new(File::create(&input).unwrap()).unwrap();
+        let mut image = tiff.new_image::<colortype::Gray8>(8, 8).unwrap();
+        image
+            .photometric_interpretation(PhotometricInterpretation::WhiteIsZero)
+            .unwrap();
+        image.write_data(&page).unwrap();
+    }
+
+    let status = tiff_cli(&["convert", &input, &output]).status;
+    assert!(status.success());
+
+    // The decoded samples are inverted, so the converted image is written as BlackIsZero.
+    assert_eq!(decode(&output), decode(&input));
+    let mut decoder = Decoder::new(File::open(&output).unwrap()).unwrap();
+    let photometric = decoder.get_tag_u32(Tag::PhotometricInterpretation).unwrap() as u16;
+    assert_eq!(photometric, PhotometricInterpretation::BlackIsZero.to_u16());
+}
+
+#[test]
+fn test_usage_errors() {
+    let input = image_path("rgb-3c-8b.tiff");
+    let output = output_path("cli-usage.tif");
+    for args in [
+        &["frobnicate", &input][..],
+        &["inspect"],
+        &["inspect", "--size", &input],
+        &["convert", &input],
+        &["convert", "--compression", "jpeg", &input, &output],
+        &[
+            "convert",
+            "--tile",
+            "64",
+            "--predictor",
+            "horizontal",
+            &input,
+            &output,
+        ],
+    ] {
+        assert_eq!(tiff_cli(args).status.code(), Some(2), "{:?}", args);
+    }
+}
@@ -44,3 +44,22 @@ fn test_big_tiff() {
         )
     }
 }
+
+#[test]
+fn test_header_format() {
+    use tiff::decoder::ByteOrder;
+
+    let files = [
+        ("bigtiff/BigTIFF.tif", ByteOrder::LittleEndian, true),
+        ("bigtiff/BigTIFFMotorola.tif", ByteOrder::BigEndian, true),
+        ("rgb-3c-8b.tiff", ByteOrder::BigEndian, false),
+    ];
+    for (filename, byte_order, bigtiff) in files {
+        let path = PathBuf::from("./tests/images").join(filename);
+        let img_file = File::open(path).expect("Cannot find test image!");
+        let decoder = Decoder::new(img_file).expect("Cannot create decoder");
+        assert_eq!(decoder.byte_order(), byte_order);
+        assert_eq!(decoder.is_bigtiff(), bigtiff);
+        assert_eq!(decoder.header_version(), if bigtiff { 43 } else { 42 });
+    }
+}
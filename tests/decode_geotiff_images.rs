@@ -1,10 +1,13 @@
 extern crate tiff;
 
 use tiff::decoder::{Decoder, DecodingResult};
-use tiff::tags::Tag;
-use tiff::ColorType;
+use tiff::encoder::{colortype, TiffEncoder};
+use tiff::geotiff::{GeoKeyValue, Tiepoint};
+use tiff::tags::{GeoKey, Tag};
+use tiff::{ColorType, TiffError, TiffFormatError};
 
 use std::fs::File;
+use std::io::Cursor;
 use std::path::PathBuf;
 
 const TEST_IMAGE_DIR: &str = "./tests/images";
@@ -61,3 +64,111 @@ fn test_geo_tiff() {
         assert_eq!(data.len(), 500);
     }
 }
+
+#[test]
+fn test_geo_info() {
+    let path = PathBuf::from(TEST_IMAGE_DIR).join("geo-5b.tif");
+    let mut decoder = Decoder::new(File::open(path).unwrap()).unwrap();
+    let info = decoder.geo_info().unwrap().expect("Image is georeferenced");
+
+    assert_eq!(info.version, [1, 1, 0]);
+    assert_eq!(info.keys.len(), 7);
+    assert_eq!(info.key(GeoKey::GTModelType), Some(&GeoKeyValue::Short(1)));
+    assert_eq!(
+        info.key(GeoKey::ProjectedCSType)
+            .and_then(GeoKeyValue::as_short),
+        Some(21897)
+    );
+    assert_eq!(
+        info.key(GeoKey::GTCitation).and_then(GeoKeyValue::as_str),
+        Some("Bogota 1975 / Colombia Bogota zone")
+    );
+    assert_eq!(
+        info.key(GeoKey::GeogCitation).and_then(GeoKeyValue::as_str),
+        Some("Bogota 1975")
+    );
+    assert_eq!(info.pixel_scale, Some([60.0, 60.0, 0.0]));
+    assert_eq!(
+        info.tiepoints,
+        [Tiepoint {
+            raster: [0.0, 0.0, 0.0],
+            model: [440720.0, 100000.0, 0.0],
+        }]
+    );
+    assert_eq!(info.transformation, None);
+}
+
+#[test]
+fn test_geo_info_params() {
+    let transformation = [
+        2.0, 0.0, 0.0, 100.0, 0.0, -2.0, 0.0, 200.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0,
+    ];
+    let write = |directory: &[u16]| {
+        let mut data = Cursor::new(Vec::new());
+        let mut tiff = TiffEncoder::new(&mut data).unwrap();
+        let mut image = tiff.new_image::<colortype::Gray8>(1, 1).unwrap();
+        let encoder = image.encoder();
+        encoder
+            .write_tag(Tag::GeoKeyDirectoryTag, directory)
+            .unwrap();
+        encoder
+            .write_tag(Tag::GeoDoubleParamsTag, &[6378137.0, 298.257223563][..])
+            .unwrap();
+        encoder
+            .write_tag(Tag::GeoAsciiParamsTag, "WGS 84|")
+            .unwrap();
+        encoder
+            .write_tag(Tag::ModelTransformationTag, &transformation[..])
+            .unwrap();
+        image.write_data(&[0]).unwrap();
+        data.set_position(0);
+        Decoder::new(data).unwrap().geo_info()
+    };
+
+    #[rustfmt::skip]
+    let directory = [
+        1, 1, 1, 4,
+        1024, 0, 1, 2,
+        2049, 34737, 7, 0,
+        2057, 34736, 1, 0,
+        2062, 34735, 2, 20,
+        7, 8,
+    ];
+    let info = write(&directory).unwrap().unwrap();
+    assert_eq!(info.version, [1, 1, 1]);
+    assert_eq!(
+        info.keys,
+        [
+            (GeoKey::GTModelType, GeoKeyValue::Short(2)),
+            (GeoKey::GeogCitation, GeoKeyValue::Ascii("WGS 84".into())),
+            (
+                GeoKey::GeogSemiMajorAxis,
+                GeoKeyValue::Doubles(vec![6378137.0])
+            ),
+            (GeoKey::GeogTOWGS84, GeoKeyValue::Shorts(vec![7, 8])),
+        ]
+    );
+    assert_eq!(info.transformation, Some(transformation));
+    assert_eq!(info.pixel_scale, None);
+    assert!(info.tiepoints.is_empty());
+
+    // The key refers to a value beyond the end of the doubles.
+    #[rustfmt::skip]
+    let directory = [
+        1, 1, 1, 1,
+        2057, 34736, 1, 2,
+    ];
+    assert!(matches!(
+        write(&directory),
+        Err(TiffError::FormatError(
+            TiffFormatError::InvalidGeoKeyDirectory
+        ))
+    ));
+}
+
+#[test]
+fn test_geo_info_missing() {
+    let path = PathBuf::from(TEST_IMAGE_DIR).join("rgb-3c-8b.tiff");
+    let mut decoder = Decoder::new(File::open(path).unwrap()).unwrap();
+    assert_eq!(decoder.geo_info().unwrap(), None);
+}
@@ -1,6 +1,6 @@
 extern crate tiff;
 
-use tiff::decoder::{ifd, Decoder, DecodingResult};
+use tiff::decoder::{ifd, ChunkIndex, Decoder, DecodingResult};
 use tiff::ColorType;
 
 use std::fs::File;
@@ -53,6 +53,98 @@ fn test_image_color_type_unsupported(file: &str, expected_type: ColorType) {
     });
 }
 
+/// An entry of a directory built by [`build_tiff`] as `(tag, type, count, value)`.
+type IfdEntry<'a> = (u16, u16, u32, &'a [u8]);
+
+/// Builds a little-endian file of a single directory at offset 8, followed by `data`.
+///
+/// Values of up to 4 bytes are stored in the entry, longer ones right after the directory in
+/// the order of the entries. The directory ends at `14 + 12 * entries.len()`.
+fn build_tiff(entries: &[IfdEntry], data: &[u8]) -> Vec<u8> {
+    let ifd_end = 14 + 12 * entries.len();
+    let mut file = b"II\x2a\x00\x08\x00\x00\x00".to_vec();
+    let mut values = Vec::new();
+    file.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+    for &(tag, type_, count, value) in entries {
+        file.extend_from_slice(&tag.to_le_bytes());
+        file.extend_from_slice(&type_.to_le_bytes());
+        file.extend_from_slice(&count.to_le_bytes());
+        if value.len() <= 4 {
+            let mut field = [0; 4];
+            field[..value.len()].copy_from_slice(value);
+            file.extend_from_slice(&field);
+        } else {
+            let offset = (ifd_end + values.len()) as u32;
+            file.extend_from_slice(&offset.to_le_bytes());
+            values.extend_from_slice(value);
+        }
+    }
+    file.extend_from_slice(&0u32.to_le_bytes());
+    file.extend_from_slice(&values);
+    file.extend_from_slice(data);
+    file
+}
+
+#[test]
+fn test_palette() {
+    use tiff::decoder::SampleConversion;
+    use tiff::{TiffError, TiffFormatError};
+
+    for (file, bits) in [
+        ("palette-1c-1b.tiff", 1),
+        ("palette-1c-4b.tiff", 4),
+        ("palette-1c-8b.tiff", 8),
+    ] {
+        let path = PathBuf::from(TEST_IMAGE_DIR).join(file);
+        let mut decoder = Decoder::new(File::open(path).unwrap()).unwrap();
+        assert_eq!(decoder.colortype().unwrap(), ColorType::Palette(bits));
+        let palette = decoder.palette().unwrap().unwrap();
+        assert_eq!(palette.len(), 1 << bits, "{}", file);
+
+        let (width, height) = decoder.dimensions().unwrap();
+        let indices = match decoder
+            .read_image_unpacked(SampleConversion::Saturate)
+            .unwrap()
+        {
+            DecodingResult::U8(indices) => indices,
+            _ => panic!("Wrong data type"),
+        };
+        assert_eq!(indices.len(), (width * height) as usize);
+        assert!(indices
+            .iter()
+            .all(|&index| usize::from(index) < palette.len()));
+    }
+
+    // Other images have no palette.
+    let path = PathBuf::from(TEST_IMAGE_DIR).join("minisblack-1c-8b.tiff");
+    let mut decoder = Decoder::new(File::open(path).unwrap()).unwrap();
+    assert_eq!(decoder.palette().unwrap(), None);
+
+    // A map of 2 instead of 2**1 colors does not match the bits per sample.
+    let color_map: Vec<u8> = [0u16, 1, 2, 3]
+        .iter()
+        .flat_map(|v| v.to_le_bytes())
+        .collect();
+    let file = build_tiff(
+        &[
+            (256, 3, 1, &1u16.to_le_bytes()),
+            (257, 3, 1, &1u16.to_le_bytes()),
+            (258, 3, 1, &1u16.to_le_bytes()),
+            (262, 3, 1, &3u16.to_le_bytes()),
+            (273, 4, 1, &118u32.to_le_bytes()),
+            (278, 3, 1, &1u16.to_le_bytes()),
+            (279, 4, 1, &1u32.to_le_bytes()),
+            (320, 3, 4, &color_map),
+        ],
+        &[0],
+    );
+    let mut decoder = Decoder::new(std::io::Cursor::new(file)).unwrap();
+    match decoder.palette() {
+        Err(TiffError::FormatError(TiffFormatError::InconsistentSizesEncountered)) => {}
+        other => panic!("Mismatched color map was not rejected: {:?}", other),
+    }
+}
+
 #[test]
 fn test_cmyk_u8() {
     test_image_sum_u8("cmyk-3c-8b.tiff", ColorType::CMYK(8), 8522658);
@@ -205,16 +297,15 @@ fn issue_69() {
     test_image_sum_u16("issue_69_packbits.tiff", ColorType::Gray(16), 1015486);
 }
 
-// TODO: GrayA support
-//#[test]
-//fn test_gray_alpha_u8()
-//{
-//let img_file = File::open("./tests/images/minisblack-2c-8b-alpha.tiff").expect("Cannot find test image!");
-//let mut decoder = Decoder::new(img_file).expect("Cannot create decoder");
-//assert_eq!(decoder.colortype().unwrap(), ColorType::GrayA(8));
-//let img_res = decoder.read_image();
-//assert!(img_res.is_ok());
-//}
+#[test]
+fn test_gray_alpha_u8() {
+    let img_file =
+        File::open("./tests/images/minisblack-2c-8b-alpha.tiff").expect("Cannot find test image!");
+    let mut decoder = Decoder::new(img_file).expect("Cannot create decoder");
+    assert_eq!(decoder.colortype().unwrap(), ColorType::GrayA(8));
+    let img_res = decoder.read_image();
+    assert!(img_res.is_ok());
+}
 
 #[test]
 fn test_tiled_gray_i1() {
@@ -280,7 +371,7 @@ fn test_tiled_incremental() {
     assert_eq!(tiles as usize, sums.len());
 
     for tile in 0..tiles {
-        match decoder.read_chunk(tile).unwrap() {
+        match decoder.read_chunk_at(ChunkIndex(tile)).unwrap() {
             DecodingResult::U8(res) => {
                 let sum: u64 = res.into_iter().map(<u64>::from).sum();
                 assert_eq!(sum, sums[tile as usize]);
@@ -308,28 +399,69 @@ fn test_planar_rgb_u8() {
     // 0,0: (73,51,30)  #49331E  srgb(73,51,30)
 
     // 1st band (red)
-    match decoder.read_chunk(0).unwrap() {
+    match decoder.read_chunk_at(ChunkIndex(0)).unwrap() {
         DecodingResult::U8(chunk) => {
             assert_eq!(chunk[0], 73);
         }
         _ => panic!("Wrong bit depth"),
     }
     // 2nd band (green)
-    match decoder.read_chunk(chunks / 3).unwrap() {
+    match decoder.read_chunk_at(ChunkIndex(chunks / 3)).unwrap() {
         DecodingResult::U8(chunk) => {
             assert_eq!(chunk[0], 51);
         }
         _ => panic!("Wrong bit depth"),
     }
     // 3rd band (blue)
-    match decoder.read_chunk(chunks / 3 * 2).unwrap() {
+    match decoder.read_chunk_at(ChunkIndex(chunks / 3 * 2)).unwrap() {
         DecodingResult::U8(chunk) => {
             assert_eq!(chunk[0], 30);
         }
         _ => panic!("Wrong bit depth"),
     }
 
-    test_image_sum_u8(file, ColorType::RGB(8), 15417630);
+    // The same pixels as the tiled, chunky image.
+    test_image_sum_u8(file, ColorType::RGB(8), 39528948);
+}
+
+#[test]
+fn test_read_plane() {
+    use tiff::decoder::PlaneIndex;
+    use tiff::{TiffError, UsageError};
+
+    let open = |file: &str| {
+        let path = PathBuf::from(TEST_IMAGE_DIR).join(file);
+        Decoder::new(File::open(path).unwrap()).unwrap()
+    };
+
+    // Both files hold the same pixels, once tiled and chunky, once in planar strips.
+    let mut decoder = open("tiled-rgb-u8.tif");
+    let chunky = match decoder.read_image().unwrap() {
+        DecodingResult::U8(data) => data,
+        _ => panic!("Wrong data type"),
+    };
+    match decoder.read_plane(PlaneIndex(0)).unwrap() {
+        DecodingResult::U8(data) => assert_eq!(data, chunky),
+        _ => panic!("Wrong data type"),
+    }
+    assert!(matches!(
+        decoder.read_plane(PlaneIndex(1)),
+        Err(TiffError::UsageError(UsageError::InvalidPlaneIndex(1)))
+    ));
+
+    let mut decoder = open("planar-rgb-u8.tif");
+    match decoder.read_image().unwrap() {
+        DecodingResult::U8(data) => assert_eq!(data, chunky),
+        _ => panic!("Wrong data type"),
+    }
+    for plane in 0..3 {
+        let expected: Vec<u8> = chunky.iter().skip(plane).step_by(3).copied().collect();
+        match decoder.read_plane(PlaneIndex(plane as u16)).unwrap() {
+            DecodingResult::U8(data) => assert_eq!(data, expected),
+            _ => panic!("Wrong data type"),
+        }
+    }
+    assert!(decoder.read_plane(PlaneIndex(3)).is_err());
 }
 
 #[test]
@@ -522,3 +654,2541 @@ fn test_zstd_compression() {
     // gdal_translate -co COMPRESS=ZSTD -co ZSTD_LEVEL=20 int16.tif int16_zstd.tif
     test_image_sum_i16("int16_zstd.tif", ColorType::Gray(16), 354396);
 }
+
+#[test]
+fn test_lerc_compression_unsupported() {
+    use std::io::Cursor;
+    use tiff::tags::CompressionMethod;
+    use tiff::{TiffError, TiffUnsupportedError};
+
+    // LERC is a known compression method, but its chunks are not decoded.
+    let file = build_tiff(
+        &[
+            (256, 3, 1, &1u16.to_le_bytes()),
+            (257, 3, 1, &1u16.to_le_bytes()),
+            (258, 3, 1, &8u16.to_le_bytes()),
+            (259, 3, 1, &CompressionMethod::LERC.to_u16().to_le_bytes()),
+            (262, 3, 1, &1u16.to_le_bytes()),
+            (273, 4, 1, &110u32.to_le_bytes()),
+            (278, 3, 1, &1u16.to_le_bytes()),
+            (279, 4, 1, &1u32.to_le_bytes()),
+        ],
+        &[0],
+    );
+    match Decoder::new(Cursor::new(&file)).unwrap().read_image() {
+        Err(TiffError::UnsupportedError(TiffUnsupportedError::UnsupportedCompressionMethod(
+            CompressionMethod::LERC,
+        ))) => {}
+        other => panic!("Unexpected result {:?}", other.map(|_| ())),
+    }
+}
+
+#[test]
+#[cfg(feature = "lzma")]
+fn test_lzma_compression() {
+    use std::io::Cursor;
+    use tiff::encoder::TiffEncoder;
+    use tiff::tags::{CompressionMethod, Tag};
+
+    // Two strips of 16-bit samples in xz streams, as written by libtiff.
+    let (width, height) = (24u32, 10u32);
+    let samples: Vec<u16> = (0..width * height).map(|i| (i * 269) as u16).collect();
+    let bytes: Vec<u8> = samples.iter().flat_map(|s| s.to_le_bytes()).collect();
+
+    let mut file = Cursor::new(Vec::new());
+    {
+        let mut tiff = TiffEncoder::new(&mut file).unwrap();
+        let mut dir = tiff.new_directory().unwrap();
+        let mut offsets = Vec::new();
+        let mut byte_counts = Vec::new();
+        for strip in bytes.chunks(width as usize * 2 * 6) {
+            let mut compressed = Vec::new();
+            lzma_rs::xz_compress(&mut &strip[..], &mut compressed).unwrap();
+            offsets.push(dir.write_data(&compressed[..]).unwrap() as u32);
+            byte_counts.push(compressed.len() as u32);
+        }
+
+        dir.write_tag(Tag::ImageWidth, width).unwrap();
+        dir.write_tag(Tag::ImageLength, height).unwrap();
+        dir.write_tag(Tag::BitsPerSample, 16u16).unwrap();
+        let compression = CompressionMethod::LZMA.to_u16();
+        dir.write_tag(Tag::Compression, compression).unwrap();
+        dir.write_tag(Tag::PhotometricInterpretation, 1u16).unwrap();
+        dir.write_tag(Tag::RowsPerStrip, 6u32).unwrap();
+        dir.write_tag(Tag::StripOffsets, &offsets[..]).unwrap();
+        dir.write_tag(Tag::StripByteCounts, &byte_counts[..])
+            .unwrap();
+        dir.finish().unwrap();
+    }
+
+    file.set_position(0);
+    let mut decoder = Decoder::new(&mut file).unwrap();
+    assert_eq!(decoder.colortype().unwrap(), ColorType::Gray(16));
+    match decoder.read_image().unwrap() {
+        DecodingResult::U16(data) => assert_eq!(data, samples),
+        _ => panic!("Wrong data type"),
+    }
+}
+
+#[test]
+#[cfg(feature = "webp")]
+fn test_webp_compression() {
+    use image_webp::{ColorType as WebPColor, WebPEncoder};
+    use std::io::Cursor;
+    use tiff::decoder::{DecoderOptions, Limits};
+    use tiff::encoder::TiffEncoder;
+    use tiff::tags::{CompressionMethod, PhotometricInterpretation, Tag};
+    use tiff::{TiffError, TiffFormatError};
+
+    // Two lossless tiles as written by GDAL, the second one with an alpha channel to be dropped.
+    const TILE: u32 = 16;
+    let pixel = |x: u32, y: u32| [(x * 8) as u8, (y * 16) as u8, ((x + y) * 4) as u8];
+
+    // The tiles are encoded with the given width, which the decoder checks against the tile.
+    let image = |tile_width: u32| {
+        let mut file = Cursor::new(Vec::new());
+        let mut tiff = TiffEncoder::new(&mut file).unwrap();
+        let mut dir = tiff.new_directory().unwrap();
+        let mut offsets = Vec::new();
+        let mut byte_counts = Vec::new();
+        for (left, color) in [(0, WebPColor::Rgb8), (TILE, WebPColor::Rgba8)] {
+            let mut samples = Vec::new();
+            for y in 0..TILE {
+                for x in left..left + tile_width {
+                    samples.extend_from_slice(&pixel(x, y));
+                    if color == WebPColor::Rgba8 {
+                        samples.push(0xff);
+                    }
+                }
+            }
+            let mut tile = Vec::new();
+            WebPEncoder::new(&mut tile)
+                .encode(&samples, tile_width, TILE, color)
+                .unwrap();
+            offsets.push(dir.write_data(&tile[..]).unwrap() as u32);
+            byte_counts.push(tile.len() as u32);
+        }
+
+        dir.write_tag(Tag::ImageWidth, 2 * TILE).unwrap();
+        dir.write_tag(Tag::ImageLength, TILE).unwrap();
+        dir.write_tag(Tag::BitsPerSample, &[8u16; 3][..]).unwrap();
+        let compression = CompressionMethod::WebP.to_u16();
+        dir.write_tag(Tag::Compression, compression).unwrap();
+        let photometric = PhotometricInterpretation::RGB.to_u16();
+        dir.write_tag(Tag::PhotometricInterpretation, photometric)
+            .unwrap();
+        dir.write_tag(Tag::SamplesPerPixel, 3u16).unwrap();
+        dir.write_tag(Tag::TileWidth, TILE).unwrap();
+        dir.write_tag(Tag::TileLength, TILE).unwrap();
+        dir.write_tag(Tag::TileOffsets, &offsets[..]).unwrap();
+        dir.write_tag(Tag::TileByteCounts, &byte_counts[..])
+            .unwrap();
+        dir.finish().unwrap();
+        file.into_inner()
+    };
+
+    let file = image(TILE);
+    let mut decoder = Decoder::new(Cursor::new(&file)).unwrap();
+    assert_eq!(decoder.colortype().unwrap(), ColorType::RGB(8));
+    let expected: Vec<u8> = (0..TILE)
+        .flat_map(|y| (0..2 * TILE).flat_map(move |x| pixel(x, y)))
+        .collect();
+    match decoder.read_image().unwrap() {
+        DecodingResult::U8(data) => assert_eq!(data, expected),
+        _ => panic!("Wrong data type"),
+    }
+
+    // The decoded tiles are bounded by the intermediate buffer limit.
+    let mut limits = Limits::default();
+    limits.intermediate_buffer_size = (TILE * TILE * 3 - 1) as usize;
+    let options = DecoderOptions::new().with_limits(limits);
+    let mut decoder = Decoder::with_options(Cursor::new(&file), options).unwrap();
+    match decoder.read_image() {
+        Err(TiffError::LimitsExceeded) => {}
+        _ => panic!("Decoded tile exceeded the limits"),
+    }
+
+    let file = image(2 * TILE);
+    let mut decoder = Decoder::new(Cursor::new(&file)).unwrap();
+    match decoder.read_image() {
+        Err(TiffError::FormatError(TiffFormatError::InconsistentSizesEncountered)) => {}
+        _ => panic!("Tile of the wrong size was decoded"),
+    }
+}
+
+#[test]
+fn test_validation_inconsistent_byte_order() {
+    use std::io::Cursor;
+    use tiff::encoder::{colortype, TiffEncoder};
+    use tiff::{TiffError, TiffFormatError};
+
+    let mut data = Cursor::new(Vec::new());
+    {
+        let mut encoder = TiffEncoder::new(&mut data).unwrap();
+        let image = [0u8; 4 * 4];
+        encoder
+            .write_image::<colortype::Gray8>(4, 4, &image)
+            .unwrap();
+        encoder
+            .write_image::<colortype::Gray8>(4, 4, &image)
+            .unwrap();
+    }
+    let mut file = data.into_inner();
+
+    // A clean file passes validation.
+    let mut decoder = Decoder::new(Cursor::new(&file))
+        .unwrap()
+        .with_validation()
+        .unwrap();
+    decoder.next_image().unwrap();
+
+    // Rewrite the second directory in the opposite byte order.
+    let le = &file[..2] == b"II";
+    let u16_at = |file: &[u8], pos: usize| {
+        let bytes = [file[pos], file[pos + 1]];
+        usize::from(if le {
+            u16::from_le_bytes(bytes)
+        } else {
+            u16::from_be_bytes(bytes)
+        })
+    };
+    let u32_at = |file: &[u8], pos: usize| {
+        let bytes = [file[pos], file[pos + 1], file[pos + 2], file[pos + 3]];
+        (if le {
+            u32::from_le_bytes(bytes)
+        } else {
+            u32::from_be_bytes(bytes)
+        }) as usize
+    };
+    let ifd0 = u32_at(&file, 4);
+    let ifd1 = u32_at(&file, ifd0 + 2 + 12 * u16_at(&file, ifd0));
+    let num_entries = u16_at(&file, ifd1);
+    file[ifd1..ifd1 + 2].reverse();
+    for entry in 0..num_entries {
+        let pos = ifd1 + 2 + 12 * entry;
+        file[pos..pos + 2].reverse();
+        file[pos + 2..pos + 4].reverse();
+        file[pos + 4..pos + 8].reverse();
+    }
+
+    let mut decoder = Decoder::new(Cursor::new(&file))
+        .unwrap()
+        .with_validation()
+        .unwrap();
+    match decoder.next_image() {
+        Err(TiffError::FormatError(TiffFormatError::InconsistentByteOrder)) => {}
+        other => panic!("Unexpected result {:?}", other.map(|_| ())),
+    }
+}
+
+#[test]
+fn test_skip_corrupt_chunks() {
+    use std::io::Cursor;
+    use tiff::encoder::{colortype, Compression, TiffEncoder};
+    use tiff::tags::Tag;
+
+    let image: Vec<u8> = (0..8 * 8).map(|i| i as u8 | 1).collect();
+    let mut data = Cursor::new(Vec::new());
+    {
+        let mut encoder = TiffEncoder::new(&mut data)
+            .unwrap()
+            .with_compression(Compression::Lzw);
+        let mut encoder = encoder.new_image::<colortype::Gray8>(8, 8).unwrap();
+        encoder.rows_per_strip(2).unwrap();
+        encoder.write_data(&image).unwrap();
+    }
+    let mut file = data.into_inner();
+
+    // Overwrite the second strip with an invalid LZW stream.
+    let (offsets, byte_counts) = {
+        let mut decoder = Decoder::new(Cursor::new(&file)).unwrap();
+        (
+            decoder.get_tag_u64_vec(Tag::StripOffsets).unwrap(),
+            decoder.get_tag_u64_vec(Tag::StripByteCounts).unwrap(),
+        )
+    };
+    let start = offsets[1] as usize;
+    file[start..start + byte_counts[1] as usize].fill(0xff);
+
+    let mut decoder = Decoder::new(Cursor::new(&file)).unwrap();
+    assert!(decoder.read_image().is_err());
+
+    let mut decoder = Decoder::new(Cursor::new(&file)).unwrap();
+    let (result, failed_chunks) = decoder.read_image_skipping_corrupt_chunks().unwrap();
+    assert_eq!(failed_chunks, [1]);
+    let result = match result {
+        DecodingResult::U8(result) => result,
+        _ => panic!("Wrong data type"),
+    };
+    assert_eq!(result[..16], image[..16]);
+    assert!(result[16..32].iter().all(|&b| b == 0));
+    assert_eq!(result[32..], image[32..]);
+}
+
+#[test]
+fn test_wrapped_strip_offset() {
+    use std::io::Cursor;
+    use tiff::encoder::{colortype, TiffEncoder};
+    use tiff::{TiffError, TiffFormatError};
+
+    let mut data = Cursor::new(Vec::new());
+    TiffEncoder::new(&mut data)
+        .unwrap()
+        .write_image::<colortype::Gray8>(4, 4, &[0; 16])
+        .unwrap();
+    let mut file = data.into_inner();
+
+    // Point the only strip right below 4 GiB, so that its end wraps around.
+    let le = &file[..2] == b"II";
+    let to_u16 = |bytes: &[u8]| {
+        let bytes = [bytes[0], bytes[1]];
+        if le {
+            u16::from_le_bytes(bytes)
+        } else {
+            u16::from_be_bytes(bytes)
+        }
+    };
+    let ifd = u32::from_le_bytes(file[4..8].try_into().unwrap());
+    let ifd = if le { ifd } else { ifd.swap_bytes() } as usize;
+    let entry = (0..to_u16(&file[ifd..]) as usize)
+        .map(|i| ifd + 2 + 12 * i)
+        .find(|&entry| to_u16(&file[entry..]) == 273)
+        .unwrap();
+    let offset = 0xffff_fff8u32;
+    let offset = if le {
+        offset.to_le_bytes()
+    } else {
+        offset.to_be_bytes()
+    };
+    file[entry + 8..entry + 12].copy_from_slice(&offset);
+
+    let mut decoder = Decoder::new(Cursor::new(&file)).unwrap();
+    match decoder.read_image() {
+        Err(TiffError::FormatError(TiffFormatError::OffsetBeyondClassicTiff(0xffff_fff8, 16))) => {}
+        other => panic!("Unexpected result {:?}", other.map(|_| ())),
+    }
+
+    match Decoder::new(Cursor::new(&file)).unwrap().with_validation() {
+        Err(TiffError::FormatError(TiffFormatError::OffsetBeyondClassicTiff(..))) => {}
+        other => panic!("Unexpected result {:?}", other.map(|_| ())),
+    }
+}
+
+#[test]
+fn test_convert_decoding_result() {
+    use tiff::decoder::SampleConversion;
+
+    let result = DecodingResult::U16(vec![0, 1, 255, 256, 65535]);
+    assert_eq!(
+        result.convert_to::<u8>(SampleConversion::Saturate),
+        [0, 1, 255, 255, 255]
+    );
+    assert_eq!(
+        result.convert_to::<u8>(SampleConversion::Scale),
+        [0, 0, 1, 1, 255]
+    );
+    assert_eq!(
+        result.convert_to::<i8>(SampleConversion::Scale),
+        [0, 0, 0, 0, 127]
+    );
+    assert_eq!(
+        result.convert_to::<f32>(SampleConversion::Scale),
+        [0.0, 1.0 / 65535.0, 255.0 / 65535.0, 256.0 / 65535.0, 1.0]
+    );
+
+    let result = DecodingResult::F32(vec![-1.5, 0.5, 2.0, f32::NAN]);
+    assert_eq!(
+        result.convert_to::<u8>(SampleConversion::Saturate),
+        [0, 0, 2, 0]
+    );
+    assert_eq!(
+        result.convert_to::<u8>(SampleConversion::Scale),
+        [0, 128, 255, 0]
+    );
+    assert_eq!(
+        result.convert_to::<i16>(SampleConversion::Scale),
+        [-32767, 16384, 32767, 0]
+    );
+
+    let result = DecodingResult::U64(vec![u64::MAX - 1]);
+    assert_eq!(
+        result.convert_to::<u64>(SampleConversion::Scale),
+        [u64::MAX - 1]
+    );
+    assert_eq!(
+        result.convert_to::<i64>(SampleConversion::Saturate),
+        [i64::MAX]
+    );
+}
+
+#[test]
+fn test_read_image_unpacked() {
+    use tiff::decoder::SampleConversion;
+
+    for (file, bits) in [("miniswhite-1c-1b.tiff", 1), ("tiled-gray-i1.tif", 1)] {
+        let path = PathBuf::from(TEST_IMAGE_DIR).join(file);
+        let mut decoder = Decoder::new(File::open(path).unwrap()).unwrap();
+        let (width, height) = decoder.dimensions().unwrap();
+        let packed = match decoder.read_image().unwrap() {
+            DecodingResult::U8(data) => data,
+            _ => panic!("Wrong data type"),
+        };
+
+        let row_bytes = (width as usize * bits + 7) / 8;
+        let mut expected = Vec::new();
+        for row in packed.chunks(row_bytes) {
+            for x in 0..width as usize {
+                let shift = 8 - bits - x * bits % 8;
+                expected.push(row[x * bits / 8] >> shift & ((1 << bits) - 1));
+            }
+        }
+        assert_eq!(expected.len(), (width * height) as usize);
+
+        match decoder
+            .read_image_unpacked(SampleConversion::Saturate)
+            .unwrap()
+        {
+            DecodingResult::U8(data) => assert_eq!(data, expected),
+            _ => panic!("Wrong data type"),
+        }
+
+        let max = (1 << bits) - 1;
+        let scaled: Vec<u8> = expected.iter().map(|&v| v * (255 / max)).collect();
+        match decoder
+            .read_image_unpacked(SampleConversion::Scale)
+            .unwrap()
+        {
+            DecodingResult::U8(data) => assert_eq!(data, scaled),
+            _ => panic!("Wrong data type"),
+        }
+    }
+
+    let path = PathBuf::from(TEST_IMAGE_DIR).join("minisblack-1c-8b.tiff");
+    let mut decoder = Decoder::new(File::open(path).unwrap()).unwrap();
+    let packed = decoder.read_image().unwrap();
+    let unpacked = decoder
+        .read_image_unpacked(SampleConversion::Scale)
+        .unwrap();
+    assert_eq!(format!("{:?}", unpacked), format!("{:?}", packed));
+}
+
+#[test]
+fn test_read_image_unpacked_12bit() {
+    use std::io::Cursor;
+    use tiff::decoder::SampleConversion;
+    use tiff::encoder::TiffEncoder;
+    use tiff::tags::{PhotometricInterpretation, SampleFormat};
+    use tiff::PixelFormat;
+
+    fn pack(samples: &[u16]) -> Vec<u8> {
+        samples
+            .chunks(2)
+            .flat_map(|pair| {
+                let (a, b) = (pair[0], pair[1]);
+                [(a >> 4) as u8, (a << 4 | b >> 8) as u8, b as u8]
+            })
+            .collect()
+    }
+
+    fn scale(samples: &[u16]) -> Vec<u16> {
+        samples
+            .iter()
+            .map(|&v| ((u32::from(v) * 0xffff + 2047) / 4095) as u16)
+            .collect()
+    }
+
+    fn unpacked(file: &[u8], conversion: SampleConversion) -> Vec<u16> {
+        let mut decoder = Decoder::new(Cursor::new(file)).unwrap();
+        assert_eq!(decoder.colortype().unwrap(), ColorType::Gray(12));
+        assert!(decoder.read_image().is_err());
+        match decoder.read_image_unpacked(conversion).unwrap() {
+            DecodingResult::U16(data) => data,
+            _ => panic!("Wrong data type"),
+        }
+    }
+
+    // A 20x2 image, of which each row starts at a byte boundary.
+    let (width, height) = (20usize, 2usize);
+    let samples: Vec<u16> = (0..width * height)
+        .map(|i| (i * 211 % 4096) as u16)
+        .collect();
+    let scaled = scale(&samples);
+
+    let mut strips = Cursor::new(Vec::new());
+    let gray = PixelFormat::new(
+        PhotometricInterpretation::BlackIsZero,
+        vec![12],
+        vec![SampleFormat::Uint],
+    );
+    TiffEncoder::new(&mut strips)
+        .unwrap()
+        .write_image_with_format(width as u32, height as u32, &gray, &pack(&samples))
+        .unwrap();
+    let strips = strips.into_inner();
+
+    // The same image in two tiles of 16x16, the second of which is mostly padding. The tile
+    // tables end at 138.
+    let mut data = Vec::new();
+    for left in [0, 16] {
+        let mut tile = vec![0; 16 * 16];
+        for (y, row) in samples.chunks(width).enumerate() {
+            let row = &row[left..width.min(left + 16)];
+            tile[y * 16..y * 16 + row.len()].copy_from_slice(row);
+        }
+        data.extend_from_slice(&pack(&tile));
+    }
+    let offsets = [138u32, 138 + 384].map(u32::to_le_bytes).concat();
+    let byte_counts = [384u32, 384].map(u32::to_le_bytes).concat();
+    let tiles = build_tiff(
+        &[
+            (256, 3, 1, &20u16.to_le_bytes()),
+            (257, 3, 1, &2u16.to_le_bytes()),
+            (258, 3, 1, &12u16.to_le_bytes()),
+            (262, 3, 1, &1u16.to_le_bytes()),
+            (277, 3, 1, &1u16.to_le_bytes()),
+            (322, 3, 1, &16u16.to_le_bytes()),
+            (323, 3, 1, &16u16.to_le_bytes()),
+            (324, 4, 2, &offsets),
+            (325, 4, 2, &byte_counts),
+        ],
+        &data,
+    );
+
+    for file in [&strips, &tiles] {
+        assert_eq!(unpacked(file, SampleConversion::Saturate), samples);
+        assert_eq!(unpacked(file, SampleConversion::Scale), scaled);
+    }
+
+    for (file, color_type) in [
+        ("12bit.cropped.tiff", ColorType::Gray(12)),
+        ("12bit.cropped.rgb.tiff", ColorType::RGB(12)),
+    ] {
+        let path = PathBuf::from(TEST_IMAGE_DIR).join(file);
+        let mut decoder = Decoder::new(File::open(path).unwrap()).unwrap();
+        assert_eq!(decoder.colortype().unwrap(), color_type);
+        let (width, height) = decoder.dimensions().unwrap();
+        let samples = match decoder
+            .read_image_unpacked(SampleConversion::Saturate)
+            .unwrap()
+        {
+            DecodingResult::U16(data) => data,
+            _ => panic!("Wrong data type"),
+        };
+        let channels = if color_type == ColorType::Gray(12) {
+            1
+        } else {
+            3
+        };
+        assert_eq!(samples.len(), (width * height) as usize * channels);
+        assert!(samples.iter().all(|&v| v <= 4095));
+        match decoder
+            .read_image_unpacked(SampleConversion::Scale)
+            .unwrap()
+        {
+            DecodingResult::U16(data) => assert_eq!(data, scale(&samples)),
+            _ => panic!("Wrong data type"),
+        }
+    }
+}
+
+#[test]
+fn test_low_level_io_budget() {
+    let path = PathBuf::from(TEST_IMAGE_DIR).join("minisblack-1c-8b.tiff");
+    let mut limits = tiff::decoder::Limits::default();
+    limits.io_budget = Some(6);
+
+    let mut decoder = Decoder::new(File::open(&path).unwrap())
+        .unwrap()
+        .with_limits(limits.clone());
+    decoder.goto_offset(0).unwrap();
+    decoder.read_short().unwrap();
+    assert_eq!(decoder.read_short().unwrap(), 42);
+    assert!(matches!(
+        decoder.read_string(1 << 30),
+        Err(tiff::TiffError::LimitsExceeded)
+    ));
+    decoder.read_short().unwrap();
+    let err = decoder.read_byte().unwrap_err();
+    assert!(matches!(
+        tiff::TiffError::from(err),
+        tiff::TiffError::LimitsExceeded
+    ));
+
+    // The offset of the first directory is charged as well.
+    let mut decoder = Decoder::new(File::open(&path).unwrap())
+        .unwrap()
+        .with_limits(limits);
+    decoder.goto_offset(4).unwrap();
+    decoder.read_ifd_offset().unwrap();
+    decoder.goto_offset(4).unwrap();
+    assert!(matches!(
+        decoder.read_ifd_offset().map_err(tiff::TiffError::from),
+        Err(tiff::TiffError::LimitsExceeded)
+    ));
+}
+
+#[test]
+fn test_low_level_reads_between_chunks() {
+    let path = PathBuf::from(TEST_IMAGE_DIR).join("tiled-rgb-u8.tif");
+    let read_chunk = |decoder: &mut Decoder<File>, index| match decoder
+        .read_chunk_at(ChunkIndex(index))
+        .unwrap()
+    {
+        DecodingResult::U8(data) => data,
+        _ => panic!("Wrong data type"),
+    };
+    let mut decoder = Decoder::new(File::open(&path).unwrap()).unwrap();
+    let expected = [read_chunk(&mut decoder, 5), read_chunk(&mut decoder, 6)];
+
+    // Moving the reader in between does not affect the chunks decoded afterwards.
+    let mut decoder = Decoder::new(File::open(&path).unwrap()).unwrap();
+    decoder.goto_offset(0).unwrap();
+    decoder.read_short().unwrap();
+    assert_eq!(read_chunk(&mut decoder, 5), expected[0]);
+    decoder.goto_offset(8).unwrap();
+    decoder.read_byte().unwrap();
+    assert_eq!(read_chunk(&mut decoder, 6), expected[1]);
+    assert_eq!(read_chunk(&mut decoder, 5), expected[0]);
+}
+
+#[test]
+fn test_read_region() {
+    use tiff::decoder::DecodingBuffer;
+    use tiff::{TiffError, UsageError};
+
+    let open = |file: &str| {
+        let path = PathBuf::from(TEST_IMAGE_DIR).join(file);
+        Decoder::new(File::open(path).unwrap()).unwrap()
+    };
+
+    // Both files hold the same pixels, once tiled and chunky, once in planar strips.
+    let mut decoder = open("tiled-rgb-u8.tif");
+    let (width, _) = decoder.dimensions().unwrap();
+    let full = match decoder.read_image().unwrap() {
+        DecodingResult::U8(full) => full,
+        _ => panic!("Wrong data type"),
+    };
+
+    let (x, y, w, h) = (20, 30, 50, 40);
+    let expected: Vec<u8> = (y..y + h)
+        .flat_map(|row| {
+            let start = (row * width + x) as usize * 3;
+            full[start..start + w as usize * 3].to_vec()
+        })
+        .collect();
+
+    for file in ["tiled-rgb-u8.tif", "planar-rgb-u8.tif"] {
+        let mut decoder = open(file);
+        match decoder.read_region(x, y, w, h).unwrap() {
+            DecodingResult::U8(region) => assert_eq!(region, expected, "{}", file),
+            _ => panic!("Wrong data type"),
+        }
+        assert!(decoder.read_region(x, y, 0, h).is_err());
+        assert!(decoder.read_region(width - 1, y, 2, h).is_err());
+
+        // Into a wider buffer, leaving the samples between the rows untouched.
+        let stride = (w as usize + 5) * 3;
+        let mut buffer = vec![7u8; stride * h as usize];
+        decoder
+            .read_region_into(x, y, w, h, DecodingBuffer::U8(&mut buffer), stride)
+            .unwrap();
+        for (row, expected) in buffer.chunks(stride).zip(expected.chunks(w as usize * 3)) {
+            assert_eq!(&row[..expected.len()], expected, "{}", file);
+            assert!(row[expected.len()..].iter().all(|&v| v == 7));
+        }
+        let short = &mut buffer[..stride * (h as usize - 1)];
+        assert!(matches!(
+            decoder.read_region_into(x, y, w, h, DecodingBuffer::U8(short), stride),
+            Err(TiffError::UsageError(UsageError::InsufficientBuffer(..)))
+        ));
+        let mut signed = vec![0i8; stride * h as usize];
+        assert!(matches!(
+            decoder.read_region_into(x, y, w, h, DecodingBuffer::I8(&mut signed), stride),
+            Err(TiffError::UsageError(UsageError::InvalidBufferType(..)))
+        ));
+    }
+}
+
+#[test]
+fn test_decoder_options() {
+    use tiff::decoder::{DecoderOptions, Limits};
+
+    let path = PathBuf::from(TEST_IMAGE_DIR).join("planar-rgb-u8.tif");
+
+    // The limits apply to the first directory already.
+    let mut limits = Limits::default();
+    limits.decoding_buffer_size = 1;
+    let options = DecoderOptions::new().with_limits(limits.clone());
+    assert!(matches!(
+        Decoder::with_options(File::open(&path).unwrap(), options),
+        Err(tiff::TiffError::LimitsExceeded)
+    ));
+    // The shim only takes effect after the first directory was read.
+    let decoder = Decoder::new(File::open(&path).unwrap()).unwrap();
+    let mut decoder = decoder.with_limits(limits);
+    assert!(decoder.read_image().is_err());
+
+    let options = DecoderOptions::new().with_validation(true);
+    let mut decoder = Decoder::with_options(File::open(&path).unwrap(), options).unwrap();
+    decoder.read_image().unwrap();
+}
+
+#[test]
+fn test_limits_presets() {
+    use tiff::decoder::{DecoderOptions, Limits};
+
+    let embedded = Limits::for_embedded();
+    let untrusted = Limits::for_untrusted_input();
+    let default = Limits::default();
+    let geotiff = Limits::for_large_geotiff();
+    for (smaller, larger) in [
+        (&embedded, &untrusted),
+        (&untrusted, &default),
+        (&default, &geotiff),
+    ] {
+        assert!(smaller.decoding_buffer_size < larger.decoding_buffer_size);
+        assert!(smaller.intermediate_buffer_size < larger.intermediate_buffer_size);
+        assert!(smaller.ifd_value_size <= larger.ifd_value_size);
+    }
+
+    // Ordinary images decode with every preset.
+    let path = PathBuf::from(TEST_IMAGE_DIR).join("tiled-rgb-u8.tif");
+    for limits in [embedded, untrusted, geotiff] {
+        let options = DecoderOptions::new().with_limits(limits);
+        let mut decoder = Decoder::with_options(File::open(&path).unwrap(), options).unwrap();
+        decoder.read_image().unwrap();
+    }
+}
+
+#[test]
+fn test_decoder_is_send() {
+    fn assert_send<T: Send>() {}
+    fn assert_sync<T: Sync>() {}
+
+    assert_send::<Decoder<File>>();
+    assert_sync::<Decoder<File>>();
+    assert_send::<DecodingResult>();
+    assert_sync::<DecodingResult>();
+
+    // A decoder can be handed to a worker thread and decode there.
+    let path = PathBuf::from(TEST_IMAGE_DIR).join("tiled-rgb-u8.tif");
+    let decoder = Decoder::new(File::open(path).unwrap()).unwrap();
+    let worker = std::thread::spawn(move || {
+        let mut decoder = decoder;
+        decoder.read_image().map(|_| ())
+    });
+    worker.join().unwrap().unwrap();
+}
+
+#[test]
+fn test_decoder_try_clone() {
+    use std::io::Cursor;
+
+    let path = PathBuf::from(TEST_IMAGE_DIR).join("tiled-rgb-u8.tif");
+    let data = std::sync::Arc::<[u8]>::from(std::fs::read(path).unwrap());
+    let mut decoder = Decoder::new(Cursor::new(data)).unwrap();
+    let expected = match decoder.read_image().unwrap() {
+        DecodingResult::U8(data) => data,
+        _ => panic!("Wrong data type"),
+    };
+
+    let workers: Vec<_> = (0..2)
+        .map(|_| {
+            let mut decoder = decoder.try_clone().unwrap();
+            std::thread::spawn(move || match decoder.read_image().unwrap() {
+                DecodingResult::U8(data) => data,
+                _ => panic!("Wrong data type"),
+            })
+        })
+        .collect();
+    for worker in workers {
+        assert_eq!(worker.join().unwrap(), expected);
+    }
+}
+
+#[test]
+#[allow(deprecated)]
+fn test_chunk_buffer_too_small() {
+    use tiff::decoder::DecodingBuffer;
+    use tiff::{TiffError, UsageError};
+
+    let path = PathBuf::from(TEST_IMAGE_DIR).join("tiled-rgb-u8.tif");
+    let mut decoder = Decoder::new(File::open(path).unwrap()).unwrap();
+    let (tile_width, _) = decoder.chunk_dimensions();
+
+    let mut buffer = vec![0u8; 16];
+    let err = decoder
+        .read_chunk_to_buffer(DecodingBuffer::U8(&mut buffer), 0, tile_width as usize)
+        .unwrap_err();
+    match err {
+        TiffError::UsageError(UsageError::InsufficientBuffer(required, 16)) => {
+            assert!(required > 16)
+        }
+        unexpected => panic!("Unexpected error {}", unexpected),
+    }
+}
+
+#[test]
+fn test_read_chunk_into() {
+    use tiff::decoder::DecodingBuffer;
+    use tiff::tags::SampleFormat;
+    use tiff::{TiffError, UsageError};
+
+    // Decode every tile in place into a buffer of the whole image.
+    let path = PathBuf::from(TEST_IMAGE_DIR).join("tiled-rect-rgb-u8.tif");
+    let mut decoder = Decoder::new(File::open(path).unwrap()).unwrap();
+    let expected = match decoder.read_image().unwrap() {
+        DecodingResult::U8(data) => data,
+        _ => panic!("Wrong data type"),
+    };
+    let (width, _) = decoder.dimensions().unwrap();
+    let (tile_width, tile_length) = decoder.chunk_dimensions();
+    let tiles_across = (width + tile_width - 1) / tile_width;
+    let stride = width as usize * 3;
+
+    let mut image = vec![0u8; expected.len()];
+    for tile in 0..decoder.tile_count().unwrap() {
+        let x = (tile % tiles_across * tile_width) as usize;
+        let y = (tile / tiles_across * tile_length) as usize;
+        let start = y * stride + x * 3;
+        decoder
+            .read_chunk_into(
+                ChunkIndex(tile),
+                DecodingBuffer::U8(&mut image[start..]),
+                stride,
+            )
+            .unwrap();
+    }
+    assert_eq!(image, expected);
+
+    // A stride shorter than a row of the tile.
+    let mut buffer = vec![0u8; expected.len()];
+    match decoder.read_chunk_into(
+        ChunkIndex(0),
+        DecodingBuffer::U8(&mut buffer),
+        tile_width as usize,
+    ) {
+        Err(TiffError::UsageError(UsageError::InsufficientBuffer(..))) => {}
+        _ => panic!("Short stride was not rejected"),
+    }
+
+    // A buffer of another sample type, even if it is large enough.
+    let mut buffer = vec![0u16; expected.len()];
+    match decoder.read_chunk_into(ChunkIndex(0), DecodingBuffer::U16(&mut buffer), stride) {
+        Err(TiffError::UsageError(UsageError::InvalidBufferType(SampleFormat::Uint, 8))) => {}
+        _ => panic!("Wrong buffer type was not rejected"),
+    }
+
+    // The chunks of planar images hold one sample per pixel.
+    let path = PathBuf::from(TEST_IMAGE_DIR).join("planar-rgb-u8.tif");
+    let mut decoder = Decoder::new(File::open(path).unwrap()).unwrap();
+    let strips = decoder.strip_count().unwrap();
+    let (width, height) = decoder
+        .chunk_data_dimensions_at(ChunkIndex(strips / 3))
+        .unwrap();
+    let mut plane = vec![0u8; (width * height) as usize];
+    decoder
+        .read_chunk_into(
+            ChunkIndex(strips / 3),
+            DecodingBuffer::U8(&mut plane),
+            width as usize,
+        )
+        .unwrap();
+    match decoder.read_chunk_at(ChunkIndex(strips / 3)).unwrap() {
+        DecodingResult::U8(data) => assert_eq!(data, plane),
+        _ => panic!("Wrong data type"),
+    }
+}
+
+#[test]
+#[allow(deprecated)]
+fn test_typed_chunk_index() {
+    use tiff::decoder::{PlaneIndex, TileCoord};
+
+    let path = PathBuf::from(TEST_IMAGE_DIR).join("tiled-rect-rgb-u8.tif");
+    let mut decoder = Decoder::new(File::open(path).unwrap()).unwrap();
+    let layout = decoder.chunk_layout().unwrap();
+    assert_eq!(layout.planes, 1);
+
+    // Indices run row by row through the grid of tiles.
+    let last = TileCoord {
+        x: layout.chunks_across - 1,
+        y: layout.chunks_down - 1,
+    };
+    let index = layout.chunk_index(PlaneIndex(0), last).unwrap();
+    assert_eq!(index, ChunkIndex(layout.chunks_per_plane() - 1));
+    assert_eq!(layout.chunk_position(index), Some((PlaneIndex(0), last)));
+    let outside = TileCoord {
+        x: layout.chunks_across,
+        y: 0,
+    };
+    assert_eq!(layout.chunk_index(PlaneIndex(0), outside), None);
+    assert_eq!(layout.chunk_index(PlaneIndex(1), last), None);
+    assert_eq!(
+        layout.chunk_position(ChunkIndex(layout.chunks_per_plane())),
+        None
+    );
+
+    // The raw entry points decode the same chunks.
+    assert_eq!(
+        decoder.chunk_data_dimensions(index.0),
+        decoder.chunk_data_dimensions_at(index).unwrap()
+    );
+    match (decoder.read_chunk(index.0), decoder.read_chunk_at(index)) {
+        (Ok(DecodingResult::U8(raw)), Ok(DecodingResult::U8(typed))) => assert_eq!(raw, typed),
+        _ => panic!("Wrong data type"),
+    }
+    assert!(decoder
+        .chunk_data_dimensions_at(ChunkIndex(layout.chunks_per_plane()))
+        .is_err());
+
+    // The planes of planar images follow one another.
+    let path = PathBuf::from(TEST_IMAGE_DIR).join("planar-rgb-u8.tif");
+    let decoder = Decoder::new(File::open(path).unwrap()).unwrap();
+    let layout = decoder.chunk_layout().unwrap();
+    assert_eq!(layout.planes, 3);
+    let coord = TileCoord { x: 0, y: 1 };
+    let index = layout.chunk_index(PlaneIndex(2), coord).unwrap();
+    assert_eq!(index, ChunkIndex(layout.chunks_per_plane() * 2 + 1));
+    assert_eq!(layout.chunk_position(index), Some((PlaneIndex(2), coord)));
+}
+
+#[test]
+fn test_decode_warnings() {
+    use std::io::Cursor;
+    use tiff::decoder::{DecodeWarning, DecoderOptions};
+
+    // A 1x1 grayscale image with an entry of unknown type and a repeated, out of order tag.
+    let file = build_tiff(
+        &[
+            (256, 3, 1, &1u16.to_le_bytes()),
+            (257, 3, 1, &1u16.to_le_bytes()),
+            (258, 3, 1, &8u16.to_le_bytes()),
+            (262, 3, 1, &1u16.to_le_bytes()),
+            (273, 4, 1, &122u32.to_le_bytes()),
+            (278, 3, 1, &1u16.to_le_bytes()),
+            (279, 4, 1, &1u32.to_le_bytes()),
+            (65000, 99, 1, &0u32.to_le_bytes()),
+            (257, 3, 1, &1u16.to_le_bytes()),
+        ],
+        &[42],
+    );
+
+    let options = DecoderOptions::new().with_warnings(true);
+    let mut decoder = Decoder::with_options(Cursor::new(&file), options).unwrap();
+    match decoder.read_image().unwrap() {
+        DecodingResult::U8(data) => assert_eq!(data, [42]),
+        _ => panic!("Wrong data type"),
+    }
+    assert_eq!(
+        decoder.warnings(),
+        [
+            DecodeWarning::UnknownFieldType {
+                ifd: 8,
+                tag: 65000,
+                field_type: 99
+            },
+            DecodeWarning::UnsortedTags { ifd: 8 },
+            DecodeWarning::DuplicateTag { ifd: 8, tag: 257 },
+        ]
+    );
+    assert_eq!(decoder.take_warnings().len(), 3);
+    assert!(decoder.warnings().is_empty());
+
+    let decoder = Decoder::new(Cursor::new(&file)).unwrap();
+    assert!(decoder.warnings().is_empty());
+}
+
+#[test]
+fn test_duplicate_tag_policy() {
+    use std::io::Cursor;
+    use tiff::decoder::{DecoderOptions, DuplicateTagPolicy};
+    use tiff::tags::Tag;
+    use tiff::{TiffError, TiffFormatError};
+
+    // Grayscale images of 2x1 pixels with one strip after the 8 entries of the directory.
+
+    // The width is stored twice.
+    let file = build_tiff(
+        &[
+            (256, 3, 1, &1u16.to_le_bytes()),
+            (256, 3, 1, &2u16.to_le_bytes()),
+            (257, 3, 1, &1u16.to_le_bytes()),
+            (258, 3, 1, &8u16.to_le_bytes()),
+            (262, 3, 1, &1u16.to_le_bytes()),
+            (273, 4, 1, &110u32.to_le_bytes()),
+            (278, 3, 1, &1u16.to_le_bytes()),
+            (279, 4, 1, &2u32.to_le_bytes()),
+        ],
+        &[1, 2],
+    );
+
+    let mut decoder = Decoder::new(Cursor::new(&file)).unwrap();
+    assert_eq!(decoder.dimensions().unwrap(), (2, 1));
+
+    let options = DecoderOptions::new().with_duplicate_tags(DuplicateTagPolicy::FirstWins);
+    let mut decoder = Decoder::with_options(Cursor::new(&file), options).unwrap();
+    assert_eq!(decoder.dimensions().unwrap(), (1, 1));
+
+    let rejecting = [
+        DecoderOptions::new().with_duplicate_tags(DuplicateTagPolicy::Error),
+        DecoderOptions::new().with_validation(true),
+    ];
+    for options in rejecting {
+        match Decoder::with_options(Cursor::new(&file), options) {
+            Err(TiffError::FormatError(TiffFormatError::DuplicateTag(Tag::ImageWidth, 8))) => {}
+            other => panic!("Unexpected result {:?}", other.map(|_| ())),
+        }
+    }
+    match Decoder::new(Cursor::new(&file)).unwrap().with_validation() {
+        Err(TiffError::FormatError(TiffFormatError::DuplicateTag(Tag::ImageWidth, 8))) => {}
+        other => panic!("Unexpected result {:?}", other.map(|_| ())),
+    }
+
+    // The width and the length are swapped.
+    let file = build_tiff(
+        &[
+            (257, 3, 1, &1u16.to_le_bytes()),
+            (256, 3, 1, &2u16.to_le_bytes()),
+            (258, 3, 1, &8u16.to_le_bytes()),
+            (262, 3, 1, &1u16.to_le_bytes()),
+            (273, 4, 1, &110u32.to_le_bytes()),
+            (277, 3, 1, &1u16.to_le_bytes()),
+            (278, 3, 1, &1u16.to_le_bytes()),
+            (279, 4, 1, &2u32.to_le_bytes()),
+        ],
+        &[1, 2],
+    );
+
+    let options = DecoderOptions::new().with_validation(true);
+    match Decoder::with_options(Cursor::new(&file), options.clone()) {
+        Err(TiffError::FormatError(TiffFormatError::UnsortedTags(8))) => {}
+        other => panic!("Unexpected result {:?}", other.map(|_| ())),
+    }
+    let options = options.with_unsorted_tags(true);
+    let mut decoder = Decoder::with_options(Cursor::new(&file), options).unwrap();
+    match decoder.read_image().unwrap() {
+        DecodingResult::U8(data) => assert_eq!(data, [1, 2]),
+        _ => panic!("Wrong data type"),
+    }
+}
+
+#[test]
+fn test_chunk_byte_count_mismatch() {
+    use std::io::Cursor;
+    use tiff::decoder::{DecodeWarning, DecoderOptions};
+    use tiff::{TiffError, TiffFormatError};
+
+    // A grayscale image of 2x1 pixels with a single strip after the directory.
+    let image = |compression: u16, byte_count: u32, data: &[u8]| {
+        build_tiff(
+            &[
+                (256, 3, 1, &2u16.to_le_bytes()),
+                (257, 3, 1, &1u16.to_le_bytes()),
+                (258, 3, 1, &8u16.to_le_bytes()),
+                (259, 3, 1, &compression.to_le_bytes()),
+                (262, 3, 1, &1u16.to_le_bytes()),
+                (273, 4, 1, &110u32.to_le_bytes()),
+                (278, 3, 1, &1u16.to_le_bytes()),
+                (279, 4, 1, &byte_count.to_le_bytes()),
+            ],
+            data,
+        )
+    };
+
+    // An uncompressed strip declared larger than its size, e.g. zero padded.
+    let file = image(1, 4, &[1, 2, 0, 0]);
+
+    let options = DecoderOptions::new().with_warnings(true);
+    let mut decoder = Decoder::with_options(Cursor::new(&file), options).unwrap();
+    match decoder.read_image().unwrap() {
+        DecodingResult::U8(data) => assert_eq!(data, [1, 2]),
+        _ => panic!("Wrong data type"),
+    }
+    assert_eq!(
+        decoder.warnings(),
+        [DecodeWarning::ChunkByteCountMismatch {
+            ifd: 8,
+            chunk: 0,
+            declared: 4,
+            consumed: 2,
+        }]
+    );
+
+    let options = DecoderOptions::new().with_validation(true);
+    let mut decoder = Decoder::with_options(Cursor::new(&file), options).unwrap();
+    match decoder.read_image() {
+        Err(TiffError::FormatError(TiffFormatError::ChunkByteCountMismatch {
+            chunk: 0,
+            declared: 4,
+            consumed: 2,
+        })) => {}
+        other => panic!("Unexpected result {:?}", other.map(|_| ())),
+    }
+
+    // An uncompressed strip declared smaller than its size is not read beyond its byte count.
+    let file = image(1, 1, &[1, 2, 0, 0]);
+    match Decoder::new(Cursor::new(&file)).unwrap().read_image() {
+        Err(TiffError::FormatError(TiffFormatError::ChunkByteCountMismatch {
+            chunk: 0,
+            declared: 1,
+            consumed: 2,
+        })) => {}
+        other => panic!("Unexpected result {:?}", other.map(|_| ())),
+    }
+
+    // A PackBits literal run of two bytes, cut short by its byte count.
+    let file = image(32773, 2, &[1, 1, 2]);
+    match Decoder::new(Cursor::new(&file)).unwrap().read_image() {
+        Err(TiffError::FormatError(TiffFormatError::ChunkByteCountMismatch {
+            chunk: 0,
+            declared: 2,
+            consumed: 2,
+        })) => {}
+        other => panic!("Unexpected result {:?}", other.map(|_| ())),
+    }
+}
+
+#[test]
+fn test_empty_image() {
+    use std::io::Cursor;
+    use tiff::{TiffError, UsageError};
+
+    // Grayscale images of 4x0 pixels with a single empty strip and of 0x4 pixels without strips.
+    let images: [&[IfdEntry]; 2] = [
+        &[
+            (256, 3, 1, &4u16.to_le_bytes()),
+            (257, 3, 1, &0u16.to_le_bytes()),
+            (258, 3, 1, &8u16.to_le_bytes()),
+            (262, 3, 1, &1u16.to_le_bytes()),
+            (273, 4, 1, &0u32.to_le_bytes()),
+            (278, 3, 1, &1u16.to_le_bytes()),
+            (279, 4, 1, &0u32.to_le_bytes()),
+        ],
+        &[
+            (256, 3, 1, &0u16.to_le_bytes()),
+            (257, 3, 1, &4u16.to_le_bytes()),
+            (258, 3, 1, &8u16.to_le_bytes()),
+            (262, 3, 1, &1u16.to_le_bytes()),
+            (273, 4, 0, &[]),
+            (278, 3, 1, &1u16.to_le_bytes()),
+            (279, 4, 0, &[]),
+        ],
+    ];
+
+    for entries in images {
+        let file = build_tiff(entries, &[]);
+
+        let mut decoder = Decoder::new(Cursor::new(&file)).unwrap();
+        let (width, height) = decoder.dimensions().unwrap();
+        assert_eq!(width * height, 0);
+        match decoder.read_image().unwrap() {
+            DecodingResult::U8(data) => assert!(data.is_empty()),
+            _ => panic!("Wrong data type"),
+        }
+        match decoder.read_chunk_at(ChunkIndex(0)) {
+            Err(TiffError::UsageError(UsageError::InvalidChunkIndex(0))) => {}
+            _ => panic!("Chunk of an empty image was read"),
+        }
+    }
+}
+
+#[test]
+fn test_tiles_not_multiple_of_16() {
+    use std::io::Cursor;
+    use tiff::decoder::DecoderOptions;
+    use tiff::{TiffError, TiffFormatError};
+
+    // A 150x130 grayscale image in 100x100 tiles, whose padding is filled with 255.
+    const WIDTH: u32 = 150;
+    const HEIGHT: u32 = 130;
+    const TILE: u32 = 100;
+    let pixel = |x: u32, y: u32| ((x * 3 + y * 7) % 251) as u8;
+
+    // The tiles follow the directory and the tile tables, which end at 142.
+    let mut data = Vec::new();
+    for tile in 0..4 {
+        let (tile_x, tile_y) = (tile % 2 * TILE, tile / 2 * TILE);
+        for y in tile_y..tile_y + TILE {
+            for x in tile_x..tile_x + TILE {
+                let inside = x < WIDTH && y < HEIGHT;
+                data.push(if inside { pixel(x, y) } else { 255 });
+            }
+        }
+    }
+    let offsets: Vec<u8> = (0..4)
+        .flat_map(|tile| (142 + tile * TILE * TILE).to_le_bytes())
+        .collect();
+    let byte_counts = [TILE * TILE; 4].map(u32::to_le_bytes).concat();
+    let file = build_tiff(
+        &[
+            (256, 3, 1, &WIDTH.to_le_bytes()),
+            (257, 3, 1, &HEIGHT.to_le_bytes()),
+            (258, 3, 1, &8u16.to_le_bytes()),
+            (262, 3, 1, &1u16.to_le_bytes()),
+            (322, 3, 1, &TILE.to_le_bytes()),
+            (323, 3, 1, &TILE.to_le_bytes()),
+            (324, 4, 4, &offsets),
+            (325, 4, 4, &byte_counts),
+        ],
+        &data,
+    );
+
+    let mut decoder = Decoder::new(Cursor::new(&file)).unwrap();
+    assert_eq!(decoder.chunk_dimensions(), (TILE, TILE));
+    assert_eq!(
+        decoder.chunk_data_dimensions_at(ChunkIndex(0)).unwrap(),
+        (100, 100)
+    );
+    assert_eq!(
+        decoder.chunk_data_dimensions_at(ChunkIndex(1)).unwrap(),
+        (50, 100)
+    );
+    assert_eq!(
+        decoder.chunk_data_dimensions_at(ChunkIndex(2)).unwrap(),
+        (100, 30)
+    );
+    assert_eq!(
+        decoder.chunk_data_dimensions_at(ChunkIndex(3)).unwrap(),
+        (50, 30)
+    );
+
+    let expected: Vec<u8> = (0..HEIGHT)
+        .flat_map(|y| (0..WIDTH).map(move |x| pixel(x, y)))
+        .collect();
+    match decoder.read_image().unwrap() {
+        DecodingResult::U8(data) => assert_eq!(data, expected),
+        _ => panic!("Wrong data type"),
+    }
+
+    let corner: Vec<u8> = (100..HEIGHT)
+        .flat_map(|y| (100..WIDTH).map(move |x| pixel(x, y)))
+        .collect();
+    match decoder.read_chunk_at(ChunkIndex(3)).unwrap() {
+        DecodingResult::U8(data) => assert_eq!(data, corner),
+        _ => panic!("Wrong data type"),
+    }
+
+    match decoder.read_region(90, 90, 20, 20).unwrap() {
+        DecodingResult::U8(data) => {
+            let region: Vec<u8> = (90..110)
+                .flat_map(|y| (90..110).map(move |x| pixel(x, y)))
+                .collect();
+            assert_eq!(data, region);
+        }
+        _ => panic!("Wrong data type"),
+    }
+
+    let options = DecoderOptions::new().with_validation(true);
+    match Decoder::with_options(Cursor::new(&file), options) {
+        Err(TiffError::FormatError(TiffFormatError::InvalidTileSize(100, 100))) => {}
+        _ => panic!("Tile size was not reported"),
+    }
+}
+
+#[test]
+fn test_unsorted_tags() {
+    use std::io::Cursor;
+    use tiff::decoder::DecoderOptions;
+    use tiff::tags::Tag;
+    use tiff::{TiffError, TiffFormatError};
+
+    // A 1x1 grayscale image whose strip tags precede the image dimensions.
+    let file = build_tiff(
+        &[
+            (273, 4, 1, &98u32.to_le_bytes()),
+            (278, 3, 1, &1u16.to_le_bytes()),
+            (279, 4, 1, &1u32.to_le_bytes()),
+            (256, 3, 1, &1u16.to_le_bytes()),
+            (257, 3, 1, &1u16.to_le_bytes()),
+            (258, 3, 1, &8u16.to_le_bytes()),
+            (262, 3, 1, &1u16.to_le_bytes()),
+        ],
+        &[42],
+    );
+
+    let mut decoder = Decoder::new(Cursor::new(&file)).unwrap();
+    assert_eq!(
+        decoder.tag_order().unwrap(),
+        [
+            Tag::StripOffsets,
+            Tag::RowsPerStrip,
+            Tag::StripByteCounts,
+            Tag::ImageWidth,
+            Tag::ImageLength,
+            Tag::BitsPerSample,
+            Tag::PhotometricInterpretation,
+        ]
+    );
+    // The directory holds the entries in ascending order regardless.
+    let tags: Vec<_> = decoder.current_directory().keys().copied().collect();
+    assert_eq!(
+        tags,
+        [
+            Tag::ImageWidth,
+            Tag::ImageLength,
+            Tag::BitsPerSample,
+            Tag::PhotometricInterpretation,
+            Tag::StripOffsets,
+            Tag::RowsPerStrip,
+            Tag::StripByteCounts,
+        ]
+    );
+    assert_eq!(decoder.current_directory().len(), 7);
+    match decoder.read_image().unwrap() {
+        DecodingResult::U8(data) => assert_eq!(data, [42]),
+        _ => panic!("Wrong data type"),
+    }
+
+    match decoder.with_validation() {
+        Err(TiffError::FormatError(TiffFormatError::UnsortedTags(8))) => {}
+        _ => panic!("Unsorted tags were not reported"),
+    }
+
+    let options = DecoderOptions::new().with_validation(true);
+    match Decoder::with_options(Cursor::new(&file), options) {
+        Err(TiffError::FormatError(TiffFormatError::UnsortedTags(8))) => {}
+        _ => panic!("Unsorted tags were not reported"),
+    }
+}
+
+#[cfg(feature = "legacy-compressions")]
+fn legacy_compressed_image(
+    compression: u16,
+    bits: u16,
+    width: u16,
+    height: u16,
+    data: &[u8],
+) -> Vec<u8> {
+    build_tiff(
+        &[
+            (256, 3, 1, &width.to_le_bytes()),
+            (257, 3, 1, &height.to_le_bytes()),
+            (258, 3, 1, &bits.to_le_bytes()),
+            (259, 3, 1, &compression.to_le_bytes()),
+            (262, 3, 1, &1u16.to_le_bytes()),
+            (273, 4, 1, &110u32.to_le_bytes()),
+            (278, 3, 1, &height.to_le_bytes()),
+            (279, 4, 1, &(data.len() as u32).to_le_bytes()),
+        ],
+        data,
+    )
+}
+
+#[test]
+#[cfg(feature = "legacy-compressions")]
+fn test_thunderscan() {
+    use std::io::Cursor;
+
+    // Row one: a literal 5, then the 2-bit deltas +1, +1, -1.
+    // Row two: a literal 8, the 3-bit deltas -3, +1 and a run of one sample.
+    let data = [0xc5, 0x57, 0xc8, 0xa9, 0x01];
+    let file = legacy_compressed_image(32809, 4, 4, 2, &data);
+
+    let mut decoder = Decoder::new(Cursor::new(file)).unwrap();
+    assert_eq!(decoder.colortype().unwrap(), ColorType::Gray(4));
+    match decoder.read_image().unwrap() {
+        DecodingResult::U8(data) => assert_eq!(data, [0x56, 0x76, 0x85, 0x66]),
+        _ => panic!("Wrong data type"),
+    }
+
+    // A row that ends early is corrupt.
+    let file = legacy_compressed_image(32809, 4, 4, 2, &data[..3]);
+    let mut decoder = Decoder::new(Cursor::new(file)).unwrap();
+    assert!(decoder.read_image().is_err());
+}
+
+#[test]
+#[cfg(feature = "legacy-compressions")]
+fn test_next_2bit() {
+    use std::io::Cursor;
+
+    // A literal row, a row of runs, a literal span and a missing row that stays white.
+    let data = [
+        0x00, 0x1b, 0xe4, // literal row
+        0x03, 0x85, // three samples of 0, five samples of 2
+        0x40, 0x00, 0x01, 0x00, 0x01, 0x00, // one literal byte at offset 1
+    ];
+    let file = legacy_compressed_image(32766, 2, 8, 4, &data);
+
+    let mut decoder = Decoder::new(Cursor::new(file)).unwrap();
+    assert_eq!(decoder.colortype().unwrap(), ColorType::Gray(2));
+    match decoder.read_image().unwrap() {
+        DecodingResult::U8(data) => {
+            assert_eq!(data, [0x1b, 0xe4, 0x02, 0xaa, 0xff, 0x00, 0xff, 0xff])
+        }
+        _ => panic!("Wrong data type"),
+    }
+
+    // NeXT images always hold 2-bit samples.
+    let file = legacy_compressed_image(32766, 4, 4, 4, &data);
+    let mut decoder = Decoder::new(Cursor::new(file)).unwrap();
+    assert!(decoder.read_image().is_err());
+}
+
+fn sgilog_image(photometric: u16, samples: u16, width: u16, data: &[u8]) -> Vec<u8> {
+    build_tiff(
+        &[
+            (256, 3, 1, &width.to_le_bytes()),
+            (257, 3, 1, &1u16.to_le_bytes()),
+            (258, 3, 1, &32u16.to_le_bytes()),
+            (259, 3, 1, &34676u16.to_le_bytes()),
+            (262, 3, 1, &photometric.to_le_bytes()),
+            (273, 4, 1, &134u32.to_le_bytes()),
+            (277, 3, 1, &samples.to_le_bytes()),
+            (278, 3, 1, &1u16.to_le_bytes()),
+            (279, 4, 1, &(data.len() as u32).to_le_bytes()),
+            (339, 3, 1, &3u16.to_le_bytes()),
+        ],
+        data,
+    )
+}
+
+#[test]
+fn test_sgilog() {
+    use std::io::Cursor;
+    use tiff::decoder::{DecoderOptions, LogLuvOutput};
+
+    // Two LogLuv pixels, a white of luminance ~1 and black, each byte plane run-length encoded.
+    let data = [
+        0x02, 0x40, 0x00, // literal high bytes of L
+        0x80, 0x00, // run of two zero low bytes of L
+        0x02, 0x51, 0x00, // literal u
+        0x02, 0xbf, 0x00, // literal v
+    ];
+    let file = sgilog_image(32845, 3, 2, &data);
+
+    let mut decoder = Decoder::new(Cursor::new(&file)).unwrap();
+    assert_eq!(
+        decoder.colortype().unwrap(),
+        ColorType::Multiband {
+            bit_depth: 32,
+            num_samples: 3
+        }
+    );
+    match decoder.read_image().unwrap() {
+        DecodingResult::F32(xyz) => {
+            let expected = [0.9589, 1.0014, 1.1053, 0.0, 0.0, 0.0];
+            for (value, expected) in xyz.iter().zip(expected) {
+                assert!((value - expected).abs() < 1e-3, "{:?}", xyz);
+            }
+        }
+        _ => panic!("Wrong data type"),
+    }
+
+    let options = DecoderOptions::new().with_log_luv_output(LogLuvOutput::Luv);
+    let mut decoder = Decoder::with_options(Cursor::new(&file), options).unwrap();
+    match decoder.read_image().unwrap() {
+        DecodingResult::F32(luv) => {
+            let expected = [1.0014, 81.5 / 410.0, 191.5 / 410.0];
+            for (value, expected) in luv.iter().zip(expected) {
+                assert!((value - expected).abs() < 1e-3, "{:?}", luv);
+            }
+        }
+        _ => panic!("Wrong data type"),
+    }
+
+    // A single LogL pixel decodes to its luminance.
+    let file = sgilog_image(32844, 1, 1, &[0x01, 0x40, 0x01, 0x00]);
+    let mut decoder = Decoder::new(Cursor::new(&file)).unwrap();
+    assert_eq!(decoder.colortype().unwrap(), ColorType::Gray(32));
+    match decoder.read_image().unwrap() {
+        DecodingResult::F32(y) => assert!((y[0] - 1.0014).abs() < 1e-3),
+        _ => panic!("Wrong data type"),
+    }
+}
+
+#[test]
+fn test_chunk_layout() {
+    use tiff::decoder::ChunkType;
+
+    let open = |file: &str| {
+        let path = PathBuf::from(TEST_IMAGE_DIR).join(file);
+        Decoder::new(File::open(path).unwrap()).unwrap()
+    };
+
+    // 374x499 pixels in 32x32 tiles.
+    let mut decoder = open("tiled-rgb-u8.tif");
+    let layout = decoder.chunk_layout().unwrap();
+    assert_eq!(layout.chunk_type, ChunkType::Tile);
+    assert_eq!((layout.chunk_width, layout.chunk_height), (32, 32));
+    assert_eq!((layout.chunks_across, layout.chunks_down), (12, 16));
+    assert_eq!((layout.padding_right, layout.padding_down), (10, 13));
+    assert_eq!((layout.planes, layout.rows_per_strip), (1, None));
+    assert_eq!(layout.chunks_per_plane(), decoder.tile_count().unwrap());
+
+    // 374x499 pixels in three planes of strips with 21 rows each.
+    let mut decoder = open("planar-rgb-u8.tif");
+    let layout = decoder.chunk_layout().unwrap();
+    assert_eq!(layout.chunk_type, ChunkType::Strip);
+    assert_eq!((layout.chunk_width, layout.chunk_height), (374, 21));
+    assert_eq!((layout.chunks_across, layout.chunks_down), (1, 24));
+    assert_eq!((layout.padding_right, layout.padding_down), (0, 5));
+    assert_eq!((layout.planes, layout.rows_per_strip), (3, Some(21)));
+    assert_eq!(
+        layout.chunks_per_plane() * u32::from(layout.planes),
+        decoder.strip_count().unwrap()
+    );
+}
+
+#[test]
+fn test_lenient_missing_photometric_interpretation() {
+    use std::io::Cursor;
+    use tiff::decoder::{DecodeWarning, DecoderOptions};
+    use tiff::{TiffError, TiffUnsupportedError};
+
+    // A 1x1 grayscale image without a PhotometricInterpretation.
+    let file = build_tiff(
+        &[
+            (256, 3, 1, &1u16.to_le_bytes()),
+            (257, 3, 1, &1u16.to_le_bytes()),
+            (258, 3, 1, &8u16.to_le_bytes()),
+            (273, 4, 1, &86u32.to_le_bytes()),
+            (278, 3, 1, &1u16.to_le_bytes()),
+            (279, 4, 1, &1u32.to_le_bytes()),
+        ],
+        &[42],
+    );
+
+    match Decoder::new(Cursor::new(&file)) {
+        Err(TiffError::UnsupportedError(TiffUnsupportedError::UnknownInterpretation)) => {}
+        other => panic!("Unexpected result {:?}", other.map(|_| ())),
+    }
+
+    let options = DecoderOptions::new().with_lenient(true).with_warnings(true);
+    let mut decoder = Decoder::with_options(Cursor::new(&file), options).unwrap();
+    assert_eq!(decoder.colortype().unwrap(), ColorType::Gray(8));
+    assert_eq!(
+        decoder.warnings(),
+        [DecodeWarning::MissingPhotometricInterpretation { ifd: 8 }]
+    );
+    match decoder.read_image().unwrap() {
+        DecodingResult::U8(data) => assert_eq!(data, [42]),
+        _ => panic!("Wrong data type"),
+    }
+}
+
+#[test]
+fn test_lenient_field_types() {
+    use std::io::Cursor;
+    use tiff::decoder::{ifd::Value, DecodeWarning, DecoderOptions};
+    use tiff::tags::Tag;
+    use tiff::{TiffError, TiffFormatError};
+
+    // A 2x1 grayscale image with a SSHORT width, a SLONG length, a BYTE bit depth and a LONG
+    // resolution.
+    let file = |width: u16| {
+        build_tiff(
+            &[
+                (256, 8, 1, &width.to_le_bytes()),
+                (257, 9, 1, &1u32.to_le_bytes()),
+                (258, 1, 1, &8u8.to_le_bytes()),
+                (262, 3, 1, &1u16.to_le_bytes()),
+                (273, 4, 1, &110u32.to_le_bytes()),
+                (278, 3, 1, &1u16.to_le_bytes()),
+                (279, 4, 1, &2u32.to_le_bytes()),
+                (282, 4, 1, &72u32.to_le_bytes()),
+            ],
+            &[1, 2],
+        )
+    };
+
+    match Decoder::new(Cursor::new(file(2))) {
+        Err(TiffError::FormatError(TiffFormatError::UnsignedIntegerExpected(_))) => {}
+        other => panic!("Unexpected result {:?}", other.map(|_| ())),
+    }
+
+    let options = DecoderOptions::new().with_lenient(true).with_warnings(true);
+    let mut decoder = Decoder::with_options(Cursor::new(file(2)), options).unwrap();
+    assert_eq!(decoder.dimensions().unwrap(), (2, 1));
+    assert_eq!(decoder.colortype().unwrap(), ColorType::Gray(8));
+    assert_eq!(
+        decoder.find_tag(Tag::XResolution).unwrap(),
+        Some(Value::Rational(72, 1))
+    );
+    let coerced = |tag: u16, field_type: u16| DecodeWarning::CoercedFieldType {
+        ifd: 8,
+        tag,
+        field_type,
+    };
+    assert_eq!(
+        decoder.warnings(),
+        [
+            coerced(256, 8),
+            coerced(257, 9),
+            coerced(258, 1),
+            coerced(282, 4)
+        ]
+    );
+    match decoder.read_image().unwrap() {
+        DecodingResult::U8(data) => assert_eq!(data, [1, 2]),
+        _ => panic!("Wrong data type"),
+    }
+
+    // Negative values cannot be converted.
+    let negative = (-2i16) as u16;
+    let options = DecoderOptions::new().with_lenient(true);
+    match Decoder::with_options(Cursor::new(file(negative)), options) {
+        Err(TiffError::FormatError(TiffFormatError::UnsignedIntegerExpected(_))) => {}
+        other => panic!("Unexpected result {:?}", other.map(|_| ())),
+    }
+}
+
+#[test]
+fn test_short_strip_tags() {
+    use std::io::Cursor;
+    use tiff::decoder::DecoderOptions;
+
+    // A 4x`strips` grayscale image with one row per strip, whose chunk tags are SHORT as written
+    // by some legacy scanners. Up to two SHORT values are stored in the entry itself.
+    fn decode(strips: u16) {
+        // The tables are stored after the directory if they do not fit in the entries.
+        let ifd_end = 14 + 12 * 8;
+        let data_start = match strips {
+            1 | 2 => ifd_end,
+            _ => ifd_end + 4 * u32::from(strips),
+        };
+        let offsets: Vec<u8> = (0..strips)
+            .flat_map(|strip| ((data_start + 4 * u32::from(strip)) as u16).to_le_bytes())
+            .collect();
+        let counts: Vec<u8> = (0..strips).flat_map(|_| 4u16.to_le_bytes()).collect();
+        let data: Vec<u8> = (0..4 * strips as u8).collect();
+        let file = build_tiff(
+            &[
+                (256, 3, 1, &4u16.to_le_bytes()),
+                (257, 3, 1, &strips.to_le_bytes()),
+                (258, 3, 1, &8u16.to_le_bytes()),
+                (262, 3, 1, &1u16.to_le_bytes()),
+                (273, 3, strips.into(), &offsets),
+                (277, 3, 1, &1u16.to_le_bytes()),
+                (278, 3, 1, &1u16.to_le_bytes()),
+                (279, 3, strips.into(), &counts),
+            ],
+            &data,
+        );
+        assert_eq!(file.len() as u32, data_start + data.len() as u32);
+
+        let options = DecoderOptions::new().with_validation(true);
+        let mut decoder = Decoder::with_options(Cursor::new(&file), options).unwrap();
+        assert_eq!(decoder.strip_count().unwrap(), u32::from(strips));
+        match decoder.read_image().unwrap() {
+            DecodingResult::U8(decoded) => assert_eq!(decoded, data),
+            _ => panic!("Wrong data type"),
+        }
+    }
+
+    for strips in 1..=3 {
+        decode(strips);
+    }
+}
+
+#[test]
+fn test_memory_usage() {
+    use tiff::decoder::MemoryUsage;
+
+    let path = PathBuf::from(TEST_IMAGE_DIR).join("tiled-rgb-u8.tif");
+    let mut decoder = Decoder::new(File::open(path).unwrap()).unwrap();
+    // The directory was read, but no image data yet.
+    let usage = decoder.memory_usage();
+    assert_eq!((usage.result_bytes, usage.intermediate_bytes), (0, 0));
+    assert!(usage.tag_value_bytes > 0);
+
+    let largest_tile = decoder
+        .get_tag_u64_vec(tiff::tags::Tag::TileByteCounts)
+        .unwrap()
+        .into_iter()
+        .max()
+        .unwrap();
+    decoder.read_chunk_at(ChunkIndex(0)).unwrap();
+    assert_eq!(decoder.memory_usage().result_bytes, 32 * 32 * 3);
+
+    decoder.read_image().unwrap();
+    let usage = decoder.memory_usage();
+    assert_eq!(usage.result_bytes, 374 * 499 * 3);
+    assert_eq!(usage.intermediate_bytes as u64, largest_tile);
+
+    decoder.reset_memory_usage();
+    assert_eq!(decoder.memory_usage(), MemoryUsage::default());
+
+    #[cfg(feature = "memory-stats")]
+    {
+        let images = decoder.image_memory_usage();
+        assert_eq!(images.len(), 1);
+        assert_eq!(images[0].1, usage);
+    }
+}
+
+#[test]
+fn test_dump_metadata() {
+    use tiff::tags::{Tag, Type};
+
+    // An image with the given pointers to the EXIF directory, which holds the `ExifVersion`
+    // and is followed by the strip.
+    fn file(pointers: &[(u16, u32)]) -> Vec<u8> {
+        let exif_offset = 14 + 12 * (6 + pointers.len() as u32);
+        let mut exif = 1u16.to_le_bytes().to_vec();
+        exif.extend_from_slice(&36864u16.to_le_bytes());
+        exif.extend_from_slice(&7u16.to_le_bytes());
+        exif.extend_from_slice(&4u32.to_le_bytes());
+        exif.extend_from_slice(b"0232");
+        exif.extend_from_slice(&0u32.to_le_bytes());
+        exif.push(0x80);
+
+        let strip_offset = (exif_offset + 18).to_le_bytes();
+        let pointers: Vec<_> = pointers
+            .iter()
+            .map(|&(tag, offset)| (tag, offset.to_le_bytes()))
+            .collect();
+        let mut entries: Vec<(u16, u16, u32, &[u8])> = vec![
+            (256, 3, 1, &[1, 0]),
+            (257, 3, 1, &[1, 0]),
+            (258, 3, 1, &[8, 0]),
+            (262, 3, 1, &[1, 0]),
+            (273, 4, 1, &strip_offset),
+            (279, 4, 1, &[1, 0, 0, 0]),
+        ];
+        entries.extend(
+            pointers
+                .iter()
+                .map(|(tag, offset)| (*tag, 4, 1, &offset[..])),
+        );
+        build_tiff(&entries, &exif)
+    }
+
+    let mut decoder = Decoder::new(std::io::Cursor::new(file(&[(34665, 98)]))).unwrap();
+    let tree = decoder.dump_metadata().unwrap();
+    assert_eq!(tree.images.len(), 1);
+    let image = &tree.images[0];
+    assert_eq!(image.offset, 8);
+    let tags: Vec<_> = image.entries.iter().map(|entry| entry.tag).collect();
+    assert_eq!(tags[..2], [Tag::ImageWidth, Tag::ImageLength]);
+    assert_eq!(tags[6], Tag::ExifDirectory);
+    let width = image.entry(Tag::ImageWidth).unwrap();
+    assert_eq!((width.field_type, width.count), (Type::SHORT, 1));
+    assert_eq!(width.value, ifd::Value::Unsigned(1));
+
+    assert_eq!(image.sub_directories.len(), 1);
+    let (pointer, exif) = &image.sub_directories[0];
+    assert_eq!((*pointer, exif.offset), (Tag::ExifDirectory, 98));
+    let version = exif.entry(Tag::Unknown(36864)).unwrap();
+    assert_eq!(
+        version.value,
+        ifd::Value::List(b"0232".iter().map(|&b| ifd::Value::Byte(b)).collect())
+    );
+
+    // The image data and the current image are not affected.
+    assert_eq!(decoder.dump_metadata().unwrap(), tree);
+    match decoder.read_image().unwrap() {
+        DecodingResult::U8(data) => assert_eq!(data, [0x80]),
+        _ => panic!("Wrong data type"),
+    }
+
+    // An EXIF directory pointing back to the image.
+    let mut decoder = Decoder::new(std::io::Cursor::new(file(&[(34665, 8)]))).unwrap();
+    assert!(matches!(
+        decoder.dump_metadata(),
+        Err(tiff::TiffError::FormatError(
+            tiff::TiffFormatError::CycleInOffsets
+        ))
+    ));
+
+    // A directory pointed to by both the EXIF and the GPS tag is dumped for each of them.
+    let file = file(&[(34665, 110), (34853, 110)]);
+    let mut decoder = Decoder::new(std::io::Cursor::new(file)).unwrap();
+    let tree = decoder.dump_metadata().unwrap();
+    let pointers: Vec<_> = tree.images[0]
+        .sub_directories
+        .iter()
+        .map(|(tag, directory)| (*tag, directory.offset))
+        .collect();
+    assert_eq!(
+        pointers,
+        [(Tag::ExifDirectory, 110), (Tag::GpsDirectory, 110)]
+    );
+}
+
+#[test]
+fn test_exif_and_gps() {
+    use std::io::Cursor;
+    use tiff::encoder::{colortype, TiffEncoder};
+    use tiff::tags::{ExifTag, GpsTag, Tag, Type};
+
+    let rationals = |values: &[(u32, u32)]| -> Vec<u8> {
+        values
+            .iter()
+            .flat_map(|&(n, d)| [n.to_ne_bytes(), d.to_ne_bytes()])
+            .flatten()
+            .collect()
+    };
+    let latitude = rationals(&[(4, 1), (38, 1), (2145, 100)]);
+    let f_number = rationals(&[(28, 10)]);
+
+    let mut file = Cursor::new(Vec::new());
+    {
+        let mut tiff = TiffEncoder::new(&mut file).unwrap();
+        let exif = tiff
+            .write_ifd(&[
+                (0x829D, Type::RATIONAL, 1, &f_number),
+                (0x9000, Type::UNDEFINED, 4, b"0232"),
+                (0xC000, Type::SHORT, 1, &7u16.to_ne_bytes()),
+            ])
+            .unwrap();
+        let gps = tiff
+            .write_ifd(&[
+                (1, Type::ASCII, 2, b"N\0"),
+                (2, Type::RATIONAL, 3, &latitude),
+            ])
+            .unwrap();
+        let mut image = tiff.new_image::<colortype::Gray8>(1, 1).unwrap();
+        image.encoder().write_tag(Tag::ExifDirectory, exif).unwrap();
+        image.encoder().write_tag(Tag::GpsDirectory, gps).unwrap();
+        image.write_data(&[0]).unwrap();
+        tiff.write_image::<colortype::Gray8>(1, 1, &[0]).unwrap();
+    }
+
+    let mut decoder = Decoder::new(Cursor::new(file.into_inner())).unwrap();
+    let exif = decoder.exif().unwrap().unwrap();
+    assert_eq!(exif.len(), 3);
+    assert_eq!(exif[&ExifTag::FNumber], ifd::Value::Rational(28, 10));
+    assert_eq!(
+        exif[&ExifTag::ExifVersion],
+        ifd::Value::List(b"0232".iter().map(|&b| ifd::Value::Byte(b)).collect())
+    );
+    assert_eq!(exif[&ExifTag::Unknown(0xC000)], ifd::Value::Unsigned(7));
+
+    let gps = decoder.gps().unwrap().unwrap();
+    assert_eq!(gps[&GpsTag::LatitudeRef], ifd::Value::Ascii("N".into()));
+    assert_eq!(
+        gps[&GpsTag::Latitude],
+        ifd::Value::List(vec![
+            ifd::Value::Rational(4, 1),
+            ifd::Value::Rational(38, 1),
+            ifd::Value::Rational(2145, 100),
+        ])
+    );
+
+    // The current image is not affected, the next one has neither directory.
+    match decoder.read_image().unwrap() {
+        DecodingResult::U8(data) => assert_eq!(data, [0]),
+        _ => panic!("Wrong data type"),
+    }
+    decoder.next_image().unwrap();
+    assert_eq!(decoder.exif().unwrap(), None);
+    assert_eq!(decoder.gps().unwrap(), None);
+}
+
+#[test]
+fn test_offsets_out_of_bounds() {
+    use std::io::Cursor;
+    use tiff::decoder::DecoderOptions;
+    use tiff::tags::Tag;
+    use tiff::{OffsetSource, TiffError, TiffFormatError};
+
+    // The description and the strip lie beyond the end of the file.
+    let mut file = build_tiff(
+        &[
+            (256, 3, 1, &2u16.to_le_bytes()),
+            (257, 3, 1, &1u16.to_le_bytes()),
+            (258, 3, 1, &8u16.to_le_bytes()),
+            (262, 3, 1, &1u16.to_le_bytes()),
+            (270, 2, 20, &5000u32.to_le_bytes()),
+            (273, 4, 1, &4000u32.to_le_bytes()),
+            (278, 3, 1, &1u16.to_le_bytes()),
+            (279, 4, 1, &2u32.to_le_bytes()),
+        ],
+        &[],
+    );
+    let stream_length = file.len() as u64;
+
+    let mut decoder = Decoder::new(Cursor::new(&file)).unwrap();
+    match decoder.find_tag(Tag::ImageDescription) {
+        Err(TiffError::FormatError(TiffFormatError::OffsetOutOfBounds {
+            offset: 5000,
+            stream_length: length,
+            source: OffsetSource::Tag(Tag::ImageDescription),
+        })) if length == stream_length => {}
+        other => panic!("Unexpected result {:?}", other),
+    }
+    match decoder.read_image() {
+        Err(TiffError::FormatError(TiffFormatError::OffsetOutOfBounds {
+            offset: 4000,
+            source: OffsetSource::Chunk(0),
+            ..
+        })) => {}
+        other => panic!("Unexpected result {:?}", other.map(|_| ())),
+    }
+    assert!(decoder.goto_offset(4000).is_err());
+
+    // Without knowing the length, the reads run into the end of the stream.
+    let options = DecoderOptions::new().with_stream_length_check(false);
+    let mut decoder = Decoder::with_options(Cursor::new(&file), options).unwrap();
+    match decoder.read_image() {
+        Err(TiffError::IoError(_)) => {}
+        other => panic!("Unexpected result {:?}", other.map(|_| ())),
+    }
+
+    file[4..8].copy_from_slice(&9000u32.to_le_bytes());
+    match Decoder::new(Cursor::new(&file)) {
+        Err(TiffError::FormatError(TiffFormatError::OffsetOutOfBounds {
+            offset: 9000,
+            source: OffsetSource::Ifd,
+            ..
+        })) => {}
+        other => panic!("Unexpected result {:?}", other.map(|_| ())),
+    }
+}
+
+#[test]
+fn test_overstated_last_strip() {
+    use std::io::Cursor;
+    use tiff::decoder::{DecodeWarning, DecoderOptions};
+    use tiff::{OffsetSource, TiffError, TiffFormatError};
+
+    // The strip holds all 16 bytes of the image but its byte count runs past the end of the file.
+    let data: Vec<u8> = (0..16).collect();
+    let mut file = build_tiff(
+        &[
+            (256, 3, 1, &4u16.to_le_bytes()),
+            (257, 3, 1, &4u16.to_le_bytes()),
+            (258, 3, 1, &8u16.to_le_bytes()),
+            (262, 3, 1, &1u16.to_le_bytes()),
+            (273, 4, 1, &110u32.to_le_bytes()),
+            (277, 3, 1, &1u16.to_le_bytes()),
+            (278, 3, 1, &4u16.to_le_bytes()),
+            (279, 4, 1, &1000u32.to_le_bytes()),
+        ],
+        &data,
+    );
+
+    match Decoder::new(Cursor::new(&file))
+        .unwrap()
+        .read_image()
+        .unwrap()
+    {
+        DecodingResult::U8(decoded) => assert_eq!(decoded, data),
+        _ => panic!("Wrong data type"),
+    }
+
+    let options = DecoderOptions::new().with_warnings(true);
+    let mut decoder = Decoder::with_options(Cursor::new(&file), options).unwrap();
+    decoder.read_image().unwrap();
+    assert_eq!(
+        decoder.warnings(),
+        [DecodeWarning::ChunkByteCountMismatch {
+            ifd: 8,
+            chunk: 0,
+            declared: 1000,
+            consumed: 16,
+        }]
+    );
+
+    // Validation mode rejects the byte count.
+    let options = DecoderOptions::new().with_validation(true);
+    match Decoder::with_options(Cursor::new(&file), options) {
+        Err(TiffError::FormatError(TiffFormatError::OffsetOutOfBounds {
+            offset: 110,
+            source: OffsetSource::Chunk(0),
+            ..
+        })) => {}
+        other => panic!("Unexpected result {:?}", other.map(|_| ())),
+    }
+
+    // A strip that is actually cut short still lies beyond the end of the file.
+    file.truncate(120);
+    match Decoder::new(Cursor::new(&file)).unwrap().read_image() {
+        Err(TiffError::FormatError(TiffFormatError::OffsetOutOfBounds {
+            offset: 110,
+            stream_length: 120,
+            source: OffsetSource::Chunk(0),
+        })) => {}
+        other => panic!("Unexpected result {:?}", other.map(|_| ())),
+    }
+}
+
+#[test]
+fn test_lazy_chunk_tables() {
+    use std::io::Cursor;
+    use tiff::decoder::{ChunkIndex, DecoderOptions};
+    use tiff::encoder::{colortype, TiffEncoder};
+
+    // More strips than the tables are read at once when walking over all chunks.
+    let (width, height) = (3, 5000);
+    let data: Vec<u8> = (0..width * height).map(|i| (i % 251) as u8).collect();
+    let mut file = Cursor::new(Vec::new());
+    {
+        let mut encoder = TiffEncoder::new(&mut file).unwrap();
+        let mut image = encoder
+            .new_image::<colortype::Gray8>(width, height)
+            .unwrap();
+        image.rows_per_strip(1).unwrap();
+        image.write_data(&data).unwrap();
+    }
+    let file = file.into_inner();
+
+    let mut eager = Decoder::new(Cursor::new(&file)).unwrap();
+    let options = DecoderOptions::new()
+        .with_lazy_chunk_tables(true)
+        .with_validation(true);
+    let mut decoder = Decoder::with_options(Cursor::new(&file), options).unwrap();
+    assert_eq!(decoder.strip_count().unwrap(), height);
+    assert_eq!(
+        decoder.chunk_placement().unwrap(),
+        eager.chunk_placement().unwrap()
+    );
+    assert!(decoder.chunk_placement().unwrap().contiguous);
+    match decoder.read_chunk_at(ChunkIndex(4321)).unwrap() {
+        DecodingResult::U8(row) => assert_eq!(row, data[4321 * 3..4322 * 3]),
+        _ => panic!("Wrong data type"),
+    }
+    match decoder.read_image().unwrap() {
+        DecodingResult::U8(image) => assert_eq!(image, data),
+        _ => panic!("Wrong data type"),
+    }
+}
+
+#[cfg(feature = "image-interop")]
+#[test]
+fn test_convert_to_dynamic_image() {
+    use std::io::Cursor;
+    use tiff::convert::{read_dynamic_image, to_dynamic_image};
+    use tiff::encoder::{colortype, TiffEncoder};
+    use tiff::{TiffError, TiffUnsupportedError};
+
+    let mut file = Cursor::new(Vec::new());
+    {
+        let mut tiff = TiffEncoder::new(&mut file).unwrap();
+        tiff.write_image::<colortype::RGB16>(2, 1, &[1, 2, 3, 4, 5, 6])
+            .unwrap();
+        tiff.write_image::<colortype::Gray32Float>(2, 1, &[0.25, 0.5])
+            .unwrap();
+        tiff.write_image::<colortype::CMYK8>(1, 1, &[1, 2, 3, 4])
+            .unwrap();
+    }
+    file.set_position(0);
+
+    let mut decoder = Decoder::new(file).unwrap();
+    let image = read_dynamic_image(&mut decoder).unwrap();
+    assert_eq!(image.as_rgb16().unwrap().as_raw(), &[1, 2, 3, 4, 5, 6]);
+
+    decoder.next_image().unwrap();
+    let image = read_dynamic_image(&mut decoder).unwrap();
+    let expected = [0.25, 0.25, 0.25, 0.5, 0.5, 0.5];
+    assert_eq!(image.as_rgb32f().unwrap().as_raw(), &expected);
+
+    decoder.next_image().unwrap();
+    match read_dynamic_image(&mut decoder) {
+        Err(TiffError::UnsupportedError(TiffUnsupportedError::UnsupportedColorType(
+            ColorType::CMYK(8),
+        ))) => {}
+        other => panic!("Unexpected result {:?}", other.map(|_| ())),
+    }
+
+    // Packed bilevel samples do not fill a buffer of one byte per pixel.
+    let packed = DecodingResult::U8(vec![0b1010_0000]);
+    assert!(to_dynamic_image(4, 1, ColorType::Gray(1), packed).is_err());
+}
+
+#[test]
+fn test_rational_values_into_f64_vec() {
+    use tiff::decoder::ifd::Value;
+
+    // EXIF-style rational arrays, such as GPS coordinates in degrees, minutes and seconds.
+    let rationals = Value::List(vec![
+        Value::Rational(48, 1),
+        Value::Rational(51, 2),
+        Value::RationalBig(3, 4),
+    ]);
+    assert_eq!(rationals.into_f64_vec().unwrap(), [48.0, 25.5, 0.75]);
+    let signed = Value::List(vec![Value::SRational(-1, 8), Value::SRationalBig(5, -2)]);
+    assert_eq!(signed.into_f64_vec().unwrap(), [-0.125, -2.5]);
+    assert_eq!(Value::Rational(1, 2).into_f64_vec().unwrap(), [0.5]);
+
+    // Doubles are taken as they are, and a zero denominator has no quotient.
+    let doubles = Value::List(vec![Value::Double(60.0), Value::Double(0.5)]);
+    assert_eq!(doubles.into_f64_vec().unwrap(), [60.0, 0.5]);
+    assert!(Value::List(vec![Value::Rational(1, 0)])
+        .into_f64_vec()
+        .is_err());
+    assert!(Value::SRational(1, 0).into_f64_vec().is_err());
+}
+
+#[test]
+fn test_chunks_iterator() {
+    use tiff::decoder::{ChunkType, DecoderOptions, Limits};
+    use tiff::{TiffError, UsageError};
+
+    let path = PathBuf::from(TEST_IMAGE_DIR).join("tiled-rect-rgb-u8.tif");
+    let mut decoder = Decoder::new(File::open(&path).unwrap()).unwrap();
+    let tiles = decoder.tile_count().unwrap();
+    let mut expected = Vec::new();
+    for tile in 0..tiles {
+        expected.push((
+            ChunkIndex(tile),
+            decoder.read_chunk_at(ChunkIndex(tile)).unwrap(),
+        ));
+    }
+    let chunks = decoder.tiles().unwrap();
+    assert_eq!(chunks.len(), tiles as usize);
+    let chunks: Vec<_> = chunks.map(Result::unwrap).collect();
+    assert_eq!(format!("{:?}", chunks), format!("{:?}", expected));
+    match decoder.strips() {
+        Err(TiffError::UsageError(UsageError::InvalidChunkType(
+            ChunkType::Strip,
+            ChunkType::Tile,
+        ))) => {}
+        other => panic!("Unexpected result {:?}", other.map(|_| ())),
+    }
+
+    // A budget too small for the whole image still fits every strip.
+    let path = PathBuf::from(TEST_IMAGE_DIR).join("planar-rgb-u8.tif");
+    let decoder = Decoder::new(File::open(&path).unwrap()).unwrap();
+    let layout = decoder.chunk_layout().unwrap();
+    let mut limits = Limits::default();
+    limits.decoding_buffer_size = (layout.chunk_width * layout.chunk_height) as usize;
+    let options = DecoderOptions::new().with_limits(limits);
+    let mut decoder = Decoder::with_options(File::open(&path).unwrap(), options).unwrap();
+    assert!(decoder.read_image().is_err());
+    let mut strips = 0;
+    for (strip, chunk) in decoder.strips().unwrap().map(Result::unwrap) {
+        assert_eq!(strip, ChunkIndex(strips));
+        let (width, height) = strip_dimensions(&layout, strip);
+        match chunk {
+            DecodingResult::U8(data) => assert_eq!(data.len(), (width * height) as usize),
+            _ => panic!("Wrong data type"),
+        }
+        strips += 1;
+    }
+    assert_eq!(strips, decoder.strip_count().unwrap());
+
+    fn strip_dimensions(layout: &tiff::decoder::ChunkLayout, strip: ChunkIndex) -> (u32, u32) {
+        let (_, coord) = layout.chunk_position(strip).unwrap();
+        let last_row = coord.y == layout.chunks_down - 1;
+        let height = layout.chunk_height - if last_row { layout.padding_down } else { 0 };
+        (layout.chunk_width, height)
+    }
+}
+
+#[test]
+fn test_current_directory() {
+    use tiff::tags::{Tag, Type};
+
+    let path = PathBuf::from(TEST_IMAGE_DIR).join("tiled-rect-rgb-u8.tif");
+    let mut decoder = Decoder::new(File::open(path).unwrap()).unwrap();
+    let tiles = u64::from(decoder.tile_count().unwrap());
+
+    let directory = decoder.current_directory();
+    let bits = &directory[&Tag::BitsPerSample];
+    assert_eq!((bits.field_type(), bits.count()), (Type::SHORT, 3));
+    assert_eq!(directory[&Tag::TileOffsets].count(), tiles);
+    assert!(!directory.contains_key(&Tag::StripOffsets));
+
+    // Both list the tags in ascending order.
+    let tags: Vec<_> = directory.keys().copied().collect();
+    let decoded: Vec<_> = decoder.tag_iter().map(|tag| tag.unwrap().0).collect();
+    assert_eq!(tags, decoded);
+    assert!(tags
+        .windows(2)
+        .all(|pair| pair[0].to_u16() < pair[1].to_u16()));
+}
+
+#[test]
+fn test_planar_chunky_conversion() {
+    use tiff::tags::{PhotometricInterpretation, SampleFormat};
+    use tiff::PixelFormat;
+
+    // Both files hold the same pixels, once chunky and once with planes of strips.
+    let path = PathBuf::from(TEST_IMAGE_DIR).join("tiled-rgb-u8.tif");
+    let mut decoder = Decoder::new(File::open(path).unwrap()).unwrap();
+    let (width, height) = decoder.dimensions().unwrap();
+    let chunky = match decoder.read_image().unwrap() {
+        DecodingResult::U8(data) => data,
+        _ => panic!("Wrong data type"),
+    };
+    let path = PathBuf::from(TEST_IMAGE_DIR).join("planar-rgb-u8.tif");
+    let mut decoder = Decoder::new(File::open(path).unwrap()).unwrap();
+    let mut planar = Vec::new();
+    for strip in decoder.strips().unwrap() {
+        match strip.unwrap().1 {
+            DecodingResult::U8(data) => planar.extend_from_slice(&data),
+            _ => panic!("Wrong data type"),
+        }
+    }
+
+    let rgb = PixelFormat::new(
+        PhotometricInterpretation::RGB,
+        vec![8; 3],
+        vec![SampleFormat::Uint; 3],
+    );
+    assert_eq!(
+        rgb.planar_to_chunky(width, height, &planar).unwrap(),
+        chunky
+    );
+    assert_eq!(
+        rgb.chunky_to_planar(width, height, &chunky).unwrap(),
+        planar
+    );
+    assert!(rgb.planar_to_chunky(width, height, &planar[1..]).is_err());
+
+    // Packed samples start every row of a plane at a byte boundary.
+    let bilevel = PixelFormat::new(
+        PhotometricInterpretation::RGB,
+        vec![1; 3],
+        vec![SampleFormat::Uint; 3],
+    );
+    let planar = [0b1010_0000, 0b0100_0000, 0b1110_0000];
+    let chunky = [0b1010_1110, 0b1000_0000];
+    assert_eq!(bilevel.planar_to_chunky(3, 1, &planar).unwrap(), chunky);
+    assert_eq!(bilevel.chunky_to_planar(3, 1, &chunky).unwrap(), planar);
+
+    // Images too large to count in bytes are rejected.
+    let gray = PixelFormat::new(
+        PhotometricInterpretation::BlackIsZero,
+        vec![16],
+        vec![SampleFormat::Uint],
+    );
+    match gray.planar_to_chunky(u32::MAX, u32::MAX, &[]) {
+        Err(tiff::TiffError::LimitsExceeded) => {}
+        other => panic!("Oversized image was not rejected: {:?}", other),
+    }
+}
+
+#[test]
+fn test_stored_as_decoded() {
+    use std::io::Cursor;
+
+    // A 2x2 grayscale image of 16 bit samples in one uncompressed strip, in either byte order and
+    // with or without the horizontal predictor. Only samples in native byte order without a
+    // predictor are read as they are stored, all others must still be converted. The strip is
+    // read as a chunk, `read_image` has a path of its own for single uncompressed strips.
+    fn image(big_endian: bool, predictor: u16, stored: &[u16]) -> Vec<u8> {
+        let to_u16 = |value: u16| match big_endian {
+            true => value.to_be_bytes(),
+            false => value.to_le_bytes(),
+        };
+        let to_u32 = |value: u32| match big_endian {
+            true => value.to_be_bytes(),
+            false => value.to_le_bytes(),
+        };
+        let entries: [(u16, u16, u32); 9] = [
+            (256, 3, 2),
+            (257, 3, 2),
+            (258, 3, 16),
+            (262, 3, 1),
+            (273, 4, 122),
+            (277, 3, 1),
+            (278, 3, 2),
+            (279, 4, 8),
+            (317, 3, predictor.into()),
+        ];
+        let mut file = match big_endian {
+            true => b"MM\x00\x2a\x00\x00\x00\x08".to_vec(),
+            false => b"II\x2a\x00\x08\x00\x00\x00".to_vec(),
+        };
+        file.extend_from_slice(&to_u16(entries.len() as u16));
+        for (tag, type_, value) in entries {
+            file.extend_from_slice(&to_u16(tag));
+            file.extend_from_slice(&to_u16(type_));
+            file.extend_from_slice(&to_u32(1));
+            match type_ {
+                3 => file.extend_from_slice(&[to_u16(value as u16), [0; 2]].concat()),
+                _ => file.extend_from_slice(&to_u32(value)),
+            }
+        }
+        file.extend_from_slice(&0u32.to_le_bytes());
+        assert_eq!(file.len(), 122);
+        for &sample in stored {
+            file.extend_from_slice(&to_u16(sample));
+        }
+        file
+    }
+
+    let samples = [0x0102, 0x0304, 0x0506, 0x0a0b];
+    let predicted = [0x0102, 0x0202, 0x0506, 0x0505];
+    for big_endian in [false, true] {
+        let native = big_endian == cfg!(target_endian = "big");
+        for (predictor, stored) in [(1, &samples), (2, &predicted)] {
+            let file = image(big_endian, predictor, stored);
+            let mut decoder = Decoder::new(Cursor::new(file)).unwrap();
+            match decoder.read_chunk_at(ChunkIndex(0)).unwrap() {
+                DecodingResult::U16(decoded) => assert_eq!(
+                    decoded, samples,
+                    "native: {}, predictor: {}",
+                    native, predictor
+                ),
+                _ => panic!("Wrong data type"),
+            }
+        }
+    }
+}
+
+#[test]
+fn test_single_strip() {
+    use std::io::Cursor;
+
+    // A 3x2 grayscale image of 16 bit samples in one uncompressed strip of `byte_count` bytes.
+    fn image(big_endian: bool, photometric: u16, byte_count: u32, samples: &[u16]) -> Vec<u8> {
+        let to_u16 = |value: u16| match big_endian {
+            true => value.to_be_bytes(),
+            false => value.to_le_bytes(),
+        };
+        let to_u32 = |value: u32| match big_endian {
+            true => value.to_be_bytes(),
+            false => value.to_le_bytes(),
+        };
+        let entries: [(u16, u16, u32); 8] = [
+            (256, 3, 3),
+            (257, 3, 2),
+            (258, 3, 16),
+            (262, 3, photometric.into()),
+            (273, 4, 110),
+            (277, 3, 1),
+            (278, 3, 2),
+            (279, 4, byte_count),
+        ];
+        let mut file = match big_endian {
+            true => b"MM\x00\x2a\x00\x00\x00\x08".to_vec(),
+            false => b"II\x2a\x00\x08\x00\x00\x00".to_vec(),
+        };
+        file.extend_from_slice(&to_u16(entries.len() as u16));
+        for (tag, type_, value) in entries {
+            file.extend_from_slice(&to_u16(tag));
+            file.extend_from_slice(&to_u16(type_));
+            file.extend_from_slice(&to_u32(1));
+            match type_ {
+                3 => file.extend_from_slice(&[to_u16(value as u16), [0; 2]].concat()),
+                _ => file.extend_from_slice(&to_u32(value)),
+            }
+        }
+        file.extend_from_slice(&0u32.to_le_bytes());
+        assert_eq!(file.len(), 110);
+        for &sample in samples {
+            file.extend_from_slice(&to_u16(sample));
+        }
+        file.resize(110 + byte_count as usize, 0);
+        file
+    }
+
+    let samples = [0, 1, 0x0102, 0x8000, 0xfffe, 0xffff];
+    let inverted: Vec<u16> = samples.iter().map(|sample| !sample).collect();
+    for big_endian in [false, true] {
+        // The strip holds exactly the image, or is longer and decoded chunk by chunk.
+        for byte_count in [12, 16] {
+            for (photometric, expected) in [(1, &samples[..]), (0, &inverted[..])] {
+                let file = image(big_endian, photometric, byte_count, &samples);
+                let mut decoder = Decoder::new(Cursor::new(&file)).unwrap();
+                match decoder.read_image().unwrap() {
+                    DecodingResult::U16(decoded) => assert_eq!(decoded, expected),
+                    _ => panic!("Wrong data type"),
+                }
+            }
+        }
+    }
+
+    // A strip that is too short for the image is not read past its end.
+    let file = image(false, 1, 10, &samples);
+    let mut decoder = Decoder::new(Cursor::new(&file[..120])).unwrap();
+    assert!(decoder.read_image().is_err());
+}
+
+#[test]
+fn test_multiple_extra_samples() {
+    use std::io::Cursor;
+    use tiff::encoder::TiffEncoder;
+    use tiff::tags::{ExtraSample, PhotometricInterpretation, SampleFormat};
+    use tiff::PixelFormat;
+
+    // Alpha followed by spot color channels, with a single alpha channel for comparison. Values
+    // beyond those of the specification are kept as they are.
+    let spot = ExtraSample::Unspecified;
+    let alpha = ExtraSample::UnassociatedAlpha;
+    for (interpretation, extra_samples, color_type) in [
+        (
+            PhotometricInterpretation::RGB,
+            vec![alpha, spot, ExtraSample::Unknown(3)],
+            ColorType::Multiband {
+                bit_depth: 8,
+                num_samples: 6,
+            },
+        ),
+        (
+            PhotometricInterpretation::CMYK,
+            vec![alpha, spot],
+            ColorType::Multiband {
+                bit_depth: 8,
+                num_samples: 6,
+            },
+        ),
+        (
+            PhotometricInterpretation::RGB,
+            vec![alpha],
+            ColorType::RGBA(8),
+        ),
+    ] {
+        let samples = match color_type {
+            ColorType::Multiband { num_samples, .. } => usize::from(num_samples),
+            _ => 4,
+        };
+        let format = PixelFormat::new(
+            interpretation,
+            vec![8; samples],
+            vec![SampleFormat::Uint; samples],
+        )
+        .with_extra_samples(extra_samples.clone());
+        let data: Vec<u8> = (0..3 * 2 * samples as u8).collect();
+        let mut file = Cursor::new(Vec::new());
+        TiffEncoder::new(&mut file)
+            .unwrap()
+            .write_image_with_format(3, 2, &format, &data)
+            .unwrap();
+
+        file.set_position(0);
+        let mut decoder = Decoder::new(&mut file).unwrap();
+        assert_eq!(decoder.colortype().unwrap(), color_type);
+        assert_eq!(decoder.extra_samples().unwrap(), extra_samples);
+        assert_eq!(decoder.pixel_format().unwrap().alpha(), Some(alpha));
+        match decoder.read_image().unwrap() {
+            DecodingResult::U8(decoded) => assert_eq!(decoded, data),
+            _ => panic!("Wrong data type"),
+        }
+    }
+}
+
+#[test]
+fn test_packbits_planar_cmyk() {
+    use std::io::Cursor;
+    use tiff::decoder::PlaneIndex;
+    use tiff::encoder::compression::{CompressionAlgorithm, Packbits};
+    use tiff::encoder::TiffEncoder;
+    use tiff::tags::{CompressionMethod, PhotometricInterpretation, PlanarConfiguration, Tag};
+
+    // A separation as written by legacy prepress software: every ink in its own plane of
+    // PackBits compressed strips, with runs and literals within the rows.
+    const ROWS_PER_STRIP: u32 = 3;
+    let (width, height) = (16u32, 10u32);
+    let pixel = |x: u32, y: u32, ink: u32| match ink {
+        0 => (x * 16) as u8,
+        1 => (y * 25) as u8,
+        2 if x < 8 => 0xff,
+        2 => 0,
+        _ => ((x + y) % 3 * 100) as u8,
+    };
+
+    let mut file = Cursor::new(Vec::new());
+    {
+        let mut tiff = TiffEncoder::new(&mut file).unwrap();
+        let mut dir = tiff.new_directory().unwrap();
+        let mut offsets = Vec::new();
+        let mut byte_counts = Vec::new();
+        for ink in 0..4 {
+            for top in (0..height).step_by(ROWS_PER_STRIP as usize) {
+                let mut strip = Vec::new();
+                for y in top..(top + ROWS_PER_STRIP).min(height) {
+                    let row: Vec<u8> = (0..width).map(|x| pixel(x, y, ink)).collect();
+                    Packbits.write_to(&mut strip, &row).unwrap();
+                }
+                offsets.push(dir.write_data(&strip[..]).unwrap() as u32);
+                byte_counts.push(strip.len() as u32);
+            }
+        }
+
+        dir.write_tag(Tag::ImageWidth, width).unwrap();
+        dir.write_tag(Tag::ImageLength, height).unwrap();
+        dir.write_tag(Tag::BitsPerSample, &[8u16; 4][..]).unwrap();
+        let compression = CompressionMethod::PackBits.to_u16();
+        dir.write_tag(Tag::Compression, compression).unwrap();
+        let photometric = PhotometricInterpretation::CMYK.to_u16();
+        dir.write_tag(Tag::PhotometricInterpretation, photometric)
+            .unwrap();
+        dir.write_tag(Tag::SamplesPerPixel, 4u16).unwrap();
+        let planar = PlanarConfiguration::Planar.to_u16();
+        dir.write_tag(Tag::PlanarConfiguration, planar).unwrap();
+        dir.write_tag(Tag::RowsPerStrip, ROWS_PER_STRIP).unwrap();
+        dir.write_tag(Tag::StripOffsets, &offsets[..]).unwrap();
+        dir.write_tag(Tag::StripByteCounts, &byte_counts[..])
+            .unwrap();
+        dir.finish().unwrap();
+    }
+
+    file.set_position(0);
+    let mut decoder = Decoder::new(&mut file).unwrap();
+    assert_eq!(decoder.colortype().unwrap(), ColorType::CMYK(8));
+    assert_eq!(decoder.strip_count().unwrap(), 16);
+
+    let expected: Vec<u8> = (0..height)
+        .flat_map(|y| (0..width).flat_map(move |x| (0..4).map(move |ink| pixel(x, y, ink))))
+        .collect();
+    match decoder.read_image().unwrap() {
+        DecodingResult::U8(data) => assert_eq!(data, expected),
+        _ => panic!("Wrong data type"),
+    }
+    for ink in 0..4 {
+        let plane: Vec<u8> = expected.iter().skip(ink).step_by(4).copied().collect();
+        match decoder.read_plane(PlaneIndex(ink as u16)).unwrap() {
+            DecodingResult::U8(data) => assert_eq!(data, plane),
+            _ => panic!("Wrong data type"),
+        }
+    }
+    match decoder.read_region(3, 4, 8, 5).unwrap() {
+        DecodingResult::U8(data) => {
+            let expected: Vec<u8> = (4..9)
+                .flat_map(|y| (3..11).flat_map(move |x| (0..4).map(move |ink| pixel(x, y, ink))))
+                .collect();
+            assert_eq!(data, expected);
+        }
+        _ => panic!("Wrong data type"),
+    }
+}
+
+#[test]
+fn test_gray_alpha_4bit() {
+    use std::io::Cursor;
+
+    // A 3x2 image of 4-bit gray and alpha samples, whose rows are padded to whole bytes.
+    let file = build_tiff(
+        &[
+            (256, 3, 1, &3u16.to_le_bytes()),
+            (257, 3, 1, &2u16.to_le_bytes()),
+            (258, 3, 2, &0x0004_0004u32.to_le_bytes()),
+            (262, 3, 1, &1u16.to_le_bytes()),
+            (273, 4, 1, &110u32.to_le_bytes()),
+            (277, 3, 1, &2u16.to_le_bytes()),
+            (278, 3, 1, &2u16.to_le_bytes()),
+            (279, 4, 1, &6u32.to_le_bytes()),
+        ],
+        &[0x1f, 0x2f, 0x3f, 0x4f, 0x5f, 0x6f],
+    );
+
+    let mut decoder = Decoder::new(Cursor::new(&file)).unwrap();
+    assert_eq!(decoder.colortype().unwrap(), ColorType::GrayA(4));
+    match decoder.read_image().unwrap() {
+        DecodingResult::U8(data) => assert_eq!(data, [0x1f, 0x2f, 0x3f, 0x4f, 0x5f, 0x6f]),
+        _ => panic!("Wrong data type"),
+    }
+}
+
+#[test]
+fn test_white_is_zero_gray_alpha() {
+    use std::io::Cursor;
+
+    // A 2x1 WhiteIsZero image with an alpha sample, which is not inverted.
+    let file = build_tiff(
+        &[
+            (256, 3, 1, &2u16.to_le_bytes()),
+            (257, 3, 1, &1u16.to_le_bytes()),
+            (258, 3, 2, &0x0008_0008u32.to_le_bytes()),
+            (262, 3, 1, &0u16.to_le_bytes()),
+            (273, 4, 1, &110u32.to_le_bytes()),
+            (277, 3, 1, &2u16.to_le_bytes()),
+            (278, 3, 1, &1u16.to_le_bytes()),
+            (279, 4, 1, &4u32.to_le_bytes()),
+        ],
+        &[0x10, 0x80, 0xf0, 0xff],
+    );
+
+    let mut decoder = Decoder::new(Cursor::new(&file)).unwrap();
+    assert_eq!(decoder.colortype().unwrap(), ColorType::GrayA(8));
+    match decoder.read_image().unwrap() {
+        DecodingResult::U8(data) => assert_eq!(data, [0xef, 0x80, 0x0f, 0xff]),
+        _ => panic!("Wrong data type"),
+    }
+}
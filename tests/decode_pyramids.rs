@@ -0,0 +1,328 @@
+extern crate tiff;
+
+use tiff::decoder::{Decoder, Overview};
+use tiff::encoder::{colortype, TiffEncoder};
+use tiff::tags::{NewSubfileType, Tag};
+
+use std::io::Cursor;
+
+/// Encodes one gray image per entry of `sizes`, flagging all but the first as reduced resolution.
+fn encode_levels(sizes: &[u32], reduced_first: bool) -> Cursor<Vec<u8>> {
+    let mut file = Cursor::new(Vec::new());
+    {
+        let mut tiff = TiffEncoder::new(&mut file).unwrap();
+        for (index, &size) in sizes.iter().enumerate() {
+            let data = vec![index as u8; (size * size) as usize];
+            let mut image = tiff.new_image::<colortype::Gray8>(size, size).unwrap();
+            if index > 0 || reduced_first {
+                let new_subfile_type = NewSubfileType::REDUCED_RESOLUTION;
+                image
+                    .encoder()
+                    .write_tag(Tag::NewSubfileType, new_subfile_type.bits())
+                    .unwrap();
+            }
+            image.write_data(&data).unwrap();
+        }
+    }
+    file.set_position(0);
+    file
+}
+
+#[test]
+fn test_new_subfile_type() {
+    let mask = NewSubfileType::REDUCED_RESOLUTION | NewSubfileType::MASK;
+    let mut unknown = NewSubfileType::from_bits(0x11);
+    unknown.remove(NewSubfileType::REDUCED_RESOLUTION);
+    unknown |= NewSubfileType::PAGE;
+    let flags = [
+        None,
+        Some(mask),
+        Some(NewSubfileType::empty()),
+        Some(unknown),
+    ];
+
+    let mut file = Cursor::new(Vec::new());
+    {
+        let mut tiff = TiffEncoder::new(&mut file).unwrap();
+        for new_subfile_type in flags {
+            let mut image = tiff.new_image::<colortype::Gray8>(1, 1).unwrap();
+            if let Some(new_subfile_type) = new_subfile_type {
+                image
+                    .encoder()
+                    .write_tag(Tag::NewSubfileType, new_subfile_type.bits())
+                    .unwrap();
+            }
+            image.write_data(&[0]).unwrap();
+        }
+    }
+
+    file.set_position(0);
+    let mut decoder = Decoder::new(&mut file).unwrap();
+    assert!(decoder.new_subfile_type().unwrap().is_empty());
+
+    decoder.next_image().unwrap();
+    let new_subfile_type = decoder.new_subfile_type().unwrap();
+    assert_eq!(new_subfile_type, mask);
+    assert_eq!(new_subfile_type.bits(), 5);
+    assert!(new_subfile_type.contains(NewSubfileType::MASK));
+    assert!(!new_subfile_type.contains(NewSubfileType::PAGE));
+
+    decoder.next_image().unwrap();
+    assert_eq!(decoder.new_subfile_type().unwrap(), NewSubfileType::empty());
+
+    // Flags unknown to the type are kept.
+    decoder.next_image().unwrap();
+    assert_eq!(decoder.new_subfile_type().unwrap().bits(), 0x12);
+}
+
+#[test]
+fn test_external_overviews() {
+    let main = Decoder::new(encode_levels(&[64], false)).unwrap();
+    let ovr = Decoder::new(encode_levels(&[32, 16], true)).unwrap();
+    let mut decoder = main.with_external_overviews(ovr);
+
+    assert_eq!(
+        decoder.best_overview_for_scale(1.0).unwrap(),
+        Overview::Main(0)
+    );
+    assert_eq!(
+        decoder.best_overview_for_scale(2.0).unwrap(),
+        Overview::Main(0)
+    );
+    assert_eq!(
+        decoder.best_overview_for_scale(0.6).unwrap(),
+        Overview::Main(0)
+    );
+    assert_eq!(
+        decoder.best_overview_for_scale(0.5).unwrap(),
+        Overview::External(0)
+    );
+    assert_eq!(
+        decoder.best_overview_for_scale(0.3).unwrap(),
+        Overview::External(0)
+    );
+    assert_eq!(
+        decoder.best_overview_for_scale(0.01).unwrap(),
+        Overview::External(1)
+    );
+    assert!(decoder.best_overview_for_scale(0.0).is_err());
+    assert!(decoder.best_overview_for_scale(f64::NAN).is_err());
+
+    let level = decoder.seek_to_overview(Overview::External(1)).unwrap();
+    assert_eq!(level.dimensions().unwrap(), (16, 16));
+
+    // The primary image is not affected by looking at the overviews.
+    assert_eq!(decoder.dimensions().unwrap(), (64, 64));
+}
+
+#[test]
+fn test_internal_overviews_win_ties() {
+    let main = Decoder::new(encode_levels(&[64, 32], false)).unwrap();
+    let ovr = Decoder::new(encode_levels(&[32, 16], true)).unwrap();
+    let mut decoder = main.with_external_overviews(ovr);
+
+    assert_eq!(
+        decoder.best_overview_for_scale(0.5).unwrap(),
+        Overview::Main(1)
+    );
+    assert_eq!(
+        decoder.best_overview_for_scale(0.25).unwrap(),
+        Overview::External(1)
+    );
+}
+
+#[test]
+fn test_no_external_overviews() {
+    let mut decoder = Decoder::new(encode_levels(&[64], false)).unwrap();
+
+    assert_eq!(
+        decoder.best_overview_for_scale(0.1).unwrap(),
+        Overview::Main(0)
+    );
+    assert!(decoder.external_overviews().is_none());
+    assert!(decoder.seek_to_overview(Overview::External(0)).is_err());
+}
+
+#[test]
+fn test_select_level() {
+    let mut decoder = Decoder::new(encode_levels(&[64, 32, 16, 8], false)).unwrap();
+
+    assert_eq!(decoder.select_level(20, 20).unwrap(), 1);
+    assert_eq!(decoder.dimensions().unwrap(), (32, 32));
+    assert_eq!(decoder.select_level(16, 16).unwrap(), 2);
+    assert_eq!(decoder.dimensions().unwrap(), (16, 16));
+    assert_eq!(decoder.select_level(1, 1).unwrap(), 3);
+    assert_eq!(decoder.select_level(33, 8).unwrap(), 0);
+    assert_eq!(decoder.select_level(1000, 1000).unwrap(), 0);
+    assert_eq!(decoder.dimensions().unwrap(), (64, 64));
+}
+
+/// Builds a little-endian tiled file with a 32x16 image and a 16x8 overview in COG layout: the
+/// GDAL ghost header and both directories come first, followed by the tiles of the overview and
+/// then of the full resolution image, each preceded by its size.
+fn build_cog() -> Vec<u8> {
+    const TILE: usize = 16 * 16;
+    let ghost = b"LAYOUT=IFDS_BEFORE_DATA\nBLOCK_LEADER=SIZE_AS_UINT4\n";
+
+    let mut file = b"II*\0\0\0\0\0".to_vec();
+    file.extend_from_slice(
+        format!("GDAL_STRUCTURAL_METADATA_SIZE={:06} bytes\n", ghost.len()).as_bytes(),
+    );
+    file.extend_from_slice(ghost);
+
+    // Each directory has up to 11 entries, the tile offsets of the full image are stored after.
+    let ifd0 = file.len();
+    let ifd1 = ifd0 + 2 + 11 * 12 + 4;
+    let arrays = ifd1 + 2 + 11 * 12 + 4;
+    let overview_tile = arrays + 16 + 4;
+    let main_tiles = [overview_tile + TILE + 4, overview_tile + 2 * (TILE + 4)];
+
+    let entry = |tag: u16, typ: u16, count: u32, value: u32| {
+        let mut entry = Vec::new();
+        entry.extend_from_slice(&tag.to_le_bytes());
+        entry.extend_from_slice(&typ.to_le_bytes());
+        entry.extend_from_slice(&count.to_le_bytes());
+        entry.extend_from_slice(&value.to_le_bytes());
+        entry
+    };
+    let directory = |width: u32, height: u32, overview: bool, tiles: (u32, u32, u32), next| {
+        let mut entries = Vec::new();
+        if overview {
+            entries.push(entry(254, 4, 1, 1));
+        }
+        entries.push(entry(256, 4, 1, width));
+        entries.push(entry(257, 4, 1, height));
+        entries.push(entry(258, 3, 1, 8));
+        entries.push(entry(259, 3, 1, 1));
+        entries.push(entry(262, 3, 1, 1));
+        entries.push(entry(277, 3, 1, 1));
+        entries.push(entry(322, 3, 1, 16));
+        entries.push(entry(323, 3, 1, 16));
+        entries.push(entry(324, 4, tiles.0, tiles.1));
+        entries.push(entry(325, 4, tiles.0, tiles.2));
+        while entries.len() < 11 {
+            // Pad with a harmless private tag to keep the layout fixed.
+            entries.push(entry(65000, 3, 1, 0));
+        }
+
+        let mut ifd = 11u16.to_le_bytes().to_vec();
+        ifd.extend(entries.concat());
+        ifd.extend_from_slice(&u32::to_le_bytes(next));
+        ifd
+    };
+
+    file[4..8].copy_from_slice(&(ifd0 as u32).to_le_bytes());
+    file.extend(directory(
+        32,
+        16,
+        false,
+        (2, arrays as u32, arrays as u32 + 8),
+        ifd1 as u32,
+    ));
+    file.extend(directory(
+        16,
+        8,
+        true,
+        (1, overview_tile as u32, TILE as u32),
+        0,
+    ));
+    for offset in main_tiles {
+        file.extend_from_slice(&(offset as u32).to_le_bytes());
+    }
+    for _ in main_tiles {
+        file.extend_from_slice(&(TILE as u32).to_le_bytes());
+    }
+
+    for value in [1, 2, 3] {
+        file.extend_from_slice(&(TILE as u32).to_le_bytes());
+        file.extend_from_slice(&[value; TILE]);
+    }
+    assert_eq!(file.len(), main_tiles[1] + TILE);
+    file
+}
+
+#[test]
+fn test_cog_report() {
+    let file = build_cog();
+    let mut decoder = Decoder::new(Cursor::new(&file)).unwrap();
+    let report = decoder.cog_report().unwrap();
+    assert!(report.is_compliant(), "{:?}", report);
+    assert_eq!(report.overview_count, 1);
+    assert!(report.ghost_header);
+    assert!(report.block_leaders);
+    // The current image is not changed by the assessment.
+    assert_eq!(decoder.dimensions().unwrap(), (32, 16));
+
+    // A broken block leader.
+    let mut broken = file.clone();
+    let last_leader = broken.len() - 256 - 4;
+    broken[last_leader] = 0xff;
+    let report = Decoder::new(Cursor::new(&broken))
+        .unwrap()
+        .cog_report()
+        .unwrap();
+    assert!(report.is_compliant());
+    assert!(!report.block_leaders);
+
+    // Files written by the encoder store the directories after the data and are not tiled.
+    let mut file = encode_levels(&[32, 16], false);
+    let report = Decoder::new(&mut file).unwrap().cog_report().unwrap();
+    assert!(!report.is_compliant());
+    assert!(!report.tiled);
+    assert!(!report.ifds_before_data);
+    assert!(!report.ghost_header);
+}
+
+#[test]
+fn test_svs_layout() {
+    const LIBRARY: &str = "Aperio Image Library v12.0.15";
+
+    // The full resolution image, the thumbnail, a reduced resolution level, the label and the
+    // macro image, in the order of an SVS file.
+    let images = [
+        (
+            64,
+            0,
+            "\n64x64 [0,0 64x64] (16x16) JPEG/RGB Q=70|AppMag = 20|MPP = 0.499",
+        ),
+        (16, 0, "\n64x64 -> 16x16 - |AppMag = 20|MPP = 0.499"),
+        (32, 1, "\n64x64 [0,0 64x64] (16x16) -> 32x32 JPEG/RGB Q=70"),
+        (8, 1, "\nlabel 8x8"),
+        (24, 9, "\nmacro 24x24"),
+    ];
+
+    let mut file = Cursor::new(Vec::new());
+    {
+        let mut tiff = TiffEncoder::new(&mut file).unwrap();
+        for &(size, new_subfile_type, description) in &images {
+            let mut image = tiff.new_image::<colortype::Gray8>(size, size).unwrap();
+            let encoder = image.encoder();
+            encoder
+                .write_tag(Tag::NewSubfileType, new_subfile_type)
+                .unwrap();
+            encoder
+                .write_tag(
+                    Tag::ImageDescription,
+                    &*format!("{}{}", LIBRARY, description),
+                )
+                .unwrap();
+            image.write_data(&vec![0; (size * size) as usize]).unwrap();
+        }
+    }
+
+    file.set_position(0);
+    let mut decoder = Decoder::new(&mut file).unwrap();
+    decoder.seek_to_image(2).unwrap();
+    let layout = decoder.svs_layout().unwrap().unwrap();
+    assert!(layout.description.starts_with(LIBRARY));
+    assert!(layout.description.contains("MPP = 0.499"));
+    assert_eq!(layout.levels, [0, 2]);
+    assert_eq!(layout.thumbnail, Some(1));
+    assert_eq!(layout.label, Some(3));
+    assert_eq!(layout.macro_image, Some(4));
+    // The current image is not changed by the identification.
+    assert_eq!(decoder.dimensions().unwrap(), (32, 32));
+
+    let mut decoder = Decoder::new(encode_levels(&[32, 16], false)).unwrap();
+    assert_eq!(decoder.svs_layout().unwrap(), None);
+}
@@ -1,8 +1,11 @@
 extern crate tiff;
 
-use tiff::decoder::{ifd, Decoder, DecodingResult};
-use tiff::encoder::{colortype, Ifd, Ifd8, SRational, TiffEncoder};
-use tiff::tags::Tag;
+use tiff::decoder::{ifd, ChunkIndex, Decoder, DecodingResult, MetadataDirectory, PlaneIndex};
+use tiff::encoder::{
+    colortype, Ifd, Ifd8, IfdPointer, Rational, SRational, TiffEncoder, TiffKindBig,
+    TiffKindStandard, Undefined,
+};
+use tiff::tags::{Tag, Type};
 use tiff::ColorType;
 
 use std::fs::File;
@@ -89,6 +92,98 @@ fn encode_decode() {
     }
 }
 
+#[test]
+fn test_new_auto() {
+    use tiff::encoder::{image_size, Compression};
+    use tiff::tags::{
+        CompressionMethod, PhotometricInterpretation, PlanarConfiguration, SampleFormat,
+    };
+    use tiff::PixelFormat;
+
+    // Sizes saturate instead of overflowing, and rows of planes are padded separately.
+    assert_eq!(image_size::<colortype::Gray8>(100, 100), 10_000);
+    assert_eq!(
+        image_size::<colortype::RGBA64>(u32::MAX, u32::MAX),
+        u64::MAX
+    );
+    let bilevel = PixelFormat::new(
+        PhotometricInterpretation::RGB,
+        vec![1; 3],
+        vec![SampleFormat::Uint; 3],
+    );
+    assert_eq!(bilevel.image_size(3, 2), 2 * 2);
+    let planar = bilevel.with_planar_config(PlanarConfiguration::Planar);
+    assert_eq!(planar.image_size(3, 2), 3 * 2);
+
+    let image_data: Vec<u8> = (0..100 * 100).map(|i| i as u8).collect();
+    for (expected_size, big) in [
+        (image_size::<colortype::Gray8>(100, 100), false),
+        (image_size::<colortype::RGB16>(40_000, 40_000), true),
+    ] {
+        let mut file = Cursor::new(Vec::new());
+        {
+            let mut tiff = TiffEncoder::new_auto(&mut file, expected_size)
+                .unwrap()
+                .with_compression(Compression::Lzw);
+            assert_eq!(tiff.is_big(), big);
+            tiff.write_image::<colortype::Gray8>(100, 100, &image_data)
+                .unwrap();
+        }
+
+        file.set_position(0);
+        let mut decoder = Decoder::new(&mut file).unwrap();
+        assert_eq!(decoder.is_bigtiff(), big);
+        assert_eq!(
+            decoder.get_tag_u32(Tag::Compression).unwrap(),
+            u32::from(CompressionMethod::LZW.to_u16())
+        );
+        match decoder.read_image().unwrap() {
+            DecodingResult::U8(data) => assert_eq!(data, image_data),
+            _ => panic!("Wrong data type"),
+        }
+    }
+}
+
+#[test]
+fn test_standard_tiff_beyond_4gib() {
+    use std::io::Write;
+    use tiff::{TiffError, UsageError};
+
+    /// Discards everything written to it.
+    struct Sink(u64);
+
+    impl Write for Sink {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0 += buf.len() as u64;
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Seek for Sink {
+        fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+            if let SeekFrom::Start(pos) = pos {
+                self.0 = pos;
+            }
+            Ok(self.0)
+        }
+    }
+
+    let chunk = vec![0u8; 1 << 26];
+    let mut tiff = TiffEncoder::new(Sink(0)).unwrap();
+    let mut dir = tiff.new_directory().unwrap();
+    while dir.write_data(&chunk[..]).unwrap() <= u64::from(u32::MAX) {}
+    dir.write_tag(Tag::Artist, "beyond 4 GiB").unwrap();
+    let err = dir.finish().unwrap_err();
+    assert!(matches!(
+        err,
+        TiffError::UsageError(UsageError::BigTiffRequired)
+    ));
+}
+
 #[test]
 fn encode_decode_big() {
     let mut image_data = Vec::new();
@@ -217,6 +312,253 @@ fn test_encode_ifd() {
     }
 }
 
+#[test]
+fn test_encode_empty_image() {
+    use tiff::{TiffError, TiffFormatError};
+
+    for (width, height) in [(0, 4), (4, 0), (0, 0)] {
+        let mut data = Cursor::new(Vec::new());
+        let mut tiff = TiffEncoder::new(&mut data).unwrap();
+        match tiff.new_image::<colortype::Gray8>(width, height) {
+            Err(TiffError::FormatError(TiffFormatError::InvalidDimensions(w, h))) => {
+                assert_eq!((w, h), (width, height));
+            }
+            _ => panic!("Empty image was not rejected"),
+        }
+        match tiff.write_image::<colortype::Gray8>(width, height, &[]) {
+            Err(TiffError::FormatError(TiffFormatError::InvalidDimensions(..))) => {}
+            _ => panic!("Empty image was not rejected"),
+        }
+    }
+}
+
+#[test]
+fn test_encode_ifd_pointer() {
+    fn pointer_types(data: &mut Cursor<Vec<u8>>) -> Vec<(u16, Type, u64)> {
+        data.set_position(0);
+        let mut decoder = Decoder::new(data).unwrap();
+        decoder
+            .raw_tag_iter()
+            .map(|entry| entry.map(|(tag, type_, count, _)| (tag, type_, count)))
+            .filter(|entry| !matches!(entry, Ok((tag, ..)) if *tag < 65000))
+            .collect::<Result<_, _>>()
+            .unwrap()
+    }
+
+    let mut data = Cursor::new(Vec::new());
+    {
+        let mut tiff = TiffEncoder::new(&mut data).unwrap();
+        let mut image = tiff.new_image::<colortype::Gray8>(1, 1).unwrap();
+        let pointer = IfdPointer::<TiffKindStandard>::new(42).unwrap();
+        image
+            .encoder()
+            .write_tag(Tag::Unknown(65000), pointer)
+            .unwrap();
+        let pointers = [
+            IfdPointer::<TiffKindStandard>::new(1).unwrap(),
+            IfdPointer::<TiffKindStandard>::new(2).unwrap(),
+        ];
+        image
+            .encoder()
+            .write_tag(Tag::Unknown(65001), &pointers[..])
+            .unwrap();
+        image.write_data(&[0]).unwrap();
+    }
+    assert_eq!(
+        pointer_types(&mut data),
+        [(65000, Type::IFD, 1), (65001, Type::IFD, 2)]
+    );
+
+    let mut data = Cursor::new(Vec::new());
+    {
+        let mut tiff = TiffEncoder::new_big(&mut data).unwrap();
+        let mut image = tiff.new_image::<colortype::Gray8>(1, 1).unwrap();
+        let pointer = IfdPointer::<TiffKindBig>::new(1 << 40).unwrap();
+        image
+            .encoder()
+            .write_tag(Tag::Unknown(65000), pointer)
+            .unwrap();
+        image.write_data(&[0]).unwrap();
+    }
+    assert_eq!(pointer_types(&mut data), [(65000, Type::IFD8, 1)]);
+
+    assert!(IfdPointer::<TiffKindStandard>::new(1 << 40).is_err());
+}
+
+#[test]
+fn test_write_raw_ifd() {
+    let mut data = Cursor::new(Vec::new());
+    {
+        let mut tiff = TiffEncoder::new(&mut data).unwrap();
+        let pointer = tiff
+            .write_ifd(&[
+                (1, Type::UNDEFINED, 4, b"0100"),
+                (2, Type::ASCII, 12, b"hello world\0"),
+                (0x8000, Type::SHORT, 1, &[7, 0]),
+            ])
+            .unwrap();
+        let mut image = tiff.new_image::<colortype::Gray8>(1, 1).unwrap();
+        image
+            .encoder()
+            .write_tag(Tag::ExifDirectory, pointer)
+            .unwrap();
+        image.write_data(&[0]).unwrap();
+    }
+
+    data.set_position(0);
+    let mut decoder = Decoder::new(&mut data).unwrap();
+    let offset = decoder.get_tag_u64(Tag::ExifDirectory).unwrap() as usize;
+    assert!(!decoder.more_images());
+
+    let bytes = data.get_ref();
+    let value_offset = u32::from_le_bytes(bytes[offset + 22..offset + 26].try_into().unwrap());
+    let value_offset = value_offset as usize;
+    assert_eq!(&bytes[value_offset..value_offset + 12], b"hello world\0");
+
+    let mut expected = vec![3, 0];
+    expected.extend_from_slice(&[1, 0, 7, 0, 4, 0, 0, 0, b'0', b'1', b'0', b'0']);
+    expected.extend_from_slice(&[2, 0, 2, 0, 12, 0, 0, 0]);
+    expected.extend_from_slice(&(value_offset as u32).to_le_bytes());
+    expected.extend_from_slice(&[0, 0x80, 3, 0, 1, 0, 0, 0, 7, 0, 0, 0]);
+    expected.extend_from_slice(&[0, 0, 0, 0]);
+    assert_eq!(&bytes[offset..offset + expected.len()], &expected[..]);
+}
+
+/// The entries of a directory and the directories it points to, without the offsets that change
+/// when the file is rewritten.
+fn entries_without_offsets(
+    directory: &MetadataDirectory,
+) -> Vec<Vec<(Tag, Type, u64, ifd::Value)>> {
+    let offset_tags = [
+        Tag::StripOffsets,
+        Tag::TileOffsets,
+        Tag::SubIFDs,
+        Tag::ExifDirectory,
+        Tag::JPEGInterchangeFormat,
+    ];
+    let entries = directory
+        .entries
+        .iter()
+        .filter(|entry| !offset_tags.contains(&entry.tag))
+        .map(|entry| {
+            (
+                entry.tag,
+                entry.field_type,
+                entry.count,
+                entry.value.clone(),
+            )
+        })
+        .collect();
+
+    let mut directories = vec![entries];
+    for (_, sub_directory) in &directory.sub_directories {
+        directories.extend(entries_without_offsets(sub_directory));
+    }
+    directories
+}
+
+fn assert_same_images(original: &[u8], compacted: &[u8]) {
+    let mut original = Decoder::new(Cursor::new(original)).unwrap();
+    let mut compacted = Decoder::new(Cursor::new(compacted)).unwrap();
+
+    let expected = original.dump_metadata().unwrap();
+    let actual = compacted.dump_metadata().unwrap();
+    assert_eq!(actual.images.len(), expected.images.len());
+    for (actual, expected) in actual.images.iter().zip(&expected.images) {
+        assert_eq!(
+            entries_without_offsets(actual),
+            entries_without_offsets(expected)
+        );
+    }
+
+    loop {
+        let expected = original.read_image().unwrap();
+        let actual = compacted.read_image().unwrap();
+        assert_eq!(format!("{:?}", actual), format!("{:?}", expected));
+        if !original.more_images() {
+            break;
+        }
+        original.next_image().unwrap();
+        compacted.next_image().unwrap();
+    }
+    assert!(!compacted.more_images());
+}
+
+#[test]
+fn test_compact() {
+    let mut data = Cursor::new(Vec::new());
+    {
+        let mut tiff = TiffEncoder::new(&mut data).unwrap();
+        let exif = tiff
+            .write_ifd(&[(0x9000, Type::UNDEFINED, 4, b"0232")])
+            .unwrap();
+
+        let mut image = tiff.new_image::<colortype::Gray8>(32, 32).unwrap();
+        // Space that no directory refers to, as left behind by edits.
+        image.encoder().write_data(&[0xaa; 4096][..]).unwrap();
+        image
+            .encoder()
+            .write_tag(Tag::Artist, "Image-tiff")
+            .unwrap();
+        image
+            .encoder()
+            .write_tag(Tag::ExifDirectory, &exif)
+            .unwrap();
+        image
+            .new_sub_image::<colortype::Gray8>(16, 16)
+            .unwrap()
+            .write_data(&[1; 16 * 16])
+            .unwrap();
+        image.rows_per_strip(4).unwrap();
+        let samples: Vec<u8> = (0..32 * 32).map(|i| i as u8).collect();
+        image.write_data(&samples).unwrap();
+
+        // A directory shared by both images is no cycle.
+        let mut image = tiff.new_image::<colortype::RGB16>(3, 2).unwrap();
+        image.encoder().write_tag(Tag::ExifDirectory, exif).unwrap();
+        image.write_data(&[7; 18]).unwrap();
+    }
+
+    let mut compacted = Cursor::new(Vec::new());
+    let mut decoder = Decoder::new(Cursor::new(data.get_ref())).unwrap();
+    tiff::encoder::compact(&mut decoder, &mut compacted).unwrap();
+
+    let (original, compacted) = (data.get_ref(), compacted.get_ref());
+    assert!(compacted.len() + 4096 <= original.len());
+    assert_same_images(original, compacted);
+
+    let mut decoder = Decoder::new(Cursor::new(compacted)).unwrap();
+    decoder.seek_to_sub_image(0).unwrap();
+    match decoder.read_image().unwrap() {
+        DecodingResult::U8(samples) => assert_eq!(samples, [1; 16 * 16]),
+        _ => panic!("Wrong data type"),
+    }
+}
+
+#[test]
+fn test_compact_files() {
+    for file in [
+        "rgb-3c-8b.tiff",
+        "tiled-rgb-u8.tif",
+        "planar-rgb-u8.tif",
+        "quad-tile.jpg.tiff",
+        "bigtiff/BigTIFFMotorola.tif",
+    ] {
+        let original = std::fs::read(PathBuf::from(TEST_IMAGE_DIR).join(file)).unwrap();
+        let mut compacted = Cursor::new(Vec::new());
+        let mut decoder = Decoder::new(Cursor::new(&original)).unwrap();
+        tiff::encoder::compact(&mut decoder, &mut compacted).unwrap();
+
+        assert_eq!(
+            Decoder::new(Cursor::new(compacted.get_ref()))
+                .unwrap()
+                .is_bigtiff(),
+            decoder.is_bigtiff()
+        );
+        assert_same_images(&original, compacted.get_ref());
+    }
+}
+
 #[test]
 /// Test that attempting to encode when the input buffer is undersized returns
 /// an error rather than panicking.
@@ -233,6 +575,48 @@ fn test_encode_undersized_buffer() {
 
 const TEST_IMAGE_DIR: &str = "./tests/images/";
 
+#[test]
+fn test_write_decoding_result() {
+    use tiff::{TiffError, TiffUnsupportedError};
+
+    for file in [
+        "minisblack-1c-8b.tiff",
+        "minisblack-1c-i16b.tiff",
+        "rgb-3c-16b.tiff",
+        "gradient-3c-32b-float.tiff",
+        "cmyk-3c-8b.tiff",
+    ] {
+        let path = PathBuf::from(TEST_IMAGE_DIR).join(file);
+        let mut decoder = Decoder::new(File::open(path).unwrap()).unwrap();
+        let (width, height) = decoder.dimensions().unwrap();
+        let color_type = decoder.colortype().unwrap();
+        let image = decoder.read_image().unwrap();
+
+        let mut data = Cursor::new(Vec::new());
+        TiffEncoder::new(&mut data)
+            .unwrap()
+            .write_decoding_result(width, height, color_type, &image)
+            .unwrap();
+
+        data.set_position(0);
+        let mut decoder = Decoder::new(&mut data).unwrap();
+        assert_eq!(decoder.dimensions().unwrap(), (width, height));
+        assert_eq!(decoder.colortype().unwrap(), color_type);
+        let roundtrip = decoder.read_image().unwrap();
+        assert_eq!(format!("{:?}", roundtrip), format!("{:?}", image));
+    }
+
+    let mut data = Cursor::new(Vec::new());
+    let mut tiff = TiffEncoder::new(&mut data).unwrap();
+    let image = DecodingResult::U16(vec![0; 4]);
+    match tiff.write_decoding_result(2, 2, ColorType::Gray(8), &image) {
+        Err(TiffError::UnsupportedError(TiffUnsupportedError::UnsupportedColorType(
+            ColorType::Gray(8),
+        ))) => {}
+        _ => panic!("Mismatched sample type was not rejected"),
+    }
+}
+
 macro_rules! test_roundtrip {
     ($name:ident, $buffer:ident, $buffer_ty:ty) => {
         fn $name<C: colortype::ColorType<Inner = $buffer_ty>>(
@@ -594,6 +978,7 @@ fn test_rows_per_strip() {
         assert_eq!(image.next_strip_sample_count(), 100 * 100);
         image.rows_per_strip(2).unwrap();
         assert_eq!(image.next_strip_sample_count(), 2 * 100);
+        image.reserve_strips(50);
 
         let img2: Vec<u8> = vec![0; 2 * 100];
         image.write_strip(&img2[..]).unwrap();
@@ -614,10 +999,1233 @@ fn test_rows_per_strip() {
 
         for i in 0..50 {
             let img2 = [i; 2 * 100];
-            match decoder.read_chunk(i as u32).unwrap() {
+            match decoder.read_chunk_at(ChunkIndex(i as u32)).unwrap() {
                 DecodingResult::U8(data) => assert_eq!(&img2[..], &data[..]),
                 other => panic!("Incorrect strip type {:?}", other),
             }
         }
     }
 }
+
+#[test]
+fn test_reserve_strip_tables() {
+    let data: Vec<u8> = (0..100 * 100).map(|i| (i % 251) as u8).collect();
+    let encode = |reserved: Option<usize>| {
+        let mut file = Cursor::new(Vec::new());
+        let mut tiff = TiffEncoder::new(&mut file).unwrap();
+        let mut image = tiff.new_image::<colortype::Gray8>(100, 100).unwrap();
+        image.rows_per_strip(2).unwrap();
+        if let Some(strips) = reserved {
+            image.reserve_strip_tables(strips).unwrap();
+        }
+        image.write_data(&data).map(|()| file.into_inner())
+    };
+
+    // The tables are written into the reserved room ahead of the strips, not after them.
+    let plain = encode(None).unwrap();
+    let reserved = encode(Some(50)).unwrap();
+    assert_eq!(reserved.len(), plain.len());
+
+    let mut decoder = Decoder::new(Cursor::new(&reserved)).unwrap();
+    let offsets = decoder.get_tag_u64_vec(Tag::StripOffsets).unwrap();
+    assert_eq!(offsets[0], 8 + 2 * 50 * 4);
+    assert_eq!(reserved[8..12], 408u32.to_ne_bytes());
+    assert_eq!(reserved[208..212], 200u32.to_ne_bytes());
+    match decoder.read_image().unwrap() {
+        DecodingResult::U8(decoded) => assert_eq!(decoded, data),
+        _ => panic!("Wrong data type"),
+    }
+
+    // Reserving more strips than written leaves room unused, fewer strips fail.
+    let mut decoder = Decoder::new(Cursor::new(encode(Some(60)).unwrap())).unwrap();
+    assert_eq!(decoder.strip_count().unwrap(), 50);
+    assert!(encode(Some(49)).is_err());
+
+    // A capacity hint alone neither limits the strips nor moves the tables.
+    let mut file = Cursor::new(Vec::new());
+    let mut tiff = TiffEncoder::new(&mut file).unwrap();
+    let mut image = tiff.new_image::<colortype::Gray8>(100, 100).unwrap();
+    image.rows_per_strip(2).unwrap();
+    image.reserve_strips(10);
+    image.write_data(&data).unwrap();
+    assert_eq!(file.into_inner(), plain);
+}
+
+#[test]
+/// verify placement tags survive a roundtrip
+fn test_position_and_clip_path() {
+    let path: Vec<u8> = (0..26).collect();
+    let mut file = Cursor::new(Vec::new());
+    {
+        let mut img_encoder = TiffEncoder::new(&mut file).unwrap();
+        let mut image = img_encoder.new_image::<colortype::Gray8>(10, 10).unwrap();
+
+        assert!(image.x_position(Rational { n: 1, d: 0 }).is_err());
+        assert!(image.y_position(Rational { n: 1, d: 0 }).is_err());
+        assert!(image.clip_path(&[], 10, 10).is_err());
+        assert!(image.clip_path(&path, 0, 10).is_err());
+
+        image.x_position(Rational { n: 3, d: 2 }).unwrap();
+        image.y_position(Rational { n: 7, d: 4 }).unwrap();
+        image.clip_path(&path, 20, 40).unwrap();
+        image.write_data(&[0; 100]).unwrap();
+    }
+
+    file.seek(SeekFrom::Start(0)).unwrap();
+    {
+        let mut decoder = Decoder::new(&mut file).unwrap();
+        assert_eq!(
+            decoder.get_tag(Tag::XPosition).unwrap(),
+            ifd::Value::Rational(3, 2)
+        );
+        assert_eq!(
+            decoder.get_tag(Tag::YPosition).unwrap(),
+            ifd::Value::Rational(7, 4)
+        );
+        assert_eq!(
+            decoder.find_tag_unsigned_vec::<u8>(Tag::ClipPath).unwrap(),
+            Some(path)
+        );
+        assert_eq!(decoder.get_tag_u32(Tag::XClipPathUnits).unwrap(), 20);
+        assert_eq!(decoder.get_tag_u32(Tag::YClipPathUnits).unwrap(), 40);
+    }
+}
+
+#[test]
+/// verify scalar tags can be read without going through the file
+fn test_inline_tags() {
+    let mut file = Cursor::new(Vec::new());
+    {
+        let mut img_encoder = TiffEncoder::new(&mut file).unwrap();
+        let mut image = img_encoder.new_image::<colortype::RGB8>(7, 3).unwrap();
+        image
+            .encoder()
+            .write_tag(Tag::Artist, "Image-tiff")
+            .unwrap();
+        image.write_data(&[0; 7 * 3 * 3]).unwrap();
+    }
+
+    file.seek(SeekFrom::Start(0)).unwrap();
+    {
+        let decoder = Decoder::new(&mut file).unwrap();
+        assert_eq!(
+            decoder.find_inline_tag(Tag::ImageWidth),
+            Some(ifd::Value::Unsigned(7))
+        );
+        assert_eq!(
+            decoder
+                .find_inline_tag_unsigned::<u16>(Tag::ImageLength)
+                .unwrap(),
+            Some(3)
+        );
+        assert_eq!(
+            decoder
+                .find_inline_tag_unsigned::<u8>(Tag::SamplesPerPixel)
+                .unwrap(),
+            Some(3)
+        );
+        // Not present, a list of values and a value stored out of line.
+        assert_eq!(decoder.find_inline_tag(Tag::Copyright), None);
+        assert_eq!(decoder.find_inline_tag(Tag::BitsPerSample), None);
+        assert_eq!(decoder.find_inline_tag(Tag::XResolution), None);
+        assert_eq!(decoder.find_inline_tag(Tag::Artist), None);
+    }
+}
+
+#[test]
+fn test_jpeg_thumbnail() {
+    // Not a valid image, the encoder only checks for the start of image marker.
+    let jpeg = [0xff, 0xd8, 0xff, 0xe0, 0x00, 0x02, 0xff, 0xd9];
+
+    let mut file = Cursor::new(Vec::new());
+    {
+        let mut img_encoder = TiffEncoder::new(&mut file).unwrap();
+        img_encoder
+            .write_image::<colortype::Gray8>(4, 4, &[0; 16])
+            .unwrap();
+        img_encoder.write_jpeg_thumbnail(2, 2, &jpeg).unwrap();
+        assert!(img_encoder.write_jpeg_thumbnail(2, 2, &[0, 0]).is_err());
+    }
+
+    file.seek(SeekFrom::Start(0)).unwrap();
+    let mut decoder = Decoder::new(&mut file).unwrap();
+    decoder.next_image().unwrap();
+    assert_eq!(decoder.dimensions().unwrap(), (2, 2));
+    assert_eq!(decoder.get_tag_u32(Tag::NewSubfileType).unwrap(), 1);
+    assert_eq!(decoder.get_tag_u32(Tag::Compression).unwrap(), 6);
+    let offset = decoder.get_tag_u32(Tag::JPEGInterchangeFormat).unwrap() as usize;
+    let length = decoder
+        .get_tag_u32(Tag::JPEGInterchangeFormatLength)
+        .unwrap() as usize;
+    assert!(!decoder.more_images());
+
+    let data = file.get_ref();
+    assert_eq!(&data[offset..offset + length], &jpeg);
+}
+
+#[test]
+fn test_ycbcra_roundtrip() {
+    use tiff::tags::ExtraSample;
+
+    let image_data: Vec<u8> = (0..16 * 8 * 4).map(|i| i as u8).collect();
+
+    let mut file = Cursor::new(Vec::new());
+    {
+        let mut tiff = TiffEncoder::new(&mut file).unwrap();
+        tiff.write_image::<colortype::YCbCrA8>(16, 8, &image_data)
+            .unwrap();
+    }
+
+    file.seek(SeekFrom::Start(0)).unwrap();
+    let mut decoder = Decoder::new(&mut file).unwrap();
+    assert_eq!(decoder.colortype().unwrap(), ColorType::YCbCrA(8));
+    assert_eq!(
+        decoder.extra_samples().unwrap(),
+        [ExtraSample::UnassociatedAlpha]
+    );
+    assert_eq!(
+        decoder.get_tag_u32_vec(Tag::YCbCrSubSampling).unwrap(),
+        [1, 1]
+    );
+    let rationals = |values: &[u32], d| {
+        ifd::Value::List(values.iter().map(|&n| ifd::Value::Rational(n, d)).collect())
+    };
+    assert_eq!(
+        decoder.get_tag(Tag::ReferenceBlackWhite).unwrap(),
+        rationals(&[0, 255, 128, 255, 128, 255], 1)
+    );
+    assert_eq!(
+        decoder.get_tag(Tag::YCbCrCoefficients).unwrap(),
+        rationals(&[299, 587, 114], 1000)
+    );
+    match decoder.read_image().unwrap() {
+        DecodingResult::U8(data) => assert_eq!(data, image_data),
+        _ => panic!("Wrong data type"),
+    }
+}
+
+#[test]
+fn test_cmyka_roundtrip() {
+    let image_data: Vec<u16> = (0..16 * 8 * 5).map(|i| i as u16 * 97).collect();
+
+    let mut file = Cursor::new(Vec::new());
+    {
+        let mut tiff = TiffEncoder::new(&mut file).unwrap();
+        tiff.write_image::<colortype::CMYKA16>(16, 8, &image_data)
+            .unwrap();
+    }
+
+    file.seek(SeekFrom::Start(0)).unwrap();
+    let mut decoder = Decoder::new(&mut file).unwrap();
+    assert_eq!(decoder.colortype().unwrap(), ColorType::CMYKA(16));
+    assert_eq!(decoder.get_tag_u32(Tag::ExtraSamples).unwrap(), 2);
+    match decoder.read_image().unwrap() {
+        DecodingResult::U16(data) => assert_eq!(data, image_data),
+        _ => panic!("Wrong data type"),
+    }
+}
+
+#[test]
+fn test_gray_alpha_roundtrip() {
+    use tiff::tags::ExtraSample;
+
+    let image_data: Vec<u8> = (0..16 * 8 * 2).map(|i| i as u8).collect();
+    let wide_data: Vec<u16> = (0..16 * 8 * 2).map(|i| i as u16 * 251).collect();
+
+    let mut file = Cursor::new(Vec::new());
+    {
+        let mut tiff = TiffEncoder::new(&mut file).unwrap();
+        tiff.write_image::<colortype::GrayA8>(16, 8, &image_data)
+            .unwrap();
+        tiff.write_decoding_result(
+            16,
+            8,
+            ColorType::GrayA(16),
+            &DecodingResult::U16(wide_data.clone()),
+        )
+        .unwrap();
+        tiff.write_image::<colortype::Gray8>(16, 8, &image_data[..16 * 8])
+            .unwrap();
+    }
+
+    file.seek(SeekFrom::Start(0)).unwrap();
+    let mut decoder = Decoder::new(&mut file).unwrap();
+    assert_eq!(decoder.colortype().unwrap(), ColorType::GrayA(8));
+    assert_eq!(
+        decoder.extra_samples().unwrap(),
+        [ExtraSample::UnassociatedAlpha]
+    );
+    match decoder.read_image().unwrap() {
+        DecodingResult::U8(data) => assert_eq!(data, image_data),
+        _ => panic!("Wrong data type"),
+    }
+
+    decoder.next_image().unwrap();
+    assert_eq!(decoder.colortype().unwrap(), ColorType::GrayA(16));
+    assert_eq!(
+        decoder.extra_samples().unwrap(),
+        [ExtraSample::UnassociatedAlpha]
+    );
+    match decoder.read_image().unwrap() {
+        DecodingResult::U16(data) => assert_eq!(data, wide_data),
+        _ => panic!("Wrong data type"),
+    }
+
+    decoder.next_image().unwrap();
+    assert_eq!(decoder.colortype().unwrap(), ColorType::Gray(8));
+    assert_eq!(decoder.find_tag(Tag::ExtraSamples).unwrap(), None);
+}
+
+#[test]
+fn test_tiff_it_detection() {
+    use tiff::decoder::{TiffItImageType, TiffItProfile};
+    use tiff::encoder::Compression;
+
+    let image_data = [0u8; 8 * 8 * 4];
+    let encode = |compression, tiff_it: bool| {
+        let mut file = Cursor::new(Vec::new());
+        {
+            let mut tiff = TiffEncoder::new(&mut file)
+                .unwrap()
+                .with_compression(compression);
+            let mut image = tiff.new_image::<colortype::CMYK8>(8, 8).unwrap();
+            if tiff_it {
+                image.encoder().write_tag(Tag::Site, "Image-tiff").unwrap();
+                image
+                    .encoder()
+                    .write_tag(Tag::ColorSequence, "CMYK")
+                    .unwrap();
+            }
+            image.write_data(&image_data).unwrap();
+        }
+        file.seek(SeekFrom::Start(0)).unwrap();
+        file
+    };
+
+    let mut file = encode(Compression::Uncompressed, true);
+    let mut decoder = Decoder::new(&mut file).unwrap();
+    assert_eq!(decoder.tiff_it_image_type(), Some(TiffItImageType::CT));
+    assert_eq!(decoder.tiff_it_profile(), Some(TiffItProfile::P1));
+    match decoder.read_image().unwrap() {
+        DecodingResult::U8(data) => assert_eq!(data, image_data),
+        _ => panic!("Wrong data type"),
+    }
+
+    let mut file = encode(Compression::Lzw, true);
+    let decoder = Decoder::new(&mut file).unwrap();
+    assert_eq!(decoder.tiff_it_image_type(), Some(TiffItImageType::CT));
+    assert_eq!(decoder.tiff_it_profile(), Some(TiffItProfile::P2));
+
+    let mut file = encode(Compression::Uncompressed, false);
+    let decoder = Decoder::new(&mut file).unwrap();
+    assert_eq!(decoder.tiff_it_image_type(), None);
+    assert_eq!(decoder.tiff_it_profile(), None);
+}
+
+#[test]
+fn test_reserved_tags() {
+    let mut file = Cursor::new(Vec::new());
+    {
+        let mut tiff = TiffEncoder::new(&mut file).unwrap();
+        let mut image = tiff.new_image::<colortype::Gray8>(4, 4).unwrap();
+        let checksum = image
+            .encoder()
+            .reserve_tag(Tag::Unknown(65000), 0u32)
+            .unwrap();
+        let software = image
+            .encoder()
+            .reserve_tag(Tag::Software, "????????")
+            .unwrap();
+        image.write_data(&[1; 16]).unwrap();
+
+        // The type and the number of values must match the placeholder.
+        assert!(tiff.patch_tag(&checksum, 0u16).is_err());
+        assert!(tiff.patch_tag(&software, "too long!").is_err());
+        tiff.patch_tag(&checksum, 0xdead_beefu32).unwrap();
+        tiff.patch_tag(&software, "patched!").unwrap();
+    }
+
+    file.seek(SeekFrom::Start(0)).unwrap();
+    let mut decoder = Decoder::new(&mut file).unwrap();
+    assert_eq!(
+        decoder.get_tag_u32(Tag::Unknown(65000)).unwrap(),
+        0xdead_beef
+    );
+    assert_eq!(
+        decoder.get_tag_ascii_string(Tag::Software).unwrap(),
+        "patched!"
+    );
+    match decoder.read_image().unwrap() {
+        DecodingResult::U8(data) => assert_eq!(data, [1; 16]),
+        _ => panic!("Wrong data type"),
+    }
+}
+
+#[test]
+fn test_deferred_directories() {
+    use tiff::encoder::TiffKind;
+
+    fn encode<K: TiffKind>(first_ifd: impl Fn(&[u8]) -> u64) {
+        let mut file = Cursor::new(Vec::new());
+        {
+            let mut tiff = TiffEncoder::<_, K>::new_generic(&mut file)
+                .unwrap()
+                .with_deferred_directories();
+            let mut reserved = None;
+            for page in 0..2u8 {
+                let mut image = tiff.new_image::<colortype::Gray8>(8, 8).unwrap();
+                if page == 1 {
+                    let tag = image.encoder().reserve_tag(Tag::Unknown(65000), 0u32);
+                    reserved = Some(tag.unwrap());
+                }
+                image.write_data(&[page; 64]).unwrap();
+            }
+
+            // The value of the reserved tag is stored in its entry, which was not written yet.
+            let reserved = reserved.unwrap();
+            assert!(tiff.patch_tag(&reserved, 42u32).is_err());
+            tiff.write_deferred_directories().unwrap();
+            tiff.patch_tag(&reserved, 42u32).unwrap();
+
+            tiff.write_image::<colortype::Gray8>(8, 8, &[2; 64])
+                .unwrap();
+            tiff.write_deferred_directories().unwrap();
+        }
+
+        // Both directories of the first flush follow the data of their images.
+        let first_ifd = first_ifd(file.get_ref());
+        file.set_position(0);
+        let mut decoder = Decoder::new(&mut file).unwrap();
+        for page in 0..3u8 {
+            if page > 0 {
+                decoder.next_image().unwrap();
+            }
+            let strip = decoder.get_tag_u64(Tag::StripOffsets).unwrap();
+            if page < 2 {
+                assert!(strip + 64 <= first_ifd);
+            } else {
+                assert!(strip > first_ifd);
+            }
+            if page == 1 {
+                assert_eq!(decoder.get_tag_u32(Tag::Unknown(65000)).unwrap(), 42);
+            }
+            match decoder.read_image().unwrap() {
+                DecodingResult::U8(data) => assert_eq!(data, [page; 64]),
+                _ => panic!("Wrong data type"),
+            }
+        }
+        assert!(!decoder.more_images());
+    }
+
+    encode::<TiffKindStandard>(|file| u32::from_ne_bytes(file[4..8].try_into().unwrap()).into());
+    encode::<TiffKindBig>(|file| u64::from_ne_bytes(file[8..16].try_into().unwrap()));
+}
+
+#[test]
+fn test_deferred_values() {
+    let description = "a description too long to be stored in its entry";
+
+    let mut file = Cursor::new(Vec::new());
+    {
+        let mut tiff = TiffEncoder::new(&mut file).unwrap().with_deferred_values();
+        let mut reserved = None;
+        for page in 0..2u8 {
+            let mut image = tiff.new_image::<colortype::Gray8>(8, 8).unwrap();
+            let encoder = image.encoder();
+            encoder
+                .write_tag(Tag::ImageDescription, description)
+                .unwrap();
+            if page == 1 {
+                let tag = encoder.reserve_tag(Tag::Unknown(65000), &[0u32; 4][..]);
+                reserved = Some(tag.unwrap());
+            }
+            image.write_data(&[page; 64]).unwrap();
+        }
+
+        // The value of the reserved tag is held back with its directory.
+        let reserved = reserved.unwrap();
+        assert!(tiff.patch_tag(&reserved, &[1u32, 2, 3, 4][..]).is_err());
+        tiff.write_deferred_directories().unwrap();
+        tiff.patch_tag(&reserved, &[1u32, 2, 3, 4][..]).unwrap();
+    }
+
+    // Both descriptions follow the directories, which follow the image data.
+    let first_ifd = u64::from(u32::from_ne_bytes(file.get_ref()[4..8].try_into().unwrap()));
+    let positions: Vec<_> = file
+        .get_ref()
+        .windows(description.len())
+        .enumerate()
+        .filter(|(_, window)| *window == description.as_bytes())
+        .map(|(position, _)| position as u64)
+        .collect();
+    assert_eq!(positions.len(), 2);
+    assert!(positions.iter().all(|&position| position > first_ifd));
+
+    file.set_position(0);
+    let mut decoder = Decoder::new(&mut file).unwrap();
+    for page in 0..2u8 {
+        if page > 0 {
+            decoder.next_image().unwrap();
+        }
+        assert!(decoder.get_tag_u64(Tag::StripOffsets).unwrap() + 64 <= first_ifd);
+        assert_eq!(
+            decoder.get_tag_ascii_string(Tag::ImageDescription).unwrap(),
+            description
+        );
+        if page == 1 {
+            assert_eq!(
+                decoder.get_tag_u32_vec(Tag::Unknown(65000)).unwrap(),
+                [1, 2, 3, 4]
+            );
+        }
+        match decoder.read_image().unwrap() {
+            DecodingResult::U8(data) => assert_eq!(data, [page; 64]),
+            _ => panic!("Wrong data type"),
+        }
+    }
+    assert!(!decoder.more_images());
+}
+
+#[test]
+fn test_shared_values() {
+    let software = "a software name too long to be stored in its entry";
+    // Of the same length, to patch one with the other.
+    let artist = "an artist name, too long to be stored in its entry";
+
+    // Every page repeats the strings, the last one with its description reserved.
+    fn encode(deferred: bool, software: &str, artist: &str) -> Vec<u8> {
+        let mut file = Cursor::new(Vec::new());
+        {
+            let mut tiff = TiffEncoder::new(&mut file).unwrap().with_shared_values();
+            if deferred {
+                tiff = tiff.with_deferred_values();
+            }
+            let mut reserved = None;
+            for page in 0..3u8 {
+                let mut image = tiff.new_image::<colortype::Gray8>(8, 8).unwrap();
+                let encoder = image.encoder();
+                encoder.write_tag(Tag::Software, software).unwrap();
+                encoder.write_tag(Tag::Artist, artist).unwrap();
+                if page == 2 {
+                    let tag = encoder.reserve_tag(Tag::ImageDescription, software);
+                    reserved = Some(tag.unwrap());
+                }
+                image.write_data(&[page; 64]).unwrap();
+            }
+            tiff.write_deferred_directories().unwrap();
+            tiff.patch_tag(&reserved.unwrap(), artist).unwrap();
+        }
+        file.into_inner()
+    }
+
+    for deferred in [false, true] {
+        let mut file = Cursor::new(encode(deferred, software, artist));
+        let count = |text: &str| {
+            file.get_ref()
+                .windows(text.len())
+                .filter(|window| *window == text.as_bytes())
+                .count()
+        };
+        // The reserved description was written on its own and patched.
+        assert_eq!(count(software), 1);
+        assert_eq!(count(artist), 2);
+
+        let mut decoder = Decoder::new(&mut file).unwrap();
+        for page in 0..3u8 {
+            if page > 0 {
+                decoder.next_image().unwrap();
+            }
+            assert_eq!(
+                decoder.get_tag_ascii_string(Tag::Software).unwrap(),
+                software
+            );
+            assert_eq!(decoder.get_tag_ascii_string(Tag::Artist).unwrap(), artist);
+            match decoder.read_image().unwrap() {
+                DecodingResult::U8(data) => assert_eq!(data, [page; 64]),
+                _ => panic!("Wrong data type"),
+            }
+        }
+        assert_eq!(
+            decoder.get_tag_ascii_string(Tag::ImageDescription).unwrap(),
+            artist
+        );
+    }
+}
+
+#[test]
+fn test_write_image_with_format() {
+    use tiff::encoder::Compression;
+    use tiff::tags::{ExtraSample, PhotometricInterpretation, PlanarConfiguration, SampleFormat};
+    use tiff::PixelFormat;
+
+    fn roundtrip(format: &PixelFormat, compression: Compression, data: &[u8]) {
+        let mut file = Cursor::new(Vec::new());
+        {
+            let mut tiff = TiffEncoder::new(&mut file)
+                .unwrap()
+                .with_compression(compression);
+            tiff.write_image_with_format(5, 3, format, data).unwrap();
+        }
+
+        file.set_position(0);
+        let mut decoder = Decoder::new(&mut file).unwrap();
+        assert_eq!(&decoder.pixel_format().unwrap(), format);
+        // Read every strip, as `read_image` interleaves the planes of planar images.
+        let mut decoded = Vec::new();
+        for strip in 0..decoder.strip_count().unwrap() {
+            match decoder.read_chunk_at(ChunkIndex(strip)).unwrap() {
+                DecodingResult::U8(chunk) => decoded.extend(chunk),
+                _ => panic!("Wrong data type"),
+            }
+        }
+        assert_eq!(decoded, data);
+    }
+
+    // Gray with an alpha channel, which has no encoder color type.
+    let gray_alpha = PixelFormat::new(
+        PhotometricInterpretation::BlackIsZero,
+        vec![8, 8],
+        vec![SampleFormat::Uint; 2],
+    )
+    .with_extra_samples(vec![ExtraSample::UnassociatedAlpha]);
+    assert_eq!(gray_alpha.alpha(), Some(ExtraSample::UnassociatedAlpha));
+    let data: Vec<u8> = (0..30).collect();
+    roundtrip(&gray_alpha, Compression::Uncompressed, &data);
+    roundtrip(&gray_alpha, Compression::Packbits, &data);
+
+    // Planar RGB, one plane after the other.
+    let planar_rgb = PixelFormat::new(
+        PhotometricInterpretation::RGB,
+        vec![8; 3],
+        vec![SampleFormat::Uint; 3],
+    )
+    .with_planar_config(PlanarConfiguration::Planar);
+    let data: Vec<u8> = (0..45).collect();
+    roundtrip(&planar_rgb, Compression::Lzw, &data);
+
+    // The data must match the format.
+    let mut tiff = TiffEncoder::new(Cursor::new(Vec::new())).unwrap();
+    assert!(tiff
+        .write_image_with_format(5, 3, &planar_rgb, &data[..44])
+        .is_err());
+}
+
+#[test]
+fn test_write_tiled_image_with_format() {
+    use tiff::encoder::Compression;
+    use tiff::tags::{PhotometricInterpretation, PlanarConfiguration, SampleFormat};
+    use tiff::{PixelFormat, TiffError, TiffFormatError, TiffUnsupportedError};
+
+    let (width, height) = (40, 20);
+    fn roundtrip(format: &PixelFormat, compression: Compression, data: &[u8]) {
+        let mut file = Cursor::new(Vec::new());
+        {
+            let mut tiff = TiffEncoder::new(&mut file)
+                .unwrap()
+                .with_compression(compression);
+            tiff.write_tiled_image_with_format(40, 20, (32, 16), format, data)
+                .unwrap();
+        }
+
+        file.set_position(0);
+        let mut decoder = Decoder::new(&mut file).unwrap();
+        assert_eq!(&decoder.pixel_format().unwrap(), format);
+        let layout = decoder.chunk_layout().unwrap();
+        assert_eq!((layout.chunk_width, layout.chunk_height), (32, 16));
+        assert_eq!((layout.chunks_across, layout.chunks_down), (2, 2));
+        // Read every plane, as `read_image` interleaves the planes of planar images.
+        let mut decoded = Vec::new();
+        for plane in 0..layout.planes {
+            match decoder.read_plane(PlaneIndex(plane)).unwrap() {
+                DecodingResult::U8(plane) => decoded.extend(plane),
+                _ => panic!("Wrong data type"),
+            }
+        }
+        assert_eq!(decoded, data);
+    }
+
+    let gray = PixelFormat::new(
+        PhotometricInterpretation::BlackIsZero,
+        vec![8],
+        vec![SampleFormat::Uint],
+    );
+    let data: Vec<u8> = (0..width * height).map(|i| (i * 7) as u8).collect();
+    roundtrip(&gray, Compression::Uncompressed, &data);
+    roundtrip(&gray, Compression::Packbits, &data);
+
+    let planar_rgb = PixelFormat::new(
+        PhotometricInterpretation::RGB,
+        vec![8; 3],
+        vec![SampleFormat::Uint; 3],
+    )
+    .with_planar_config(PlanarConfiguration::Planar);
+    let data: Vec<u8> = (0..width * height * 3).map(|i| (i * 3) as u8).collect();
+    roundtrip(&planar_rgb, Compression::Lzw, &data);
+
+    let mut tiff = TiffEncoder::new(Cursor::new(Vec::new())).unwrap();
+    let err = tiff
+        .write_tiled_image_with_format(40, 20, (24, 16), &gray, &data[..800])
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        TiffError::FormatError(TiffFormatError::InvalidTileSize(24, 16))
+    ));
+    let bilevel = PixelFormat::new(
+        PhotometricInterpretation::WhiteIsZero,
+        vec![1],
+        vec![SampleFormat::Uint],
+    );
+    let err = tiff
+        .write_tiled_image_with_format(40, 20, (16, 16), &bilevel, &data[..100])
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        TiffError::UnsupportedError(TiffUnsupportedError::UnsupportedBitsPerChannel(1))
+    ));
+}
+
+#[test]
+fn test_raw_tag_iter() {
+    let mut file = Cursor::new(Vec::new());
+    {
+        let mut tiff = TiffEncoder::new(&mut file).unwrap();
+        let mut image = tiff.new_image::<colortype::Gray8>(1, 1).unwrap();
+        image
+            .encoder()
+            .write_tag(Tag::Unknown(65000), &[7u16, 8][..])
+            .unwrap();
+        image.write_data(&[0]).unwrap();
+    }
+
+    file.seek(SeekFrom::Start(0)).unwrap();
+    let mut decoder = Decoder::new(&mut file).unwrap();
+    let entries = decoder
+        .raw_tag_iter()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+
+    let ids: Vec<u16> = entries.iter().map(|entry| entry.0).collect();
+    let mut sorted = ids.clone();
+    sorted.sort_unstable();
+    assert_eq!(ids, sorted);
+
+    assert_eq!(
+        entries.last().unwrap(),
+        &(
+            65000,
+            Type::SHORT,
+            2,
+            ifd::Value::List(vec![ifd::Value::Short(7), ifd::Value::Short(8)])
+        )
+    );
+}
+
+#[test]
+fn test_colorimetry_tags() {
+    let mut file = Cursor::new(Vec::new());
+    let transfer: Vec<u16> = (0..3 * 256).map(|i| (i % 256) as u16 * 257).collect();
+    {
+        let mut tiff = TiffEncoder::new(&mut file).unwrap();
+        let mut image = tiff.new_image::<colortype::RGB8>(1, 1).unwrap();
+        let encoder = image.encoder();
+        encoder
+            .write_tag(
+                Tag::WhitePoint,
+                &[
+                    Rational { n: 3127, d: 10000 },
+                    Rational { n: 3290, d: 10000 },
+                ][..],
+            )
+            .unwrap();
+        encoder
+            .write_tag(
+                Tag::PrimaryChromaticities,
+                &[
+                    Rational { n: 64, d: 100 },
+                    Rational { n: 33, d: 100 },
+                    Rational { n: 30, d: 100 },
+                    Rational { n: 60, d: 100 },
+                    Rational { n: 15, d: 100 },
+                    Rational { n: 6, d: 100 },
+                ][..],
+            )
+            .unwrap();
+        encoder
+            .write_tag(Tag::TransferFunction, &transfer[..])
+            .unwrap();
+        image.write_data(&[0, 0, 0]).unwrap();
+    }
+
+    file.seek(SeekFrom::Start(0)).unwrap();
+    let mut decoder = Decoder::new(&mut file).unwrap();
+    assert_eq!(decoder.white_point().unwrap(), Some([0.3127, 0.329]));
+    assert_eq!(
+        decoder.primary_chromaticities().unwrap(),
+        Some([0.64, 0.33, 0.3, 0.6, 0.15, 0.06])
+    );
+    let tables = decoder.transfer_function().unwrap().unwrap();
+    assert_eq!(tables.len(), 3);
+    assert!(tables.iter().all(|table| table[..] == transfer[..256]));
+
+    // The alpha channel of an RGBA image has no table.
+    let mut file = Cursor::new(Vec::new());
+    {
+        let mut tiff = TiffEncoder::new(&mut file).unwrap();
+        let mut image = tiff.new_image::<colortype::RGBA8>(1, 1).unwrap();
+        image
+            .encoder()
+            .write_tag(Tag::TransferFunction, &transfer[..])
+            .unwrap();
+        image.write_data(&[0, 0, 0, 0]).unwrap();
+    }
+    file.seek(SeekFrom::Start(0)).unwrap();
+    let mut decoder = Decoder::new(&mut file).unwrap();
+    assert_eq!(decoder.transfer_function().unwrap().unwrap().len(), 3);
+
+    // Images without colorimetry tags.
+    let mut file = Cursor::new(Vec::new());
+    TiffEncoder::new(&mut file)
+        .unwrap()
+        .write_image::<colortype::Gray8>(1, 1, &[0])
+        .unwrap();
+    file.seek(SeekFrom::Start(0)).unwrap();
+    let mut decoder = Decoder::new(&mut file).unwrap();
+    assert_eq!(decoder.primary_chromaticities().unwrap(), None);
+    assert_eq!(decoder.white_point().unwrap(), None);
+    assert_eq!(decoder.transfer_function().unwrap(), None);
+}
+
+#[test]
+fn test_image_source_data() {
+    use tiff::decoder::{DecoderOptions, Limits};
+
+    let layers: Vec<u8> = (0..5000).map(|i| (i % 251) as u8).collect();
+
+    let mut file = Cursor::new(Vec::new());
+    {
+        let mut tiff = TiffEncoder::new(&mut file).unwrap();
+        let mut image = tiff.new_image::<colortype::Gray8>(1, 1).unwrap();
+        image
+            .encoder()
+            .write_tag(Tag::ImageSourceData, Undefined(&layers))
+            .unwrap();
+        image.write_data(&[0]).unwrap();
+        tiff.write_image::<colortype::Gray8>(1, 1, &[0]).unwrap();
+    }
+
+    file.seek(SeekFrom::Start(0)).unwrap();
+    let mut decoder = Decoder::new(&mut file).unwrap();
+    let entry = decoder
+        .raw_tag_iter()
+        .find(|entry| matches!(entry, Ok((37724, ..))))
+        .unwrap()
+        .unwrap();
+    assert_eq!((entry.1, entry.2), (Type::UNDEFINED, 5000));
+    assert_eq!(decoder.image_source_data().unwrap().unwrap(), layers);
+    decoder.next_image().unwrap();
+    assert_eq!(decoder.image_source_data().unwrap(), None);
+
+    let mut limits = Limits::default();
+    limits.decoding_buffer_size = 4096;
+    file.seek(SeekFrom::Start(0)).unwrap();
+    let options = DecoderOptions::new().with_limits(limits);
+    let mut decoder = Decoder::with_options(&mut file, options).unwrap();
+    assert!(matches!(
+        decoder.image_source_data(),
+        Err(tiff::TiffError::LimitsExceeded)
+    ));
+}
+
+#[test]
+fn test_sub_ifd_pyramid() {
+    fn level(size: u32) -> Vec<u8> {
+        (0..size * size).map(|i| (i * 7 / size) as u8).collect()
+    }
+
+    fn roundtrip<K: tiff::encoder::TiffKind>(levels: &[u32], pointer_type: Type) {
+        let mut file = Cursor::new(Vec::new());
+        {
+            let mut tiff = TiffEncoder::<_, K>::new_generic(&mut file).unwrap();
+            let mut image = tiff.new_image::<colortype::Gray8>(64, 64).unwrap();
+            for &size in levels {
+                image
+                    .new_sub_image::<colortype::Gray8>(size, size)
+                    .unwrap()
+                    .write_data(&level(size))
+                    .unwrap();
+            }
+            image.write_data(&level(64)).unwrap();
+            tiff.write_image::<colortype::Gray8>(4, 4, &level(4))
+                .unwrap();
+        }
+
+        file.seek(SeekFrom::Start(0)).unwrap();
+        let mut decoder = Decoder::new(&mut file).unwrap();
+        let entry = decoder
+            .raw_tag_iter()
+            .find(|entry| matches!(entry, Ok((330, ..))))
+            .unwrap()
+            .unwrap();
+        assert_eq!((entry.1, entry.2), (pointer_type, levels.len() as u64));
+        assert_eq!(decoder.sub_ifds().unwrap().len(), levels.len());
+
+        for (index, &size) in levels.iter().enumerate() {
+            decoder.seek_to_sub_image(index).unwrap();
+            assert_eq!(decoder.dimensions().unwrap(), (size, size));
+            assert_eq!(decoder.get_tag_u32(Tag::NewSubfileType).unwrap(), 1);
+            assert!(decoder.sub_ifds().unwrap().is_empty());
+            match decoder.read_image().unwrap() {
+                DecodingResult::U8(data) => assert_eq!(data, level(size)),
+                _ => panic!("Wrong data type"),
+            }
+            decoder.seek_to_image(0).unwrap();
+        }
+        assert!(decoder.seek_to_sub_image(levels.len()).is_err());
+
+        // The sub images are not part of the chain of images.
+        decoder.next_image().unwrap();
+        assert_eq!(decoder.dimensions().unwrap(), (4, 4));
+        assert!(!decoder.more_images());
+    }
+
+    // More than one `IFD8` pointer does not fit into the entry of a BigTiff directory.
+    roundtrip::<TiffKindBig>(&[32, 16, 8], Type::IFD8);
+    roundtrip::<TiffKindBig>(&[32], Type::IFD8);
+    roundtrip::<TiffKindStandard>(&[32, 16, 8], Type::IFD);
+}
+
+#[test]
+fn test_write_pyramid() {
+    let level = |size: u32| -> Vec<u16> { (0..size * size * 3).map(|i| (i * 31) as u16).collect() };
+
+    let mut file = Cursor::new(Vec::new());
+    {
+        let mut tiff = TiffEncoder::new(&mut file)
+            .unwrap()
+            .with_compression(tiff::encoder::Compression::Lzw);
+        tiff.write_pyramid::<colortype::RGB16>(
+            32,
+            32,
+            &level(32),
+            &[(16, 16, &level(16)), (8, 8, &level(8))],
+        )
+        .unwrap();
+        tiff.write_image::<colortype::RGB16>(4, 4, &level(4))
+            .unwrap();
+    }
+
+    file.seek(SeekFrom::Start(0)).unwrap();
+    let mut decoder = Decoder::new(&mut file).unwrap();
+    assert_eq!(decoder.find_tag(Tag::NewSubfileType).unwrap(), None);
+    match decoder.read_image().unwrap() {
+        DecodingResult::U16(data) => assert_eq!(data, level(32)),
+        _ => panic!("Wrong data type"),
+    }
+    assert_eq!(decoder.sub_ifds().unwrap().len(), 2);
+    for (index, size) in [16, 8].into_iter().enumerate() {
+        decoder.seek_to_sub_image(index).unwrap();
+        assert_eq!(decoder.dimensions().unwrap(), (size, size));
+        assert_eq!(decoder.get_tag_u32(Tag::NewSubfileType).unwrap(), 1);
+        assert_eq!(decoder.get_tag_u32(Tag::Compression).unwrap(), 5);
+        match decoder.read_image().unwrap() {
+            DecodingResult::U16(data) => assert_eq!(data, level(size)),
+            _ => panic!("Wrong data type"),
+        }
+        decoder.seek_to_image(0).unwrap();
+    }
+
+    decoder.next_image().unwrap();
+    assert_eq!(decoder.dimensions().unwrap(), (4, 4));
+    assert!(!decoder.more_images());
+}
+
+#[test]
+fn test_dyn_encoders() {
+    use tiff::encoder::DynTiffEncoder;
+
+    let mut standard = Cursor::new(Vec::new());
+    let mut big = Cursor::new(Vec::new());
+    {
+        let mut encoders: Vec<DynTiffEncoder> = vec![
+            DynTiffEncoder::new(Box::new(&mut standard)).unwrap(),
+            DynTiffEncoder::new(Box::new(Cursor::new(Vec::new()))).unwrap(),
+        ];
+        for (index, encoder) in encoders.iter_mut().enumerate() {
+            let image = encoder.new_image::<colortype::Gray8>(2, 1).unwrap();
+            image.write_data(&[index as u8, 1]).unwrap();
+        }
+
+        let mut big_encoder = DynTiffEncoder::<TiffKindBig>::new_big(Box::new(&mut big)).unwrap();
+        big_encoder
+            .write_image::<colortype::Gray8>(2, 1, &[2, 1])
+            .unwrap();
+    }
+
+    for (mut file, first) in [(standard, 0), (big, 2)] {
+        file.seek(SeekFrom::Start(0)).unwrap();
+        let mut decoder = Decoder::new(&mut file).unwrap();
+        match decoder.read_image().unwrap() {
+            DecodingResult::U8(data) => assert_eq!(data, [first, 1]),
+            _ => panic!("Wrong data type"),
+        }
+    }
+}
+
+#[test]
+fn test_band_metadata() {
+    use tiff::decoder::{BandInfo, BandMetadataParser, BandText};
+    use tiff::tags::ExtraSample;
+    use tiff::TiffResult;
+
+    /// Band names stored as a JSON list of strings, e.g. `{"bands": ["a", "b"]}`.
+    struct JsonNames;
+
+    impl BandMetadataParser for JsonNames {
+        fn parse(&self, text: &BandText<'_>, bands: &mut [BandInfo]) -> TiffResult<()> {
+            let json = text.image_description.unwrap_or_default();
+            let list = json.split('[').nth(1).unwrap_or_default();
+            let names = list.split(']').next().unwrap_or_default().split(',');
+            for (band, name) in bands.iter_mut().zip(names) {
+                band.name = Some(name.trim().trim_matches('"').to_string());
+            }
+            Ok(())
+        }
+    }
+
+    let gdal_metadata = r#"<GDALMetadata>
+  <Item name="AREA_OR_POINT">Area</Item>
+  <Item name="DESCRIPTION" sample="0" role="description">Red &amp; infrared</Item>
+  <Item name="UNITTYPE" sample="0" role="unittype">W/m2</Item>
+  <Item name="DESCRIPTION" sample='2' role='description'>Blue</Item>
+  <Item name="DESCRIPTION" sample="9" role="description">Missing</Item>
+</GDALMetadata>"#;
+
+    let mut file = Cursor::new(Vec::new());
+    {
+        let mut tiff = TiffEncoder::new(&mut file).unwrap();
+        let mut image = tiff.new_image::<colortype::RGBA8>(1, 1).unwrap();
+        let encoder = image.encoder();
+        encoder.write_tag(Tag::GdalMetadata, gdal_metadata).unwrap();
+        encoder
+            .write_tag(Tag::ImageDescription, r#"{"bands": ["r", "g", "b", "a"]}"#)
+            .unwrap();
+        image.write_data(&[1, 2, 3, 4]).unwrap();
+    }
+
+    file.set_position(0);
+    let mut decoder = Decoder::new(&mut file).unwrap();
+    let bands = decoder.bands().unwrap();
+    assert_eq!(bands.len(), 4);
+    assert_eq!(bands[0].name.as_deref(), Some("Red & infrared"));
+    assert_eq!(bands[0].unit.as_deref(), Some("W/m2"));
+    assert_eq!(
+        (bands[1].name.as_deref(), bands[1].unit.as_deref()),
+        (None, None)
+    );
+    assert_eq!(bands[2].name.as_deref(), Some("Blue"));
+    let extra_samples: Vec<_> = bands.iter().map(|band| band.extra_sample).collect();
+    assert_eq!(
+        extra_samples,
+        [None, None, None, Some(ExtraSample::UnassociatedAlpha)]
+    );
+
+    let names: Vec<_> = decoder
+        .bands_with(&JsonNames)
+        .unwrap()
+        .into_iter()
+        .map(|band| band.name.unwrap())
+        .collect();
+    assert_eq!(names, ["r", "g", "b", "a"]);
+}
+
+#[test]
+fn test_write_image_with_progress() {
+    use std::ops::ControlFlow;
+
+    // Three strips of 1000 rows.
+    let (width, height) = (1000, 3000);
+    let data = vec![7u8; width as usize * height as usize];
+
+    let mut file = Cursor::new(Vec::new());
+    {
+        let mut tiff = TiffEncoder::new(&mut file).unwrap();
+        let mut calls = Vec::new();
+        let flow = tiff
+            .write_image_with_progress::<colortype::Gray8>(width, height, &data, |progress| {
+                calls.push((progress.strips_written, progress.strip_count));
+                ControlFlow::Continue(())
+            })
+            .unwrap();
+        assert_eq!(flow, ControlFlow::Continue(()));
+        assert_eq!(calls, [(1, 3), (2, 3), (3, 3)]);
+
+        let flow = tiff
+            .write_image_with_progress::<colortype::Gray8>(width, height, &data, |progress| {
+                if progress.strips_written == 2 {
+                    ControlFlow::Break(())
+                } else {
+                    ControlFlow::Continue(())
+                }
+            })
+            .unwrap();
+        assert_eq!(flow, ControlFlow::Break(()));
+
+        tiff.write_image::<colortype::Gray8>(2, 2, &[1, 2, 3, 4])
+            .unwrap();
+    }
+
+    // The aborted image is skipped by the chain of directories.
+    file.seek(SeekFrom::Start(0)).unwrap();
+    let mut decoder = Decoder::new(&mut file).unwrap();
+    assert_eq!(decoder.dimensions().unwrap(), (width, height));
+    decoder.next_image().unwrap();
+    assert_eq!(decoder.dimensions().unwrap(), (2, 2));
+    match decoder.read_image().unwrap() {
+        DecodingResult::U8(image) => assert_eq!(image, [1, 2, 3, 4]),
+        _ => panic!("Wrong data type"),
+    }
+    assert!(!decoder.more_images());
+}
+
+#[test]
+fn test_write_rows() {
+    use tiff::encoder::{Compression, Predictor};
+
+    // Rows that do not fill the last strip of 3 rows.
+    let (width, height) = (5, 10);
+    let data: Vec<u16> = (0..width * height * 3).map(|i| (i * 97) as u16).collect();
+    let rows = data.chunks(width as usize * 3);
+
+    let mut file = Cursor::new(Vec::new());
+    {
+        let mut tiff = TiffEncoder::new(&mut file)
+            .unwrap()
+            .with_compression(Compression::Lzw)
+            .with_predictor(Predictor::Horizontal);
+        let mut image = tiff.new_image::<colortype::RGB16>(width, height).unwrap();
+        image.rows_per_strip(3).unwrap();
+        // Rows beyond the height are not taken.
+        image.write_rows(rows.clone().chain(rows.clone())).unwrap();
+
+        let image = tiff.new_image::<colortype::RGB16>(width, height).unwrap();
+        assert!(image.write_rows(rows.clone().take(9)).is_err());
+        let image = tiff.new_image::<colortype::RGB16>(width, height).unwrap();
+        assert!(image.write_rows(data.chunks(width as usize)).is_err());
+
+        tiff.write_image::<colortype::Gray8>(2, 2, &[1, 2, 3, 4])
+            .unwrap();
+    }
+
+    file.seek(SeekFrom::Start(0)).unwrap();
+    let mut decoder = Decoder::new(&mut file).unwrap();
+    assert_eq!(decoder.strip_count().unwrap(), 4);
+    assert_eq!(
+        decoder.get_tag_u32(Tag::Compression).unwrap(),
+        u32::from(tiff::tags::CompressionMethod::LZW.to_u16())
+    );
+    match decoder.read_image().unwrap() {
+        DecodingResult::U16(decoded) => assert_eq!(decoded, data),
+        _ => panic!("Wrong data type"),
+    }
+
+    // The abandoned images are skipped by the chain of directories.
+    decoder.next_image().unwrap();
+    assert_eq!(decoder.dimensions().unwrap(), (2, 2));
+    assert!(!decoder.more_images());
+}
+
+#[test]
+fn test_white_is_zero() {
+    use tiff::encoder::Predictor;
+    use tiff::tags::PhotometricInterpretation;
+
+    let page: Vec<u8> = (0..64).map(|i| if i % 3 == 0 { 255 } else { 0 }).collect();
+    let depth: Vec<u16> = (0..64).map(|i| i * 1000).collect();
+
+    let mut file = Cursor::new(Vec::new());
+    {
+        let mut tiff = TiffEncoder::new(&mut file)
+            .unwrap()
+            .with_predictor(Predictor::Horizontal);
+        let mut image = tiff.new_image::<colortype::Gray8>(8, 8).unwrap();
+        image
+            .photometric_interpretation(PhotometricInterpretation::WhiteIsZero)
+            .unwrap();
+        image.write_data(&page).unwrap();
+
+        let mut image = tiff.new_image::<colortype::Gray16>(8, 8).unwrap();
+        image
+            .photometric_interpretation(PhotometricInterpretation::WhiteIsZero)
+            .unwrap();
+        image.write_data(&depth).unwrap();
+
+        let mut image = tiff.new_image::<colortype::RGB8>(1, 1).unwrap();
+        assert!(image
+            .photometric_interpretation(PhotometricInterpretation::WhiteIsZero)
+            .is_err());
+        image.write_data(&[1, 2, 3]).unwrap();
+
+        // Inverting the alpha samples along with the gray samples would change their meaning.
+        let mut image = tiff.new_image::<colortype::GrayA8>(1, 1).unwrap();
+        assert!(image
+            .photometric_interpretation(PhotometricInterpretation::WhiteIsZero)
+            .is_err());
+        image.write_data(&[1, 2]).unwrap();
+
+        let mut image = tiff.new_image::<colortype::GrayI8>(1, 1).unwrap();
+        assert!(image
+            .photometric_interpretation(PhotometricInterpretation::WhiteIsZero)
+            .is_err());
+        image.write_data(&[-1]).unwrap();
+    }
+
+    // The decoder inverts the samples of WhiteIsZero images back.
+    file.seek(SeekFrom::Start(0)).unwrap();
+    let mut decoder = Decoder::new(&mut file).unwrap();
+    let white_is_zero = PhotometricInterpretation::WhiteIsZero.to_u16();
+    assert_eq!(
+        decoder.get_tag_u32(Tag::PhotometricInterpretation).unwrap(),
+        u32::from(white_is_zero)
+    );
+    match decoder.read_image().unwrap() {
+        DecodingResult::U8(data) => assert_eq!(data, page),
+        _ => panic!("Wrong data type"),
+    }
+
+    decoder.next_image().unwrap();
+    match decoder.read_image().unwrap() {
+        DecodingResult::U16(data) => assert_eq!(data, depth),
+        _ => panic!("Wrong data type"),
+    }
+}
+
+#[test]
+fn test_icc_profile() {
+    // A profile of a header and some arbitrary tag data, with the size and signature set.
+    let mut profile: Vec<u8> = (0..300u32).map(|i| (i * 7) as u8).collect();
+    profile[..4].copy_from_slice(&300u32.to_be_bytes());
+    profile[36..40].copy_from_slice(b"acsp");
+
+    let mut file = Cursor::new(Vec::new());
+    {
+        let mut tiff = TiffEncoder::new(&mut file).unwrap();
+        let mut image = tiff.new_image::<colortype::RGB8>(2, 2).unwrap();
+        assert!(image.icc_profile(&profile[..299]).is_err());
+        assert!(image.icc_profile(&profile[..100]).is_err());
+        let mut unsigned = profile.clone();
+        unsigned[36..40].copy_from_slice(b"    ");
+        assert!(image.icc_profile(&unsigned).is_err());
+        image.icc_profile(&profile).unwrap();
+        image.write_data(&[0; 12]).unwrap();
+
+        tiff.write_image::<colortype::RGB8>(1, 1, &[1, 2, 3])
+            .unwrap();
+    }
+
+    // The profile round-trips byte-exact.
+    file.seek(SeekFrom::Start(0)).unwrap();
+    let mut decoder = Decoder::new(&mut file).unwrap();
+    assert_eq!(decoder.icc_profile().unwrap(), Some(profile));
+    decoder.next_image().unwrap();
+    assert_eq!(decoder.icc_profile().unwrap(), None);
+}
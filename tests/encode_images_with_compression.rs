@@ -154,3 +154,128 @@ fn encode_decode_with_deflate() {
 fn encode_decode_with_packbits() {
     encode_decode_with_compression(Compression::Packbits);
 }
+
+#[test]
+fn per_image_compression_and_predictor() {
+    use tiff::encoder::Predictor;
+    use tiff::tags::Tag;
+
+    let image_rgb = TestImageColor::generate();
+    let image_grayscale = TestImageGrayscale::generate();
+
+    let mut file = Cursor::new(Vec::new());
+    {
+        let mut encoder = TiffEncoder::new(&mut file)
+            .unwrap()
+            .with_compression(Compression::Lzw);
+        encoder
+            .write_image_with::<colortype::RGB16>(
+                TestImageColor::WIDTH,
+                TestImageColor::HEIGHT,
+                image_rgb.reference_data(),
+                Some(Compression::Deflate(DeflateLevel::Fast)),
+                Some(Predictor::Horizontal),
+            )
+            .unwrap();
+        image_grayscale.compress(&mut encoder);
+    }
+
+    file.rewind().unwrap();
+    let mut decoder = Decoder::new(&mut file).unwrap();
+    assert_eq!(decoder.get_tag_u32(Tag::Compression).unwrap(), 8);
+    assert_eq!(decoder.get_tag_u32(Tag::Predictor).unwrap(), 2);
+    match decoder.read_image().unwrap() {
+        DecodingResult::U16(decoded) => assert_eq!(decoded, image_rgb.reference_data()),
+        _ => panic!("Wrong data type"),
+    }
+
+    decoder.next_image().unwrap();
+    assert_eq!(decoder.get_tag_u32(Tag::Compression).unwrap(), 5);
+    assert_eq!(decoder.get_tag_u32(Tag::Predictor).unwrap(), 1);
+    match decoder.read_image().unwrap() {
+        DecodingResult::U8(decoded) => assert_eq!(decoded, image_grayscale.reference_data()),
+        _ => panic!("Wrong data type"),
+    }
+}
+
+#[test]
+fn packbits_rows_in_one_strip() {
+    use tiff::tags::Tag;
+
+    const WIDTH: u32 = 16;
+    const HEIGHT: u32 = 8;
+    // Every row ends with the value the next one starts with, so runs would cross rows.
+    let data = vec![7u8; (WIDTH * HEIGHT) as usize];
+
+    let mut file = Cursor::new(Vec::new());
+    TiffEncoder::new(&mut file)
+        .unwrap()
+        .with_compression(Compression::Packbits)
+        .write_image::<colortype::Gray8>(WIDTH, HEIGHT, &data)
+        .unwrap();
+
+    let mut packed_row = Vec::new();
+    Packbits
+        .write_to(&mut packed_row, &data[..WIDTH as usize])
+        .unwrap();
+    let expected = packed_row.repeat(HEIGHT as usize);
+
+    file.rewind().unwrap();
+    let mut decoder = Decoder::new(&mut file).unwrap();
+    assert!(decoder.get_tag_u32(Tag::RowsPerStrip).unwrap() >= HEIGHT);
+    let offset = decoder.get_tag_u32(Tag::StripOffsets).unwrap() as usize;
+    let length = decoder.get_tag_u32(Tag::StripByteCounts).unwrap() as usize;
+    match decoder.read_image().unwrap() {
+        DecodingResult::U8(decoded) => assert_eq!(decoded, data),
+        _ => panic!("Wrong data type"),
+    }
+
+    assert_eq!(&file.get_ref()[offset..offset + length], &expected[..]);
+}
+
+#[test]
+fn strips_in_file_order() {
+    use tiff::decoder::ChunkPlacement;
+
+    fn placement(compression: Compression, interleave: bool) -> ChunkPlacement {
+        let data: Vec<u8> = (0..64 * 64).map(|i| (i % 251) as u8).collect();
+
+        let mut file = Cursor::new(Vec::new());
+        {
+            let mut tiff = TiffEncoder::new(&mut file)
+                .unwrap()
+                .with_compression(compression);
+            let mut image = tiff.new_image::<colortype::Gray8>(64, 64).unwrap();
+            image.rows_per_strip(4).unwrap();
+            let mut idx = 0;
+            while image.next_strip_sample_count() > 0 {
+                let sample_count = image.next_strip_sample_count() as usize;
+                image.write_strip(&data[idx..idx + sample_count]).unwrap();
+                idx += sample_count;
+                if interleave {
+                    image.encoder().write_data(&[0u8; 3][..]).unwrap();
+                }
+            }
+            image.finish().unwrap();
+        }
+
+        file.rewind().unwrap();
+        let mut decoder = Decoder::new(&mut file).unwrap();
+        assert_eq!(decoder.strip_count().unwrap(), 16);
+        decoder.chunk_placement().unwrap()
+    }
+
+    for compression in [
+        Compression::Uncompressed,
+        Compression::Lzw,
+        Compression::Deflate(DeflateLevel::Fast),
+        Compression::Packbits,
+    ] {
+        let placement = placement(compression, false);
+        assert!(placement.ascending && placement.contiguous);
+    }
+
+    let placement = placement(Compression::Uncompressed, true);
+    assert!(placement.ascending);
+    assert!(!placement.contiguous);
+}
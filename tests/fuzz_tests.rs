@@ -49,3 +49,75 @@ fn divide_by_zero() {
         true
     });
 }
+
+/// A gray image of a single LZW compressed chunk holding `data`, either a strip or a tile of
+/// `chunk_rows` rows.
+fn lzw_image(width: u32, height: u32, tiled: bool, chunk_rows: u32, data: &[u8]) -> Vec<u8> {
+    use std::io::Cursor;
+    use tiff::encoder::compression::{CompressionAlgorithm, Lzw};
+    use tiff::encoder::TiffEncoder;
+    use tiff::tags::{CompressionMethod, Tag};
+
+    let mut compressed = Vec::new();
+    Lzw.write_to(&mut compressed, data).unwrap();
+
+    let mut file = Cursor::new(Vec::new());
+    {
+        let mut tiff = TiffEncoder::new(&mut file).unwrap();
+        let mut dir = tiff.new_directory().unwrap();
+        let offset = dir.write_data(&compressed[..]).unwrap() as u32;
+        dir.write_tag(Tag::ImageWidth, width).unwrap();
+        dir.write_tag(Tag::ImageLength, height).unwrap();
+        dir.write_tag(Tag::BitsPerSample, 8u16).unwrap();
+        dir.write_tag(Tag::Compression, CompressionMethod::LZW.to_u16())
+            .unwrap();
+        dir.write_tag(Tag::PhotometricInterpretation, 1u16).unwrap();
+        if tiled {
+            dir.write_tag(Tag::TileWidth, 16u32).unwrap();
+            dir.write_tag(Tag::TileLength, chunk_rows).unwrap();
+            dir.write_tag(Tag::TileOffsets, offset).unwrap();
+            dir.write_tag(Tag::TileByteCounts, compressed.len() as u32)
+                .unwrap();
+        } else {
+            dir.write_tag(Tag::RowsPerStrip, chunk_rows).unwrap();
+            dir.write_tag(Tag::StripOffsets, offset).unwrap();
+            dir.write_tag(Tag::StripByteCounts, compressed.len() as u32)
+                .unwrap();
+        }
+        dir.finish().unwrap();
+    }
+    file.into_inner()
+}
+
+#[test]
+fn lzw_chunk_size() {
+    use std::io::Cursor;
+    use tiff::{TiffError, TiffFormatError};
+
+    // A strip of 8x4 samples, a last strip of 4 rows padded to 6 and a tile of 16x16 samples,
+    // of which 12x10 are within the image. The padding after the last sample within the image
+    // may be missing.
+    let chunks = [
+        (8, 4, false, 4, 32, 32),
+        (8, 4, false, 6, 32, 48),
+        (12, 10, true, 16, 9 * 16 + 12, 256),
+    ];
+    for &(width, height, tiled, chunk_rows, required, expected) in &chunks {
+        for len in 0..expected + 40 {
+            let data: Vec<u8> = (0..len).map(|i| (i * 7 % 13) as u8).collect();
+            let file = lzw_image(width, height, tiled, chunk_rows, &data);
+            let result = Decoder::new(Cursor::new(file)).and_then(|mut d| d.read_image());
+            match result {
+                Ok(_) => assert!(len >= required && len <= expected),
+                Err(TiffError::FormatError(TiffFormatError::DecompressedChunkTooLarge {
+                    chunk: 0,
+                    expected_bytes,
+                })) => {
+                    assert!(len > expected);
+                    assert_eq!(expected_bytes, expected as u64);
+                }
+                Err(_) => assert!(len < required),
+            }
+        }
+    }
+}
@@ -1,8 +1,8 @@
 extern crate tiff;
 
-use tiff::decoder::{Decoder, DecodingResult};
+use tiff::decoder::{ChunkIndex, Decoder, DecodingResult};
 use tiff::encoder::{colortype, Compression, Predictor, TiffEncoder};
-use tiff::ColorType;
+use tiff::{ColorType, TiffError, UsageError};
 
 use std::fs::File;
 use std::io::{Cursor, Seek, SeekFrom};
@@ -116,7 +116,7 @@ fn test_ycbcr_u8_predict() {
 }
 
 macro_rules! test_predict_roundtrip {
-    ($name:ident, $buffer:ident, $buffer_ty:ty) => {
+    ($name:ident, $buffer:ident, $buffer_ty:ty, $predictor:expr) => {
         fn $name<C: colortype::ColorType<Inner = $buffer_ty>>(
             file: &str,
             expected_type: ColorType,
@@ -135,7 +135,7 @@ macro_rules! test_predict_roundtrip {
             {
                 let mut tiff = TiffEncoder::new(&mut file)
                     .unwrap()
-                    .with_predictor(Predictor::Horizontal);
+                    .with_predictor($predictor);
 
                 let (width, height) = decoder.dimensions().unwrap();
                 tiff.write_image::<C>(width, height, &image_data).unwrap();
@@ -155,12 +155,24 @@ macro_rules! test_predict_roundtrip {
     };
 }
 
-test_predict_roundtrip!(test_u8_predict_roundtrip, U8, u8);
-test_predict_roundtrip!(test_i8_predict_roundtrip, I8, i8);
-test_predict_roundtrip!(test_u16_predict_roundtrip, U16, u16);
-test_predict_roundtrip!(test_i16_predict_roundtrip, I16, i16);
-test_predict_roundtrip!(test_u32_predict_roundtrip, U32, u32);
-test_predict_roundtrip!(test_u64_predict_roundtrip, U64, u64);
+test_predict_roundtrip!(test_u8_predict_roundtrip, U8, u8, Predictor::Horizontal);
+test_predict_roundtrip!(test_i8_predict_roundtrip, I8, i8, Predictor::Horizontal);
+test_predict_roundtrip!(test_u16_predict_roundtrip, U16, u16, Predictor::Horizontal);
+test_predict_roundtrip!(test_i16_predict_roundtrip, I16, i16, Predictor::Horizontal);
+test_predict_roundtrip!(test_u32_predict_roundtrip, U32, u32, Predictor::Horizontal);
+test_predict_roundtrip!(test_u64_predict_roundtrip, U64, u64, Predictor::Horizontal);
+test_predict_roundtrip!(
+    test_f32_predict_roundtrip,
+    F32,
+    f32,
+    Predictor::FloatingPoint
+);
+test_predict_roundtrip!(
+    test_f64_predict_roundtrip,
+    F64,
+    f64,
+    Predictor::FloatingPoint
+);
 
 #[test]
 fn test_gray_u8_predict_roundtrip() {
@@ -229,3 +241,213 @@ fn test_rgb_u64_predict_roundtrip() {
 fn test_ycbcr_u8_predict_roundtrip() {
     test_u8_predict_roundtrip::<colortype::YCbCr8>("tiled-jpeg-ycbcr.tif", ColorType::YCbCr(8));
 }
+
+#[test]
+fn test_gray_f32_predict_roundtrip() {
+    test_f32_predict_roundtrip::<colortype::Gray32Float>(
+        "predictor-3-gray-f32.tif",
+        ColorType::Gray(32),
+    );
+}
+
+#[test]
+fn test_rgb_f32_predict_roundtrip() {
+    test_f32_predict_roundtrip::<colortype::RGB32Float>(
+        "predictor-3-rgb-f32.tif",
+        ColorType::RGB(32),
+    );
+}
+
+#[test]
+fn test_gray_f64_predict_roundtrip() {
+    test_f64_predict_roundtrip::<colortype::Gray64Float>(
+        "gradient-1c-64b-float.tiff",
+        ColorType::Gray(64),
+    );
+}
+
+#[test]
+fn test_floating_point_predictor_rejects_integers() {
+    let mut file = Cursor::new(Vec::new());
+    let mut tiff = TiffEncoder::new(&mut file)
+        .unwrap()
+        .with_predictor(Predictor::FloatingPoint);
+    let err = tiff
+        .write_image::<colortype::Gray16>(1, 1, &[0])
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        TiffError::UsageError(UsageError::PredictorIncompatible)
+    ));
+}
+
+#[test]
+fn test_floating_point_predictor_unavailable() {
+    use tiff::tags::{PhotometricInterpretation, SampleFormat};
+
+    // A floating point color type that does not implement the predictor.
+    struct GrayF32;
+    impl colortype::ColorType for GrayF32 {
+        type Inner = f32;
+        const TIFF_VALUE: PhotometricInterpretation = PhotometricInterpretation::BlackIsZero;
+        const BITS_PER_SAMPLE: &'static [u16] = &[32];
+        const SAMPLE_FORMAT: &'static [SampleFormat] = &[SampleFormat::IEEEFP];
+
+        fn horizontal_predict(row: &[f32], result: &mut Vec<f32>) {
+            result.extend_from_slice(row);
+        }
+    }
+
+    let mut file = Cursor::new(Vec::new());
+    let mut tiff = TiffEncoder::new(&mut file)
+        .unwrap()
+        .with_predictor(Predictor::FloatingPoint);
+    let err = tiff.write_image::<GrayF32>(1, 1, &[0.5]).unwrap_err();
+    assert!(matches!(
+        err,
+        TiffError::UsageError(UsageError::PredictorUnavailable)
+    ));
+}
+
+/// Applies the floating point predictor to a row of 16 bit float samples, given as their bits.
+fn floating_point_predict_f16(row: &[u16], samples_per_pixel: usize) -> Vec<u8> {
+    let mut bytes: Vec<u8> = row.iter().map(|sample| (sample >> 8) as u8).collect();
+    bytes.extend(row.iter().map(|&sample| sample as u8));
+    for i in (samples_per_pixel..bytes.len()).rev() {
+        bytes[i] = bytes[i].wrapping_sub(bytes[i - samples_per_pixel]);
+    }
+    bytes
+}
+
+/// Writes an uncompressed image of 16 bit floats with the floating point predictor, as GDAL does
+/// with `PREDICTOR=3` and `NBITS=16`. Tiles are predicted over their full width, padding included.
+fn write_f16_predicted(
+    samples: &[u16],
+    (width, height): (u32, u32),
+    samples_per_pixel: u16,
+    tile_size: Option<u32>,
+) -> Cursor<Vec<u8>> {
+    use tiff::tags::{PhotometricInterpretation, SampleFormat, Tag};
+
+    let spp = usize::from(samples_per_pixel);
+    let row_samples = width as usize * spp;
+    let mut file = Cursor::new(Vec::new());
+    {
+        let mut tiff = TiffEncoder::new(&mut file).unwrap();
+        let mut dir = tiff.new_directory().unwrap();
+        let mut offsets = Vec::new();
+        let mut byte_counts = Vec::new();
+        match tile_size {
+            None => {
+                for strip in samples.chunks(row_samples * 2) {
+                    let data: Vec<u8> = strip
+                        .chunks(row_samples)
+                        .flat_map(|row| floating_point_predict_f16(row, spp))
+                        .collect();
+                    offsets.push(dir.write_data(&data[..]).unwrap() as u32);
+                    byte_counts.push(data.len() as u32);
+                }
+                dir.write_tag(Tag::RowsPerStrip, 2u32).unwrap();
+            }
+            Some(tile) => {
+                let tile = tile as usize;
+                for top in (0..height as usize).step_by(tile) {
+                    for left in (0..width as usize).step_by(tile) {
+                        let mut data = Vec::new();
+                        for y in top..top + tile {
+                            let mut row = vec![0u16; tile * spp];
+                            if y < height as usize {
+                                let end = (left + tile).min(width as usize);
+                                let start = y * row_samples;
+                                row[..(end - left) * spp].copy_from_slice(
+                                    &samples[start + left * spp..start + end * spp],
+                                );
+                            }
+                            data.extend(floating_point_predict_f16(&row, spp));
+                        }
+                        offsets.push(dir.write_data(&data[..]).unwrap() as u32);
+                        byte_counts.push(data.len() as u32);
+                    }
+                }
+                dir.write_tag(Tag::TileWidth, tile as u32).unwrap();
+                dir.write_tag(Tag::TileLength, tile as u32).unwrap();
+            }
+        }
+
+        let photometric = match samples_per_pixel {
+            1 => PhotometricInterpretation::BlackIsZero,
+            _ => PhotometricInterpretation::RGB,
+        };
+        dir.write_tag(Tag::ImageWidth, width).unwrap();
+        dir.write_tag(Tag::ImageLength, height).unwrap();
+        dir.write_tag(Tag::BitsPerSample, &vec![16u16; spp][..])
+            .unwrap();
+        dir.write_tag(
+            Tag::SampleFormat,
+            &vec![SampleFormat::IEEEFP.to_u16(); spp][..],
+        )
+        .unwrap();
+        dir.write_tag(Tag::SamplesPerPixel, samples_per_pixel)
+            .unwrap();
+        dir.write_tag(Tag::PhotometricInterpretation, photometric.to_u16())
+            .unwrap();
+        dir.write_tag(Tag::Predictor, Predictor::FloatingPoint.to_u16())
+            .unwrap();
+        let (offsets_tag, byte_counts_tag) = match tile_size {
+            None => (Tag::StripOffsets, Tag::StripByteCounts),
+            Some(_) => (Tag::TileOffsets, Tag::TileByteCounts),
+        };
+        dir.write_tag(offsets_tag, &offsets[..]).unwrap();
+        dir.write_tag(byte_counts_tag, &byte_counts[..]).unwrap();
+        dir.finish().unwrap();
+    }
+
+    file.set_position(0);
+    file
+}
+
+#[test]
+fn test_rgb_f16_predict_strips() {
+    let (width, height) = (5, 5);
+    let samples: Vec<u16> = (0..width * height * 3)
+        .map(|i| 0x3c00 + i as u16 * 37)
+        .collect();
+    let file = write_f16_predicted(&samples, (width, height), 3, None);
+
+    let mut decoder = Decoder::new(file).unwrap();
+    assert_eq!(decoder.colortype().unwrap(), ColorType::RGB(16));
+    match decoder.read_image().unwrap() {
+        DecodingResult::U16(data) => assert_eq!(data, samples),
+        _ => panic!("Wrong data type"),
+    }
+    match decoder.read_chunk_at(ChunkIndex(2)).unwrap() {
+        DecodingResult::U16(data) => assert_eq!(data, samples[2 * 2 * 15..]),
+        _ => panic!("Wrong data type"),
+    }
+}
+
+#[test]
+fn test_gray_f16_predict_tiles() {
+    let (width, height) = (20, 12);
+    let samples: Vec<u16> = (0..width * height)
+        .map(|i| 0xbc00 ^ (i as u16 * 91))
+        .collect();
+    let file = write_f16_predicted(&samples, (width, height), 1, Some(16));
+
+    let mut decoder = Decoder::new(file).unwrap();
+    assert_eq!(decoder.colortype().unwrap(), ColorType::Gray(16));
+    match decoder.read_image().unwrap() {
+        DecodingResult::U16(data) => assert_eq!(data, samples),
+        _ => panic!("Wrong data type"),
+    }
+    match decoder.read_chunk_at(ChunkIndex(1)).unwrap() {
+        DecodingResult::U16(data) => {
+            let expected: Vec<u16> = samples
+                .chunks(width as usize)
+                .flat_map(|row| row[16..].to_vec())
+                .collect();
+            assert_eq!(data, expected);
+        }
+        _ => panic!("Wrong data type"),
+    }
+}